@@ -6,7 +6,8 @@ pub use collab_persistence::snapshot::CollabSnapshot;
 pub use collab_plugins::cloud_storage::postgres::{CollabTableConfig, SupabaseDBConfig};
 pub use collab_plugins::disk::rocksdb::CollabPersistenceConfig;
 pub use collab_plugins::snapshot::{
-  calculate_snapshot_diff, try_encode_snapshot, SnapshotPersistence,
+  calculate_snapshot_diff, calculate_snapshot_diff_with_extractor, try_encode_snapshot,
+  SnapshotPersistence,
 };
 
 pub mod collab_builder;
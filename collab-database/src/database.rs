@@ -13,6 +13,7 @@ use crate::database_serde::DatabaseSerde;
 use crate::error::DatabaseError;
 use crate::fields::{Field, FieldMap};
 use crate::meta::MetaMap;
+use crate::plain_text::DatabasePlainTextExtractor;
 use crate::rows::{
   CreateRowParams, CreateRowParamsValidator, Row, RowCell, RowId, RowMeta, RowMetaUpdate, RowUpdate,
 };
@@ -33,11 +34,11 @@ pub struct Database {
   pub block: Block,
 }
 
-const DATABASE_ID: &str = "id";
-const DATABASE: &str = "database";
-const FIELDS: &str = "fields";
-const VIEWS: &str = "views";
-const METAS: &str = "metas";
+pub(crate) const DATABASE_ID: &str = "id";
+pub(crate) const DATABASE: &str = "database";
+pub(crate) const FIELDS: &str = "fields";
+pub(crate) const VIEWS: &str = "views";
+pub(crate) const METAS: &str = "metas";
 
 pub struct DatabaseContext {
   pub collab: Arc<MutexCollab>,
@@ -97,6 +98,7 @@ impl Database {
       None => Self::create(database_id, context),
       Some(database) => {
         let collab_guard = context.collab.lock();
+        collab_guard.set_plain_text_extractor(Arc::new(DatabasePlainTextExtractor));
         let (fields, views, metas) = collab_guard.with_transact_mut(|txn| {
           // { DATABASE: { FIELDS: {:} } }
           let fields = collab_guard
@@ -138,6 +140,7 @@ impl Database {
       return Err(DatabaseError::InvalidDatabaseID("database_id is empty"));
     }
     let collab_guard = context.collab.lock();
+    collab_guard.set_plain_text_extractor(Arc::new(DatabasePlainTextExtractor));
     let (database, fields, views, metas) = collab_guard.with_transact_mut(|txn| {
       // { DATABASE: {:} }
       let database = collab_guard
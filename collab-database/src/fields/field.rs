@@ -136,13 +136,13 @@ impl<'a, 'b, 'c> FieldUpdate<'a, 'b, 'c> {
   }
 }
 
-const FIELD_ID: &str = "id";
-const FIELD_NAME: &str = "name";
-const FIELD_TYPE: &str = "ty";
-const FIELD_TYPE_OPTION: &str = "type_option";
-const FIELD_VISIBILITY: &str = "visibility";
-const FIELD_WIDTH: &str = "width";
-const FIELD_PRIMARY: &str = "is_primary";
+pub(crate) const FIELD_ID: &str = "id";
+pub(crate) const FIELD_NAME: &str = "name";
+pub(crate) const FIELD_TYPE: &str = "ty";
+pub(crate) const FIELD_TYPE_OPTION: &str = "type_option";
+pub(crate) const FIELD_VISIBILITY: &str = "visibility";
+pub(crate) const FIELD_WIDTH: &str = "width";
+pub(crate) const FIELD_PRIMARY: &str = "is_primary";
 const CREATED_AT: &str = "created_at";
 const LAST_MODIFIED: &str = "last_modified";
 
@@ -2,7 +2,9 @@ pub mod database;
 pub mod fields;
 pub mod id_gen;
 pub mod meta;
+pub mod plain_text;
 pub mod rows;
+pub mod schema;
 pub mod user;
 pub mod views;
 
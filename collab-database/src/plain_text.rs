@@ -0,0 +1,100 @@
+use collab::core::plain_text::{any_to_plain_text, PlainTextExtractor};
+use collab::preclude::{Map, MapRef, MapRefExtension, ReadTxn, Transaction, YrsValue};
+
+use crate::database::{DATABASE, FIELDS, VIEWS};
+use crate::fields::{FIELD_ID, FIELD_NAME};
+use crate::rows::{CREATED_AT, DATA, LAST_MODIFIED, ROW_CELLS};
+use crate::views::{VIEW_ID, VIEW_NAME};
+
+/// The [DatabasePlainTextExtractor] converts the database into plain text. It writes the name
+/// of each view followed by the name of each field. The rows are stored in their own collab
+/// objects, so they are converted by the [RowPlainTextExtractor].
+#[derive(Default, Clone)]
+pub struct DatabasePlainTextExtractor;
+
+impl PlainTextExtractor for DatabasePlainTextExtractor {
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    let database = data.get_map_with_txn(txn, DATABASE)?;
+    let mut lines = vec![];
+    if let Some(views) = database.get_map_with_txn(txn, VIEWS) {
+      lines.extend(sorted_names(txn, &views, VIEW_ID, VIEW_NAME));
+    }
+    if let Some(fields) = database.get_map_with_txn(txn, FIELDS) {
+      lines.extend(sorted_names(txn, &fields, FIELD_ID, FIELD_NAME));
+    }
+    Some(lines.join("\n"))
+  }
+}
+
+/// The [RowPlainTextExtractor] converts a row into plain text. Each cell is written as a line.
+/// The cells are sorted by the field id so that the output is stable.
+#[derive(Default, Clone)]
+pub struct RowPlainTextExtractor;
+
+impl PlainTextExtractor for RowPlainTextExtractor {
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    let cells = data
+      .get_map_with_txn(txn, DATA)?
+      .get_map_with_txn(txn, ROW_CELLS)?;
+
+    let mut cells = cells
+      .iter(txn)
+      .flat_map(|(field_id, value)| {
+        value
+          .to_ymap()
+          .map(|map_ref| (field_id.to_string(), map_ref))
+      })
+      .collect::<Vec<_>>();
+    cells.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let lines = cells
+      .into_iter()
+      .flat_map(|(_, map_ref)| cell_to_plain_text(txn, &map_ref))
+      .collect::<Vec<_>>();
+    Some(lines.join("\n"))
+  }
+}
+
+fn cell_to_plain_text<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Option<String> {
+  let mut values = map_ref
+    .iter(txn)
+    .filter(|(key, _)| *key != CREATED_AT && *key != LAST_MODIFIED)
+    .flat_map(|(key, value)| match value {
+      YrsValue::Any(any) => any_to_plain_text(&any).map(|text| (key.to_string(), text)),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  values.sort_by(|a, b| a.0.cmp(&b.0));
+
+  if values.is_empty() {
+    None
+  } else {
+    Some(
+      values
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect::<Vec<_>>()
+        .join(" "),
+    )
+  }
+}
+
+fn sorted_names<T: ReadTxn>(
+  txn: &T,
+  map_ref: &MapRef,
+  id_key: &str,
+  name_key: &str,
+) -> Vec<String> {
+  let mut items = map_ref
+    .iter(txn)
+    .flat_map(|(_, value)| value.to_ymap())
+    .flat_map(|map_ref| {
+      let id = map_ref.get_str_with_txn(txn, id_key).unwrap_or_default();
+      map_ref
+        .get_str_with_txn(txn, name_key)
+        .map(|name| (id, name))
+    })
+    .collect::<Vec<_>>();
+  items.sort_by(|a, b| a.0.cmp(&b.0));
+  items.into_iter().map(|(_, name)| name).collect()
+}
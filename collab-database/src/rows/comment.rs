@@ -1,4 +1,5 @@
 use collab::preclude::lib0Any;
+use collab::serde::from_any;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,9 +13,7 @@ impl TryFrom<lib0Any> for RowComment {
   type Error = anyhow::Error;
 
   fn try_from(value: lib0Any) -> Result<Self, Self::Error> {
-    let mut json = String::new();
-    value.to_json(&mut json);
-    let comment = serde_json::from_str(&json)?;
+    let comment = from_any(&value)?;
     Ok(comment)
   }
 }
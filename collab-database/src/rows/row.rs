@@ -13,6 +13,7 @@ use uuid::Uuid;
 
 use crate::database::{gen_row_id, timestamp};
 use crate::error::DatabaseError;
+use crate::plain_text::RowPlainTextExtractor;
 use crate::rows::{Cell, Cells, CellsUpdate, RowId, RowMeta, RowMetaUpdate};
 use crate::user::DatabaseCollabBuilder;
 use crate::views::RowOrder;
@@ -20,9 +21,9 @@ use crate::{impl_bool_update, impl_i32_update, impl_i64_update};
 
 pub type BlockId = i64;
 
-const DATA: &str = "data";
-const META: &str = "meta";
-const COMMENT: &str = "comment";
+pub(crate) const DATA: &str = "data";
+pub(crate) const META: &str = "meta";
+pub(crate) const COMMENT: &str = "comment";
 pub const LAST_MODIFIED: &str = "last_modified";
 pub const CREATED_AT: &str = "created_at";
 
@@ -75,6 +76,7 @@ impl DatabaseRow {
     let config = CollabPersistenceConfig::new().snapshot_per_update(5);
     let collab = collab_builder.build_with_config(uid, &row_id, "row", db.clone(), &config);
     let collab_guard = collab.lock();
+    collab_guard.set_plain_text_extractor(Arc::new(RowPlainTextExtractor));
     let (data, meta, comments) = {
       let txn = collab_guard.transact();
       let data = collab_guard.get_map_with_txn(&txn, vec![DATA]);
@@ -340,10 +342,10 @@ impl<'a, 'b, 'c> RowUpdate<'a, 'b, 'c> {
   }
 }
 
-const ROW_ID: &str = "id";
-const ROW_VISIBILITY: &str = "visibility";
-const ROW_HEIGHT: &str = "height";
-const ROW_CELLS: &str = "cells";
+pub(crate) const ROW_ID: &str = "id";
+pub(crate) const ROW_VISIBILITY: &str = "visibility";
+pub(crate) const ROW_HEIGHT: &str = "height";
+pub(crate) const ROW_CELLS: &str = "cells";
 
 /// Return row id and created_at from a [YrsValue]
 pub fn row_id_from_value<T: ReadTxn>(value: YrsValue, txn: &T) -> Option<(String, i64)> {
@@ -0,0 +1,77 @@
+use collab::preclude::{CollabSchema, MapSchema, SchemaType};
+
+use crate::database::{DATABASE, DATABASE_ID, FIELDS, METAS, VIEWS};
+use crate::fields::{
+  FIELD_ID, FIELD_NAME, FIELD_PRIMARY, FIELD_TYPE, FIELD_TYPE_OPTION, FIELD_VISIBILITY, FIELD_WIDTH,
+};
+use crate::rows::{
+  COMMENT, CREATED_AT, DATA, LAST_MODIFIED, META, ROW_CELLS, ROW_HEIGHT, ROW_ID, ROW_VISIBILITY,
+};
+use crate::views::{
+  FIELD_ORDERS, ROW_ORDERS, VIEW_CREATE_AT, VIEW_DATABASE_ID, VIEW_FILTERS, VIEW_GROUPS, VIEW_ID,
+  VIEW_LAYOUT, VIEW_LAYOUT_SETTINGS, VIEW_MODIFY_AT, VIEW_NAME, VIEW_SORTS,
+};
+
+pub const DATABASE_COLLAB_TYPE: &str = "database";
+pub const DATABASE_ROW_COLLAB_TYPE: &str = "database_row";
+
+/// The schema of the database collab. It's used to validate the remote updates by the
+/// `SchemaValidationPlugin` of the collab-plugins.
+pub fn database_schema() -> CollabSchema {
+  // The field without the id, the type or the is_primary flag is dropped by field_from_map_ref.
+  let field = MapSchema::new()
+    .required(FIELD_ID, SchemaType::String)
+    .required(FIELD_TYPE, SchemaType::Integer)
+    .required(FIELD_PRIMARY, SchemaType::Bool)
+    .optional(FIELD_NAME, SchemaType::String)
+    .optional(FIELD_VISIBILITY, SchemaType::Bool)
+    .optional(FIELD_WIDTH, SchemaType::Integer)
+    .optional(FIELD_TYPE_OPTION, SchemaType::map_of(SchemaType::Any));
+
+  let view = MapSchema::new()
+    .required(VIEW_ID, SchemaType::String)
+    .required(VIEW_NAME, SchemaType::String)
+    .required(VIEW_LAYOUT, SchemaType::Integer)
+    .optional(VIEW_DATABASE_ID, SchemaType::String)
+    .optional(VIEW_LAYOUT_SETTINGS, SchemaType::map_of(SchemaType::Any))
+    .optional(VIEW_FILTERS, SchemaType::array(SchemaType::Any))
+    .optional(VIEW_GROUPS, SchemaType::array(SchemaType::Any))
+    .optional(VIEW_SORTS, SchemaType::array(SchemaType::Any))
+    .optional(ROW_ORDERS, SchemaType::array(SchemaType::Any))
+    .optional(FIELD_ORDERS, SchemaType::array(SchemaType::Any))
+    .optional(VIEW_CREATE_AT, SchemaType::Integer)
+    .optional(VIEW_MODIFY_AT, SchemaType::Integer);
+
+  let database = MapSchema::new()
+    .required(DATABASE_ID, SchemaType::String)
+    .required(FIELDS, SchemaType::map_of(field.into()))
+    .required(VIEWS, SchemaType::map_of(view.into()))
+    .required(METAS, SchemaType::map_of(SchemaType::Any));
+
+  CollabSchema::new(
+    DATABASE_COLLAB_TYPE,
+    MapSchema::new().required(DATABASE, database),
+  )
+}
+
+/// The schema of the database row collab. Each row is stored in its own collab.
+pub fn database_row_schema() -> CollabSchema {
+  // The cells are keyed by the field id, and the content of the cell depends on the field type.
+  let data = MapSchema::new()
+    .required(ROW_ID, SchemaType::String)
+    .optional(ROW_VISIBILITY, SchemaType::Bool)
+    .optional(ROW_HEIGHT, SchemaType::Integer)
+    .optional(
+      ROW_CELLS,
+      SchemaType::map_of(SchemaType::map_of(SchemaType::Any)),
+    )
+    .optional(CREATED_AT, SchemaType::Integer)
+    .optional(LAST_MODIFIED, SchemaType::Integer);
+
+  let root = MapSchema::new()
+    .required(DATA, data)
+    .optional(META, SchemaType::map_of(SchemaType::Any))
+    .optional(COMMENT, SchemaType::array(SchemaType::Any));
+
+  CollabSchema::new(DATABASE_ROW_COLLAB_TYPE, root)
+}
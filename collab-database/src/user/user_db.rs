@@ -4,8 +4,7 @@ use std::sync::Arc;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use collab::core::collab::MutexCollab;
-use collab::preclude::updates::decoder::Decode;
-use collab::preclude::{lib0Any, ArrayRefWrapper, Collab, MapPrelim, Update};
+use collab::preclude::{lib0Any, ArrayRefWrapper, Collab, MapPrelim};
 use collab_persistence::doc::YrsDocAction;
 use collab_persistence::kv::rocks_kv::RocksCollabDB;
 use collab_persistence::snapshot::{CollabSnapshot, SnapshotAction};
@@ -232,7 +231,7 @@ impl UserDatabase {
     snapshot: CollabSnapshot,
   ) -> Result<Database, DatabaseError> {
     let collab = self.collab_for_database(database_id);
-    let update = Update::decode_v1(&snapshot.data)?;
+    let update = snapshot.encoder_version.decode_update(&snapshot.data)?;
     collab.lock().with_transact_mut(|txn| {
       txn.apply_update(update);
     });
@@ -1,6 +1,7 @@
 use crate::fields::Field;
 use crate::views::{OrderArray, OrderIdentifiable};
 use collab::preclude::{lib0Any, Array, ArrayRef, ReadTxn, TransactionMut, YrsValue};
+use collab::serde::from_any;
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
@@ -101,9 +102,7 @@ impl From<&Field> for FieldOrder {
 
 impl From<lib0Any> for FieldOrder {
   fn from(any: lib0Any) -> Self {
-    let mut json = String::new();
-    any.to_json(&mut json);
-    serde_json::from_str(&json).unwrap()
+    from_any(&any).unwrap()
   }
 }
 
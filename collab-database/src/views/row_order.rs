@@ -1,6 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
 use collab::preclude::{lib0Any, ArrayRef, ReadTxn, YrsValue};
+use collab::serde::from_any;
 use serde::{Deserialize, Serialize};
 
 use crate::rows::{Row, RowId};
@@ -66,9 +67,7 @@ impl RowOrder {
 
 impl From<lib0Any> for RowOrder {
   fn from(any: lib0Any) -> Self {
-    let mut json = String::new();
-    any.to_json(&mut json);
-    serde_json::from_str(&json).unwrap()
+    from_any(&any).unwrap()
   }
 }
 
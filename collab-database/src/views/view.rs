@@ -164,18 +164,18 @@ impl From<DatabaseView> for CreateDatabaseParams {
   }
 }
 
-const VIEW_ID: &str = "id";
-const VIEW_NAME: &str = "name";
-const VIEW_DATABASE_ID: &str = "database_id";
+pub(crate) const VIEW_ID: &str = "id";
+pub(crate) const VIEW_NAME: &str = "name";
+pub(crate) const VIEW_DATABASE_ID: &str = "database_id";
 pub const VIEW_LAYOUT: &str = "layout";
-const VIEW_LAYOUT_SETTINGS: &str = "layout_settings";
-const VIEW_FILTERS: &str = "filters";
-const VIEW_GROUPS: &str = "groups";
-const VIEW_SORTS: &str = "sorts";
+pub(crate) const VIEW_LAYOUT_SETTINGS: &str = "layout_settings";
+pub(crate) const VIEW_FILTERS: &str = "filters";
+pub(crate) const VIEW_GROUPS: &str = "groups";
+pub(crate) const VIEW_SORTS: &str = "sorts";
 pub const ROW_ORDERS: &str = "row_orders";
 pub const FIELD_ORDERS: &str = "field_orders";
-const VIEW_CREATE_AT: &str = "created_at";
-const VIEW_MODIFY_AT: &str = "modified_at";
+pub(crate) const VIEW_CREATE_AT: &str = "created_at";
+pub(crate) const VIEW_MODIFY_AT: &str = "modified_at";
 
 pub struct ViewBuilder<'a, 'b> {
   id: &'a str,
@@ -3,13 +3,12 @@
 #![allow(unused_assignments)]
 
 use crate::internal::ctxt::ASTResult;
-use proc_macro2::{Ident, TokenStream};
-use quote::ToTokens;
+use proc_macro2::Ident;
 use std::fmt;
 use std::fmt::Display;
-use syn::Meta::{List, NameValue};
+use syn::Meta::{List, NameValue, Path};
 use syn::NestedMeta::Meta;
-use syn::{self, punctuated::Punctuated, Fields, LitStr, Path, Token};
+use syn::{self, punctuated::Punctuated, Fields, Token};
 
 pub struct ASTContainer<'a> {
   /// The struct or enum name (without generics).
@@ -73,7 +72,7 @@ pub struct ASTEnumVariant<'a> {
 pub struct ASTField<'a> {
   pub member: syn::Member,
   pub ty: &'a syn::Type,
-  pub yrs_attr: YrsAttribute,
+  pub collab_attr: CollabAttribute,
   pub original: &'a syn::Field,
 }
 
@@ -85,49 +84,97 @@ impl<'a> ASTField<'a> {
         None => syn::Member::Unnamed(index.into()),
       },
       ty: &field.ty,
-      yrs_attr: YrsAttribute::from_ast(ast_result, field),
+      collab_attr: CollabAttribute::from_ast(ast_result, field),
       original: field,
     })
   }
 }
 
-pub const YRS: Symbol = Symbol("yrs");
-pub const PRS_TY: Symbol = Symbol("ty");
-pub struct YrsAttribute {
-  #[allow(dead_code)]
-  ty: Option<LitStr>,
+pub const COLLAB: Symbol = Symbol("collab");
+pub const NESTED: Symbol = Symbol("nested");
+pub const ENUM: Symbol = Symbol("enum");
+pub const TEXT: Symbol = Symbol("text");
+pub const SKIP: Symbol = Symbol("skip");
+pub const RENAME: Symbol = Symbol("rename");
+pub const DEFAULT: Symbol = Symbol("default");
+
+/// The `#[collab(...)]` attributes of a struct field. For `Option<T>`, `Vec<T>` and
+/// `HashMap<String, T>` fields, the `nested`, `enum` and `text` attributes describe the `T`.
+#[derive(Default)]
+pub struct CollabAttribute {
+  /// `#[collab(nested)]`, the type derives `Collab` and is stored as a nested map.
+  pub nested: bool,
+  /// `#[collab(enum)]`, the type derives `Collab` and is stored as an i64.
+  pub is_enum: bool,
+  /// `#[collab(text)]`, the `String` is stored as a YText.
+  pub text: bool,
+  /// `#[collab(skip)]`, the field is not stored. `into_object` uses the default value.
+  pub skip: bool,
+  /// `#[collab(rename = "name")]`, the key of the field in the map.
+  pub rename: Option<String>,
+  /// `#[collab(default = "path")]`, the function that returns the value of the field if it's
+  /// not stored.
+  pub default: Option<syn::ExprPath>,
 }
 
-impl YrsAttribute {
-  /// Extract out the `#[yrs(...)]` attributes from a struct field.
+impl CollabAttribute {
+  /// Extract out the `#[collab(...)]` attributes from a struct field.
   pub fn from_ast(ast_result: &ASTResult, field: &syn::Field) -> Self {
-    let mut ty = ASTFieldAttr::none(ast_result, PRS_TY);
+    let mut attr = CollabAttribute::default();
     for meta_item in field
       .attrs
       .iter()
-      .flat_map(|attr| get_yrs_nested_meta(ast_result, attr))
+      .flat_map(|attr| get_collab_nested_meta(ast_result, attr))
       .flatten()
     {
       match &meta_item {
-        // Parse '#[yrs(ty = x)]'
-        Meta(NameValue(m)) if m.path == PRS_TY => {
-          if let syn::Lit::Str(lit) = &m.lit {
-            ty.set(&m.path, lit.clone());
-          }
+        // Parse '#[collab(nested)]'
+        Meta(Path(word)) if word == NESTED => attr.nested = true,
+        // Parse '#[collab(enum)]'
+        Meta(Path(word)) if word == ENUM => attr.is_enum = true,
+        // Parse '#[collab(text)]'
+        Meta(Path(word)) if word == TEXT => attr.text = true,
+        // Parse '#[collab(skip)]'
+        Meta(Path(word)) if word == SKIP => attr.skip = true,
+        // Parse '#[collab(rename = "name")]'
+        Meta(NameValue(m)) if m.path == RENAME => match &m.lit {
+          syn::Lit::Str(lit) => attr.rename = Some(lit.value()),
+          _ => ast_result.error_spanned_by(&m.lit, "expected #[collab(rename = \"name\")]"),
+        },
+        // Parse '#[collab(default = "path")]'
+        Meta(NameValue(m)) if m.path == DEFAULT => match &m.lit {
+          syn::Lit::Str(lit) => match lit.parse::<syn::ExprPath>() {
+            Ok(path) => attr.default = Some(path),
+            Err(err) => ast_result.syn_error(err),
+          },
+          _ => ast_result.error_spanned_by(&m.lit, "expected #[collab(default = \"path\")]"),
         },
-
         _ => {
           ast_result.error_spanned_by(meta_item, "unexpected meta in field attribute");
         },
       }
     }
-    YrsAttribute { ty: ty.get() }
+    if [attr.nested, attr.is_enum, attr.text]
+      .iter()
+      .filter(|is_set| **is_set)
+      .count()
+      > 1
+    {
+      ast_result.error_spanned_by(
+        field,
+        "only one of `nested`, `enum` and `text` can be applied to a field",
+      );
+    }
+    attr
   }
 }
 
-fn get_yrs_nested_meta(cx: &ASTResult, attr: &syn::Attribute) -> Result<Vec<syn::NestedMeta>, ()> {
+fn get_collab_nested_meta(
+  cx: &ASTResult,
+  attr: &syn::Attribute,
+) -> Result<Vec<syn::NestedMeta>, ()> {
   // Only handle the attribute that we have defined
-  if attr.path != YRS {
+  if attr.path != COLLAB {
     return Ok(vec![]);
   }
 
@@ -135,47 +182,13 @@ fn get_yrs_nested_meta(cx: &ASTResult, attr: &syn::Attribute) -> Result<Vec<syn:
     Ok(List(meta)) => Ok(meta.nested.into_iter().collect()),
     Ok(_) => Ok(vec![]),
     Err(err) => {
-      cx.error_spanned_by(attr, "attribute must be str, e.g. #[yrs(xx = \"xxx\")]");
+      cx.error_spanned_by(attr, "attribute must be a list, e.g. #[collab(nested)]");
       cx.syn_error(err);
       Err(())
     },
   }
 }
 
-pub struct ASTFieldAttr<'c, T> {
-  ast_result: &'c ASTResult,
-  name: Symbol,
-  tokens: TokenStream,
-  value: Option<T>,
-}
-
-impl<'c, T> ASTFieldAttr<'c, T> {
-  pub(crate) fn none(ast_result: &'c ASTResult, name: Symbol) -> Self {
-    ASTFieldAttr {
-      ast_result,
-      name,
-      tokens: TokenStream::new(),
-      value: None,
-    }
-  }
-
-  pub(crate) fn set<A: ToTokens>(&mut self, obj: A, value: T) {
-    let tokens = obj.into_token_stream();
-    if self.value.is_some() {
-      self
-        .ast_result
-        .error_spanned_by(tokens, format!("duplicate attribute `{}`", self.name));
-    } else {
-      self.tokens = tokens;
-      self.value = Some(value);
-    }
-  }
-
-  pub(crate) fn get(self) -> Option<T> {
-    self.value
-  }
-}
-
 #[derive(Copy, Clone)]
 pub enum ASTStyle {
   Struct,
@@ -240,13 +253,13 @@ impl<'a> PartialEq<Symbol> for &'a Ident {
   }
 }
 
-impl PartialEq<Symbol> for Path {
+impl PartialEq<Symbol> for syn::Path {
   fn eq(&self, word: &Symbol) -> bool {
     self.is_ident(word.0)
   }
 }
 
-impl<'a> PartialEq<Symbol> for &'a Path {
+impl<'a> PartialEq<Symbol> for &'a syn::Path {
   fn eq(&self, word: &Symbol) -> bool {
     self.is_ident(word.0)
   }
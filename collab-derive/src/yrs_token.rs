@@ -1,12 +1,17 @@
-use crate::internal::{ASTContainer, ASTResult};
+use crate::internal::{
+  ASTContainer, ASTData, ASTEnumVariant, ASTResult, ASTStyle, CollabAttribute,
+};
 use proc_macro2::{Ident, TokenStream};
 
 use syn::{AngleBracketedGenericArguments, PathSegment, Type};
 
 pub fn make_yrs_token_steam(ast_result: &ASTResult, ast: &ASTContainer) -> Option<TokenStream> {
-  let map_token_stream = token_stream_for_yrs_map(ast_result, ast);
+  let token_stream = match &ast.data {
+    ASTData::Struct(..) => token_stream_for_yrs_map(ast_result, ast),
+    ASTData::Enum(variants) => token_stream_for_enum(ast_result, &ast.ident, variants),
+  };
   let token_stream: TokenStream = quote! {
-      #map_token_stream
+      #token_stream
 
 
   };
@@ -16,15 +21,30 @@ pub fn make_yrs_token_steam(ast_result: &ASTResult, ast: &ASTContainer) -> Optio
 fn token_stream_for_yrs_map(ast_result: &ASTResult, ast: &ASTContainer) -> Option<TokenStream> {
   let struct_name = ast.ident.clone();
   let struct_map_modifier = format_ident!("{}MapRef", struct_name.to_string());
-  let setter_getter_stream_token = ast
-    .data
-    .all_fields()
-    .flat_map(|field| setter_getter_token_stream(ast_result, &field.member, field.ty));
+  let stored_fields = || {
+    ast
+      .data
+      .all_fields()
+      .filter(|field| !field.collab_attr.skip)
+  };
+  let setter_getter_stream_token = stored_fields().flat_map(|field| {
+    setter_getter_token_stream(ast_result, &field.member, field.ty, &field.collab_attr)
+  });
+
+  let observer_token_stream = stored_fields().flat_map(|field| {
+    observer_token_stream(ast_result, &field.member, field.ty, &field.collab_attr)
+  });
 
-  let into_inner_token_stream = ast
-    .data
-    .all_fields()
-    .flat_map(|field| into_inner_token_stream(ast_result, &field.member, field.ty));
+  let mut fill_token_stream = stored_fields()
+    .flat_map(|field| fill_token_stream(ast_result, &field.member, field.ty, &field.collab_attr))
+    .collect::<Vec<_>>();
+  if fill_token_stream.is_empty() {
+    fill_token_stream.push(quote! { let _ = object; });
+  }
+
+  let into_inner_token_stream = ast.data.all_fields().flat_map(|field| {
+    into_inner_token_stream(ast_result, &field.member, field.ty, &field.collab_attr)
+  });
 
   Some(quote! {
       pub struct #struct_map_modifier {
@@ -36,9 +56,27 @@ fn token_stream_for_yrs_map(ast_result: &ASTResult, ast: &ASTContainer) -> Optio
               Self { map_ref }
           }
 
+          /// Write all the fields of the object into the map.
+          pub fn from_object(
+              txn: &mut collab::preclude::TransactionMut,
+              map_ref: collab::preclude::MapRefWrapper,
+              object: #struct_name,
+          ) -> Self {
+              let mut map_ref = Self::new(map_ref);
+              map_ref.fill_with_txn(txn, object);
+              map_ref
+          }
+
+          /// Write all the fields of the object into the map. The `None` fields are removed.
+          pub fn fill_with_txn(&mut self, txn: &mut collab::preclude::TransactionMut, object: #struct_name) {
+              #(#fill_token_stream)*
+          }
+
           #(#setter_getter_stream_token)*
 
-          pub fn into_object(&self, txn: &collab::preclude::Transaction) -> #struct_name {
+          #(#observer_token_stream)*
+
+          pub fn into_object<R: collab::preclude::ReadTxn>(&self, txn: &R) -> #struct_name {
               #struct_name {
                   #(#into_inner_token_stream)*
               }
@@ -61,51 +99,181 @@ fn token_stream_for_yrs_map(ast_result: &ASTResult, ast: &ASTContainer) -> Optio
   })
 }
 
-fn into_inner_token_stream(
+/// The enums are stored as i64, the same as the `repr` of the variants. Only the enums without
+/// fields are supported. The enum is also serialized as i64 with serde, so that it's stored the
+/// same way in the `Vec` and `HashMap` fields. It must not derive `Serialize` or `Deserialize`.
+fn token_stream_for_enum(
   ast_result: &ASTResult,
-  member: &syn::Member,
-  ty: &Type,
+  enum_name: &Ident,
+  variants: &[ASTEnumVariant],
 ) -> Option<TokenStream> {
-  let ident_type = IdentType::from_ty(ast_result, ty);
-  into_inner_field_token_stream(ast_result, member, ty, &ident_type, false)
+  for variant in variants {
+    if !matches!(variant.style, ASTStyle::Unit) {
+      ast_result.error_spanned_by(
+        variant.original,
+        "Collab only supports the enums without fields",
+      );
+      return None;
+    }
+  }
+
+  let enum_name_str = enum_name.to_string();
+  let variant_idents = variants
+    .iter()
+    .map(|variant| &variant.ident)
+    .collect::<Vec<_>>();
+  Some(quote! {
+      impl From<#enum_name> for i64 {
+          fn from(value: #enum_name) -> Self {
+              value as i64
+          }
+      }
+
+      impl From<#enum_name> for collab::preclude::lib0Any {
+          fn from(value: #enum_name) -> Self {
+              collab::preclude::lib0Any::BigInt(value as i64)
+          }
+      }
+
+      impl std::convert::TryFrom<i64> for #enum_name {
+          type Error = collab::error::CollabError;
+
+          fn try_from(value: i64) -> Result<Self, Self::Error> {
+              #(
+                  if value == #enum_name::#variant_idents as i64 {
+                      return Ok(#enum_name::#variant_idents);
+                  }
+              )*
+              Err(collab::error::CollabError::UnknownEnumValue {
+                  ty: #enum_name_str.to_string(),
+                  value,
+              })
+          }
+      }
+
+      impl serde::Serialize for #enum_name {
+          fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+              let value = match self {
+                  #(#enum_name::#variant_idents => #enum_name::#variant_idents as i64,)*
+              };
+              serializer.serialize_i64(value)
+          }
+      }
+
+      impl<'de> serde::Deserialize<'de> for #enum_name {
+          fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+              let value = <i64 as serde::Deserialize>::deserialize(deserializer)?;
+              <#enum_name as std::convert::TryFrom<i64>>::try_from(value)
+                  .map_err(serde::de::Error::custom)
+          }
+      }
+  })
 }
 
-fn into_inner_field_token_stream(
+fn into_inner_token_stream(
   ast_result: &ASTResult,
   member: &syn::Member,
   ty: &Type,
-  ident_type: &IdentType,
-  is_option: bool,
+  attr: &CollabAttribute,
 ) -> Option<TokenStream> {
   let ident = get_member_ident(ast_result, member)?;
+  if attr.skip {
+    return match &attr.default {
+      None => Some(quote! { #ident: Default::default(), }),
+      Some(default) => Some(quote! { #ident: #default(), }),
+    };
+  }
+
   let getter = format_ident!("get_{}", ident.to_string());
-  match ident_type {
-    IdentType::StringType
-    | IdentType::I64Type
-    | IdentType::F64Type
-    | IdentType::BoolType
-    | IdentType::ArrayType { .. }
-    | IdentType::HashMapType { .. } => {
-      if is_option {
-        Some(quote! {
-            #ident: self.#getter(txn),
-        })
-      } else {
-        Some(quote! {
-            #ident: self.#getter(txn).unwrap_or_default(),
-        })
-      }
+  let ident_type = IdentType::from_ty(ast_result, ty, attr);
+  let value = getter_call_token_stream(quote! { self }, &getter, ty, &ident_type);
+  match (&ident_type, &attr.default) {
+    (IdentType::OptionType { .. }, None) => Some(quote! { #ident: #value, }),
+    (IdentType::OptionType { .. }, Some(default)) => {
+      Some(quote! { #ident: #value.or_else(#default), })
     },
-    IdentType::Others => Some(quote! {
-       #ident: self.#getter::<#ty>(txn).unwrap_or_default(),
-    }),
+    (_, None) => Some(quote! { #ident: #value.unwrap_or_default(), }),
+    (_, Some(default)) => Some(quote! { #ident: #value.unwrap_or_else(#default), }),
+  }
+}
+
+/// Returns the call of the getter, which returns `Option<T>`. For the `Option<T>` fields, it's
+/// the `T`.
+fn getter_call_token_stream(
+  receiver: TokenStream,
+  getter: &Ident,
+  ty: &Type,
+  ident_type: &IdentType,
+) -> TokenStream {
+  match ident_type {
+    IdentType::Others => quote! { #receiver.#getter::<#ty, _>(txn) },
     IdentType::OptionType {
       ident_type,
       inner_ty,
-    } => into_inner_field_token_stream(ast_result, member, inner_ty, ident_type, true),
+    } => getter_call_token_stream(receiver, getter, inner_ty, ident_type),
+    _ => quote! { #receiver.#getter(txn) },
+  }
+}
+
+fn fill_token_stream(
+  ast_result: &ASTResult,
+  member: &syn::Member,
+  ty: &Type,
+  attr: &CollabAttribute,
+) -> Option<TokenStream> {
+  let ident = get_member_ident(ast_result, member)?;
+  let setter = format_ident!("set_{}", ident.to_string());
+  match IdentType::from_ty(ast_result, ty, attr) {
+    IdentType::OptionType { .. } => {
+      let remover = format_ident!("remove_{}", ident.to_string());
+      Some(quote! {
+          match object.#ident {
+              Some(value) => self.#setter(txn, value),
+              None => self.#remover(txn),
+          }
+      })
+    },
+    _ => Some(quote! {
+        self.#setter(txn, object.#ident);
+    }),
   }
 }
 
+/// The observer is called when the field is inserted, replaced or removed, and when the content
+/// of the nested map, array or text of the field is changed. The new value is passed to the
+/// callback.
+fn observer_token_stream(
+  ast_result: &ASTResult,
+  member: &syn::Member,
+  ty: &Type,
+  attr: &CollabAttribute,
+) -> Option<TokenStream> {
+  let ident = get_member_ident(ast_result, member)?;
+  let key = field_key(ident, attr);
+  let getter = format_ident!("get_{}", ident.to_string());
+  let observer = format_ident!("observe_{}", ident.to_string());
+  let ident_type = IdentType::from_ty(ast_result, ty, attr);
+  let value_ty = match &ident_type {
+    IdentType::OptionType { inner_ty, .. } => inner_ty,
+    _ => ty,
+  };
+  let value = getter_call_token_stream(quote! { map_ref }, &getter, ty, &ident_type);
+  Some(quote! {
+      pub fn #observer<F>(&mut self, callback: F) -> collab::preclude::DeepEventsSubscription
+      where
+          F: Fn(&collab::preclude::TransactionMut, Option<#value_ty>) + 'static,
+      {
+          let map_ref = Self::new(self.map_ref.clone());
+          self.map_ref.observe_key(#key, move |txn| callback(txn, #value))
+      }
+  })
+}
+
+/// The key of the field in the map, which is the name of the field if it's not renamed.
+fn field_key(ident: &Ident, attr: &CollabAttribute) -> String {
+  attr.rename.clone().unwrap_or_else(|| ident.to_string())
+}
+
 fn setter_getter_token_steam_for_item_type(
   key: String,
   setter: Ident,
@@ -119,7 +287,7 @@ fn setter_getter_token_steam_for_item_type(
         pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
             self.map_ref.insert_with_txn(txn, #key, value)
         }
-        pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
+        pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
             self.map_ref.get_str_with_txn(txn, #key)
         }
     }),
@@ -127,7 +295,7 @@ fn setter_getter_token_steam_for_item_type(
         pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
             self.map_ref.insert_with_txn(txn, #key, value)
         }
-        pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
+        pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
             self.map_ref.get_i64_with_txn(txn, #key)
         }
     }),
@@ -135,7 +303,7 @@ fn setter_getter_token_steam_for_item_type(
         pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
             self.map_ref.insert_with_txn(txn, #key, value)
         }
-        pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
+        pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
             self.map_ref.get_f64_with_txn(txn, #key)
         }
     }),
@@ -143,26 +311,158 @@ fn setter_getter_token_steam_for_item_type(
         pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
             self.map_ref.insert_with_txn(txn, #key, value)
         }
-        pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
+        pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
             self.map_ref.get_bool_with_txn(txn, #key)
         }
     }),
-    IdentType::HashMapType { value_type } => {
-      let update = format_ident!("update_{}_key_value", ident.to_string());
+    IdentType::TextType => {
+      let text_ref_getter = format_ident!("get_{}_text_ref", ident.to_string());
       Some(quote! {
-          pub fn #update(&mut self, txn: &mut collab::preclude::TransactionMut, key: &str, value: #value_type) {
-              if let Some(map_ref) = self.map_ref.get_map_with_txn(txn, #key) {
-                  map_ref.insert_with_txn(txn, key, value);
+          /// Replace the content of the text. Use the text ref to edit the text collaboratively.
+          pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+              let text_ref = self
+                  .map_ref
+                  .get_text_ref_with_txn(txn, #key)
+                  .unwrap_or_else(|| self.map_ref.insert_text_with_txn(txn, #key));
+              let len = collab::preclude::GetString::get_string(&*text_ref, &*txn).len() as u32;
+              if len > 0 {
+                  collab::preclude::Text::remove_range(&*text_ref, txn, 0, len);
               }
+              collab::preclude::Text::insert(&*text_ref, txn, 0, &value);
           }
 
+          pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+              let text_ref = self.#text_ref_getter(txn)?;
+              Some(collab::preclude::GetString::get_string(&*text_ref, txn))
+          }
+
+          pub fn #text_ref_getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<collab::preclude::TextRefWrapper> {
+              self.map_ref.get_text_ref_with_txn(txn, #key)
+          }
+      })
+    },
+    IdentType::EnumType => Some(quote! {
+        pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+            self.map_ref.insert_with_txn(txn, #key, i64::from(value))
+        }
+        pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+            let value = self.map_ref.get_i64_with_txn(txn, #key)?;
+            <#ty as std::convert::TryFrom<i64>>::try_from(value).ok()
+        }
+    }),
+    IdentType::NestedType { map_ref_ty } => {
+      let map_ref_getter = format_ident!("get_{}_map_ref", ident.to_string());
+      Some(quote! {
+          /// Replace the map of the field with a new one that is filled with the value.
           pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
-              self.map_ref.insert_json_with_txn(txn, #key, value)
+              let map_ref = self.map_ref.insert_map_with_txn(txn, #key);
+              <#map_ref_ty>::from_object(txn, map_ref, value);
+          }
+
+          pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+              self.#map_ref_getter(txn).map(|map_ref| map_ref.into_object(txn))
+          }
+
+          pub fn #map_ref_getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#map_ref_ty> {
+              self.map_ref.get_map_with_txn(txn, #key).map(<#map_ref_ty>::new)
+          }
+      })
+    },
+    IdentType::HashMapType {
+      ident_type,
+      value_ty,
+    } => {
+      let update = format_ident!("update_{}_key_value", ident.to_string());
+      let value_getter = format_ident!("get_{}_value", ident.to_string());
+      let remover = format_ident!("remove_{}_key", ident.to_string());
+      let map_ref_token_stream = match ident_type.as_ref() {
+        IdentType::NestedType { map_ref_ty } => {
+          let map_ref_getter = format_ident!("get_{}_map_ref", ident.to_string());
+          Some(quote! {
+              pub fn #map_ref_getter<R: collab::preclude::ReadTxn>(&self, txn: &R, key: &str) -> Option<#map_ref_ty> {
+                  let map_ref = self.map_ref.get_map_with_txn(txn, #key)?;
+                  map_ref.get_map_with_txn(txn, key).map(<#map_ref_ty>::new)
+              }
+          })
+        },
+        _ => None,
+      };
+      // The nested values are written with the generated MapRef, the others with serde.
+      let insert_value_token_stream = match ident_type.as_ref() {
+        IdentType::NestedType { map_ref_ty } => quote! {
+            let value_map_ref = map_ref.insert_map_with_txn(txn, key);
+            <#map_ref_ty>::from_object(txn, value_map_ref, value);
+        },
+        _ => quote! {
+            map_ref.insert_json_with_txn(txn, key, value);
+        },
+      };
+      let setter_getter_token_stream = match ident_type.as_ref() {
+        IdentType::NestedType { map_ref_ty } => quote! {
+            pub fn #value_getter<R: collab::preclude::ReadTxn>(&self, txn: &R, key: &str) -> Option<#value_ty> {
+                let map_ref = self.map_ref.get_map_with_txn(txn, #key)?;
+                let value_map_ref = map_ref.get_map_with_txn(txn, key)?;
+                Some(<#map_ref_ty>::new(value_map_ref).into_object(txn))
+            }
+
+            pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+                let map_ref = self.map_ref.insert_map_with_txn(txn, #key);
+                for (key, value) in value {
+                    let key: &str = key.as_ref();
+                    #insert_value_token_stream
+                }
+            }
+
+            pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+                let map_ref = self.map_ref.get_map_with_txn(txn, #key)?;
+                let value = collab::preclude::Map::iter(&*map_ref, txn)
+                    .flat_map(|(key, value)| match value {
+                        collab::preclude::YrsValue::YMap(value_map_ref) => {
+                            let value_map_ref = collab::preclude::MapRefWrapper::new(
+                                value_map_ref,
+                                map_ref.collab_ctx.clone(),
+                            );
+                            Some((key.to_string(), <#map_ref_ty>::new(value_map_ref).into_object(txn)))
+                        },
+                        _ => None,
+                    })
+                    .collect();
+                Some(value)
+            }
+        },
+        _ => quote! {
+            pub fn #value_getter<R: collab::preclude::ReadTxn>(&self, txn: &R, key: &str) -> Option<#value_ty> {
+                let map_ref = self.map_ref.get_map_with_txn(txn, #key)?;
+                map_ref.get_json_with_txn(txn, key)
+            }
+
+            pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+                self.map_ref.insert_json_with_txn(txn, #key, value)
+            }
+
+            pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+                self.map_ref.get_json_with_txn(txn, #key)
+            }
+        },
+      };
+      Some(quote! {
+          pub fn #update(&mut self, txn: &mut collab::preclude::TransactionMut, key: &str, value: #value_ty) {
+              let map_ref = self
+                  .map_ref
+                  .get_map_with_txn(txn, #key)
+                  .unwrap_or_else(|| self.map_ref.insert_map_with_txn(txn, #key));
+              #insert_value_token_stream
           }
 
-          pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
-              self.map_ref.get_json_with_txn(txn, #key)
+          pub fn #remover(&mut self, txn: &mut collab::preclude::TransactionMut, key: &str) {
+              if let Some(map_ref) = self.map_ref.get_map_with_txn(txn, #key) {
+                  map_ref.delete_with_txn(txn, key);
+              }
           }
+
+          #map_ref_token_stream
+
+          #setter_getter_token_stream
       })
     },
     IdentType::Others => Some(quote! {
@@ -170,38 +470,129 @@ fn setter_getter_token_steam_for_item_type(
             self.map_ref.insert_json_with_txn(txn, #key, value);
         }
 
-        pub fn #getter<T: serde::de::DeserializeOwned>(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
-            self.map_ref.get_json_with_txn::<#ty>(txn, #key)
+        pub fn #getter<T: serde::de::DeserializeOwned, R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+            self.map_ref.get_json_with_txn::<#ty, _>(txn, #key)
         }
     }),
     IdentType::OptionType {
       ident_type,
       inner_ty,
-    } => setter_getter_token_steam_for_item_type(key, setter, getter, inner_ty, ident, ident_type),
+    } => {
+      let remover = format_ident!("remove_{}", ident.to_string());
+      let token_stream = setter_getter_token_steam_for_item_type(
+        key.clone(),
+        setter,
+        getter,
+        inner_ty,
+        ident,
+        ident_type,
+      );
+      Some(quote! {
+          #token_stream
+
+          pub fn #remover(&mut self, txn: &mut collab::preclude::TransactionMut) {
+              self.map_ref.delete_with_txn(txn, #key)
+          }
+      })
+    },
     IdentType::ArrayType {
-      ident_type: _,
-      inner_ty: _,
-    } => Some(quote! {
-          pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
-              self.map_ref.insert_json_with_txn(txn, #key, value)
+      ident_type,
+      inner_ty,
+    } => {
+      let array_ref_getter = format_ident!("get_{}_array_ref", ident.to_string());
+      let push = format_ident!("push_{}", ident.to_string());
+      let remover = format_ident!("remove_{}_at", ident.to_string());
+      let token_stream = match ident_type.as_ref() {
+        IdentType::NestedType { map_ref_ty } => {
+          let map_refs_getter = format_ident!("get_{}_map_refs", ident.to_string());
+          quote! {
+              /// Replace the array of the field with a new one that contains a map of each item.
+              pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+                  let array_ref = self
+                      .map_ref
+                      .insert_array_with_txn::<collab::preclude::lib0Any>(txn, #key, vec![]);
+                  for value in value {
+                      let map_ref = array_ref.push_map_with_txn(txn);
+                      <#map_ref_ty>::from_object(txn, map_ref, value);
+                  }
+              }
+
+              pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+                  self.#array_ref_getter(txn)?;
+                  let value = self
+                      .#map_refs_getter(txn)
+                      .into_iter()
+                      .map(|map_ref| map_ref.into_object(txn))
+                      .collect();
+                  Some(value)
+              }
+
+              pub fn #map_refs_getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Vec<#map_ref_ty> {
+                  match self.#array_ref_getter(txn) {
+                      None => vec![],
+                      Some(array_ref) => array_ref
+                          .to_map_refs_with_txn(txn)
+                          .into_iter()
+                          .map(<#map_ref_ty>::new)
+                          .collect(),
+                  }
+              }
+
+              pub fn #push(&mut self, txn: &mut collab::preclude::TransactionMut, value: #inner_ty) -> Result<(), collab::error::CollabError> {
+                  let array_ref = self
+                      .map_ref
+                      .get_or_insert_array_with_txn::<collab::preclude::lib0Any>(txn, #key);
+                  let map_ref = array_ref.push_map_with_txn(txn);
+                  <#map_ref_ty>::from_object(txn, map_ref, value);
+                  Ok(())
+              }
           }
+        },
+        _ => quote! {
+            pub fn #setter(&mut self, txn: &mut collab::preclude::TransactionMut, value: #ty) {
+                self.map_ref.insert_json_with_txn(txn, #key, value)
+            }
+
+            pub fn #getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<#ty> {
+                self.map_ref.get_json_with_txn(txn, #key)
+            }
 
-          pub fn #getter(&self, txn: &collab::preclude::Transaction) -> Option<#ty> {
-              self.map_ref.get_json_with_txn(txn, #key)
+            pub fn #push(&mut self, txn: &mut collab::preclude::TransactionMut, value: #inner_ty) -> Result<(), collab::error::CollabError> {
+                let array_ref = self
+                    .map_ref
+                    .get_or_insert_array_with_txn::<collab::preclude::lib0Any>(txn, #key);
+                collab::serde::push_to_array_ref(txn, &array_ref, &value)
+            }
+        },
+      };
+      Some(quote! {
+          #token_stream
+
+          pub fn #array_ref_getter<R: collab::preclude::ReadTxn>(&self, txn: &R) -> Option<collab::preclude::ArrayRefWrapper> {
+              self.map_ref.get_array_ref_with_txn(txn, #key)
           }
-    }),
+
+          pub fn #remover(&mut self, txn: &mut collab::preclude::TransactionMut, index: u32) {
+              if let Some(array_ref) = self.map_ref.get_array_ref_with_txn(txn, #key) {
+                  array_ref.remove_with_txn(txn, index);
+              }
+          }
+      })
+    },
   }
 }
+
 fn setter_getter_token_stream(
   ast_result: &ASTResult,
   member: &syn::Member,
   ty: &Type,
+  attr: &CollabAttribute,
 ) -> Option<TokenStream> {
   let ident = get_member_ident(ast_result, member)?;
-  let key = ident.to_string();
+  let key = field_key(ident, attr);
   let setter = format_ident!("set_{}", ident.to_string());
   let getter = format_ident!("get_{}", ident.to_string());
-  let ident_type = IdentType::from_ty(ast_result, ty);
+  let ident_type = IdentType::from_ty(ast_result, ty, attr);
   setter_getter_token_steam_for_item_type(key, setter, getter, ty, ident, &ident_type)
 }
 
@@ -226,8 +617,17 @@ enum IdentType {
   I64Type,
   F64Type,
   BoolType,
+  /// A struct that derives `Collab`, marked with `#[collab(nested)]`.
+  NestedType {
+    map_ref_ty: Type,
+  },
+  /// An enum that derives `Collab`, marked with `#[collab(enum)]`.
+  EnumType,
+  /// A `String` that is stored as YText, marked with `#[collab(text)]`.
+  TextType,
   HashMapType {
-    value_type: Ident,
+    ident_type: Box<IdentType>,
+    value_ty: Type,
   },
   OptionType {
     ident_type: Box<IdentType>,
@@ -241,7 +641,7 @@ enum IdentType {
 }
 
 impl IdentType {
-  pub fn from_ty(ast_result: &ASTResult, ty: &Type) -> Self {
+  pub fn from_ty(ast_result: &ASTResult, ty: &Type, attr: &CollabAttribute) -> Self {
     if let Type::Path(p) = &ty {
       let mut ident_type = match p.path.get_ident() {
         None => IdentType::Others,
@@ -258,49 +658,85 @@ impl IdentType {
         if let Some(seg) = p.path.segments.last() {
           if seg.ident == "HashMap" {
             let types = get_bracketed_value_type_from(ast_result, seg);
-            let ident = parse_ty(types[1]).unwrap();
-            ident_type = IdentType::HashMapType { value_type: ident };
-          }
-
-          if seg.ident == "Vec" {
+            if let Some(value_ty) = types.get(1) {
+              let item_type = IdentType::from_item_ty(ast_result, value_ty, attr);
+              ident_type = IdentType::HashMapType {
+                ident_type: Box::new(item_type),
+                value_ty: (*value_ty).clone(),
+              };
+            }
+          } else if seg.ident == "Vec" {
             let types = get_bracketed_value_type_from(ast_result, seg);
-            let item_type = IdentType::from_ty(ast_result, types[0]);
-            ident_type = IdentType::ArrayType {
-              ident_type: Box::new(item_type),
-              inner_ty: types[0].clone(),
-            };
-          }
-
-          if seg.ident == "Option" {
+            if let Some(inner_ty) = types.first() {
+              let item_type = IdentType::from_item_ty(ast_result, inner_ty, attr);
+              ident_type = IdentType::ArrayType {
+                ident_type: Box::new(item_type),
+                inner_ty: (*inner_ty).clone(),
+              };
+            }
+          } else if seg.ident == "Option" {
             let types = get_bracketed_value_type_from(ast_result, seg);
-            let item_type = IdentType::from_ty(ast_result, types[0]);
-            ident_type = IdentType::OptionType {
-              ident_type: Box::new(item_type),
-              inner_ty: types[0].clone(),
+            if let Some(inner_ty) = types.first() {
+              let item_type = IdentType::from_ty(ast_result, inner_ty, attr);
+              ident_type = IdentType::OptionType {
+                ident_type: Box::new(item_type),
+                inner_ty: (*inner_ty).clone(),
+              };
+            }
+          } else if attr.nested {
+            ident_type = match map_ref_ty(ty) {
+              Some(map_ref_ty) => IdentType::NestedType { map_ref_ty },
+              None => {
+                ast_result.error_spanned_by(ty, "Can not infer the MapRef of the nested type");
+                IdentType::Others
+              },
             };
-
-            // if let Type::Path(p) = types[0] {
-            //     let inner_ty = p.path.get_ident().cloned().unwrap();
-            //     let item_type = IdentType::from_ty(ast_result, types[0]);
-            //     ident_type = IdentType::OptionType {
-            //         ident_type: Box::new(item_type),
-            //         inner_ty: types[0].clone(),
-            //     };
-            // } else {
-            //     ast_result.error_spanned_by(
-            //         types[0],
-            //         "Can not infer the bracket inner type of the Option",
-            //     );
-            //     IdentType::Others
-            // };
+          } else if attr.is_enum {
+            ident_type = IdentType::EnumType;
           }
         }
       }
+      if attr.text {
+        match ident_type {
+          IdentType::StringType => ident_type = IdentType::TextType,
+          // The inner type is checked by itself.
+          IdentType::OptionType { .. }
+          | IdentType::ArrayType { .. }
+          | IdentType::HashMapType { .. }
+          | IdentType::TextType => {},
+          _ => ast_result.error_spanned_by(ty, "#[collab(text)] only applies to String"),
+        }
+      }
       ident_type
     } else {
       IdentType::Others
     }
   }
+
+  /// The items of the `Vec` and the values of the `HashMap` are serialized with serde, except
+  /// the nested structs. The enums are serialized as i64 and the strings are not stored as YText
+  /// there.
+  fn from_item_ty(ast_result: &ASTResult, ty: &Type, attr: &CollabAttribute) -> Self {
+    let ident_type = IdentType::from_ty(ast_result, ty, attr);
+    if ident_type == IdentType::TextType {
+      ast_result.error_spanned_by(ty, "#[collab(text)] is not supported in Vec or HashMap");
+    }
+    ident_type
+  }
+}
+
+/// Returns the `<Name>MapRef` generated by the `Collab` derive of the given type.
+fn map_ref_ty(ty: &Type) -> Option<Type> {
+  let mut map_ref_ty = ty.clone();
+  if let Type::Path(ref mut p) = map_ref_ty {
+    let seg = p.path.segments.last_mut()?;
+    if !seg.arguments.is_empty() {
+      return None;
+    }
+    seg.ident = format_ident!("{}MapRef", seg.ident.to_string());
+    return Some(map_ref_ty);
+  }
+  None
 }
 
 fn get_bracketed_value_type_from<'a>(
@@ -335,17 +771,3 @@ fn parse_bracketed(bracketed: &AngleBracketedGenericArguments) -> Vec<&Type> {
     })
     .collect::<Vec<&syn::Type>>()
 }
-
-fn parse_ty(ty: &Type) -> Option<Ident> {
-  if let Type::Path(ref p) = ty {
-    if p.path.segments.len() != 1 {
-      return None;
-    }
-
-    return match p.path.segments.last() {
-      Some(seg) => Some(seg.ident.clone()),
-      None => return None,
-    };
-  }
-  None
-}
@@ -8,13 +8,13 @@ pub const EXTERNAL_TYPE_TEXT: &str = "text";
 pub const EXTERNAL_TYPE_ARRAY: &str = "array";
 pub const EXTERNAL_TYPE_MAP: &str = "map";
 
-const ID: &str = "id";
-const TYPE: &str = "ty";
-const PARENT: &str = "parent";
-const CHILDREN: &str = "children";
-const DATA: &str = "data";
-const EXTERNAL_ID: &str = "external_id";
-const EXTERNAL_TYPE: &str = "external_type";
+pub(crate) const ID: &str = "id";
+pub(crate) const TYPE: &str = "ty";
+pub(crate) const PARENT: &str = "parent";
+pub(crate) const CHILDREN: &str = "children";
+pub(crate) const DATA: &str = "data";
+pub(crate) const EXTERNAL_ID: &str = "external_id";
+pub(crate) const EXTERNAL_TYPE: &str = "external_type";
 
 /// for block operate, there has a root map, and a children map.
 pub struct BlockOperation {
@@ -133,7 +133,7 @@ impl BlockOperation {
 }
 
 /// Build the block from the [MapRef]
-fn block_from_map<T: ReadTxn>(txn: &T, map: MapRef) -> Block {
+pub(crate) fn block_from_map<T: ReadTxn>(txn: &T, map: MapRef) -> Block {
   let id = map.get_str_with_txn(txn, ID).unwrap_or_default();
   let ty = map.get_str_with_txn(txn, TYPE).unwrap_or_default();
   let parent = map.get_str_with_txn(txn, PARENT).unwrap_or_default();
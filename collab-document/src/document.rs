@@ -11,19 +11,20 @@ use crate::blocks::{
   DocumentMeta, RootDeepSubscription,
 };
 use crate::error::DocumentError;
+use crate::plain_text::DocumentPlainTextExtractor;
 
-const ROOT: &str = "document";
+pub(crate) const ROOT: &str = "document";
 
 /// The page_id is a reference that points to the block’s id.
 /// The block that is referenced by this page_id is the first block of the document.
 /// Crossing this block, we can build the whole document tree.
-const PAGE_ID: &str = "page_id";
+pub(crate) const PAGE_ID: &str = "page_id";
 /// Document's all [Block] Map.
-const BLOCKS: &str = "blocks";
+pub(crate) const BLOCKS: &str = "blocks";
 /// Document's meta data.
-const META: &str = "meta";
+pub(crate) const META: &str = "meta";
 /// [Block]'s children map. And it's also in [META].
-const CHILDREN_MAP: &str = "children_map";
+pub(crate) const CHILDREN_MAP: &str = "children_map";
 
 pub struct Document {
   inner: Arc<MutexCollab>,
@@ -99,6 +100,11 @@ impl Document {
     Ok(document_data)
   }
 
+  /// Returns the plain text of the document. Each block's text is written as a line.
+  pub fn to_plain_text(&self) -> String {
+    self.inner.lock().to_plain_text()
+  }
+
   /// Apply actions to the document.
   pub fn apply_action(&self, actions: Vec<BlockAction>) {
     self.inner.lock().with_transact_mut(|txn| {
@@ -358,6 +364,42 @@ impl Document {
     }
   }
 
+  /// Returns the items of the undo stack. The last item is the one that is undone next.
+  pub fn undo_stack_items(&self) -> Vec<UndoStackItem> {
+    match self.inner.try_lock() {
+      Some(collab_guard) => collab_guard.undo_stack_items(),
+      None => Default::default(),
+    }
+  }
+
+  /// Returns the items of the redo stack. The last item is the one that is redone next.
+  pub fn redo_stack_items(&self) -> Vec<UndoStackItem> {
+    match self.inner.try_lock() {
+      Some(collab_guard) => collab_guard.redo_stack_items(),
+      None => Default::default(),
+    }
+  }
+
+  pub fn undo_stack_len(&self) -> usize {
+    match self.inner.try_lock() {
+      Some(collab_guard) => collab_guard.undo_stack_len(),
+      None => Default::default(),
+    }
+  }
+
+  pub fn redo_stack_len(&self) -> usize {
+    match self.inner.try_lock() {
+      Some(collab_guard) => collab_guard.redo_stack_len(),
+      None => Default::default(),
+    }
+  }
+
+  /// Subscribe to the changes of the undo and redo stacks, for example, to refresh the undo
+  /// and redo buttons of the toolbar.
+  pub fn subscribe_undo_stack(&self) -> UndoStackReceiver {
+    self.inner.lock().subscribe_undo_stack()
+  }
+
   fn create_document(
     collab: Arc<MutexCollab>,
     data: Option<DocumentData>,
@@ -396,6 +438,7 @@ impl Document {
     })?;
 
     collab_guard.enable_undo_redo();
+    collab_guard.set_plain_text_extractor(Arc::new(DocumentPlainTextExtractor));
     let subscription = RootDeepSubscription::default();
 
     drop(collab_guard);
@@ -428,6 +471,7 @@ impl Document {
     };
 
     collab_guard.enable_undo_redo();
+    collab_guard.set_plain_text_extractor(Arc::new(DocumentPlainTextExtractor));
     drop(collab_guard);
 
     Self {
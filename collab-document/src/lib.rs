@@ -3,3 +3,5 @@ pub use collab_persistence::doc::YrsDocAction;
 pub mod blocks;
 pub mod document;
 pub mod error;
+pub mod plain_text;
+pub mod schema;
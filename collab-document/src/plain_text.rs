@@ -0,0 +1,73 @@
+use collab::core::plain_text::PlainTextExtractor;
+use collab::preclude::{Array, MapRef, MapRefExtension, ReadTxn, Transaction};
+use serde_json::Value;
+
+use crate::blocks::{block_from_map, Block};
+use crate::document::{BLOCKS, CHILDREN_MAP, META, PAGE_ID, ROOT};
+
+const DELTA: &str = "delta";
+const INSERT: &str = "insert";
+
+/// The [DocumentPlainTextExtractor] converts the document into plain text. Starting from the
+/// page block, it walks the blocks in the order of the children map and writes the text of
+/// each block's delta as a line.
+#[derive(Default, Clone)]
+pub struct DocumentPlainTextExtractor;
+
+impl PlainTextExtractor for DocumentPlainTextExtractor {
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    let root = data.get_map_with_txn(txn, ROOT)?;
+    let page_id = root.get_str_with_txn(txn, PAGE_ID)?;
+    let blocks = root.get_map_with_txn(txn, BLOCKS)?;
+    let children_map = root
+      .get_map_with_txn(txn, META)?
+      .get_map_with_txn(txn, CHILDREN_MAP)?;
+
+    let mut lines = vec![];
+    write_block_text(txn, &blocks, &children_map, &page_id, &mut lines);
+    Some(lines.join("\n"))
+  }
+}
+
+fn write_block_text<T: ReadTxn>(
+  txn: &T,
+  blocks: &MapRef,
+  children_map: &MapRef,
+  block_id: &str,
+  lines: &mut Vec<String>,
+) {
+  let block = match blocks.get_map_with_txn(txn, block_id) {
+    None => return,
+    Some(map_ref) => block_from_map(txn, map_ref),
+  };
+
+  if let Some(text) = text_from_block(&block) {
+    lines.push(text);
+  }
+
+  if let Some(children) = children_map.get_array_ref_with_txn(txn, &block.children) {
+    let child_ids = children
+      .iter(txn)
+      .map(|child| child.to_string(txn))
+      .collect::<Vec<String>>();
+    for child_id in child_ids {
+      write_block_text(txn, blocks, children_map, &child_id, lines);
+    }
+  }
+}
+
+/// Returns the text of the block's delta. For example, the data of a text block:
+/// `{"delta": [{"insert": "Hello "}, {"insert": "world", "attributes": {"bold": true}}]}`
+/// will be converted into `Hello world`.
+pub fn text_from_block(block: &Block) -> Option<String> {
+  let delta = block.data.get(DELTA)?.as_array()?;
+  let text = delta
+    .iter()
+    .flat_map(|op| op.get(INSERT).and_then(Value::as_str))
+    .collect::<String>();
+  if text.is_empty() {
+    None
+  } else {
+    Some(text)
+  }
+}
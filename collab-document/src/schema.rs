@@ -0,0 +1,35 @@
+use collab::preclude::{CollabSchema, MapSchema, SchemaType};
+
+use crate::blocks::{CHILDREN, DATA, EXTERNAL_ID, EXTERNAL_TYPE, ID, PARENT, TYPE};
+use crate::document::{BLOCKS, CHILDREN_MAP, META, PAGE_ID, ROOT};
+
+pub const DOCUMENT_COLLAB_TYPE: &str = "document";
+
+/// The schema of the document collab. It's used to validate the remote updates by the
+/// `SchemaValidationPlugin` of the collab-plugins.
+pub fn document_schema() -> CollabSchema {
+  // The data of the block is stored as a json string.
+  let block = MapSchema::new()
+    .required(ID, SchemaType::String)
+    .required(TYPE, SchemaType::String)
+    .required(PARENT, SchemaType::String)
+    .required(CHILDREN, SchemaType::String)
+    .required(DATA, SchemaType::String)
+    .optional(EXTERNAL_ID, SchemaType::String)
+    .optional(EXTERNAL_TYPE, SchemaType::String);
+
+  let meta = MapSchema::new().required(
+    CHILDREN_MAP,
+    SchemaType::map_of(SchemaType::array(SchemaType::String)),
+  );
+
+  let document = MapSchema::new()
+    .optional(PAGE_ID, SchemaType::String)
+    .required(BLOCKS, SchemaType::map_of(block.into()))
+    .required(META, meta);
+
+  CollabSchema::new(
+    DOCUMENT_COLLAB_TYPE,
+    MapSchema::new().required(ROOT, document),
+  )
+}
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use collab_document::blocks::{Block, BlockAction, BlockActionPayload, BlockActionType};
 use nanoid::nanoid;
+use serde_json::json;
 
 use crate::util::{
   apply_actions, create_document, create_document_with_db, db, get_document_data,
@@ -69,3 +72,34 @@ fn reopen_document() {
   let (page_id2, _, _) = get_document_data(&document);
   assert_eq!(page_id, page_id2);
 }
+
+#[test]
+fn document_to_plain_text() {
+  let test = create_document(1, "1");
+  let (page_id, _, _) = get_document_data(&test.document);
+  let block_id = nanoid!(10);
+  let mut data = HashMap::new();
+  data.insert(
+    "delta".to_string(),
+    json!([{"insert": "Hello "}, {"insert": "world", "attributes": {"bold": true}}]),
+  );
+  let block = Block {
+    id: block_id,
+    ty: "text".to_string(),
+    parent: page_id.clone(),
+    children: nanoid!(10),
+    external_id: None,
+    external_type: None,
+    data,
+  };
+  let insert_action = BlockAction {
+    action: BlockActionType::Insert,
+    payload: BlockActionPayload {
+      block,
+      prev_id: None,
+      parent_id: Some(page_id),
+    },
+  };
+  apply_actions(&test.document, vec![insert_action]);
+  assert_eq!(test.document.to_plain_text(), "Hello world");
+}
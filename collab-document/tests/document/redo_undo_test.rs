@@ -200,3 +200,25 @@ async fn undo_redo_after_reopen_document() {
   let block = document.get_block(&block_id).unwrap();
   assert_eq!(block.data, data);
 }
+
+#[tokio::test]
+async fn undo_redo_stack_items() {
+  let test = create_document(1, "1");
+  let document = test.document;
+  let mut rx = document.subscribe_undo_stack();
+  assert_eq!(document.undo_stack_len(), 0);
+
+  insert_block_for_page(&document, nanoid!(10));
+  assert_eq!(document.undo_stack_len(), 1);
+  assert!(!document.undo_stack_items()[0].touched_paths.is_empty());
+  assert!(rx.try_recv().is_ok());
+
+  assert!(document.undo());
+  assert_eq!(document.undo_stack_len(), 0);
+  assert_eq!(document.redo_stack_len(), 1);
+  assert_eq!(document.redo_stack_items().len(), 1);
+
+  assert!(document.redo());
+  assert_eq!(document.undo_stack_len(), 1);
+  assert_eq!(document.redo_stack_len(), 0);
+}
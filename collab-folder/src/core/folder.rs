@@ -4,27 +4,29 @@ use std::sync::Arc;
 
 use collab::core::array_wrapper::ArrayRefExtension;
 use collab::core::collab::MutexCollab;
-use collab::core::collab_state::CollabState;
+use collab::core::collab_state::CollabStateChange;
 use collab::preclude::*;
+use collab::serde::from_any;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tokio_stream::wrappers::WatchStream;
 
 use crate::core::folder_observe::{TrashChangeSender, ViewChangeSender};
+use crate::core::plain_text::FolderPlainTextExtractor;
 use crate::core::trash::{TrashArray, TrashRecord};
 use crate::core::{
   subscribe_folder_change, FolderData, TrashInfo, View, ViewIdentifier, ViewRelations, ViewsMap,
   Workspace, WorkspaceMap, WorkspaceUpdate,
 };
 
-const FOLDER: &str = "folder";
-const WORKSPACES: &str = "workspaces";
-const VIEWS: &str = "views";
-const TRASH: &str = "trash";
-const META: &str = "meta";
-const VIEW_RELATION: &str = "relation";
-const CURRENT_WORKSPACE: &str = "current_workspace";
-const CURRENT_VIEW: &str = "current_view";
+pub(crate) const FOLDER: &str = "folder";
+pub(crate) const WORKSPACES: &str = "workspaces";
+pub(crate) const VIEWS: &str = "views";
+pub(crate) const TRASH: &str = "trash";
+pub(crate) const META: &str = "meta";
+pub(crate) const VIEW_RELATION: &str = "relation";
+pub(crate) const CURRENT_WORKSPACE: &str = "current_workspace";
+pub(crate) const CURRENT_VIEW: &str = "current_view";
 
 pub struct FolderContext {
   pub view_change_tx: ViewChangeSender,
@@ -63,7 +65,7 @@ impl Folder {
     }
   }
 
-  pub fn subscribe_state_change(&self) -> WatchStream<CollabState> {
+  pub fn subscribe_state_change(&self) -> WatchStream<CollabStateChange> {
     let rx = self.inner.lock().subscribe_state_change();
     WatchStream::new(rx)
   }
@@ -363,9 +365,7 @@ pub struct WorkspaceItem {
 
 impl From<lib0Any> for WorkspaceItem {
   fn from(any: lib0Any) -> Self {
-    let mut json = String::new();
-    any.to_json(&mut json);
-    serde_json::from_str(&json).unwrap()
+    from_any(&any).unwrap()
   }
 }
 
@@ -378,6 +378,7 @@ impl From<WorkspaceItem> for lib0Any {
 
 fn create_folder(collab: Arc<MutexCollab>, context: FolderContext) -> Folder {
   let collab_guard = collab.lock();
+  collab_guard.set_plain_text_extractor(Arc::new(FolderPlainTextExtractor));
 
   let (folder, workspaces, views, trash, meta, subscription) =
     collab_guard.with_transact_mut(|txn| {
@@ -420,6 +421,7 @@ fn is_folder_exist(txn: Transaction, collab: &Collab) -> bool {
 
 fn get_folder(collab: Arc<MutexCollab>, context: FolderContext) -> Folder {
   let collab_guard = collab.lock();
+  collab_guard.set_plain_text_extractor(Arc::new(FolderPlainTextExtractor));
   let txn = collab_guard.transact();
   let mut folder = collab_guard.get_map_with_txn(&txn, vec![FOLDER]).unwrap();
   let folder_sub = subscribe_folder_change(&mut folder, context.view_change_tx.clone());
@@ -1,6 +1,8 @@
 mod entities;
 mod folder;
+mod plain_text;
 mod relation;
+mod schema;
 mod trash;
 mod view;
 mod workspace;
@@ -8,7 +10,9 @@ mod workspace;
 pub use entities::*;
 pub use folder::*;
 pub use folder_observe::*;
+pub use plain_text::*;
 pub use relation::*;
+pub use schema::*;
 pub use trash::*;
 pub use view::*;
 pub use workspace::*;
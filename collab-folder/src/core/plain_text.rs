@@ -0,0 +1,53 @@
+use collab::core::plain_text::PlainTextExtractor;
+use collab::preclude::{Array, Map, MapRef, MapRefExtension, Transaction, YrsValue};
+
+use crate::core::folder::{FOLDER, VIEWS, WORKSPACES};
+use crate::core::view::{VIEW_DESC, VIEW_ID, VIEW_NAME};
+use crate::core::workspace::WORKSPACE_NAME;
+
+/// The [FolderPlainTextExtractor] converts the folder into plain text. It writes the name of
+/// each workspace, followed by the name and the description of each view.
+#[derive(Default, Clone)]
+pub struct FolderPlainTextExtractor;
+
+impl PlainTextExtractor for FolderPlainTextExtractor {
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    let folder = data.get_map_with_txn(txn, FOLDER)?;
+    let mut lines = vec![];
+
+    if let Some(workspaces) = folder.get_array_ref_with_txn(txn, WORKSPACES) {
+      for value in workspaces.iter(txn) {
+        if let YrsValue::YMap(map_ref) = value {
+          if let Some(name) = map_ref.get_str_with_txn(txn, WORKSPACE_NAME) {
+            lines.push(name);
+          }
+        }
+      }
+    }
+
+    if let Some(views) = folder.get_map_with_txn(txn, VIEWS) {
+      let mut views = views
+        .iter(txn)
+        .flat_map(|(_, value)| value.to_ymap())
+        .map(|map_ref| {
+          let id = map_ref.get_str_with_txn(txn, VIEW_ID).unwrap_or_default();
+          (id, map_ref)
+        })
+        .collect::<Vec<_>>();
+      views.sort_by(|a, b| a.0.cmp(&b.0));
+
+      for (_, map_ref) in views {
+        if let Some(name) = map_ref.get_str_with_txn(txn, VIEW_NAME) {
+          lines.push(name);
+        }
+        if let Some(desc) = map_ref.get_str_with_txn(txn, VIEW_DESC) {
+          if !desc.is_empty() {
+            lines.push(desc);
+          }
+        }
+      }
+    }
+
+    Some(lines.join("\n"))
+  }
+}
@@ -0,0 +1,49 @@
+use collab::preclude::{CollabSchema, MapSchema, SchemaType};
+
+use crate::core::{
+  CURRENT_VIEW, CURRENT_WORKSPACE, FOLDER, META, TRASH, VIEWS, VIEW_COVER_URL, VIEW_CREATE_AT,
+  VIEW_DATABASE_ID, VIEW_DESC, VIEW_ICON_URL, VIEW_ID, VIEW_LAYOUT, VIEW_NAME, VIEW_PARENT_ID,
+  VIEW_RELATION, WORKSPACES, WORKSPACE_CREATED_AT, WORKSPACE_ID, WORKSPACE_NAME,
+};
+
+pub const FOLDER_COLLAB_TYPE: &str = "folder";
+
+/// The schema of the folder collab. It's used to validate the remote updates by the
+/// `SchemaValidationPlugin` of the collab-plugins.
+pub fn folder_schema() -> CollabSchema {
+  let workspace = MapSchema::new()
+    .required(WORKSPACE_ID, SchemaType::String)
+    .optional(WORKSPACE_NAME, SchemaType::String)
+    .optional(WORKSPACE_CREATED_AT, SchemaType::Integer);
+
+  // The view without the id, the parent id or the layout is dropped by view_from_map_ref.
+  let view = MapSchema::new()
+    .required(VIEW_ID, SchemaType::String)
+    .required(VIEW_PARENT_ID, SchemaType::String)
+    .required(VIEW_LAYOUT, SchemaType::Integer)
+    .optional(VIEW_NAME, SchemaType::String)
+    .optional(VIEW_DESC, SchemaType::String)
+    .optional(VIEW_DATABASE_ID, SchemaType::String)
+    .optional(VIEW_CREATE_AT, SchemaType::Integer)
+    .optional(VIEW_ICON_URL, SchemaType::String)
+    .optional(VIEW_COVER_URL, SchemaType::String);
+
+  let meta = MapSchema::new()
+    .optional(CURRENT_WORKSPACE, SchemaType::String)
+    .optional(CURRENT_VIEW, SchemaType::String);
+
+  let folder = MapSchema::new()
+    .required(WORKSPACES, SchemaType::array(workspace.into()))
+    .required(VIEWS, SchemaType::map_of(view.into()))
+    .required(TRASH, SchemaType::array(SchemaType::Any))
+    .required(META, meta)
+    .required(
+      VIEW_RELATION,
+      SchemaType::map_of(SchemaType::array(SchemaType::Any)),
+    );
+
+  CollabSchema::new(
+    FOLDER_COLLAB_TYPE,
+    MapSchema::new().required(FOLDER, folder),
+  )
+}
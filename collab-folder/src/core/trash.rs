@@ -5,6 +5,7 @@ use collab::preclude::array::ArrayEvent;
 use collab::preclude::{
   lib0Any, Array, ArrayRefWrapper, ReadTxn, Subscription, TransactionMut, Value, YrsValue,
 };
+use collab::serde::from_any;
 use serde::{Deserialize, Serialize};
 use std::rc::Rc;
 use std::sync::Arc;
@@ -128,9 +129,7 @@ pub struct TrashRecord {
 
 impl From<lib0Any> for TrashRecord {
   fn from(any: lib0Any) -> Self {
-    let mut json = String::new();
-    any.to_json(&mut json);
-    serde_json::from_str(&json).unwrap()
+    from_any(&any).unwrap()
   }
 }
 
@@ -14,15 +14,15 @@ use crate::core::folder_observe::ViewChangeSender;
 use crate::core::{subscribe_view_change, RepeatedViewIdentifier, ViewIdentifier, ViewRelations};
 use crate::{impl_any_update, impl_i64_update, impl_option_str_update, impl_str_update};
 
-const VIEW_ID: &str = "id";
-const VIEW_NAME: &str = "name";
-const VIEW_PARENT_ID: &str = "bid";
-const VIEW_DESC: &str = "desc";
-const VIEW_DATABASE_ID: &str = "database_id";
-const VIEW_LAYOUT: &str = "layout";
-const VIEW_CREATE_AT: &str = "created_at";
-const VIEW_ICON_URL: &str = "icon_url";
-const VIEW_COVER_URL: &str = "cover_url";
+pub(crate) const VIEW_ID: &str = "id";
+pub(crate) const VIEW_NAME: &str = "name";
+pub(crate) const VIEW_PARENT_ID: &str = "bid";
+pub(crate) const VIEW_DESC: &str = "desc";
+pub(crate) const VIEW_DATABASE_ID: &str = "database_id";
+pub(crate) const VIEW_LAYOUT: &str = "layout";
+pub(crate) const VIEW_CREATE_AT: &str = "created_at";
+pub(crate) const VIEW_ICON_URL: &str = "icon_url";
+pub(crate) const VIEW_COVER_URL: &str = "cover_url";
 
 pub struct ViewsMap {
   container: MapRefWrapper,
@@ -11,9 +11,9 @@ pub struct WorkspaceMap {
   view_relations: Rc<ViewRelations>,
 }
 
-const WORKSPACE_ID: &str = "id";
-const WORKSPACE_NAME: &str = "name";
-const WORKSPACE_CREATED_AT: &str = "created_at";
+pub(crate) const WORKSPACE_ID: &str = "id";
+pub(crate) const WORKSPACE_NAME: &str = "name";
+pub(crate) const WORKSPACE_CREATED_AT: &str = "created_at";
 
 impl WorkspaceMap {
   pub fn new(container: MapRefWrapper, view_relations: Rc<ViewRelations>) -> Self {
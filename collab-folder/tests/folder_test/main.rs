@@ -1,5 +1,6 @@
 mod child_views_test;
 mod load_disk;
+mod schema_test;
 mod serde_test;
 mod trash_test;
 mod util;
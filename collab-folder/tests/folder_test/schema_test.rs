@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use collab::preclude::{CollabBuilder, Transact};
+use collab_folder::core::{folder_schema, Folder, FolderContext, Workspace};
+
+use crate::util::make_test_view;
+
+#[test]
+fn folder_matches_schema_test() {
+  let collab = Arc::new(CollabBuilder::new(1, "1").build());
+  collab.initial();
+  let (view_change_tx, _) = tokio::sync::broadcast::channel(100);
+  let (trash_change_tx, _) = tokio::sync::broadcast::channel(100);
+  let folder = Folder::get_or_create(
+    collab.clone(),
+    FolderContext {
+      view_change_tx,
+      trash_change_tx,
+    },
+  );
+  folder.workspaces.create_workspace(Workspace {
+    id: "w1".to_string(),
+    name: "My first workspace".to_string(),
+    child_views: Default::default(),
+    created_at: 123,
+  });
+  folder.set_current_workspace("w1");
+  folder.insert_view(make_test_view("v1", "w1", vec![]));
+  folder.add_trash(vec!["v1".to_string()]);
+
+  let collab = collab.lock();
+  let violations = folder_schema().validate(&collab.transact());
+  assert!(violations.is_empty(), "{:?}", violations);
+}
@@ -0,0 +1,51 @@
+use std::fmt::Debug;
+
+use crate::keys::make_branch_meta_key;
+use crate::kv::KVStore;
+use crate::PersistenceError;
+
+impl<'a, T> BranchAction<'a> for T
+where
+  T: KVStore<'a>,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+/// The [BranchAction] stores the meta data of a branch. The content of the branch is stored
+/// as a regular document under the branch's own object id, see [YrsDocAction](crate::doc::YrsDocAction).
+pub trait BranchAction<'a>: KVStore<'a> + Sized
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Insert or replace the meta data of the given branch.
+  fn insert_branch_meta<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    branch_id: &K,
+    meta: &[u8],
+  ) -> Result<(), PersistenceError> {
+    tracing::trace!("[🦀Collab] => insert branch meta for {:?}", branch_id);
+    let key = make_branch_meta_key(&uid.to_be_bytes(), branch_id.as_ref());
+    self.insert(key, meta)?;
+    Ok(())
+  }
+
+  /// Return the meta data of the given branch.
+  fn get_branch_meta<K: AsRef<[u8]> + ?Sized>(&self, uid: i64, branch_id: &K) -> Option<Vec<u8>> {
+    let key = make_branch_meta_key(&uid.to_be_bytes(), branch_id.as_ref());
+    let value = self.get(key).ok()??;
+    Some(value.as_ref().to_vec())
+  }
+
+  /// Delete the meta data of the given branch. The content of the branch should be deleted by
+  /// calling [YrsDocAction::delete_doc](crate::doc::YrsDocAction::delete_doc).
+  fn delete_branch_meta<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    branch_id: &K,
+  ) -> Result<(), PersistenceError> {
+    let key = make_branch_meta_key(&uid.to_be_bytes(), branch_id.as_ref());
+    self.remove(key.as_ref())?;
+    Ok(())
+  }
+}
@@ -0,0 +1,53 @@
+use collab::core::bundle::{BundleOptions, CollabBundle};
+use yrs::{Doc, Transact};
+
+use crate::doc::YrsDocAction;
+use crate::kv::rocks_kv::RocksCollabDB;
+use crate::{PersistenceError, TransactionMutExt};
+
+impl RocksCollabDB {
+  /// Export the document that is stored on disk as the bytes of a [CollabBundle] file, without
+  /// opening the collab object.
+  pub fn export_bundle(
+    &self,
+    uid: i64,
+    object_id: &str,
+    options: BundleOptions,
+  ) -> Result<Vec<u8>, PersistenceError> {
+    let doc = Doc::new();
+    {
+      let mut txn = doc.transact_mut();
+      self.read_txn().load_doc(uid, object_id, &mut txn)?;
+    }
+    let txn = doc.transact();
+    Ok(CollabBundle::from_txn(&txn, object_id, options).encode()?)
+  }
+
+  /// Import the bytes of a [CollabBundle] file into the document that is stored on disk. The
+  /// document is created if it doesn't exist, otherwise the state of the bundle is merged into
+  /// it. The bundle is rejected if its checksum doesn't match or it belongs to another object.
+  pub fn import_bundle(
+    &self,
+    uid: i64,
+    object_id: &str,
+    data: &[u8],
+  ) -> Result<CollabBundle, PersistenceError> {
+    let bundle = CollabBundle::decode(data)?;
+    bundle.validate_object_id(object_id)?;
+    let update = bundle.decode_state()?;
+
+    self.with_write_txn(|w_db_txn| {
+      let doc = Doc::new();
+      let mut txn = doc.transact_mut();
+      if w_db_txn.is_exist(uid, object_id) {
+        w_db_txn.load_doc(uid, object_id, &mut txn)?;
+        txn.try_apply_update(update)?;
+        w_db_txn.flush_doc(uid, object_id, &txn)
+      } else {
+        txn.try_apply_update(update)?;
+        w_db_txn.create_new_doc(uid, object_id, &txn)
+      }
+    })?;
+    Ok(bundle)
+  }
+}
@@ -3,22 +3,26 @@ use std::io::Write;
 use std::panic;
 use std::panic::AssertUnwindSafe;
 
+use collab::core::encoding::EncoderVersion;
 use smallvec::SmallVec;
 use yrs::{TransactionMut, Update};
 
 use crate::error::PersistenceError;
 use crate::keys::{
-  clock_from_key, make_doc_update_key, make_snapshot_update_key, Clock, DocID, Key, SnapshotID,
+  clock_from_key, make_doc_update_key_with_version, make_snapshot_update_key_with_version, Clock,
+  DocID, Key, SnapshotID,
 };
 use crate::kv::{KVEntry, KVStore};
 use crate::oid::{DOC_ID_LEN, LOCAL_DOC_ID_GEN, OID};
 use crate::snapshot::CollabSnapshot;
 
+/// Insert the snapshot data. The [EncoderVersion] of the data is recorded in the key.
 pub fn insert_snapshot_update<'a, K, S>(
   store: &S,
   snapshot_id: SnapshotID,
   object_id: &K,
   data: Vec<u8>,
+  version: EncoderVersion,
 ) -> Result<(), PersistenceError>
 where
   K: AsRef<[u8]> + ?Sized + Debug,
@@ -26,23 +30,29 @@ where
   PersistenceError: From<<S as KVStore<'a>>::Error>,
 {
   let snapshot = CollabSnapshot::new(data).to_vec();
-  let update_key = create_update_key(snapshot_id, store, object_id, make_snapshot_update_key)?;
+  let update_key = create_update_key(snapshot_id, store, object_id, |id, clock| {
+    make_snapshot_update_key_with_version(id, clock, version)
+  })?;
   store.insert(update_key, snapshot)?;
   Ok(())
 }
 
+/// Insert the update. The [EncoderVersion] of the update is recorded in the key.
 pub fn insert_doc_update<'a, K, S>(
   db: &S,
   doc_id: DocID,
   object_id: &K,
   value: Vec<u8>,
+  version: EncoderVersion,
 ) -> Result<Vec<u8>, PersistenceError>
 where
   K: AsRef<[u8]> + ?Sized + Debug,
   S: KVStore<'a>,
   PersistenceError: From<<S as KVStore<'a>>::Error>,
 {
-  let update_key = create_update_key(doc_id, db, object_id, make_doc_update_key)?;
+  let update_key = create_update_key(doc_id, db, object_id, |id, clock| {
+    make_doc_update_key_with_version(id, clock, version)
+  })?;
   if let Ok(Some(_)) = db.get(update_key.as_ref()) {
     // The duplicate key might corrupt the document data when restoring from the disk,
     // So we return an error here.
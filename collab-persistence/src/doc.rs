@@ -1,13 +1,14 @@
 use std::fmt::Debug;
 
-use yrs::updates::decoder::Decode;
+use collab::core::encoding::EncoderVersion;
 use yrs::updates::encoder::Encode;
 use yrs::{ReadTxn, StateVector, TransactionMut, Update};
 
 use crate::keys::{
-  doc_name_from_key, make_doc_end_key, make_doc_id_key, make_doc_start_key, make_doc_state_key,
-  make_doc_update_key, make_state_vector_key, Clock, DocID, Key, DOC_SPACE, DOC_SPACE_OBJECT,
-  DOC_SPACE_OBJECT_KEY,
+  clock_from_key, doc_name_from_key, encoder_version_from_key, make_compaction_point_key,
+  make_doc_end_key, make_doc_id_key, make_doc_start_key, make_doc_state_key,
+  make_doc_state_key_with_version, make_doc_update_key, make_doc_update_key_with_version,
+  make_state_vector_key, Clock, DocID, Key, DOC_SPACE, DOC_SPACE_OBJECT, DOC_SPACE_OBJECT_KEY,
 };
 use crate::kv::KVEntry;
 use crate::kv::KVStore;
@@ -34,6 +35,18 @@ where
     uid: i64,
     object_id: &K,
     txn: &T,
+  ) -> Result<(), PersistenceError> {
+    self.create_new_doc_with_version(uid, object_id, txn, EncoderVersion::V1)
+  }
+
+  /// Same as [YrsDocAction::create_new_doc], but the document state is encoded with the given
+  /// [EncoderVersion].
+  fn create_new_doc_with_version<K: AsRef<[u8]> + ?Sized + Debug, T: ReadTxn>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    txn: &T,
+    version: EncoderVersion,
   ) -> Result<(), PersistenceError> {
     if self.is_exist(uid, object_id) {
       tracing::warn!("🟡{:?} already exist", object_id);
@@ -45,9 +58,9 @@ where
       object_id,
       doc_id
     );
-    let doc_state = txn.encode_diff_v1(&StateVector::default());
+    let doc_state = version.encode_state_as_update(txn, &StateVector::default());
     let sv = txn.state_vector().encode_v1();
-    let doc_state_key = make_doc_state_key(doc_id);
+    let doc_state_key = make_doc_state_key_with_version(doc_id, version);
     let sv_key = make_state_vector_key(doc_id);
 
     tracing::trace!(
@@ -70,16 +83,35 @@ where
     uid: i64,
     object_id: &K,
     txn: &T,
+  ) -> Result<(), PersistenceError> {
+    self.flush_doc_with_version(uid, object_id, txn, EncoderVersion::V1)
+  }
+
+  /// Same as [YrsDocAction::flush_doc], but the document state is encoded with the given
+  /// [EncoderVersion]. The state that was stored with the other version is removed, so flushing
+  /// also migrates the document to the given version.
+  fn flush_doc_with_version<K: AsRef<[u8]> + ?Sized + Debug, T: ReadTxn>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    txn: &T,
+    version: EncoderVersion,
   ) -> Result<(), PersistenceError> {
     let doc_id = get_or_create_did(uid, self, object_id)?;
     tracing::trace!("[🦀Collab] => [{}:{:?}]: flush doc", doc_id, object_id);
 
-    let doc_state = txn.encode_state_as_update_v1(&StateVector::default());
+    let doc_state = version.encode_state_as_update(txn, &StateVector::default());
     let sv = txn.state_vector().encode_v1();
 
-    let doc_state_key = make_doc_state_key(doc_id);
+    let doc_state_key = make_doc_state_key_with_version(doc_id, version);
     let sv_key = make_state_vector_key(doc_id);
 
+    // Remove the document states, the state vector and the updates. The range starts at the
+    // document state key, so it must be removed before the new state is inserted.
+    let start = make_doc_start_key(doc_id);
+    let end = make_doc_end_key(doc_id);
+    self.remove_range(start.as_ref(), end.as_ref())?;
+
     tracing::trace!(
       "[🦀Collab] => [{}:{:?}] insert doc state: {:?} : {}",
       doc_id,
@@ -91,11 +123,65 @@ where
     self.insert(doc_state_key, doc_state)?;
     self.insert(sv_key, sv)?;
 
-    // Remove the updates
-    let start = make_doc_start_key(doc_id);
-    let end = make_doc_end_key(doc_id);
-    self.remove_range(start.as_ref(), end.as_ref())?;
+    Ok(())
+  }
+
+  /// Re-encode the stored state and updates of the document with the given [EncoderVersion].
+  /// The data that is already encoded with the version is not changed.
+  fn migrate_doc<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    version: EncoderVersion,
+  ) -> Result<(), PersistenceError> {
+    let doc_id = get_doc_id(uid, self, object_id).ok_or(PersistenceError::DocumentNotExist)?;
+    tracing::trace!(
+      "[🦀Collab] => [{}:{:?}]: migrate doc to {:?}",
+      doc_id,
+      object_id,
+      version
+    );
 
+    // Migrate the document state
+    let mut doc_states = vec![];
+    for state_version in [EncoderVersion::V1, EncoderVersion::V2] {
+      let doc_state_key = make_doc_state_key_with_version(doc_id, state_version);
+      if let Some(doc_state) = self.get(doc_state_key.as_ref())? {
+        doc_states.push(state_version.convert_update(doc_state.as_ref(), version)?);
+        self.remove(doc_state_key.as_ref())?;
+      }
+    }
+    if !doc_states.is_empty() {
+      let doc_states = doc_states
+        .iter()
+        .map(|state| state.as_slice())
+        .collect::<Vec<_>>();
+      let doc_state = version.merge_updates(&doc_states)?;
+      self.insert(make_doc_state_key_with_version(doc_id, version), doc_state)?;
+    }
+
+    // Migrate the updates. The clock of each update is kept, so the order is not changed.
+    let start = make_doc_update_key(doc_id, 0);
+    let end = make_doc_update_key(doc_id, Clock::MAX);
+    let updates = self
+      .range(start.as_ref()..end.as_ref())?
+      .filter(|entry| encoder_version_from_key(entry.key()) != version)
+      .map(|entry| (entry.key().to_vec(), entry.value().to_vec()))
+      .collect::<Vec<_>>();
+    for (key, value) in updates {
+      let update = encoder_version_from_key(&key).convert_update(&value, version)?;
+      let clock = clock_from_key(&key)
+        .try_into()
+        .map(Clock::from_be_bytes)
+        .map_err(|_| {
+          PersistenceError::InvalidData(format!("invalid clock of the update key: {:?}", key))
+        })?;
+      self.remove(&key)?;
+      self.insert(
+        make_doc_update_key_with_version(doc_id, clock, version),
+        update,
+      )?;
+    }
     Ok(())
   }
 
@@ -118,14 +204,24 @@ where
     let mut update_count = 0;
 
     if let Some(doc_id) = get_doc_id(uid, self, object_id) {
-      let doc_state_key = make_doc_state_key(doc_id);
-      if let Some(doc_state) = self.get(doc_state_key.as_ref())? {
+      // The document state is stored with the version it was encoded with.
+      let mut doc_states = vec![];
+      for version in [EncoderVersion::V1, EncoderVersion::V2] {
+        let doc_state_key = make_doc_state_key_with_version(doc_id, version);
+        if let Some(doc_state) = self.get(doc_state_key.as_ref())? {
+          doc_states.push((version, doc_state));
+        }
+      }
+      if !doc_states.is_empty() {
         // Load the doc state
-        if let Err(e) = Update::decode_v1(doc_state.as_ref())
-          .map_err(PersistenceError::Yrs)
-          .and_then(|update| txn.try_apply_update(update))
-        {
-          tracing::error!("🔴{:?} apply doc state error: {}", object_id, e)
+        for (version, doc_state) in doc_states {
+          if let Err(e) = version
+            .decode_update(doc_state.as_ref())
+            .map_err(PersistenceError::Yrs)
+            .and_then(|update| txn.try_apply_update(update))
+          {
+            tracing::error!("🔴{:?} apply doc state error: {}", object_id, e)
+          }
         }
 
         // If the enable_snapshot is true, we will try to load the snapshot.
@@ -144,7 +240,8 @@ where
         for encoded_update in encoded_updates {
           // Decode the update and apply it to the transaction. If the update is invalid, we will
           // remove the update and the following updates.
-          if let Err(e) = Update::decode_v1(encoded_update.value())
+          if let Err(e) = encoder_version_from_key(encoded_update.key())
+            .decode_update(encoded_update.value())
             .map_err(PersistenceError::Yrs)
             .and_then(|update| txn.try_apply_update(update))
           {
@@ -204,12 +301,24 @@ where
   //   Some(snapshot.update_key)
   // }
 
-  /// Push an update to the persistence
+  /// Push an update that is encoded with v1 to the persistence
   fn push_update<K: AsRef<[u8]> + ?Sized + Debug>(
     &self,
     uid: i64,
     object_id: &K,
     update: &[u8],
+  ) -> Result<Vec<u8>, PersistenceError> {
+    self.push_update_with_version(uid, object_id, update, EncoderVersion::V1)
+  }
+
+  /// Push an update that is encoded with the given [EncoderVersion] to the persistence. The
+  /// version is recorded in the key of the update.
+  fn push_update_with_version<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    update: &[u8],
+    version: EncoderVersion,
   ) -> Result<Vec<u8>, PersistenceError> {
     match get_doc_id(uid, self, object_id.as_ref()) {
       None => {
@@ -219,7 +328,7 @@ where
         );
         Err(PersistenceError::DocumentNotExist)
       },
-      Some(doc_id) => insert_doc_update(self, doc_id, object_id, update.to_vec()),
+      Some(doc_id) => insert_doc_update(self, doc_id, object_id, update.to_vec(), version),
     }
   }
 
@@ -256,16 +365,45 @@ where
 
       // Delete the document state and the state vector
       let doc_state_key = make_doc_state_key(did);
+      let doc_state_v2_key = make_doc_state_key_with_version(did, EncoderVersion::V2);
       let sv_key = make_state_vector_key(did);
       let _ = self.remove(doc_state_key.as_ref());
+      let _ = self.remove(doc_state_v2_key.as_ref());
       let _ = self.remove(sv_key.as_ref());
 
       // Delete the snapshot
       self.delete_all_snapshots(uid, object_id)?;
     }
+    let compaction_point_key = make_compaction_point_key(&uid.to_be_bytes(), object_id.as_ref());
+    let _ = self.remove(compaction_point_key.as_ref());
+    Ok(())
+  }
+
+  /// Insert or replace the encoded compaction point of the given document. The point is kept
+  /// next to the document so that it survives a restart.
+  fn insert_compaction_point<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    point: &[u8],
+  ) -> Result<(), PersistenceError> {
+    tracing::trace!("[🦀Collab] => insert compaction point for {:?}", object_id);
+    let key = make_compaction_point_key(&uid.to_be_bytes(), object_id.as_ref());
+    self.insert(key, point)?;
     Ok(())
   }
 
+  /// Return the encoded compaction point of the given document.
+  fn get_compaction_point<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    object_id: &K,
+  ) -> Option<Vec<u8>> {
+    let key = make_compaction_point_key(&uid.to_be_bytes(), object_id.as_ref());
+    let value = self.get(key).ok()??;
+    Some(value.as_ref().to_vec())
+  }
+
   fn get_all_docs(
     &self,
   ) -> Result<NameIter<<Self as KVStore<'a>>::Range, <Self as KVStore<'a>>::Entry>, PersistenceError>
@@ -276,7 +414,8 @@ where
     Ok(NameIter { iter })
   }
 
-  /// Return all the updates for the given document
+  /// Return all the updates for the given document. Each update is decoded with the
+  /// [EncoderVersion] that is recorded in its key.
   fn get_decoded_v1_updates<K: AsRef<[u8]> + ?Sized>(
     &self,
     uid: i64,
@@ -289,7 +428,8 @@ where
       let mut updates = vec![];
       if let Ok(encoded_updates) = self.range(start.as_ref()..=end.as_ref()) {
         for encoded_update in encoded_updates {
-          updates.push(Update::decode_v1(encoded_update.value())?);
+          let version = encoder_version_from_key(encoded_update.key());
+          updates.push(version.decode_update(encoded_update.value())?);
         }
       }
       Ok(updates)
@@ -8,6 +8,10 @@ pub enum PersistenceError {
   #[error(transparent)]
   RocksDb(#[from] rocksdb::Error),
 
+  #[cfg(feature = "sqlite_db")]
+  #[error(transparent)]
+  Sqlite(#[from] rusqlite::Error),
+
   #[error(transparent)]
   Bincode(#[from] bincode::Error),
 
@@ -17,6 +21,9 @@ pub enum PersistenceError {
   #[error(transparent)]
   Yrs(#[from] lib0::error::Error),
 
+  #[error(transparent)]
+  Collab(#[from] collab::error::CollabError),
+
   #[error("invalid data: {0}")]
   InvalidData(String),
 
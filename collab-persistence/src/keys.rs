@@ -1,6 +1,7 @@
 use std::io::Write;
 use std::ops::Deref;
 
+use collab::core::encoding::EncoderVersion;
 use smallvec::{smallvec, SmallVec};
 
 // https://github.com/spacejam/sled
@@ -16,11 +17,21 @@ use smallvec::{smallvec, SmallVec};
 //     DOC_SPACE_OBJECT_KEY     doc_id      DOC_STATE (state start)
 //     DOC_SPACE_OBJECT_KEY     doc_id      TERMINATOR_HI_WATERMARK (state end)
 //     DOC_SPACE_OBJECT_KEY     doc_id      DOC_STATE_VEC (state vector)
-//     DOC_SPACE_OBJECT_KEY     doc_id      DOC_UPDATE clock TERMINATOR (update)
+//     DOC_SPACE_OBJECT_KEY     doc_id      DOC_UPDATE clock ENCODING (update)
+//     DOC_SPACE_OBJECT_KEY     doc_id      DOC_STATE_V2 (state encoded with v2)
 //
 // SNAPSHOT_SPACE
 //     SNAPSHOT_SPACE_OBJECT        object_id       TERMINATOR
-//     SNAPSHOT_SPACE_OBJECT_KEY    snapshot_id     SNAPSHOT_UPDATE(snapshot)
+//     SNAPSHOT_SPACE_OBJECT_KEY    snapshot_id     SNAPSHOT_UPDATE clock ENCODING (snapshot)
+//
+// The ENCODING is the last byte of the update and snapshot keys. It's the TERMINATOR for the
+// data that is encoded with v1, which includes all the data written before v2 was supported.
+//
+// BRANCH_SPACE
+//     BRANCH_SPACE_OBJECT          uid     object_id       TERMINATOR (branch meta)
+//
+// COMPACTION_SPACE
+//     COMPACTION_SPACE_OBJECT      uid     object_id       TERMINATOR (compaction point)
 
 /// Prefix byte used for all of the yrs object entries.
 pub const DOC_SPACE: u8 = 1;
@@ -45,6 +56,15 @@ pub const REMOTE_DOC_STATE_VEC: u8 = 2;
 /// Tag byte within [DOC_SPACE_OBJECT_KEY] used to identify object's update entries.
 pub const DOC_UPDATE: u8 = 2;
 
+/// Tag byte within [DOC_SPACE_OBJECT_KEY] used to identify object's state entry that is encoded
+/// with [EncoderVersion::V2]. The v1 state is stored with [DOC_STATE].
+pub const DOC_STATE_V2: u8 = 3;
+
+/// The last byte of the update and snapshot keys for the data encoded with [EncoderVersion::V1].
+pub const ENCODING_V1: u8 = TERMINATOR;
+/// The last byte of the update and snapshot keys for the data encoded with [EncoderVersion::V2].
+pub const ENCODING_V2: u8 = 2;
+
 /// Prefix byte used for snapshot id -> [SnapshotID] mapping index key space.
 pub const SNAPSHOT_SPACE: u8 = 2;
 
@@ -57,6 +77,16 @@ pub const SNAPSHOT_UPDATE: u8 = 1;
 pub const COLLAB_SPACE: u8 = 3;
 pub const COLLAB_SPACE_OBJECT: u8 = 0;
 
+/// Prefix byte used for the branch meta key space.
+pub const BRANCH_SPACE: u8 = 4;
+/// Tag byte within [BRANCH_SPACE] used to identify the branch meta entries.
+pub const BRANCH_SPACE_OBJECT: u8 = 0;
+
+/// Prefix byte used for the compaction point key space.
+pub const COMPACTION_SPACE: u8 = 5;
+/// Tag byte within [COMPACTION_SPACE] used to identify the compaction point entries.
+pub const COMPACTION_SPACE_OBJECT: u8 = 0;
+
 pub type DocID = u64;
 pub const DOC_ID_LEN: usize = 8;
 pub const DOC_STATE_KEY_LEN: usize = DOC_ID_LEN + 4;
@@ -103,6 +133,25 @@ pub fn make_doc_end_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
   Key(v)
 }
 
+// [1,1,  0,0,0,0,0,0,0,0,  3]
+pub fn make_doc_state_v2_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
+  let mut v: SmallVec<[u8; DOC_STATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
+  v.write_all(&doc_id.to_be_bytes()).unwrap();
+  v.push(DOC_STATE_V2);
+  Key(v)
+}
+
+/// Returns the key of the doc state that is encoded with the given version.
+pub fn make_doc_state_key_with_version(
+  doc_id: DocID,
+  version: EncoderVersion,
+) -> Key<DOC_STATE_KEY_LEN> {
+  match version {
+    EncoderVersion::V1 => make_doc_state_key(doc_id),
+    EncoderVersion::V2 => make_doc_state_v2_key(doc_id),
+  }
+}
+
 // [1,1,  0,0,0,0,0,0,0,0,  1]
 pub fn make_state_vector_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
   let mut v: SmallVec<[u8; DOC_STATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
@@ -121,11 +170,20 @@ pub fn make_remote_state_vector_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
 
 // [1,1,  0,0,0,0,0,0,0,0,  2   0,0,0,0,  0]
 pub fn make_doc_update_key(doc_id: DocID, clock: Clock) -> Key<DOC_UPDATE_KEY_LEN> {
+  make_doc_update_key_with_version(doc_id, clock, EncoderVersion::V1)
+}
+
+// [1,1,  0,0,0,0,0,0,0,0,  2   0,0,0,0,  ENCODING]
+pub fn make_doc_update_key_with_version(
+  doc_id: DocID,
+  clock: Clock,
+  version: EncoderVersion,
+) -> Key<DOC_UPDATE_KEY_LEN> {
   let mut v: SmallVec<[u8; DOC_UPDATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
   v.write_all(&doc_id.to_be_bytes()).unwrap();
   v.push(DOC_UPDATE);
   v.write_all(&clock.to_be_bytes()).unwrap();
-  v.push(TERMINATOR);
+  v.push(encoding_tag(version));
   Key(v)
 }
 
@@ -156,16 +214,40 @@ pub fn make_snapshot_id_key(uid: &[u8], object_id: &[u8]) -> Key<20> {
 pub fn make_snapshot_update_key(
   snapshot_id: SnapshotID,
   clock: Clock,
+) -> Key<SNAPSHOT_UPDATE_KEY_LEN> {
+  make_snapshot_update_key_with_version(snapshot_id, clock, EncoderVersion::V1)
+}
+
+// [10,0,  0,0,0,0,0,0,0,0,  1   [0,0,0,0],  ENCODING]
+pub fn make_snapshot_update_key_with_version(
+  snapshot_id: SnapshotID,
+  clock: Clock,
+  version: EncoderVersion,
 ) -> Key<SNAPSHOT_UPDATE_KEY_LEN> {
   let mut v: SmallVec<[u8; SNAPSHOT_UPDATE_KEY_LEN]> =
     smallvec![SNAPSHOT_SPACE, SNAPSHOT_SPACE_OBJECT];
   v.write_all(&snapshot_id.to_be_bytes()).unwrap();
   v.push(SNAPSHOT_UPDATE);
   v.write_all(&clock.to_be_bytes()).unwrap();
-  v.push(TERMINATOR);
+  v.push(encoding_tag(version));
   Key(v)
 }
 
+pub fn encoding_tag(version: EncoderVersion) -> u8 {
+  match version {
+    EncoderVersion::V1 => ENCODING_V1,
+    EncoderVersion::V2 => ENCODING_V2,
+  }
+}
+
+/// Returns the [EncoderVersion] of the value of the given update or snapshot key.
+pub fn encoder_version_from_key(key: &[u8]) -> EncoderVersion {
+  match key.last() {
+    Some(&ENCODING_V2) => EncoderVersion::V2,
+    _ => EncoderVersion::V1,
+  }
+}
+
 pub fn make_snapshot_update_key_prefix(
   snapshot_id: SnapshotID,
 ) -> Key<SNAPSHOT_UPDATE_KEY_PREFIX_LEN> {
@@ -183,6 +265,24 @@ pub fn make_collab_id_key(object_id: &[u8]) -> Key<20> {
   Key(v)
 }
 
+// [4,0, uid,  object_id,  0]
+pub fn make_branch_meta_key(uid: &[u8], object_id: &[u8]) -> Key<20> {
+  let mut v: SmallVec<[u8; 20]> = smallvec![BRANCH_SPACE, BRANCH_SPACE_OBJECT];
+  v.write_all(uid).unwrap();
+  v.write_all(object_id).unwrap();
+  v.push(TERMINATOR);
+  Key(v)
+}
+
+// [5,0, uid,  object_id,  0]
+pub fn make_compaction_point_key(uid: &[u8], object_id: &[u8]) -> Key<20> {
+  let mut v: SmallVec<[u8; 20]> = smallvec![COMPACTION_SPACE, COMPACTION_SPACE_OBJECT];
+  v.write_all(uid).unwrap();
+  v.write_all(object_id).unwrap();
+  v.push(TERMINATOR);
+  Key(v)
+}
+
 #[repr(transparent)]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Key<const N: usize>(pub SmallVec<[u8; N]>);
@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::kv::{KVEntry, KVStore};
+use crate::PersistenceError;
+
+pub type MemCollabDB = MemStore;
+
+type MemMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A [KVStore] that keeps all the data in memory. It has the same transactional semantics as
+/// the [RocksStore](crate::kv::rocks_kv::RocksStore):
+/// 1. A transaction reads from the snapshot that is taken when the transaction is created.
+/// 2. The writes of a transaction are only visible to the transaction itself until it's
+/// committed by [MemStore::with_write_txn]. If the closure returns an error, the writes are
+/// discarded. The writes of the [MemStore::read_txn] are never committed.
+///
+/// The write transactions are executed one by one. Cloning the [MemStore] shares the data.
+#[derive(Clone, Default)]
+pub struct MemStore {
+  /// The committed data. The snapshot of a transaction is a clone of the [Arc], the map is only
+  /// copied when it's committed while the snapshot is still alive.
+  db: Arc<RwLock<Arc<MemMap>>>,
+  write_lock: Arc<Mutex<()>>,
+}
+
+impl MemStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Return a read transaction that reads from the snapshot of the current data.
+  pub fn read_txn(&self) -> MemKVStoreImpl {
+    MemKVStoreImpl::new(self.db.read().clone())
+  }
+
+  /// Create a write transaction that accesses the database exclusively.
+  /// The transaction will be committed when the closure [F] returns.
+  pub fn with_write_txn<F, O>(&self, f: F) -> Result<O, PersistenceError>
+  where
+    F: FnOnce(&MemKVStoreImpl) -> Result<O, PersistenceError>,
+  {
+    let _write_guard = self.write_lock.lock();
+    let store = MemKVStoreImpl::new(self.db.read().clone());
+    let result = f(&store)?;
+    store.commit(&self.db);
+    Ok(result)
+  }
+
+  /// Return the number of the committed keys.
+  pub fn len(&self) -> usize {
+    self.db.read().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.db.read().is_empty()
+  }
+}
+
+/// Implementation of [KVStore] for [MemStore]. The writes are kept in `changes` until the
+/// transaction is committed, a `None` value marks the key as removed.
+pub struct MemKVStoreImpl {
+  snapshot: Arc<MemMap>,
+  changes: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+impl MemKVStoreImpl {
+  fn new(snapshot: Arc<MemMap>) -> Self {
+    Self {
+      snapshot,
+      changes: Default::default(),
+    }
+  }
+
+  fn commit(self, db: &RwLock<Arc<MemMap>>) {
+    let changes = self.changes.into_inner();
+    if changes.is_empty() {
+      return;
+    }
+    // Drop the snapshot first, so the map can be updated in place if no one else holds it.
+    drop(self.snapshot);
+    let mut db = db.write();
+    let map = Arc::make_mut(&mut db);
+    for (key, value) in changes {
+      match value {
+        None => map.remove(&key),
+        Some(value) => map.insert(key, value),
+      };
+    }
+  }
+
+  /// Return the entries in the range, the changes of the transaction are merged with the
+  /// snapshot.
+  fn entries(&self, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> Vec<MemEntry> {
+    if is_empty_range(&range) {
+      return vec![];
+    }
+    let changes = self.changes.borrow();
+    let mut entries = self
+      .snapshot
+      .range(range.clone())
+      .filter(|(key, _)| !changes.contains_key(*key))
+      .map(|(key, value)| MemEntry::new(key.clone(), value.clone()))
+      .collect::<Vec<_>>();
+    entries.extend(changes.range(range).filter_map(|(key, value)| {
+      value
+        .as_ref()
+        .map(|value| MemEntry::new(key.clone(), value.clone()))
+    }));
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    entries
+  }
+}
+
+impl KVStore<'static> for MemKVStoreImpl {
+  type Range = MemRange;
+  type Entry = MemEntry;
+  type Value = Vec<u8>;
+  type Error = PersistenceError;
+
+  fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Self::Value>, Self::Error> {
+    let key = key.as_ref();
+    if let Some(value) = self.changes.borrow().get(key) {
+      return Ok(value.clone());
+    }
+    Ok(self.snapshot.get(key).cloned())
+  }
+
+  fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Self::Error> {
+    self
+      .changes
+      .borrow_mut()
+      .insert(key.as_ref().to_vec(), Some(value.as_ref().to_vec()));
+    Ok(())
+  }
+
+  fn remove(&self, key: &[u8]) -> Result<(), Self::Error> {
+    self.changes.borrow_mut().insert(key.to_vec(), None);
+    Ok(())
+  }
+
+  fn remove_range(&self, from: &[u8], to: &[u8]) -> Result<(), Self::Error> {
+    let entries = self.entries((Bound::Included(from.to_vec()), Bound::Excluded(to.to_vec())));
+    let mut changes = self.changes.borrow_mut();
+    for entry in entries {
+      changes.insert(entry.key, None);
+    }
+    Ok(())
+  }
+
+  fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Result<Self::Range, Self::Error> {
+    let entries = self.entries((
+      to_owned_bound(range.start_bound()),
+      to_owned_bound(range.end_bound()),
+    ));
+    Ok(MemRange(entries.into_iter()))
+  }
+
+  /// Return the entry of the given key or the last entry prior to it.
+  fn next_back_entry(&self, key: &[u8]) -> Result<Option<Self::Entry>, Self::Error> {
+    let changes = self.changes.borrow();
+    let snapshot_entry = self
+      .snapshot
+      .range::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+      .rev()
+      .find(|(key, _)| !changes.contains_key(*key));
+    let changed_entry = changes
+      .range::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+      .rev()
+      .find_map(|(key, value)| value.as_ref().map(|value| (key, value)));
+
+    let entry = match (snapshot_entry, changed_entry) {
+      (Some(a), Some(b)) => Some(if a.0 > b.0 { a } else { b }),
+      (a, b) => a.or(b),
+    };
+    Ok(entry.map(|(key, value)| MemEntry::new(key.clone(), value.clone())))
+  }
+}
+
+fn to_owned_bound<K: AsRef<[u8]>>(bound: Bound<&K>) -> Bound<Vec<u8>> {
+  match bound {
+    Bound::Included(key) => Bound::Included(key.as_ref().to_vec()),
+    Bound::Excluded(key) => Bound::Excluded(key.as_ref().to_vec()),
+    Bound::Unbounded => Bound::Unbounded,
+  }
+}
+
+/// [BTreeMap::range] panics if the start is greater than the end, or if they are equal and
+/// both excluded.
+fn is_empty_range(range: &(Bound<Vec<u8>>, Bound<Vec<u8>>)) -> bool {
+  match range {
+    (Bound::Included(start), Bound::Included(end)) => start > end,
+    (Bound::Included(start), Bound::Excluded(end))
+    | (Bound::Excluded(start), Bound::Included(end))
+    | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+    _ => false,
+  }
+}
+
+pub struct MemRange(std::vec::IntoIter<MemEntry>);
+
+impl Iterator for MemRange {
+  type Item = MemEntry;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.next()
+  }
+}
+
+pub struct MemEntry {
+  key: Vec<u8>,
+  value: Vec<u8>,
+}
+
+impl MemEntry {
+  pub fn new(key: Vec<u8>, value: Vec<u8>) -> Self {
+    Self { key, value }
+  }
+}
+
+impl KVEntry for MemEntry {
+  fn key(&self) -> &[u8] {
+    self.key.as_ref()
+  }
+
+  fn value(&self) -> &[u8] {
+    self.value.as_ref()
+  }
+}
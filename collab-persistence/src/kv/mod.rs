@@ -4,12 +4,17 @@ use std::sync::Arc;
 
 use crate::PersistenceError;
 
+pub mod mem_kv;
+
 #[cfg(feature = "rocksdb_db")]
 pub mod rocks_kv;
 
 #[cfg(feature = "sled_db")]
 pub mod sled_lv;
 
+#[cfg(feature = "sqlite_db")]
+pub mod sqlite_kv;
+
 pub trait KVStore<'a> {
   type Range: Iterator<Item = Self::Entry>;
   type Entry: KVEntry;
@@ -0,0 +1,271 @@
+use std::ops::{Bound, Deref, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags, OptionalExtension};
+
+use crate::kv::{KVEntry, KVStore};
+use crate::PersistenceError;
+
+pub type SqliteCollabDB = SqliteStore;
+
+const TABLE_NAME: &str = "collab_kv";
+/// The number of the idle connections that are kept for the next transactions.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+/// How long a transaction waits for the other writer before returning the busy error.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [KVStore] that is backed by a single-file SQLite database. The keys and values are stored
+/// as blobs in one table, and the blobs are compared byte by byte, so the ranges have the same
+/// order as the other stores.
+///
+/// Each transaction uses its own connection. The database is opened in the WAL mode, so the read
+/// transactions don't block the write transaction, and the write transactions are executed one
+/// by one.
+#[derive(Clone)]
+pub struct SqliteStore {
+  path: PathBuf,
+  idle_connections: Arc<Mutex<Vec<Connection>>>,
+}
+
+impl SqliteStore {
+  /// Open the SQLite database at the given path, the file is created if it doesn't exist.
+  pub fn open(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+    let path = path.as_ref().to_path_buf();
+    let conn = open_connection(&path)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.execute_batch(&format!(
+      "CREATE TABLE IF NOT EXISTS {} (key BLOB PRIMARY KEY NOT NULL, value BLOB NOT NULL) WITHOUT ROWID;",
+      TABLE_NAME
+    ))?;
+    Ok(Self {
+      path,
+      idle_connections: Arc::new(Mutex::new(vec![conn])),
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Return a read transaction. The writes of the read transaction are rolled back when it's
+  /// dropped. Unlike the other stores, it returns an error if the connection can't be opened.
+  pub fn read_txn(&self) -> Result<SqliteKVStoreImpl, PersistenceError> {
+    self.begin("BEGIN DEFERRED")
+  }
+
+  /// Create a write transaction that accesses the database exclusively.
+  /// The transaction will be committed when the closure [F] returns. If the closure returns an
+  /// error, the transaction is rolled back.
+  pub fn with_write_txn<F, O>(&self, f: F) -> Result<O, PersistenceError>
+  where
+    F: FnOnce(&SqliteKVStoreImpl) -> Result<O, PersistenceError>,
+  {
+    let store = self.begin("BEGIN IMMEDIATE")?;
+    let result = f(&store)?;
+    store.commit()?;
+    Ok(result)
+  }
+
+  fn begin(&self, sql: &str) -> Result<SqliteKVStoreImpl, PersistenceError> {
+    let conn = match self.idle_connections.lock().pop() {
+      None => open_connection(&self.path)?,
+      Some(conn) => conn,
+    };
+    // The connection is returned to the pool if the transaction can't be started.
+    let mut store = SqliteKVStoreImpl {
+      conn: Some(conn),
+      idle_connections: self.idle_connections.clone(),
+      is_active: false,
+    };
+    store.execute_batch(sql)?;
+    store.is_active = true;
+    Ok(store)
+  }
+}
+
+fn open_connection(path: &Path) -> Result<Connection, PersistenceError> {
+  let conn = Connection::open_with_flags(
+    path,
+    OpenFlags::SQLITE_OPEN_READ_WRITE
+      | OpenFlags::SQLITE_OPEN_CREATE
+      | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+  )?;
+  conn.busy_timeout(BUSY_TIMEOUT)?;
+  Ok(conn)
+}
+
+/// Implementation of [KVStore] for [SqliteStore]. This is a wrapper around a [Connection] with
+/// an active transaction.
+pub struct SqliteKVStoreImpl {
+  conn: Option<Connection>,
+  idle_connections: Arc<Mutex<Vec<Connection>>>,
+  /// True if the transaction is not committed or rolled back yet.
+  is_active: bool,
+}
+
+impl SqliteKVStoreImpl {
+  fn commit(mut self) -> Result<(), PersistenceError> {
+    self.execute_batch("COMMIT")?;
+    self.is_active = false;
+    Ok(())
+  }
+}
+
+impl Deref for SqliteKVStoreImpl {
+  type Target = Connection;
+
+  fn deref(&self) -> &Self::Target {
+    // The connection is only taken when the store is dropped.
+    self.conn.as_ref().unwrap()
+  }
+}
+
+impl Drop for SqliteKVStoreImpl {
+  fn drop(&mut self) {
+    if let Some(conn) = self.conn.take() {
+      if self.is_active {
+        if let Err(e) = conn.execute_batch("ROLLBACK") {
+          tracing::error!("🔴 rollback sqlite transaction failed: {}", e);
+          return;
+        }
+      }
+      let mut idle_connections = self.idle_connections.lock();
+      if idle_connections.len() < MAX_IDLE_CONNECTIONS {
+        idle_connections.push(conn);
+      }
+    }
+  }
+}
+
+impl KVStore<'static> for SqliteKVStoreImpl {
+  type Range = SqliteRange;
+  type Entry = SqliteEntry;
+  type Value = Vec<u8>;
+  type Error = PersistenceError;
+
+  fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Self::Value>, Self::Error> {
+    let value = self
+      .prepare_cached(&format!("SELECT value FROM {} WHERE key = ?1", TABLE_NAME))?
+      .query_row(params![key.as_ref()], |row| row.get(0))
+      .optional()?;
+    Ok(value)
+  }
+
+  fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Self::Error> {
+    self
+      .prepare_cached(&format!(
+        "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+        TABLE_NAME
+      ))?
+      .execute(params![key.as_ref(), value.as_ref()])?;
+    Ok(())
+  }
+
+  fn remove(&self, key: &[u8]) -> Result<(), Self::Error> {
+    self
+      .prepare_cached(&format!("DELETE FROM {} WHERE key = ?1", TABLE_NAME))?
+      .execute(params![key])?;
+    Ok(())
+  }
+
+  fn remove_range(&self, from: &[u8], to: &[u8]) -> Result<(), Self::Error> {
+    self
+      .prepare_cached(&format!(
+        "DELETE FROM {} WHERE key >= ?1 AND key < ?2",
+        TABLE_NAME
+      ))?
+      .execute(params![from, to])?;
+    Ok(())
+  }
+
+  fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Result<Self::Range, Self::Error> {
+    let mut conditions = vec![];
+    let mut values: Vec<&[u8]> = vec![];
+    match range.start_bound() {
+      Bound::Included(start) => {
+        conditions.push("key >= ?");
+        values.push(start.as_ref());
+      },
+      Bound::Excluded(start) => {
+        conditions.push("key > ?");
+        values.push(start.as_ref());
+      },
+      Bound::Unbounded => {},
+    }
+    match range.end_bound() {
+      Bound::Included(end) => {
+        conditions.push("key <= ?");
+        values.push(end.as_ref());
+      },
+      Bound::Excluded(end) => {
+        conditions.push("key < ?");
+        values.push(end.as_ref());
+      },
+      Bound::Unbounded => {},
+    }
+
+    let where_clause = if conditions.is_empty() {
+      String::new()
+    } else {
+      format!("WHERE {}", conditions.join(" AND "))
+    };
+    let mut stmt = self.prepare_cached(&format!(
+      "SELECT key, value FROM {} {} ORDER BY key",
+      TABLE_NAME, where_clause
+    ))?;
+    let entries = stmt
+      .query_map(params_from_iter(values), |row| {
+        Ok(SqliteEntry::new(row.get(0)?, row.get(1)?))
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(SqliteRange(entries.into_iter()))
+  }
+
+  /// Return the entry of the given key or the last entry prior to it.
+  fn next_back_entry(&self, key: &[u8]) -> Result<Option<Self::Entry>, Self::Error> {
+    let entry = self
+      .prepare_cached(&format!(
+        "SELECT key, value FROM {} WHERE key <= ?1 ORDER BY key DESC LIMIT 1",
+        TABLE_NAME
+      ))?
+      .query_row(params![key], |row| {
+        Ok(SqliteEntry::new(row.get(0)?, row.get(1)?))
+      })
+      .optional()?;
+    Ok(entry)
+  }
+}
+
+pub struct SqliteRange(std::vec::IntoIter<SqliteEntry>);
+
+impl Iterator for SqliteRange {
+  type Item = SqliteEntry;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.next()
+  }
+}
+
+pub struct SqliteEntry {
+  key: Vec<u8>,
+  value: Vec<u8>,
+}
+
+impl SqliteEntry {
+  pub fn new(key: Vec<u8>, value: Vec<u8>) -> Self {
+    Self { key, value }
+  }
+}
+
+impl KVEntry for SqliteEntry {
+  fn key(&self) -> &[u8] {
+    self.key.as_ref()
+  }
+
+  fn value(&self) -> &[u8] {
+    self.value.as_ref()
+  }
+}
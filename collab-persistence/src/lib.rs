@@ -2,6 +2,9 @@ pub use db::*;
 pub use error::*;
 pub use range::*;
 
+pub mod branch;
+#[cfg(feature = "rocksdb_db")]
+mod bundle;
 mod db;
 pub mod doc;
 pub mod error;
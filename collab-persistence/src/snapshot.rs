@@ -2,11 +2,14 @@ use std::fmt::Debug;
 use std::panic;
 use std::panic::AssertUnwindSafe;
 
+use collab::core::encoding::EncoderVersion;
 use serde::{Deserialize, Serialize};
-use yrs::updates::encoder::{Encoder, EncoderV1};
-use yrs::{ReadTxn, Snapshot};
+use yrs::updates::encoder::{Encoder, EncoderV1, EncoderV2};
+use yrs::{ReadTxn, Snapshot, Update};
 
-use crate::keys::{make_snapshot_id_key, make_snapshot_update_key, Clock, Key, SnapshotID};
+use crate::keys::{
+  encoder_version_from_key, make_snapshot_id_key, make_snapshot_update_key, Clock, Key, SnapshotID,
+};
 use crate::kv::KVEntry;
 use crate::kv::KVStore;
 use crate::{
@@ -40,7 +43,24 @@ where
     K: AsRef<[u8]> + ?Sized + Debug,
     T: ReadTxn,
   {
-    match try_encode_snapshot(txn, snapshot) {
+    self.create_snapshot_with_version(uid, object_id, txn, snapshot, EncoderVersion::V1)
+  }
+
+  /// Same as [SnapshotAction::create_snapshot], but the data of the snapshot is encoded with the
+  /// given [EncoderVersion].
+  fn create_snapshot_with_version<K, T>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    txn: &T,
+    snapshot: Snapshot,
+    version: EncoderVersion,
+  ) -> Result<(), PersistenceError>
+  where
+    K: AsRef<[u8]> + ?Sized + Debug,
+    T: ReadTxn,
+  {
+    match try_encode_snapshot_with_version(txn, snapshot, version) {
       Ok(data) => {
         if data.is_empty() {
           tracing::warn!("🟡unexpected empty snapshot for object_id: {:?}", object_id);
@@ -48,7 +68,7 @@ where
         }
         tracing::trace!("New snapshot for object:{:?}", object_id);
         let snapshot_id = self.create_snapshot_id(uid, object_id.as_ref())?;
-        insert_snapshot_update(self, snapshot_id, object_id, data)?;
+        insert_snapshot_update(self, snapshot_id, object_id, data, version)?;
       },
       Err(e) => {
         tracing::error!(
@@ -72,7 +92,13 @@ where
   {
     tracing::trace!("New snapshot for object:{:?}", object_id);
     let snapshot_id = self.create_snapshot_id(uid, object_id.as_ref())?;
-    insert_snapshot_update(self, snapshot_id, object_id, snapshot_data)?;
+    insert_snapshot_update(
+      self,
+      snapshot_id,
+      object_id,
+      snapshot_data,
+      EncoderVersion::V1,
+    )?;
     Ok(())
   }
   /// Return list of snapshots for the given object id.
@@ -84,7 +110,8 @@ where
 
       if let Ok(encoded_updates) = self.range(start.as_ref()..=end.as_ref()) {
         for encoded_snapshot in encoded_updates {
-          if let Ok(snapshot) = CollabSnapshot::try_from(encoded_snapshot.value()) {
+          if let Ok(mut snapshot) = CollabSnapshot::try_from(encoded_snapshot.value()) {
+            snapshot.encoder_version = encoder_version_from_key(encoded_snapshot.key());
             snapshots.push(snapshot);
           }
         }
@@ -94,14 +121,10 @@ where
   }
 
   fn get_last_snapshot_by_snapshot_id(&self, snapshot_id: SnapshotID) -> Option<CollabSnapshot> {
-    let last_update_key = self.get_snapshot_last_update_key(snapshot_id)?;
-    self.get(last_update_key.as_ref()).ok()?.and_then(|value| {
-      if let Ok(snapshot) = CollabSnapshot::try_from(value.as_ref()) {
-        Some(snapshot)
-      } else {
-        None
-      }
-    })
+    let entry = self.get_snapshot_last_entry(snapshot_id)?;
+    let mut snapshot = CollabSnapshot::try_from(entry.value()).ok()?;
+    snapshot.encoder_version = encoder_version_from_key(entry.key());
+    Some(snapshot)
   }
 
   fn get_last_snapshot<K: AsRef<[u8]> + ?Sized>(
@@ -114,8 +137,8 @@ where
   }
 
   fn delete_last_snapshot_by_snapshot_id(&self, snapshot_id: SnapshotID) {
-    if let Some(last_update_key) = self.get_snapshot_last_update_key(snapshot_id) {
-      match self.remove(last_update_key.as_ref()) {
+    if let Some(entry) = self.get_snapshot_last_entry(snapshot_id) {
+      match self.remove(entry.key()) {
         Ok(_) => {},
         Err(e) => {
           tracing::error!("Failed to delete last snapshot update: {:?}", e);
@@ -156,6 +179,19 @@ where
   fn get_snapshot_last_update_key(&self, snapshot_id: SnapshotID) -> Option<Key<16>> {
     get_last_update_key(self, snapshot_id, make_snapshot_update_key).ok()
   }
+
+  /// Return the entry of the last snapshot. The encoding of the snapshot is recorded in the last
+  /// byte of the key, so the key can't be rebuilt from the clock alone.
+  fn get_snapshot_last_entry(&self, snapshot_id: SnapshotID) -> Option<Self::Entry> {
+    let start = make_snapshot_update_key(snapshot_id, 0);
+    let end = make_snapshot_update_key(snapshot_id, Clock::MAX);
+    let entry = self.next_back_entry(end.as_ref()).ok()??;
+    if entry.key() >= start.as_ref() && entry.key() < end.as_ref() {
+      Some(entry)
+    } else {
+      None
+    }
+  }
 }
 
 pub fn get_snapshot_id<'a, K, S>(uid: i64, store: &S, object_id: &K) -> Option<SnapshotID>
@@ -170,17 +206,34 @@ where
 pub fn try_encode_snapshot<T: ReadTxn>(
   txn: &T,
   snapshot: Snapshot,
+) -> Result<Vec<u8>, PersistenceError> {
+  try_encode_snapshot_with_version(txn, snapshot, EncoderVersion::V1)
+}
+
+pub fn try_encode_snapshot_with_version<T: ReadTxn>(
+  txn: &T,
+  snapshot: Snapshot,
+  version: EncoderVersion,
 ) -> Result<Vec<u8>, PersistenceError> {
   let mut encoded_data = vec![];
   match {
     let mut wrapper = AssertUnwindSafe(&mut encoded_data);
     let wrapper_txn = AssertUnwindSafe(txn);
-    panic::catch_unwind(move || {
-      let mut encoder = EncoderV1::new();
-      wrapper_txn
-        .encode_state_from_snapshot(&snapshot, &mut encoder)
-        .unwrap();
-      **wrapper = encoder.to_vec();
+    panic::catch_unwind(move || match version {
+      EncoderVersion::V1 => {
+        let mut encoder = EncoderV1::new();
+        wrapper_txn
+          .encode_state_from_snapshot(&snapshot, &mut encoder)
+          .unwrap();
+        **wrapper = encoder.to_vec();
+      },
+      EncoderVersion::V2 => {
+        let mut encoder = EncoderV2::new();
+        wrapper_txn
+          .encode_state_from_snapshot(&snapshot, &mut encoder)
+          .unwrap();
+        **wrapper = encoder.to_vec();
+      },
     })
   } {
     Ok(_) => Ok(encoded_data),
@@ -192,12 +245,25 @@ pub fn try_encode_snapshot<T: ReadTxn>(
 pub struct CollabSnapshot {
   pub data: Vec<u8>,
   pub created_at: i64,
+  /// The encoding of the [CollabSnapshot::data]. It's recorded in the key of the snapshot
+  /// instead of the value, so the snapshots that were written before stay readable.
+  #[serde(skip)]
+  pub encoder_version: EncoderVersion,
 }
 
 impl CollabSnapshot {
   pub fn new(data: Vec<u8>) -> CollabSnapshot {
     let created_at = chrono::Utc::now().timestamp();
-    Self { data, created_at }
+    Self {
+      data,
+      created_at,
+      encoder_version: EncoderVersion::V1,
+    }
+  }
+
+  /// Decode the data of the snapshot with its [EncoderVersion].
+  pub fn decode_update(&self) -> Result<Update, PersistenceError> {
+    Ok(self.encoder_version.decode_update(&self.data)?)
   }
 
   pub fn to_vec(&self) -> Vec<u8> {
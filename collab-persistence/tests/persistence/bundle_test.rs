@@ -0,0 +1,45 @@
+use collab::core::bundle::BundleOptions;
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::PersistenceError;
+use yrs::{Doc, GetString, Text, Transact};
+
+use crate::util::rocks_db;
+
+#[test]
+fn rocks_bundle_round_trip_test() {
+  let (_path, db) = rocks_db(1);
+  let doc = Doc::new();
+  {
+    let text = doc.get_or_insert_text("text");
+    let mut txn = doc.transact_mut();
+    text.insert(&mut txn, 0, "hello world");
+  }
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, "doc_1", &txn))
+      .unwrap();
+  }
+
+  let data = db
+    .export_bundle(1, "doc_1", BundleOptions::new().collab_type("document"))
+    .unwrap();
+  let (_other_path, other_db) = rocks_db(2);
+  let bundle = other_db.import_bundle(2, "doc_1", &data).unwrap();
+  assert_eq!(bundle.header.collab_type, "document");
+
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    other_db.read_txn().load_doc(2, "doc_1", &mut txn).unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  assert_eq!(text.get_string(&doc.transact()), "hello world");
+
+  // Importing into an existing document merges the state
+  other_db.import_bundle(2, "doc_1", &data).unwrap();
+  assert!(matches!(
+    other_db.import_bundle(2, "doc_2", &data),
+    Err(PersistenceError::Collab(_))
+  ));
+  assert!(!other_db.read_txn().is_exist(2, "doc_2"));
+}
@@ -0,0 +1,111 @@
+use collab::core::encoding::EncoderVersion;
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::kv::rocks_kv::RocksCollabDB;
+use yrs::{Doc, GetString, Text, Transact};
+
+use crate::util::rocks_db;
+
+fn load_text(db: &RocksCollabDB, oid: &str) -> String {
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    db.read_txn().load_doc(1, oid, &mut txn).unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let txn = doc.transact();
+  text.get_string(&txn)
+}
+
+fn insert_text(db: &RocksCollabDB, oid: &str, doc: &Doc, content: &str, version: EncoderVersion) {
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  let len = text.len(&txn);
+  text.insert(&mut txn, len, content);
+  let update = match version {
+    EncoderVersion::V1 => txn.encode_update_v1(),
+    EncoderVersion::V2 => txn.encode_update_v2(),
+  };
+  db.with_write_txn(|store| store.push_update_with_version(1, oid, &update, version))
+    .unwrap();
+}
+
+#[test]
+fn v2_doc_restore_test() {
+  let (path, db) = rocks_db(1);
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc_with_version(1, oid, &txn, EncoderVersion::V2))
+      .unwrap();
+  }
+  insert_text(&db, oid, &doc, "hello", EncoderVersion::V2);
+  insert_text(&db, oid, &doc, " world", EncoderVersion::V2);
+  drop(db);
+
+  let db = RocksCollabDB::open(path).unwrap();
+  assert_eq!(load_text(&db, oid), "hello world");
+  assert_eq!(
+    db.read_txn().get_decoded_v1_updates(1, oid).unwrap().len(),
+    2
+  );
+}
+
+#[test]
+fn mixed_versions_restore_test() {
+  let (_path, db) = rocks_db(1);
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  insert_text(&db, oid, &doc, "hello", EncoderVersion::V1);
+  insert_text(&db, oid, &doc, " world", EncoderVersion::V2);
+  insert_text(&db, oid, &doc, "!", EncoderVersion::V1);
+  assert_eq!(load_text(&db, oid), "hello world!");
+}
+
+#[test]
+fn migrate_doc_to_v2_test() {
+  let (_path, db) = rocks_db(1);
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  insert_text(&db, oid, &doc, "hello", EncoderVersion::V1);
+  insert_text(&db, oid, &doc, " world", EncoderVersion::V1);
+
+  db.with_write_txn(|store| store.migrate_doc(1, oid, EncoderVersion::V2))
+    .unwrap();
+  assert_eq!(load_text(&db, oid), "hello world");
+  assert_eq!(db.read_txn().number_of_updates(1, oid), 2);
+
+  // The updates are appended after the migrated ones.
+  insert_text(&db, oid, &doc, "!", EncoderVersion::V2);
+  assert_eq!(load_text(&db, oid), "hello world!");
+}
+
+#[test]
+fn flush_doc_with_other_version_test() {
+  let (_path, db) = rocks_db(1);
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  insert_text(&db, oid, &doc, "hello", EncoderVersion::V1);
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.flush_doc_with_version(1, oid, &txn, EncoderVersion::V2))
+      .unwrap();
+  }
+  assert_eq!(db.read_txn().number_of_updates(1, oid), 0);
+  assert_eq!(load_text(&db, oid), "hello");
+}
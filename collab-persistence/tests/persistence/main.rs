@@ -1,4 +1,9 @@
+mod bundle_test;
+mod encoding_test;
+mod mem_kv_test;
 mod range_test;
 mod restore_test;
 mod rocksdb_cf_test;
+#[cfg(feature = "sqlite_db")]
+mod sqlite_kv_test;
 mod util;
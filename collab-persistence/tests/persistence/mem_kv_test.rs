@@ -0,0 +1,144 @@
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::kv::mem_kv::MemCollabDB;
+use collab_persistence::kv::{KVEntry, KVStore};
+use collab_persistence::snapshot::SnapshotAction;
+use collab_persistence::PersistenceError;
+use yrs::{Doc, GetString, Options, ReadTxn, Text, Transact};
+
+fn insert_entries(db: &MemCollabDB) {
+  db.with_write_txn(|store| {
+    store.insert([0, 0, 0, 0, 0, 0, 0, 0], [0, 1, 1])?;
+    store.insert([0, 0, 0, 0, 0, 0, 0, 1], [0, 1, 2])?;
+    store.insert([0, 0, 0, 0, 0, 0, 0, 2], [0, 1, 3])?;
+    store.insert([0, 1, 0, 0, 0, 0, 0, 4], [0, 1, 5])?;
+    Ok(())
+  })
+  .unwrap();
+}
+
+#[test]
+fn mem_range_test() {
+  let db = MemCollabDB::new();
+  insert_entries(&db);
+
+  let store = db.read_txn();
+  let values = store
+    .range([0, 0, 0, 0, 0, 0, 0, 1]..[0, 1, 0, 0, 0, 0, 0, 4])
+    .unwrap()
+    .map(|entry| entry.value().to_vec())
+    .collect::<Vec<_>>();
+  assert_eq!(values, vec![vec![0, 1, 2], vec![0, 1, 3]]);
+
+  let keys = store
+    .range::<[u8; 2], _>(..)
+    .unwrap()
+    .map(|entry| entry.key().to_vec())
+    .collect::<Vec<_>>();
+  assert_eq!(keys.len(), 4);
+  assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn mem_next_back_entry_test() {
+  let db = MemCollabDB::new();
+  insert_entries(&db);
+
+  let store = db.read_txn();
+  let entry = store
+    .next_back_entry(&[0, 0, 0, 0, 0, 0, 0, 1])
+    .unwrap()
+    .unwrap();
+  assert_eq!(entry.value(), &[0, 1, 2]);
+
+  let entry = store.next_back_entry(&[0, 1]).unwrap().unwrap();
+  assert_eq!(entry.value(), &[0, 1, 3]);
+  assert!(store.next_back_entry(&[0]).unwrap().is_none());
+
+  // The uncommitted changes of the transaction are visible to itself.
+  store.remove(&[0, 0, 0, 0, 0, 0, 0, 2]).unwrap();
+  store.insert([0, 0, 1], [0, 1, 4]).unwrap();
+  let entry = store.next_back_entry(&[0, 1]).unwrap().unwrap();
+  assert_eq!(entry.key(), &[0, 0, 1]);
+  store.remove(&[0, 0, 1]).unwrap();
+  let entry = store.next_back_entry(&[0, 1]).unwrap().unwrap();
+  assert_eq!(entry.value(), &[0, 1, 2]);
+}
+
+#[test]
+fn mem_write_txn_test() {
+  let db = MemCollabDB::new();
+  insert_entries(&db);
+
+  // The writes are discarded if the transaction fails.
+  let result = db.with_write_txn(|store| {
+    store.remove_range(&[0, 0, 0, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 0, 0, 0, 2])?;
+    assert!(store.get([0, 0, 0, 0, 0, 0, 0, 1]).unwrap().is_none());
+    Err::<(), _>(PersistenceError::InvalidData("rollback".to_string()))
+  });
+  assert!(result.is_err());
+  assert_eq!(db.len(), 4);
+
+  // The read transaction keeps reading from its snapshot.
+  let read_txn = db.read_txn();
+  db.with_write_txn(|store| {
+    store.remove_range(&[0, 0, 0, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 0, 0, 0, 2])?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(db.len(), 2);
+  assert!(read_txn.get([0, 0, 0, 0, 0, 0, 0, 1]).unwrap().is_some());
+  assert!(db
+    .read_txn()
+    .get([0, 0, 0, 0, 0, 0, 0, 1])
+    .unwrap()
+    .is_none());
+
+  // The writes of the read transaction are never committed.
+  read_txn.insert([1], [1]).unwrap();
+  drop(read_txn);
+  assert!(db.read_txn().get([1]).unwrap().is_none());
+}
+
+#[test]
+fn mem_doc_test() {
+  let db = MemCollabDB::new();
+  let oid = "doc_1";
+  let doc = Doc::with_options(Options {
+    skip_gc: true,
+    ..Options::default()
+  });
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  for content in ["hello", " world"] {
+    let mut txn = doc.transact_mut();
+    let len = text.len(&txn);
+    text.insert(&mut txn, len, content);
+    let update = txn.encode_update_v1();
+    db.with_write_txn(|store| store.push_update(1, oid, &update))
+      .unwrap();
+  }
+  assert!(db.read_txn().is_exist(1, oid));
+  assert_eq!(db.read_txn().number_of_updates(1, oid), 2);
+
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_snapshot(1, oid, &txn, txn.snapshot()))
+      .unwrap();
+  }
+  assert_eq!(db.read_txn().get_snapshots(1, oid).len(), 1);
+
+  let restored_doc = Doc::new();
+  {
+    let mut txn = restored_doc.transact_mut();
+    db.read_txn().load_doc(1, oid, &mut txn).unwrap();
+  }
+  let text = restored_doc.get_or_insert_text("text");
+  assert_eq!(text.get_string(&restored_doc.transact()), "hello world");
+
+  db.with_write_txn(|store| store.delete_doc(1, oid)).unwrap();
+  assert!(!db.read_txn().is_exist(1, oid));
+}
@@ -135,3 +135,41 @@ fn rocks_multiple_thread_test() {
     assert_eq!(text.get_string(&txn), format!("Hello, world! {}", i));
   }
 }
+
+#[test]
+fn flush_doc_keeps_doc_state_test() {
+  let (path, db) = rocks_db(1);
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  for content in ["Hello", ", world!"] {
+    let mut txn = doc.transact_mut();
+    let len = text.len(&txn);
+    text.insert(&mut txn, len, content);
+    let update = txn.encode_update_v1();
+    db.with_write_txn(|store| store.push_update(1, oid, &update))
+      .unwrap();
+  }
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.flush_doc(1, oid, &txn))
+      .unwrap();
+  }
+  assert_eq!(db.read_txn().number_of_updates(1, oid), 0);
+  drop(db);
+
+  // The flushed document state must be kept after the updates are removed.
+  let db = RocksCollabDB::open(path).unwrap();
+  let restored_doc = Doc::new();
+  {
+    let mut txn = restored_doc.transact_mut();
+    db.read_txn().load_doc(1, oid, &mut txn).unwrap();
+  }
+  let text = restored_doc.get_or_insert_text("text");
+  assert_eq!(text.get_string(&restored_doc.transact()), "Hello, world!");
+}
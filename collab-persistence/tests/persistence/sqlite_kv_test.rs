@@ -0,0 +1,96 @@
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::kv::sqlite_kv::SqliteCollabDB;
+use collab_persistence::kv::{KVEntry, KVStore};
+use collab_persistence::PersistenceError;
+use yrs::{Doc, GetString, Text, Transact};
+
+use crate::util::sqlite_db;
+
+fn insert_entries(db: &SqliteCollabDB) {
+  db.with_write_txn(|store| {
+    store.insert([0, 0, 0, 0, 0, 0, 0, 0], [0, 1, 1])?;
+    store.insert([0, 0, 0, 0, 0, 0, 0, 1], [0, 1, 2])?;
+    store.insert([0, 0, 0, 0, 0, 0, 0, 2], [0, 1, 3])?;
+    store.insert([0, 1, 0, 0, 0, 0, 0, 4], [0, 1, 5])?;
+    Ok(())
+  })
+  .unwrap();
+}
+
+#[test]
+fn sqlite_range_and_next_back_entry_test() {
+  let (_path, db) = sqlite_db();
+  insert_entries(&db);
+
+  let store = db.read_txn().unwrap();
+  let values = store
+    .range([0, 0, 0, 0, 0, 0, 0, 1]..[0, 1, 0, 0, 0, 0, 0, 4])
+    .unwrap()
+    .map(|entry| entry.value().to_vec())
+    .collect::<Vec<_>>();
+  assert_eq!(values, vec![vec![0, 1, 2], vec![0, 1, 3]]);
+
+  let entry = store
+    .next_back_entry(&[0, 0, 0, 0, 0, 0, 0, 1])
+    .unwrap()
+    .unwrap();
+  assert_eq!(entry.value(), &[0, 1, 2]);
+  let entry = store.next_back_entry(&[0, 1]).unwrap().unwrap();
+  assert_eq!(entry.value(), &[0, 1, 3]);
+  assert!(store.next_back_entry(&[0]).unwrap().is_none());
+}
+
+#[test]
+fn sqlite_write_txn_rollback_test() {
+  let (_path, db) = sqlite_db();
+  insert_entries(&db);
+
+  let result = db.with_write_txn(|store| {
+    store.remove_range(&[0, 0, 0, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 0, 0, 0, 2])?;
+    assert!(store.get([0, 0, 0, 0, 0, 0, 0, 1]).unwrap().is_none());
+    Err::<(), _>(PersistenceError::InvalidData("rollback".to_string()))
+  });
+  assert!(result.is_err());
+  let store = db.read_txn().unwrap();
+  assert!(store.get([0, 0, 0, 0, 0, 0, 0, 1]).unwrap().is_some());
+  drop(store);
+
+  // The writes of the read transaction are never committed.
+  let store = db.read_txn().unwrap();
+  store.insert([1], [1]).unwrap();
+  drop(store);
+  assert!(db.read_txn().unwrap().get([1]).unwrap().is_none());
+}
+
+#[test]
+fn sqlite_doc_restore_test() {
+  let (path, db) = sqlite_db();
+  let oid = "doc_1";
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|store| store.create_new_doc(1, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  for content in ["hello", " world"] {
+    let mut txn = doc.transact_mut();
+    let len = text.len(&txn);
+    text.insert(&mut txn, len, content);
+    let update = txn.encode_update_v1();
+    db.with_write_txn(|store| store.push_update(1, oid, &update))
+      .unwrap();
+  }
+  drop(db);
+
+  let db = SqliteCollabDB::open(path.join("collab.db")).unwrap();
+  let store = db.read_txn().unwrap();
+  assert_eq!(store.number_of_updates(1, oid), 2);
+  let restored_doc = Doc::new();
+  {
+    let mut txn = restored_doc.transact_mut();
+    store.load_doc(1, oid, &mut txn).unwrap();
+  }
+  let text = restored_doc.get_or_insert_text("text");
+  assert_eq!(text.get_string(&restored_doc.transact()), "hello world");
+}
@@ -25,6 +25,19 @@ pub fn rocks_db(_uid: i64) -> (PathBuf, RocksCollabDB) {
   (path, RocksCollabDB::open(cloned_path).unwrap())
 }
 
+#[cfg(feature = "sqlite_db")]
+pub fn sqlite_db() -> (PathBuf, collab_persistence::kv::sqlite_kv::SqliteCollabDB) {
+  setup_log();
+
+  let tempdir = TempDir::new().unwrap();
+  let path = tempdir.into_path();
+  let db_path = path.join("collab.db");
+  (
+    path,
+    collab_persistence::kv::sqlite_kv::SqliteCollabDB::open(db_path).unwrap(),
+  )
+}
+
 fn setup_log() {
   static START: Once = Once::new();
   START.call_once(|| {
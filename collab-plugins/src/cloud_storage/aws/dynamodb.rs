@@ -85,6 +85,14 @@ impl AWSDynamoDB {
   pub fn push_update(&self, update: &[u8]) {
     self.remote_collab.push_update(update);
   }
+
+  pub fn flush(&self) {
+    self.remote_collab.flush();
+  }
+
+  pub fn has_pending_updates(&self) -> bool {
+    self.remote_collab.has_pending_updates()
+  }
 }
 
 struct AWSCollabCloudStorageImpl {
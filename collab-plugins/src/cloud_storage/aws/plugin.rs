@@ -4,15 +4,17 @@ use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use collab::core::collab::MutexCollab;
+use collab::core::collab_state::{CollabState, CollabStateHandle};
 use collab::core::origin::CollabOrigin;
 use collab::preclude::CollabPlugin;
 use parking_lot::RwLock;
 use tokio_retry::strategy::FixedInterval;
 use tokio_retry::{Action, Condition, RetryIf};
 use y_sync::awareness::Awareness;
-use yrs::Transaction;
+use yrs::{Doc, Transaction};
 
 use crate::cloud_storage::aws::{AWSDynamoDB, DEFAULT_TABLE_NAME};
+use crate::cloud_storage::merge_pending_updates;
 
 enum LoadingState {
   NotLoaded,
@@ -30,6 +32,7 @@ pub struct AWSDynamoDBPlugin {
   aws_dynamodb: Arc<RwLock<Option<AWSDynamoDB>>>,
   state: Arc<RwLock<LoadingState>>,
   pending_updates: Arc<RwLock<Vec<Vec<u8>>>>,
+  state_handle: Arc<RwLock<Option<CollabStateHandle>>>,
 }
 
 impl AWSDynamoDBPlugin {
@@ -67,6 +70,7 @@ impl AWSDynamoDBPlugin {
       region,
       state,
       pending_updates,
+      state_handle: Default::default(),
     }
   }
 
@@ -83,34 +87,62 @@ impl AWSDynamoDBPlugin {
     let weak_aws_dynamodb = Arc::downgrade(&self.aws_dynamodb);
     let weak_state = Arc::downgrade(&self.state);
     let weak_pending_updates = Arc::downgrade(&self.pending_updates);
+    let state_handle = self.state_handle.read().clone();
+    set_collab_state(
+      &state_handle,
+      CollabState::Syncing,
+      "Connecting to AWS DynamoDB",
+    );
     tokio::spawn(async move {
       if let Some(aws_dynamodb) = weak_aws_dynamodb.upgrade() {
-        if let Ok(dynamodb) = RetryIf::spawn(
+        match RetryIf::spawn(
           retry_strategy,
           action,
           RetryCondition(weak_aws_dynamodb.clone()),
         )
         .await
         {
-          if let (Some(local_collab), Some(state), Some(pending_updates)) = (
-            weak_local_collab.upgrade(),
-            weak_state.upgrade(),
-            weak_pending_updates.upgrade(),
-          ) {
-            dynamodb.start_sync(local_collab).await;
-            for update in &*pending_updates.read() {
-              dynamodb.push_update(update);
+          Ok(dynamodb) => {
+            if let (Some(local_collab), Some(state), Some(pending_updates)) = (
+              weak_local_collab.upgrade(),
+              weak_state.upgrade(),
+              weak_pending_updates.upgrade(),
+            ) {
+              dynamodb.start_sync(local_collab).await;
+              for update in pending_updates.write().drain(..) {
+                dynamodb.push_update(&update);
+              }
+              *aws_dynamodb.write() = Some(dynamodb);
+              *state.write() = LoadingState::Loaded;
+              set_collab_state(
+                &state_handle,
+                CollabState::Synced,
+                "Synced with AWS DynamoDB",
+              );
             }
-            *aws_dynamodb.write() = Some(dynamodb);
-            *state.write() = LoadingState::Loaded;
-          }
-        };
+          },
+          Err(e) => set_collab_state(
+            &state_handle,
+            CollabState::Offline,
+            &format!("Connect to AWS DynamoDB failed: {}", e),
+          ),
+        }
       }
     });
   }
 }
 
+fn set_collab_state(handle: &Option<CollabStateHandle>, state: CollabState, reason: &str) {
+  if let Some(handle) = handle {
+    handle.set(state, reason);
+  }
+}
+
 impl CollabPlugin for AWSDynamoDBPlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.state_handle.write() = Some(handle);
+  }
+
   fn did_init(&self, _awareness: &Awareness, _object_id: &str, _txn: &Transaction) {
     if matches!(&*self.state.read(), LoadingState::NotLoaded) {
       *self.state.write() = LoadingState::Loading;
@@ -125,6 +157,26 @@ impl CollabPlugin for AWSDynamoDBPlugin {
       self.pending_updates.write().push(update.to_vec());
     }
   }
+
+  fn flush(&self, _object_id: &str, _doc: &Doc) {
+    match self.aws_dynamodb.read().as_ref() {
+      Some(aws_dynamodb) => aws_dynamodb.flush(),
+      // The updates are pushed after connecting to the AWS DynamoDB. Merge them into one
+      // update before that.
+      None => merge_pending_updates(&self.pending_updates),
+    }
+  }
+
+  fn will_close(&self, object_id: &str) {
+    let has_pending_updates = match self.aws_dynamodb.read().as_ref() {
+      Some(aws_dynamodb) => aws_dynamodb.has_pending_updates(),
+      None => !self.pending_updates.read().is_empty(),
+    };
+    if has_pending_updates {
+      tracing::warn!("{} is closed before all the updates were synced", object_id);
+    }
+    self.state_handle.write().take();
+  }
 }
 
 pub(crate) struct AwsDynamodbConnectAction {
@@ -5,4 +5,6 @@ pub mod aws;
 pub mod postgres;
 
 mod remote_collab;
+#[allow(unused_imports)]
+pub(crate) use remote_collab::merge_pending_updates;
 pub use remote_collab::CollabObject;
@@ -1,7 +1,9 @@
 use collab::core::collab::MutexCollab;
+use collab::core::collab_state::{CollabState, CollabStateHandle};
 use collab::core::origin::CollabOrigin;
 use collab::preclude::CollabPlugin;
 
+use crate::cloud_storage::merge_pending_updates;
 use crate::cloud_storage::postgres::postgres_db::PostgresDB;
 use crate::cloud_storage::postgres::SupabaseDBConfig;
 use crate::cloud_storage::remote_collab::CollabObject;
@@ -9,13 +11,14 @@ use parking_lot::RwLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use y_sync::awareness::Awareness;
-use yrs::Transaction;
+use yrs::{Doc, Transaction};
 
 pub struct SupabaseDBPlugin {
   local_collab: Arc<MutexCollab>,
   postgres_db: Arc<PostgresDB>,
   pending_updates: Arc<RwLock<Vec<Vec<u8>>>>,
   is_first_sync_done: Arc<AtomicBool>,
+  state_handle: Arc<RwLock<Option<CollabStateHandle>>>,
 }
 
 impl SupabaseDBPlugin {
@@ -33,12 +36,21 @@ impl SupabaseDBPlugin {
       postgres_db: Arc::new(postgres_db),
       pending_updates,
       is_first_sync_done,
+      state_handle: Default::default(),
     }
   }
 }
 
 impl CollabPlugin for SupabaseDBPlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.state_handle.write() = Some(handle);
+  }
+
   fn did_init(&self, _awareness: &Awareness, _object_id: &str, _txn: &Transaction) {
+    let state_handle = self.state_handle.read().clone();
+    if let Some(handle) = &state_handle {
+      handle.set(CollabState::Syncing, "Syncing with the postgres");
+    }
     let weak_postgres_db = Arc::downgrade(&self.postgres_db);
     let weak_local_collab = Arc::downgrade(&self.local_collab);
     let weak_pending_updates = Arc::downgrade(&self.pending_updates);
@@ -57,11 +69,14 @@ impl CollabPlugin for SupabaseDBPlugin {
         weak_is_first_sync_done.upgrade(),
       ) {
         postgres_db.start_sync(local_collab.clone()).await;
-        for update in &*pending_updates.read() {
-          postgres_db.push_update(update);
+        for update in pending_updates.write().drain(..) {
+          postgres_db.push_update(&update);
         }
 
-        is_first_sync_done.store(true, Ordering::SeqCst)
+        is_first_sync_done.store(true, Ordering::SeqCst);
+        if let Some(handle) = &state_handle {
+          handle.set(CollabState::Synced, "Synced with the postgres");
+        }
       }
     });
   }
@@ -74,4 +89,24 @@ impl CollabPlugin for SupabaseDBPlugin {
       self.pending_updates.write().push(update.to_vec());
     }
   }
+
+  fn flush(&self, _object_id: &str, _doc: &Doc) {
+    if self.is_first_sync_done.load(Ordering::SeqCst) {
+      self.postgres_db.flush();
+    } else {
+      // The updates are pushed after the first sync. Merge them into one update before that.
+      merge_pending_updates(&self.pending_updates);
+    }
+  }
+
+  fn will_close(&self, object_id: &str) {
+    let has_pending_updates = if self.is_first_sync_done.load(Ordering::SeqCst) {
+      self.postgres_db.has_pending_updates()
+    } else {
+      !self.pending_updates.read().is_empty()
+    };
+    if has_pending_updates {
+      tracing::warn!("{} is closed before all the updates were synced", object_id);
+    }
+  }
 }
@@ -5,6 +5,7 @@ use std::time::SystemTime;
 
 use async_trait::async_trait;
 use collab::core::collab::MutexCollab;
+use collab::core::encoding::EncoderVersion;
 use collab::core::origin::CollabOrigin;
 use collab_sync::client::sink::{
   CollabSink, CollabSinkMessage, CollabSinkRunner, MsgId, MsgIdCounter, SinkConfig,
@@ -27,6 +28,13 @@ pub trait RemoteCollabStorage: Send + Sync + 'static {
   async fn get_all_updates(&self, object_id: &str) -> Result<Vec<Vec<u8>>, anyhow::Error>;
   /// Send the update to the remote storage.
   async fn send_update(&self, id: MsgId, update: Vec<u8>) -> Result<(), anyhow::Error>;
+
+  /// The encoding of the updates that are stored in the remote storage. The updates returned by
+  /// [RemoteCollabStorage::get_all_updates] are decoded with it, and the updates passed to
+  /// [RemoteCollabStorage::send_update] are encoded with it. Default is [EncoderVersion::V1].
+  fn encoder_version(&self) -> EncoderVersion {
+    EncoderVersion::V1
+  }
 }
 
 /// The [RemoteCollab] is used to sync the local collab to the remote.
@@ -62,7 +70,13 @@ impl RemoteCollab {
     spawn(async move {
       while let Some(message) = stream.recv().await {
         if let Some(storage) = weak_storage.upgrade() {
-          if let Ok((object, msg_id, payload)) = message.split() {
+          // The payloads of the message are encoded with v1, convert the merged update to the
+          // encoding of the storage.
+          let result = message.split().and_then(|(object, msg_id, payload)| {
+            let payload = EncoderVersion::V1.convert_update(&payload, storage.encoder_version())?;
+            Ok((object, msg_id, payload))
+          });
+          if let Ok((object, msg_id, payload)) = result {
             match storage.send_update(msg_id, payload).await {
               Ok(_) => {
                 tracing::debug!("ack update {}: {}", object, msg_id);
@@ -109,9 +123,10 @@ impl RemoteCollab {
     if !updates.is_empty() {
       // Apply remote updates to remote collab before encode the state as update
       // for local collab.
+      let version = self.storage.encoder_version();
       self.collab.lock().with_transact_mut(|txn| {
         for update in updates {
-          if let Ok(update) = Update::decode_v1(&update) {
+          if let Ok(update) = version.decode_update(&update) {
             txn.apply_update(update);
           } else {
             tracing::error!("Failed to decode update");
@@ -168,6 +183,16 @@ impl RemoteCollab {
     }
   }
 
+  /// Send the pending updates to the remote without waiting for the next sync interval.
+  pub fn flush(&self) {
+    self.sink.flush();
+  }
+
+  /// Returns true if there are updates that are not acknowledged by the remote yet.
+  pub fn has_pending_updates(&self) -> bool {
+    self.sink.has_pending_msgs()
+  }
+
   pub fn push_update(&self, update: &[u8]) {
     if let Ok(decode_update) = Update::decode_v1(update) {
       self.collab.lock().with_transact_mut(|txn| {
@@ -189,6 +214,22 @@ impl RemoteCollab {
   }
 }
 
+/// Merge the updates that are waiting for the connection into one update.
+pub(crate) fn merge_pending_updates(pending_updates: &parking_lot::RwLock<Vec<Vec<u8>>>) {
+  let mut write_guard = pending_updates.write();
+  if write_guard.len() <= 1 {
+    return;
+  }
+  let updates = write_guard
+    .iter()
+    .map(|update| update.as_slice())
+    .collect::<Vec<&[u8]>>();
+  match yrs::merge_updates_v1(&updates) {
+    Ok(merged_update) => *write_guard = vec![merged_update],
+    Err(e) => tracing::error!("🔴Failed to merge the pending updates: {:?}", e),
+  }
+}
+
 #[derive(Clone, Debug)]
 pub enum MessageMeta {
   Init { msg_id: MsgId },
@@ -8,10 +8,20 @@ pub mod sync {
   pub use crate::ws_sync::*;
 }
 
-#[cfg(any(feature = "disk_rocksdb", feature = "disk_sled"))]
+#[cfg(any(
+  feature = "disk_rocksdb",
+  feature = "disk_sled",
+  feature = "disk_memory",
+  feature = "disk_sqlite"
+))]
 mod local_storage;
 
-#[cfg(any(feature = "disk_rocksdb", feature = "disk_sled"))]
+#[cfg(any(
+  feature = "disk_rocksdb",
+  feature = "disk_sled",
+  feature = "disk_memory",
+  feature = "disk_sqlite"
+))]
 pub mod disk {
   pub use collab_persistence::*;
 
@@ -22,3 +32,5 @@ pub mod disk {
 pub mod cloud_storage;
 
 pub mod snapshot;
+
+pub mod schema;
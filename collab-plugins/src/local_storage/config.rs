@@ -0,0 +1,60 @@
+use collab::core::encoding::EncoderVersion;
+
+/// The config of the disk plugins. It's shared by the plugins of the rocksdb, sqlite and
+/// memory stores.
+#[derive(Clone)]
+pub struct CollabPersistenceConfig {
+  /// Enable snapshot. Default is [false].
+  pub enable_snapshot: bool,
+  /// Generate a snapshot every N updates
+  /// Default is 20. The value must be greater than 0.
+  pub snapshot_per_update: u32,
+
+  /// Flush the document. Default is [false].
+  /// After flush the document, all updates will be removed and the document state vector that
+  /// contains all the updates will be reset.
+  pub(crate) flush_doc: bool,
+
+  /// The encoding of the document state and the updates that are written to disk. Default is
+  /// [EncoderVersion::V1]. The data that was written with the other version can still be read,
+  /// and it's re-encoded with this version when the document is flushed.
+  pub(crate) encoder_version: EncoderVersion,
+}
+
+impl CollabPersistenceConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn enable_snapshot(mut self, enable_snapshot: bool) -> Self {
+    self.enable_snapshot = enable_snapshot;
+    self
+  }
+
+  pub fn snapshot_per_update(mut self, snapshot_per_update: u32) -> Self {
+    debug_assert!(snapshot_per_update > 0);
+    self.snapshot_per_update = snapshot_per_update;
+    self
+  }
+
+  pub fn flush_doc(mut self, flush_doc: bool) -> Self {
+    self.flush_doc = flush_doc;
+    self
+  }
+
+  pub fn encoder_version(mut self, encoder_version: EncoderVersion) -> Self {
+    self.encoder_version = encoder_version;
+    self
+  }
+}
+
+impl Default for CollabPersistenceConfig {
+  fn default() -> Self {
+    Self {
+      enable_snapshot: true,
+      snapshot_per_update: 100,
+      flush_doc: false,
+      encoder_version: EncoderVersion::V1,
+    }
+  }
+}
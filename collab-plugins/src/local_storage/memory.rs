@@ -0,0 +1,184 @@
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use collab::core::collab_state::{CollabState, CollabStateHandle};
+use collab::core::encoding::EncoderVersion;
+use collab::preclude::{CollabPlugin, CollabStats, CompactionPoint};
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::kv::mem_kv::MemCollabDB;
+use collab_persistence::PersistenceError;
+use parking_lot::RwLock;
+use y_sync::awareness::Awareness;
+use yrs::{Doc, Transact, Transaction, TransactionMut};
+
+use crate::local_storage::config::CollabPersistenceConfig;
+
+/// A disk plugin that stores the document in the [MemCollabDB]. The data is lost when the
+/// [MemCollabDB] is dropped, it's used by the tests and the ephemeral server instances. Sharing
+/// the same [MemCollabDB] between the collabs behaves like sharing the same disk.
+#[derive(Clone)]
+pub struct MemoryDiskPlugin {
+  uid: i64,
+  db: Arc<MemCollabDB>,
+  did_load: Arc<AtomicBool>,
+  /// the number of updates that were saved after the document was flushed
+  update_count: Arc<AtomicU32>,
+  config: CollabPersistenceConfig,
+  state: Arc<RwLock<Option<CollabStateHandle>>>,
+}
+
+impl Deref for MemoryDiskPlugin {
+  type Target = Arc<MemCollabDB>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.db
+  }
+}
+
+impl MemoryDiskPlugin {
+  pub fn new(uid: i64, db: Arc<MemCollabDB>) -> Self {
+    Self::new_with_config(uid, db, CollabPersistenceConfig::default())
+  }
+
+  pub fn new_with_config(uid: i64, db: Arc<MemCollabDB>, config: CollabPersistenceConfig) -> Self {
+    Self {
+      uid,
+      db,
+      did_load: Arc::new(AtomicBool::new(false)),
+      update_count: Arc::new(AtomicU32::new(0)),
+      config,
+      state: Default::default(),
+    }
+  }
+
+  fn save_compaction_point(
+    &self,
+    object_id: &str,
+    point: &CompactionPoint,
+  ) -> Result<(), PersistenceError> {
+    let data = point.to_vec()?;
+    self
+      .db
+      .with_write_txn(|w_db_txn| w_db_txn.insert_compaction_point(self.uid, object_id, &data))
+  }
+
+  /// Write the state of the doc and remove the updates in memory.
+  fn write_doc_state(&self, object_id: &str, doc: &Doc) {
+    let txn = doc.transact();
+    let result = self.db.with_write_txn(|w_db_txn| {
+      w_db_txn.flush_doc_with_version(self.uid, object_id, &txn, self.config.encoder_version)
+    });
+    match result {
+      Ok(_) => self.update_count.store(0, Ordering::SeqCst),
+      Err(e) => {
+        tracing::error!("🔴 flush doc:{} failed: {}", object_id, e);
+        self.report_persist_error(format!("Flush doc to memory failed: {}", e));
+      },
+    }
+  }
+
+  fn report_persist_error(&self, reason: String) {
+    if let Some(state) = self.state.read().as_ref() {
+      state.set(CollabState::PersistError, reason);
+    }
+  }
+}
+
+impl CollabPlugin for MemoryDiskPlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.state.write() = Some(handle);
+  }
+
+  fn init(&self, object_id: &str, txn: &mut TransactionMut) {
+    let r_db_txn = self.db.read_txn();
+    if r_db_txn.is_exist(self.uid, object_id) {
+      if let Err(e) = r_db_txn.load_doc(self.uid, object_id, txn) {
+        tracing::error!("🔴 load doc:{} failed: {}", object_id, e);
+        self.report_persist_error(format!("Load doc from memory failed: {}", e));
+      }
+      drop(r_db_txn);
+
+      if self.config.flush_doc {
+        let _ = self.db.with_write_txn(|w_db_txn| {
+          w_db_txn.flush_doc_with_version(self.uid, object_id, txn, self.config.encoder_version)
+        });
+      }
+    } else {
+      let result = self.db.with_write_txn(|w_db_txn| {
+        w_db_txn.create_new_doc_with_version(self.uid, object_id, txn, self.config.encoder_version)
+      });
+      if let Err(e) = result {
+        tracing::error!("🔴 create doc for {:?} failed: {}", object_id, e);
+        self.report_persist_error(format!("Create doc in memory failed: {}", e));
+      }
+    }
+  }
+
+  fn did_init(&self, _awareness: &Awareness, _object_id: &str, _txn: &Transaction) {
+    self.did_load.store(true, Ordering::SeqCst);
+  }
+
+  fn receive_update(&self, object_id: &str, _txn: &TransactionMut, update: &[u8]) {
+    if !self.did_load.load(Ordering::SeqCst) {
+      return;
+    }
+    self.update_count.fetch_add(1, Ordering::SeqCst);
+    // The collab passes the v1 update, convert it to the version that is used in memory
+    let version = self.config.encoder_version;
+    let result = self.db.with_write_txn(|w_db_txn| {
+      let update = EncoderVersion::V1.convert_update(update, version)?;
+      w_db_txn.push_update_with_version(self.uid, object_id, &update, version)?;
+      Ok(())
+    });
+    if let Err(e) = result {
+      tracing::error!("🔴Save update failed: {:?}", e);
+      self.report_persist_error(format!("Save update to memory failed: {}", e));
+    }
+  }
+
+  /// Merge the updates into the document state if the [CollabPersistenceConfig::flush_doc] is
+  /// enabled.
+  fn flush(&self, object_id: &str, doc: &Doc) {
+    if !self.did_load.load(Ordering::SeqCst)
+      || !self.config.flush_doc
+      || self.update_count.load(Ordering::SeqCst) == 0
+    {
+      return;
+    }
+
+    self.write_doc_state(object_id, doc);
+  }
+
+  fn collect_stats(&self, object_id: &str, stats: &mut CollabStats) {
+    let update_count = self.db.read_txn().number_of_updates(self.uid, object_id);
+    stats.disk_update_count = Some(update_count);
+  }
+
+  fn did_compact(&self, object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    if !self.did_load.load(Ordering::SeqCst) {
+      return;
+    }
+    self.write_doc_state(object_id, compacted_doc);
+    if let Err(e) = self.save_compaction_point(object_id, point) {
+      tracing::error!(
+        "🔴 save compaction point of doc:{} failed: {}",
+        object_id,
+        e
+      );
+      self.report_persist_error(format!("Save compaction point to memory failed: {}", e));
+    }
+  }
+
+  fn get_compaction_point(&self, object_id: &str) -> Option<CompactionPoint> {
+    let data = self
+      .db
+      .read_txn()
+      .get_compaction_point(self.uid, object_id)?;
+    CompactionPoint::from_slice(&data).ok()
+  }
+
+  fn will_close(&self, _object_id: &str) {
+    self.state.write().take();
+  }
+}
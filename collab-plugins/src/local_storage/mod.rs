@@ -1,8 +1,16 @@
+pub mod config;
+
+#[cfg(feature = "disk_memory")]
+pub mod memory;
+
 #[cfg(feature = "disk_rocksdb")]
 pub mod rocksdb;
 
 #[cfg(feature = "disk_sled")]
 pub mod sled;
 
+#[cfg(feature = "disk_sqlite")]
+pub mod sqlite;
+
 #[cfg(feature = "disk_rocksdb")]
 pub mod rocksdb_server;
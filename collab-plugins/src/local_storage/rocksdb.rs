@@ -3,11 +3,19 @@ use std::sync::atomic::Ordering::SeqCst;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 
-use collab::preclude::CollabPlugin;
+use collab::core::branch::{BranchMeta, CollabBranch};
+use collab::core::collab_state::{CollabState, CollabStateHandle};
+use collab::core::encoding::EncoderVersion;
+use collab::preclude::{CollabPlugin, CollabStats, CompactionPoint};
+use collab_persistence::branch::BranchAction;
 use collab_persistence::doc::YrsDocAction;
 use collab_persistence::kv::rocks_kv::RocksCollabDB;
+use collab_persistence::PersistenceError;
+use parking_lot::RwLock;
 use y_sync::awareness::Awareness;
-use yrs::{Transaction, TransactionMut};
+use yrs::{Doc, Transact, Transaction, TransactionMut};
+
+pub use crate::local_storage::config::CollabPersistenceConfig;
 
 #[derive(Clone)]
 pub struct RocksdbDiskPlugin {
@@ -18,6 +26,7 @@ pub struct RocksdbDiskPlugin {
   initial_update_count: Arc<AtomicU32>,
   update_count: Arc<AtomicU32>,
   config: CollabPersistenceConfig,
+  state: Arc<RwLock<Option<CollabStateHandle>>>,
 }
 
 impl Deref for RocksdbDiskPlugin {
@@ -48,15 +57,97 @@ impl RocksdbDiskPlugin {
       initial_update_count,
       update_count,
       config,
+      state: Default::default(),
+    }
+  }
+
+  /// Save the [BranchMeta] of the given branch. The content of the branch is stored under its
+  /// own object id by the [RocksdbDiskPlugin] that was added to the branch's collab. Call it
+  /// again after [CollabBranch::merge_into], which updates the meta.
+  pub fn save_branch(&self, branch: &CollabBranch) -> Result<(), PersistenceError> {
+    let branch_id = branch.object_id();
+    let meta = branch.meta().to_vec()?;
+    self
+      .db
+      .with_write_txn(|w_db_txn| w_db_txn.insert_branch_meta(self.uid, &branch_id, &meta))
+  }
+
+  /// Return the [BranchMeta] of the given branch. Use it to reopen the branch with
+  /// [CollabBuilder::build_branch_with_meta](collab::preclude::CollabBuilder::build_branch_with_meta).
+  pub fn get_branch_meta(&self, branch_id: &str) -> Option<BranchMeta> {
+    let data = self.db.read_txn().get_branch_meta(self.uid, branch_id)?;
+    match BranchMeta::from_slice(&data) {
+      Ok(meta) => Some(meta),
+      Err(e) => {
+        tracing::error!("🔴 decode branch:{} meta failed: {}", branch_id, e);
+        None
+      },
+    }
+  }
+
+  /// Delete the branch, including its content and meta.
+  pub fn delete_branch(&self, branch_id: &str) -> Result<(), PersistenceError> {
+    self.db.with_write_txn(|w_db_txn| {
+      w_db_txn.delete_doc(self.uid, branch_id)?;
+      w_db_txn.delete_branch_meta(self.uid, branch_id)?;
+      Ok(())
+    })
+  }
+
+  fn save_compaction_point(
+    &self,
+    object_id: &str,
+    point: &CompactionPoint,
+  ) -> Result<(), PersistenceError> {
+    let data = point.to_vec()?;
+    self
+      .db
+      .with_write_txn(|w_db_txn| w_db_txn.insert_compaction_point(self.uid, object_id, &data))
+  }
+
+  /// Write the state of the doc and remove the updates on disk.
+  fn write_doc_state(&self, object_id: &str, doc: &Doc) {
+    let txn = doc.transact();
+    let result = self.db.with_write_txn(|w_db_txn| {
+      w_db_txn.flush_doc_with_version(self.uid, object_id, &txn, self.config.encoder_version)
+    });
+    match result {
+      Ok(_) => {
+        self.initial_update_count.store(0, Ordering::SeqCst);
+        self.update_count.store(0, Ordering::SeqCst);
+      },
+      Err(e) => {
+        tracing::error!("🔴 flush doc:{} failed: {}", object_id, e);
+        self.report_persist_error(format!("Flush doc to disk failed: {}", e));
+      },
     }
   }
 
   fn increase_count(&self) -> u32 {
     self.update_count.fetch_add(1, SeqCst)
   }
+
+  fn report_persist_error(&self, reason: String) {
+    if let Some(state) = self.state.read().as_ref() {
+      state.set(CollabState::PersistError, reason);
+    }
+  }
+
+  /// Clear the [CollabState::PersistError] after the update was saved successfully.
+  fn report_persist_success(&self) {
+    if let Some(state) = self.state.read().as_ref() {
+      if state.get() == CollabState::PersistError {
+        state.set(CollabState::Initialized, "The update was saved to disk");
+      }
+    }
+  }
 }
 
 impl CollabPlugin for RocksdbDiskPlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.state.write() = Some(handle);
+  }
+
   fn init(&self, object_id: &str, txn: &mut TransactionMut) {
     let r_db_txn = self.db.read_txn();
     // Check the document is exist or not
@@ -68,13 +159,16 @@ impl CollabPlugin for RocksdbDiskPlugin {
             .initial_update_count
             .store(update_count, Ordering::SeqCst);
         },
-        Err(e) => tracing::error!("🔴 load doc:{} failed: {}", object_id, e),
+        Err(e) => {
+          tracing::error!("🔴 load doc:{} failed: {}", object_id, e);
+          self.report_persist_error(format!("Load doc from disk failed: {}", e));
+        },
       }
       drop(r_db_txn);
 
       if self.config.flush_doc {
         let _ = self.db.with_write_txn(|w_db_txn| {
-          w_db_txn.flush_doc(self.uid, object_id, txn)?;
+          w_db_txn.flush_doc_with_version(self.uid, object_id, txn, self.config.encoder_version)?;
           self.initial_update_count.store(0, Ordering::SeqCst);
           Ok(())
         });
@@ -82,12 +176,18 @@ impl CollabPlugin for RocksdbDiskPlugin {
     } else {
       // Drop the read txn before write txn
       let result = self.db.with_write_txn(|w_db_txn| {
-        w_db_txn.create_new_doc(self.uid, object_id, txn)?;
+        w_db_txn.create_new_doc_with_version(
+          self.uid,
+          object_id,
+          txn,
+          self.config.encoder_version,
+        )?;
         Ok(())
       });
 
       if let Err(e) = result {
-        tracing::error!("🔴 create doc for {:?} failed: {}", object_id, e)
+        tracing::error!("🔴 create doc for {:?} failed: {}", object_id, e);
+        self.report_persist_error(format!("Create doc on disk failed: {}", e));
       }
     }
   }
@@ -102,63 +202,83 @@ impl CollabPlugin for RocksdbDiskPlugin {
       return;
     }
     let _ = self.increase_count();
+    // The collab passes the v1 update, convert it to the version that is used on disk
+    let version = self.config.encoder_version;
     // /Acquire a write transaction to ensure consistency
     let result = self.db.with_write_txn(|w_db_txn| {
       tracing::trace!("Receive {} update", object_id);
-      let _ = w_db_txn.push_update(self.uid, object_id, update)?;
+      let update = EncoderVersion::V1.convert_update(update, version)?;
+      let _ = w_db_txn.push_update_with_version(self.uid, object_id, &update, version)?;
       Ok(())
     });
 
-    if let Err(e) = result {
-      tracing::error!("🔴Save update failed: {:?}", e);
+    match result {
+      Ok(_) => self.report_persist_success(),
+      Err(e) => {
+        tracing::error!("🔴Save update failed: {:?}", e);
+        self.report_persist_error(format!("Save update to disk failed: {}", e));
+      },
     }
   }
 
   fn after_transaction(&self, _object_id: &str, _txn: &mut TransactionMut) {}
-}
 
-#[derive(Clone)]
-pub struct CollabPersistenceConfig {
-  /// Enable snapshot. Default is [false].
-  pub enable_snapshot: bool,
-  /// Generate a snapshot every N updates
-  /// Default is 20. The value must be greater than 0.
-  pub snapshot_per_update: u32,
-
-  /// Flush the document. Default is [false].
-  /// After flush the document, all updates will be removed and the document state vector that
-  /// contains all the updates will be reset.
-  pub(crate) flush_doc: bool,
-}
+  /// The updates are written to disk when they are received, so there is nothing pending. If
+  /// the [CollabPersistenceConfig::flush_doc] is enabled, the updates are merged into the
+  /// document state.
+  fn flush(&self, object_id: &str, doc: &Doc) {
+    if !self.did_load.load(Ordering::SeqCst)
+      || !self.config.flush_doc
+      || self.update_count.load(Ordering::SeqCst) == 0
+    {
+      return;
+    }
 
-impl CollabPersistenceConfig {
-  pub fn new() -> Self {
-    Self::default()
+    self.write_doc_state(object_id, doc);
   }
 
-  pub fn enable_snapshot(mut self, enable_snapshot: bool) -> Self {
-    self.enable_snapshot = enable_snapshot;
-    self
+  fn collect_stats(&self, object_id: &str, stats: &mut CollabStats) {
+    let update_count = self.db.read_txn().number_of_updates(self.uid, object_id);
+    stats.disk_update_count = Some(update_count);
   }
 
-  pub fn snapshot_per_update(mut self, snapshot_per_update: u32) -> Self {
-    debug_assert!(snapshot_per_update > 0);
-    self.snapshot_per_update = snapshot_per_update;
-    self
+  /// Replace the stored state with the compacted document, which drops the deleted items and
+  /// the updates on disk. It's done regardless of the [CollabPersistenceConfig::flush_doc]. The
+  /// point is stored next to the document and restored by [Self::get_compaction_point].
+  fn did_compact(&self, object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    if !self.did_load.load(Ordering::SeqCst) {
+      return;
+    }
+    self.write_doc_state(object_id, compacted_doc);
+    if let Err(e) = self.save_compaction_point(object_id, point) {
+      tracing::error!(
+        "🔴 save compaction point of doc:{} failed: {}",
+        object_id,
+        e
+      );
+      self.report_persist_error(format!("Save compaction point to disk failed: {}", e));
+    }
   }
 
-  pub fn flush_doc(mut self, flush_doc: bool) -> Self {
-    self.flush_doc = flush_doc;
-    self
+  fn get_compaction_point(&self, object_id: &str) -> Option<CompactionPoint> {
+    let data = self
+      .db
+      .read_txn()
+      .get_compaction_point(self.uid, object_id)?;
+    match CompactionPoint::from_slice(&data) {
+      Ok(point) => Some(point),
+      Err(e) => {
+        tracing::error!(
+          "🔴 decode compaction point of doc:{} failed: {}",
+          object_id,
+          e
+        );
+        None
+      },
+    }
   }
-}
 
-impl Default for CollabPersistenceConfig {
-  fn default() -> Self {
-    Self {
-      enable_snapshot: true,
-      snapshot_per_update: 100,
-      flush_doc: false,
-    }
+  fn will_close(&self, _object_id: &str) {
+    self.state.write().take();
   }
 }
@@ -3,11 +3,11 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use collab::error::CollabError;
-use collab::preclude::CollabPlugin;
+use collab::preclude::{CollabPlugin, CollabStats, CompactionPoint};
 use collab_persistence::doc::YrsDocAction;
 use collab_persistence::kv::sled_lv::SledCollabDB;
 use y_sync::awareness::Awareness;
-use yrs::{Transaction, TransactionMut};
+use yrs::{Doc, Transact, Transaction, TransactionMut};
 
 #[derive(Clone)]
 pub struct SledDiskPlugin {
@@ -55,4 +55,44 @@ impl CollabPlugin for SledDiskPlugin {
         .unwrap();
     }
   }
+
+  fn collect_stats(&self, object_id: &str, stats: &mut CollabStats) {
+    let update_count = self.db.read_txn().number_of_updates(self.uid, object_id);
+    stats.disk_update_count = Some(update_count);
+  }
+
+  fn did_compact(&self, object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    if !self.did_load.load(Ordering::SeqCst) {
+      return;
+    }
+    let txn = compacted_doc.transact();
+    let db_txn = self.db.read_txn();
+    if let Err(e) = db_txn.flush_doc(self.uid, object_id, &txn) {
+      tracing::error!("🔴 compact doc:{} failed: {}", object_id, e);
+    }
+    match point.to_vec() {
+      Ok(data) => {
+        if let Err(e) = db_txn.insert_compaction_point(self.uid, object_id, &data) {
+          tracing::error!(
+            "🔴 save compaction point of doc:{} failed: {}",
+            object_id,
+            e
+          );
+        }
+      },
+      Err(e) => tracing::error!(
+        "🔴 encode compaction point of doc:{} failed: {}",
+        object_id,
+        e
+      ),
+    }
+  }
+
+  fn get_compaction_point(&self, object_id: &str) -> Option<CompactionPoint> {
+    let data = self
+      .db
+      .read_txn()
+      .get_compaction_point(self.uid, object_id)?;
+    CompactionPoint::from_slice(&data).ok()
+  }
 }
@@ -0,0 +1,3 @@
+pub use plugin::*;
+
+mod plugin;
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{CollabPlugin, CollabSchema, SchemaRegistry, SchemaViolation};
+use tokio::sync::broadcast;
+use yrs::TransactionMut;
+
+pub type SchemaViolationReportSender = broadcast::Sender<SchemaViolationReport>;
+pub type SchemaViolationReportReceiver = broadcast::Receiver<SchemaViolationReport>;
+
+/// Decides what the [SchemaValidationPlugin] does with the remote updates that break the schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaValidationMode {
+  /// Only report the violations by [SchemaValidationPlugin::subscribe_violation].
+  #[default]
+  Report,
+  /// Report the violations and reject the update, which quarantines the collab. The update,
+  /// and every remote update after it, is neither persisted nor sent to the remote until the
+  /// application calls [Collab::release_quarantine](collab::preclude::Collab::release_quarantine).
+  ///
+  /// The update is validated by [CollabPlugin::accept_remote_update] after it's applied, and it's
+  /// not rolled back: the document keeps the rejected changes until the application repairs them.
+  Quarantine,
+}
+
+/// Sent when a remote update leaves the `data` section in a shape that doesn't match the
+/// [CollabSchema].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolationReport {
+  pub object_id: String,
+  /// The origin of the transaction that applied the update.
+  pub origin: CollabOrigin,
+  pub violations: Vec<SchemaViolation>,
+}
+
+/// Validates the values of the `data` section that are changed by each remote update against
+/// the [CollabSchema]. The local updates are not validated, they are written by the typed code
+/// of each collab.
+pub struct SchemaValidationPlugin {
+  schema: Arc<CollabSchema>,
+  mode: SchemaValidationMode,
+  violation_tx: SchemaViolationReportSender,
+}
+
+impl SchemaValidationPlugin {
+  pub fn new(schema: Arc<CollabSchema>) -> Self {
+    Self {
+      schema,
+      mode: SchemaValidationMode::default(),
+      violation_tx: broadcast::channel(100).0,
+    }
+  }
+
+  /// Returns None if the collab type is not registered.
+  pub fn from_registry(registry: &SchemaRegistry, collab_type: &str) -> Option<Self> {
+    registry.get(collab_type).map(Self::new)
+  }
+
+  pub fn with_mode(mut self, mode: SchemaValidationMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  pub fn schema(&self) -> &CollabSchema {
+    &self.schema
+  }
+
+  pub fn subscribe_violation(&self) -> SchemaViolationReportReceiver {
+    self.violation_tx.subscribe()
+  }
+}
+
+impl CollabPlugin for SchemaValidationPlugin {
+  fn plugin_id(&self) -> String {
+    format!(
+      "{}:{}",
+      std::any::type_name::<Self>(),
+      self.schema.collab_type
+    )
+  }
+
+  fn accept_remote_update(
+    &self,
+    origin: &CollabOrigin,
+    object_id: &str,
+    txn: &TransactionMut,
+    changed_paths: &[String],
+  ) -> bool {
+    let violations = self.schema.validate_paths(txn, changed_paths);
+    if violations.is_empty() {
+      return true;
+    }
+
+    tracing::warn!(
+      "[🦀Collab]: remote update of {} from {} breaks the {} schema: {}",
+      object_id,
+      origin,
+      self.schema.collab_type,
+      violations
+        .iter()
+        .map(|violation| violation.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+    );
+    let _ = self.violation_tx.send(SchemaViolationReport {
+      object_id: object_id.to_string(),
+      origin: origin.clone(),
+      violations,
+    });
+    self.mode != SchemaValidationMode::Quarantine
+  }
+}
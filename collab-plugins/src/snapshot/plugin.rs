@@ -3,6 +3,7 @@ use std::panic::AssertUnwindSafe;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
+use collab::core::plain_text::PlainTextExtractor;
 use collab::preclude::{Collab, CollabPlugin};
 use collab_persistence::doc::YrsDocAction;
 use collab_persistence::kv::rocks_kv::RocksCollabDB;
@@ -11,7 +12,7 @@ use collab_persistence::PersistenceError;
 use parking_lot::RwLock;
 use similar::{ChangeTag, TextDiff};
 use yrs::updates::decoder::Decode;
-use yrs::{ReadTxn, StateVector, TransactionMut, Update};
+use yrs::{Doc, ReadTxn, StateVector, Transact, TransactionMut, Update};
 
 use crate::cloud_storage::CollabObject;
 
@@ -144,6 +145,29 @@ impl CollabPlugin for CollabSnapshotPlugin {
       }
     }
   }
+
+  /// Create a snapshot of the changes that were made after the last snapshot, so they are not
+  /// lost when the collab is closed.
+  fn flush(&self, object_id: &str, doc: &Doc) {
+    let pending_count = self.update_count.load(Ordering::SeqCst) % self.snapshot_per_update;
+    if pending_count == 0 || !self.state.read().is_idle() {
+      return;
+    }
+
+    let snapshot_data = doc
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default());
+    match self.snapshot_persistence.create_snapshot(
+      self.uid,
+      object_id,
+      self.object.name.clone(),
+      "".to_string(),
+      snapshot_data,
+    ) {
+      Ok(_) => self.update_count.store(0, Ordering::SeqCst),
+      Err(e) => tracing::error!("{} snapshot generation failed: {}", object_id, e),
+    }
+  }
 }
 
 impl SnapshotPersistence for Arc<RocksCollabDB> {
@@ -166,11 +190,72 @@ impl SnapshotPersistence for Arc<RocksCollabDB> {
   }
 }
 
+#[cfg(feature = "disk_memory")]
+impl SnapshotPersistence for Arc<collab_persistence::kv::mem_kv::MemCollabDB> {
+  fn get_snapshots(&self, uid: i64, object_id: &str) -> Vec<CollabSnapshot> {
+    self.read_txn().get_snapshots(uid, object_id)
+  }
+
+  fn create_snapshot(
+    &self,
+    uid: i64,
+    object_id: &str,
+    _title: String,
+    _collab_type: String,
+    snapshot_data: Vec<u8>,
+  ) -> Result<(), PersistenceError> {
+    self.with_write_txn(|txn| {
+      txn.create_snapshot_with_data(uid, object_id, snapshot_data)?;
+      Ok(())
+    })
+  }
+}
+
+#[cfg(feature = "disk_sqlite")]
+impl SnapshotPersistence for Arc<collab_persistence::kv::sqlite_kv::SqliteCollabDB> {
+  fn get_snapshots(&self, uid: i64, object_id: &str) -> Vec<CollabSnapshot> {
+    match self.read_txn() {
+      Ok(txn) => txn.get_snapshots(uid, object_id),
+      Err(e) => {
+        tracing::error!("🔴 get snapshots of doc:{} failed: {}", object_id, e);
+        vec![]
+      },
+    }
+  }
+
+  fn create_snapshot(
+    &self,
+    uid: i64,
+    object_id: &str,
+    _title: String,
+    _collab_type: String,
+    snapshot_data: Vec<u8>,
+  ) -> Result<(), PersistenceError> {
+    self.with_write_txn(|txn| {
+      txn.create_snapshot_with_data(uid, object_id, snapshot_data)?;
+      Ok(())
+    })
+  }
+}
+
 pub fn calculate_snapshot_diff(
   uid: i64,
   object_id: &str,
   old_snapshot: &[u8],
   new_snapshot: &[u8],
+) -> Result<String, anyhow::Error> {
+  calculate_snapshot_diff_with_extractor(uid, object_id, old_snapshot, new_snapshot, None)
+}
+
+/// Same as [calculate_snapshot_diff], but the snapshots will be converted into plain text with
+/// the given [PlainTextExtractor]. For example, the document's extractor only returns the text
+/// of the blocks.
+pub fn calculate_snapshot_diff_with_extractor(
+  uid: i64,
+  object_id: &str,
+  old_snapshot: &[u8],
+  new_snapshot: &[u8],
+  extractor: Option<Arc<dyn PlainTextExtractor>>,
 ) -> Result<String, anyhow::Error> {
   if old_snapshot.is_empty() {
     return Ok("".to_string());
@@ -183,8 +268,8 @@ pub fn calculate_snapshot_diff(
     ));
   }
 
-  let old = try_decode_snapshot(uid, object_id, old_snapshot)?;
-  let new = try_decode_snapshot(uid, object_id, new_snapshot)?;
+  let old = try_decode_snapshot_with_extractor(uid, object_id, old_snapshot, extractor.clone())?;
+  let new = try_decode_snapshot_with_extractor(uid, object_id, new_snapshot, extractor)?;
 
   let mut display_str = String::new();
   let diff = TextDiff::from_lines(&old, &new);
@@ -203,12 +288,27 @@ pub fn try_decode_snapshot(
   uid: i64,
   object_id: &str,
   data: &[u8],
+) -> Result<String, PersistenceError> {
+  try_decode_snapshot_with_extractor(uid, object_id, data, None)
+}
+
+/// Decode the snapshot data and return its plain text. If the extractor is None, the
+/// [DefaultPlainTextExtractor](collab::core::plain_text::DefaultPlainTextExtractor) will be used.
+pub fn try_decode_snapshot_with_extractor(
+  uid: i64,
+  object_id: &str,
+  data: &[u8],
+  extractor: Option<Arc<dyn PlainTextExtractor>>,
 ) -> Result<String, PersistenceError> {
   let mut decoded_str = String::new();
   match {
     let mut wrapper = AssertUnwindSafe(&mut decoded_str);
+    let extractor = AssertUnwindSafe(extractor);
     panic::catch_unwind(move || {
       let collab = Collab::new(uid, object_id, vec![]);
+      if let Some(extractor) = extractor.as_ref() {
+        collab.set_plain_text_extractor(extractor.clone());
+      }
       if let Ok(update) = Update::decode_v1(data) {
         let mut txn = collab.transact_mut();
         txn.apply_update(update);
@@ -1,15 +1,17 @@
 use std::sync::Arc;
 
 use collab::core::collab::MutexCollab;
+use collab::core::collab_state::CollabStateHandle;
+use collab::core::encoding::EncoderVersion;
 use collab::core::origin::CollabOrigin;
 use collab::preclude::CollabPlugin;
 use collab_sync::client::sync::SyncQueue;
 
 use collab_sync::client::sink::SinkConfig;
 use collab_sync::msg::{CSClientUpdate, CollabMessage};
+use collab_sync::protocol::update_message;
 use futures_util::{SinkExt, StreamExt};
 use y_sync::awareness::Awareness;
-use y_sync::sync::{Message, SyncMessage};
 use yrs::updates::encoder::Encode;
 use yrs::Transaction;
 
@@ -44,6 +46,18 @@ impl<Sink, Stream> SyncPlugin<Sink, Stream> {
       object_id: object_id.to_string(),
     }
   }
+
+  /// Propose the given [EncoderVersion] to the server. The updates are sent with the v1
+  /// encoding until the server accepts it.
+  pub fn with_encoder_version<E>(self, version: EncoderVersion) -> Self
+  where
+    E: std::error::Error + Send + Sync + 'static,
+    Sink: SinkExt<CollabMessage, Error = E> + Send + Sync + Unpin + 'static,
+    Stream: StreamExt<Item = Result<CollabMessage, E>> + Send + Sync + Unpin + 'static,
+  {
+    self.sync_queue.set_preferred_encoder_version(version);
+    self
+  }
 }
 
 impl<E, Sink, Stream> CollabPlugin for SyncPlugin<Sink, Stream>
@@ -52,6 +66,10 @@ where
   Sink: SinkExt<CollabMessage, Error = E> + Send + Sync + Unpin + 'static,
   Stream: StreamExt<Item = Result<CollabMessage, E>> + Send + Sync + Unpin + 'static,
 {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    self.sync_queue.set_state_handle(handle);
+  }
+
   fn did_init(&self, awareness: &Awareness, _object_id: &str, _txn: &Transaction) {
     self.sync_queue.notify(awareness);
   }
@@ -64,11 +82,25 @@ where
 
     tokio::spawn(async move {
       if let Some(sync_queue) = weak_sync_queue.upgrade() {
-        let payload = Message::Sync(SyncMessage::Update(update)).encode_v1();
+        // The collab passes the v1 update, convert it to the negotiated version
+        let version = sync_queue.encoder_version();
+        let update = match EncoderVersion::V1.convert_update(&update, version) {
+          Ok(update) => update,
+          Err(e) => {
+            tracing::error!("🔴Failed to encode the update with {:?}: {:?}", version, e);
+            return;
+          },
+        };
+        let payload = update_message(update, version).encode_v1();
         sync_queue.queue_msg(|msg_id| {
           CSClientUpdate::new(cloned_origin, object_id, msg_id, payload).into()
         });
+        sync_queue.did_queue_local_update();
       }
     });
   }
+
+  fn will_close(&self, _object_id: &str) {
+    self.sync_queue.clear_state_handle();
+  }
 }
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use collab::preclude::{CollabBuilder, ReadTxn};
+use collab_persistence::doc::YrsDocAction;
+use collab_persistence::kv::rocks_kv::RocksCollabDB;
+use collab_plugins::disk::rocksdb::RocksdbDiskPlugin;
+use serde_json::json;
+use tempfile::TempDir;
+use yrs::updates::encoder::Encode;
+
+#[tokio::test]
+async fn save_and_reopen_branch_test() {
+  let uid = 1;
+  let db = Arc::new(RocksCollabDB::open(TempDir::new().unwrap().into_path()).unwrap());
+  let parent = CollabBuilder::new(uid, "1")
+    .with_plugin(RocksdbDiskPlugin::new(uid, db.clone()))
+    .build();
+  parent.initial();
+  parent.lock().insert("title", "hello");
+
+  // Fork the parent and store the branch under its own object id
+  let disk_plugin = RocksdbDiskPlugin::new(uid, db.clone());
+  let branch = CollabBuilder::new(uid, "1_draft")
+    .with_plugin(disk_plugin.clone())
+    .build_branch(&parent.lock())
+    .unwrap();
+  branch.collab().initial();
+  branch.collab().lock().insert("title", "hello world");
+  disk_plugin.save_branch(&branch).unwrap();
+  assert!(db.read_txn().is_exist(uid, "1_draft"));
+  drop(branch);
+
+  // Reopen the branch and merge it back into the parent
+  let meta = disk_plugin.get_branch_meta("1_draft").unwrap();
+  assert_eq!(meta.parent_object_id, "1");
+  let branch = CollabBuilder::new(uid, "1_draft")
+    .with_plugin(RocksdbDiskPlugin::new(uid, db.clone()))
+    .build_branch_with_meta(meta);
+  branch.collab().initial();
+  assert_eq!(
+    branch.collab().to_json_value(),
+    json!({ "title": "hello world" })
+  );
+  assert_eq!(parent.to_json_value(), json!({ "title": "hello" }));
+  let meta = branch.merge_into(&parent.lock()).unwrap();
+  assert_eq!(parent.to_json_value(), json!({ "title": "hello world" }));
+
+  // The merged meta is persisted, so the reopened branch doesn't merge the same changes again
+  disk_plugin.save_branch(&branch).unwrap();
+  assert_eq!(disk_plugin.get_branch_meta("1_draft").unwrap(), meta);
+  assert_eq!(
+    meta.parent_state_vector,
+    parent.lock().transact().state_vector().encode_v1()
+  );
+
+  disk_plugin.delete_branch("1_draft").unwrap();
+  assert!(!db.read_txn().is_exist(uid, "1_draft"));
+  assert!(disk_plugin.get_branch_meta("1_draft").is_none());
+}
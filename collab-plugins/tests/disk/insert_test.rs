@@ -67,6 +67,10 @@ async fn insert_multiple_changes_and_restore_from_disk() {
         id: doc_id.clone(),
         expected: 4,
       },
+      AssertDiskUpdateCountInStats {
+        id: doc_id.clone(),
+        expected: 4,
+      },
       CloseDocument {
         id: doc_id.to_string(),
       },
@@ -131,3 +135,47 @@ async fn insert_multiple_docs() {
     ])
     .await;
 }
+
+#[tokio::test]
+async fn compact_and_restore_from_disk() {
+  let mut test = CollabPersistenceTest::new(CollabPersistenceConfig::new());
+  let doc_id = "1".to_string();
+  test
+    .run_scripts(vec![
+      CreateDocumentWithDiskPlugin {
+        id: doc_id.clone(),
+        plugin: disk_plugin(test.uid),
+      },
+      InsertKeyValue {
+        id: doc_id.clone(),
+        key: "1".to_string(),
+        value: "a".into(),
+      },
+      InsertKeyValue {
+        id: doc_id.clone(),
+        key: "1".to_string(),
+        value: "b".into(),
+      },
+      AssertNumOfUpdates {
+        id: doc_id.clone(),
+        expected: 2,
+      },
+      CompactDocument { id: doc_id.clone() },
+      AssertNumOfUpdates {
+        id: doc_id.clone(),
+        expected: 0,
+      },
+      CloseDocument {
+        id: doc_id.to_string(),
+      },
+      OpenDocumentWithDiskPlugin {
+        id: doc_id.to_string(),
+      },
+      GetValue {
+        id: doc_id,
+        key: "1".to_string(),
+        expected: Some("b".into()),
+      },
+    ])
+    .await;
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use collab::preclude::CollabBuilder;
+use collab_plugins::disk::kv::mem_kv::MemCollabDB;
+use collab_plugins::disk::memory::MemoryDiskPlugin;
+use collab_plugins::disk::YrsDocAction;
+use serde_json::json;
+
+#[tokio::test]
+async fn memory_disk_plugin_restore_test() {
+  let db = Arc::new(MemCollabDB::new());
+  {
+    let collab = CollabBuilder::new(1, "1")
+      .with_plugin(MemoryDiskPlugin::new(1, db.clone()))
+      .build();
+    collab.lock().initialize();
+    collab.lock().insert("1", "a");
+    collab.lock().insert("2", "b");
+  }
+  assert!(db.read_txn().is_exist(1, "1"));
+  assert_eq!(db.read_txn().number_of_updates(1, "1"), 2);
+
+  let collab = CollabBuilder::new(1, "1")
+    .with_plugin(MemoryDiskPlugin::new(1, db.clone()))
+    .build();
+  collab.lock().initialize();
+  assert_eq!(collab.lock().to_json_value(), json!({"1": "a", "2": "b"}));
+}
+
+#[tokio::test]
+async fn memory_disk_plugin_compact_test() {
+  let db = Arc::new(MemCollabDB::new());
+  let collab = CollabBuilder::new(1, "1")
+    .with_plugin(MemoryDiskPlugin::new(1, db.clone()))
+    .build();
+  collab.lock().initialize();
+  for i in 0..5 {
+    collab.lock().insert("1", i.to_string());
+  }
+  assert_eq!(db.read_txn().number_of_updates(1, "1"), 5);
+
+  let collab = collab.lock();
+  collab.compact(&collab.snapshot()).unwrap();
+  assert_eq!(db.read_txn().number_of_updates(1, "1"), 0);
+  assert_eq!(collab.to_json_value(), json!({"1": "4"}));
+}
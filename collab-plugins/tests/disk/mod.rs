@@ -1,5 +1,8 @@
+mod branch_test;
 mod delete_test;
 mod insert_test;
+mod memory_test;
 mod script;
 mod snapshot_test;
+mod sqlite_test;
 mod undo_test;
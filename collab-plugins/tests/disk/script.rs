@@ -44,6 +44,10 @@ pub enum Script {
     key: String,
     expected: Option<Any>,
   },
+  /// Compact the document up to its current state.
+  CompactDocument {
+    id: String,
+  },
   AssertSnapshot {
     id: String,
     index: u32,
@@ -57,6 +61,10 @@ pub enum Script {
     id: String,
     expected: usize,
   },
+  AssertDiskUpdateCountInStats {
+    id: String,
+    expected: usize,
+  },
   AssertNumOfDocuments {
     expected: usize,
   },
@@ -235,6 +243,11 @@ impl CollabPersistenceTest {
           .map(|value| Any::String(value.into_boxed_str()));
         assert_eq!(text, expected)
       },
+      Script::CompactDocument { id } => {
+        let collab = self.collab_by_id.get(&id).unwrap().lock();
+        let snapshot = collab.snapshot();
+        collab.compact(&snapshot).unwrap();
+      },
       Script::AssertNumOfUpdates { id, expected } => {
         let updates = self
           .disk_plugin
@@ -243,6 +256,10 @@ impl CollabPersistenceTest {
           .unwrap();
         assert_eq!(updates.len(), expected)
       },
+      Script::AssertDiskUpdateCountInStats { id, expected } => {
+        let stats = self.collab_by_id.get(&id).unwrap().lock().stats().unwrap();
+        assert_eq!(stats.disk_update_count, Some(expected));
+      },
       Script::AssertNumOfSnapshots { id, expected } => {
         let snapshot_plugin =
           self.make_snapshot_plugin(id.clone(), self.collab_by_id.get(&id).unwrap().clone());
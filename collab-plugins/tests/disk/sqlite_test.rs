@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use collab::preclude::CollabBuilder;
+use collab_plugins::disk::kv::sqlite_kv::SqliteCollabDB;
+use collab_plugins::disk::sqlite::SqliteDiskPlugin;
+use collab_plugins::disk::YrsDocAction;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn sqlite_disk_plugin_restore_test() {
+  let tempdir = TempDir::new().unwrap();
+  let db_path = tempdir.path().join("collab.db");
+  {
+    let db = Arc::new(SqliteCollabDB::open(&db_path).unwrap());
+    let collab = CollabBuilder::new(1, "1")
+      .with_plugin(SqliteDiskPlugin::new(1, db.clone()))
+      .build();
+    collab.lock().initialize();
+    collab.lock().insert("1", "a");
+    collab.lock().insert("2", "b");
+    assert_eq!(db.read_txn().unwrap().number_of_updates(1, "1"), 2);
+  }
+
+  // Reopen the database file.
+  let db = Arc::new(SqliteCollabDB::open(&db_path).unwrap());
+  assert!(db.read_txn().unwrap().is_exist(1, "1"));
+  let collab = CollabBuilder::new(1, "1")
+    .with_plugin(SqliteDiskPlugin::new(1, db))
+    .build();
+  collab.lock().initialize();
+  assert_eq!(collab.lock().to_json_value(), json!({"1": "a", "2": "b"}));
+}
+
+#[tokio::test]
+async fn sqlite_disk_plugin_compact_test() {
+  let tempdir = TempDir::new().unwrap();
+  let db = Arc::new(SqliteCollabDB::open(tempdir.path().join("collab.db")).unwrap());
+  let collab = CollabBuilder::new(1, "1")
+    .with_plugin(SqliteDiskPlugin::new(1, db.clone()))
+    .build();
+  collab.lock().initialize();
+  for i in 0..5 {
+    collab.lock().insert("1", i.to_string());
+  }
+  assert_eq!(db.read_txn().unwrap().number_of_updates(1, "1"), 5);
+
+  let collab = collab.lock();
+  collab.compact(&collab.snapshot()).unwrap();
+  assert_eq!(db.read_txn().unwrap().number_of_updates(1, "1"), 0);
+  assert_eq!(collab.to_json_value(), json!({"1": "4"}));
+}
@@ -7,6 +7,7 @@ use tracing_subscriber::EnvFilter;
 mod cloud_storage;
 mod disk;
 mod mock_sync;
+mod schema;
 mod sync;
 mod util;
 
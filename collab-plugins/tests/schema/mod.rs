@@ -0,0 +1 @@
+mod validation_test;
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use collab::core::collab::MutexCollab;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::{
+  lib0Any, Collab, CollabSchema, MapPrelim, MapSchema, SchemaRegistry, SchemaType, Transact,
+};
+use collab_plugins::schema::{SchemaValidationMode, SchemaValidationPlugin};
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+fn workspace_schema() -> CollabSchema {
+  CollabSchema::new(
+    "workspace",
+    MapSchema::new()
+      .required("views", SchemaType::map_of(SchemaType::Any))
+      .optional("name", SchemaType::String)
+      .optional("icon", SchemaType::String),
+  )
+}
+
+fn make_collab(plugin: Arc<SchemaValidationPlugin>) -> MutexCollab {
+  let collab = MutexCollab::new(
+    CollabOrigin::Client(CollabClient::new(1, "1")),
+    "1",
+    vec![plugin],
+  );
+  collab.lock().initialize();
+  collab.lock().insert("views", MapPrelim::<lib0Any>::new());
+  collab
+}
+
+fn apply_remote_update(collab: &MutexCollab, f: impl FnOnce(&Collab)) -> Vec<u8> {
+  let remote_collab = Collab::new(2, "1", vec![]);
+  f(&remote_collab);
+  let update = remote_collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+
+  let collab = collab.lock();
+  let mut txn = collab.get_doc().transact_mut_with(CollabOrigin::Server);
+  txn.apply_update(Update::decode_v1(&update).unwrap());
+  update
+}
+
+#[tokio::test]
+async fn report_remote_violation_test() {
+  let plugin = Arc::new(SchemaValidationPlugin::new(Arc::new(workspace_schema())));
+  let mut rx = plugin.subscribe_violation();
+  let collab = make_collab(plugin.clone());
+
+  // The local updates are not validated.
+  collab.lock().insert("name", lib0Any::BigInt(1));
+  assert!(rx.try_recv().is_err());
+
+  // Only the paths that are changed by the remote update are validated.
+  apply_remote_update(&collab, |remote| {
+    remote.insert("icon", lib0Any::Bool(true));
+  });
+  let report = rx.recv().await.unwrap();
+  assert_eq!(report.object_id, "1");
+  assert_eq!(report.origin, CollabOrigin::Server);
+  let paths = report
+    .violations
+    .iter()
+    .map(|violation| violation.path.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(paths, vec!["/icon"]);
+  assert!(!collab.lock().is_quarantined());
+}
+
+#[tokio::test]
+async fn quarantine_remote_update_test() {
+  let registry = SchemaRegistry::new().with_schema(workspace_schema());
+  let plugin = Arc::new(
+    SchemaValidationPlugin::from_registry(&registry, "workspace")
+      .unwrap()
+      .with_mode(SchemaValidationMode::Quarantine),
+  );
+  let mut rx = plugin.subscribe_violation();
+  let collab = make_collab(plugin.clone());
+
+  // The valid remote update is not quarantined.
+  apply_remote_update(&collab, |remote| {
+    remote.insert("name", "my workspace");
+  });
+  assert!(!collab.lock().is_quarantined());
+
+  let update = apply_remote_update(&collab, |remote| {
+    remote.insert("icon", lib0Any::BigInt(1));
+  });
+  assert!(collab.lock().is_quarantined());
+  let report = rx.recv().await.unwrap();
+  assert_eq!(report.violations[0].path, "/icon");
+
+  let released = collab.lock().release_quarantine();
+  assert_eq!(released.len(), 1);
+  assert_eq!(released[0].update, update);
+  assert!(!collab.lock().is_quarantined());
+}
+
+#[test]
+fn create_plugin_from_unknown_collab_type_test() {
+  let registry = SchemaRegistry::new().with_schema(workspace_schema());
+  assert!(SchemaValidationPlugin::from_registry(&registry, "document").is_none());
+}
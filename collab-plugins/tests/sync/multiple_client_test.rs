@@ -1,7 +1,8 @@
+use collab::core::encoding::EncoderVersion;
 use collab::preclude::MapRefExtension;
 use serde_json::json;
 
-use crate::util::{spawn_client, spawn_server, wait_one_sec};
+use crate::util::{spawn_client, spawn_client_with_encoder_version, spawn_server, wait_one_sec};
 
 #[tokio::test]
 async fn open_existing_doc_with_different_client_test() {
@@ -54,6 +55,49 @@ async fn single_write_sync_with_server_test() {
   assert_eq!(client_1.to_json_value(), client2.to_json_value());
 }
 
+#[tokio::test]
+async fn sync_between_clients_with_different_encoder_versions_test() {
+  let object_id = "1";
+
+  let server = spawn_server(object_id).await.unwrap();
+  let (_, client_1) =
+    spawn_client_with_encoder_version(1, object_id, server.address, EncoderVersion::V2)
+      .await
+      .unwrap();
+  let (_, client_2) = spawn_client(1, object_id, server.address).await.unwrap();
+  wait_one_sec().await;
+  {
+    let client = client_1.lock();
+    client
+      .with_transact_mut(|txn| {
+        let map = client.get_map_with_txn(txn, vec!["map"]).unwrap();
+        map.insert_with_txn(txn, "task3", "c");
+      })
+      .unwrap();
+  }
+  {
+    let client = client_2.lock();
+    client
+      .with_transact_mut(|txn| {
+        let map = client.get_map_with_txn(txn, vec!["map"]).unwrap();
+        map.insert_with_txn(txn, "task4", "d");
+      })
+      .unwrap();
+  }
+  wait_one_sec().await;
+
+  let expected = json!( {
+    "map": {
+      "task1": "a",
+      "task2": "b",
+      "task3": "c",
+      "task4": "d",
+    }
+  });
+  assert_json_diff::assert_json_eq!(client_1.to_json_value(), expected);
+  assert_json_diff::assert_json_eq!(client_2.to_json_value(), expected);
+}
+
 // Different clients write to the same document
 #[tokio::test]
 async fn two_writers_test() {
@@ -3,6 +3,7 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 use collab::core::collab::MutexCollab;
+use collab::core::encoding::EncoderVersion;
 use collab::core::origin::{CollabClient, CollabOrigin};
 use collab::preclude::MapRefExtension;
 use collab_persistence::kv::rocks_kv::RocksCollabDB;
@@ -39,6 +40,16 @@ pub async fn spawn_client(
   uid: i64,
   object_id: &str,
   address: SocketAddr,
+) -> std::io::Result<(Arc<RocksCollabDB>, Arc<MutexCollab>)> {
+  spawn_client_with_encoder_version(uid, object_id, address, EncoderVersion::V1).await
+}
+
+/// Like [spawn_client], but the client proposes the given [EncoderVersion] to the server.
+pub async fn spawn_client_with_encoder_version(
+  uid: i64,
+  object_id: &str,
+  address: SocketAddr,
+  version: EncoderVersion,
 ) -> std::io::Result<(Arc<RocksCollabDB>, Arc<MutexCollab>)> {
   let stream = TcpSocket::new_v4()?.connect(address).await?;
   let origin = origin_from_tcp_stream(&stream);
@@ -48,7 +59,8 @@ pub async fn spawn_client(
   // sync
   let stream = CollabStream::new(reader, CollabMsgCodec::default());
   let sink = CollabSink::new(writer, CollabMsgCodec::default());
-  let sync_plugin = SyncPlugin::new(origin, object_id, collab.clone(), sink, stream);
+  let sync_plugin =
+    SyncPlugin::new(origin, object_id, collab.clone(), sink, stream).with_encoder_version(version);
   collab.lock().add_plugin(Arc::new(sync_plugin));
 
   // disk
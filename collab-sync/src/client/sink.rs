@@ -142,6 +142,28 @@ where
     self.notify();
   }
 
+  /// Send the pending messages without waiting for the next interval of the
+  /// [SinkStrategy::FixInterval].
+  pub fn flush(&self) {
+    if let SinkStrategy::FixInterval(duration) = &self.config.strategy {
+      if let Ok(mut instant) = self.instant.try_lock() {
+        if let Some(expired) = Instant::now().checked_sub(*duration) {
+          *instant = expired;
+        }
+      }
+    }
+    self.notify();
+  }
+
+  /// Returns true if there are messages that are not acknowledged by the remote yet.
+  pub fn has_pending_msgs(&self) -> bool {
+    self
+      .pending_msgs
+      .lock()
+      .iter()
+      .any(|pending_msg| !pending_msg.state().is_done())
+  }
+
   /// Notify the sink to process the next message and mark the current message as done.
   pub async fn ack_msg(&self, msg_id: MsgId) {
     if let Some(mut pending_msg) = self.pending_msgs.lock().peek_mut() {
@@ -3,9 +3,12 @@ use std::ops::Deref;
 use std::sync::{Arc, Weak};
 
 use collab::core::collab::MutexCollab;
+use collab::core::collab_state::{CollabState, CollabStateHandle};
+use collab::core::encoding::EncoderVersion;
 use collab::core::origin::CollabOrigin;
 use futures_util::{SinkExt, StreamExt};
 use lib0::decoding::Cursor;
+use parking_lot::RwLock;
 use tokio::spawn;
 use tokio::sync::watch;
 use tokio::task::JoinHandle;
@@ -17,7 +20,7 @@ use yrs::updates::encoder::{Encode, Encoder, EncoderV1};
 use crate::client::sink::{CollabSink, CollabSinkRunner, DefaultMsgIdCounter, SinkConfig};
 use crate::error::SyncError;
 use crate::msg::{CSClientInit, CSClientUpdate, CSServerSync, CollabMessage};
-use crate::protocol::{handle_msg, CollabSyncProtocol, DefaultSyncProtocol};
+use crate::protocol::{handle_msg, CollabSyncProtocol, VersionedSyncProtocol};
 
 pub const DEFAULT_SYNC_TIMEOUT: u64 = 2;
 
@@ -32,7 +35,22 @@ pub struct SyncQueue<Sink, Stream> {
   /// the updates from the remote.
   #[allow(dead_code)]
   stream: SyncStream<Sink, Stream>,
-  protocol: DefaultSyncProtocol,
+  protocol: VersionedSyncProtocol,
+  /// Used to publish [CollabState::Syncing], [CollabState::Synced] and [CollabState::Offline].
+  state: SyncStateHandle,
+}
+
+/// The [CollabStateHandle] is set after the [SyncQueue] is created, when the plugin is
+/// attached to the [Collab](collab::preclude::Collab).
+#[derive(Clone, Default)]
+pub(crate) struct SyncStateHandle(Arc<RwLock<Option<CollabStateHandle>>>);
+
+impl SyncStateHandle {
+  fn set(&self, state: CollabState, reason: &str) {
+    if let Some(handle) = self.0.read().as_ref() {
+      handle.set(state, reason);
+    }
+  }
 }
 
 impl<E, Sink, Stream> SyncQueue<Sink, Stream>
@@ -49,7 +67,7 @@ where
     collab: Arc<MutexCollab>,
     config: SinkConfig,
   ) -> Self {
-    let protocol = DefaultSyncProtocol;
+    let protocol = VersionedSyncProtocol::default();
     let (notifier, notifier_rx) = watch::channel(false);
     let sink = Arc::new(CollabSink::new(
       sink,
@@ -61,6 +79,7 @@ where
     spawn(CollabSinkRunner::run(Arc::downgrade(&sink), notifier_rx));
     let cloned_protocol = protocol.clone();
     let object_id = object_id.to_string();
+    let state = SyncStateHandle::default();
     let stream = SyncStream::new(
       origin.clone(),
       object_id.to_string(),
@@ -68,6 +87,7 @@ where
       protocol,
       collab,
       sink.clone(),
+      state.clone(),
     );
 
     Self {
@@ -76,10 +96,44 @@ where
       sink,
       stream,
       protocol: cloned_protocol,
+      state,
     }
   }
 
+  /// Set the [EncoderVersion] that is proposed to the server when the sync starts. It must be
+  /// called before the [SyncQueue::notify].
+  pub fn set_preferred_encoder_version(&self, version: EncoderVersion) {
+    self.protocol.set_preferred_encoder_version(version);
+  }
+
+  /// The [EncoderVersion] of the updates that are sent to the server. It's v1 until the server
+  /// accepts the preferred version.
+  pub fn encoder_version(&self) -> EncoderVersion {
+    self.protocol.encoder_version()
+  }
+
+  pub fn set_state_handle(&self, handle: CollabStateHandle) {
+    *self.state.0.write() = Some(handle);
+  }
+
+  /// Release the [CollabStateHandle]. The state is not published after this.
+  pub fn clear_state_handle(&self) {
+    self.state.0.write().take();
+  }
+
+  /// Publish [CollabState::Syncing] after queuing the local changes.
+  pub fn did_queue_local_update(&self) {
+    self.state.set(
+      CollabState::Syncing,
+      "Sending the local changes to the remote",
+    );
+  }
+
   pub fn notify(&self, awareness: &Awareness) {
+    self.state.set(
+      CollabState::Syncing,
+      "Sending the initial state to the remote",
+    );
     if let Some(payload) = doc_init_state(awareness, &self.protocol) {
       self.sink.queue_msg(|msg_id| {
         CSClientInit::new(self.origin.clone(), self.object_id.clone(), msg_id, payload).into()
@@ -134,6 +188,7 @@ where
     protocol: P,
     collab: Arc<MutexCollab>,
     sink: Arc<CollabSink<Sink, CollabMessage>>,
+    state: SyncStateHandle,
   ) -> Self
   where
     P: CollabSyncProtocol + Send + Sync + 'static,
@@ -147,6 +202,7 @@ where
       weak_collab,
       weak_sink,
       protocol,
+      state,
     ));
     Self {
       collab,
@@ -164,6 +220,7 @@ where
     weak_collab: Weak<MutexCollab>,
     weak_sink: Weak<CollabSink<Sink, CollabMessage>>,
     protocol: P,
+    state: SyncStateHandle,
   ) -> Result<(), SyncError>
   where
     P: CollabSyncProtocol + Send + Sync + 'static,
@@ -175,7 +232,13 @@ where
             SyncStream::<Sink, Stream>::process_message::<P>(
               &origin, &object_id, &protocol, &awareness, &sink, msg,
             )
-            .await?
+            .await?;
+            if !sink.has_pending_msgs() {
+              state.set(
+                CollabState::Synced,
+                "All the local changes were acknowledged",
+              );
+            }
           },
           _ => {
             tracing::trace!("ClientSync is dropped. Stopping receive incoming changes.");
@@ -185,6 +248,7 @@ where
         Err(e) => {
           // If the client has disconnected, the stream will return an error, So stop receiving
           // messages if the client has disconnected.
+          state.set(CollabState::Offline, &e.to_string());
           return Err(SyncError::Internal(Box::new(e)));
         },
       }
@@ -2,6 +2,6 @@ pub mod client;
 
 pub mod error;
 pub mod msg;
-mod protocol;
+pub mod protocol;
 
 pub mod server;
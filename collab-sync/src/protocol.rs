@@ -1,11 +1,44 @@
+use std::sync::Arc;
+
 use collab::core::collab::MutexCollab;
+use collab::core::encoding::EncoderVersion;
 use collab::core::origin::CollabOrigin;
+use parking_lot::RwLock;
 use y_sync::awareness::{Awareness, AwarenessUpdate};
 use y_sync::sync::{Error, Message, SyncMessage};
 use yrs::updates::decoder::Decode;
 use yrs::updates::encoder::{Encode, Encoder};
 use yrs::{ReadTxn, StateVector, Transact, Update};
 
+/// The custom message that negotiates the [EncoderVersion] of the updates. The data is
+/// `[kind, version]`, where the kind is [ENCODER_VERSION_PROPOSAL] or [ENCODER_VERSION_ANSWER].
+pub const MSG_ENCODER_VERSION: u8 = 100;
+/// The sync-step-2 message whose update is encoded with [EncoderVersion::V2].
+pub const MSG_SYNC_STEP_2_V2: u8 = 101;
+/// The update message whose update is encoded with [EncoderVersion::V2].
+pub const MSG_SYNC_UPDATE_V2: u8 = 102;
+
+pub const ENCODER_VERSION_PROPOSAL: u8 = 0;
+pub const ENCODER_VERSION_ANSWER: u8 = 1;
+
+/// Returns the sync-step-2 message for the update that is encoded with the given version.
+/// The v1 update uses the y-sync message, so the peers that don't support the v2 encoding can
+/// still read it.
+pub fn sync_step2_message(update: Vec<u8>, version: EncoderVersion) -> Message {
+  match version {
+    EncoderVersion::V1 => Message::Sync(SyncMessage::SyncStep2(update)),
+    EncoderVersion::V2 => Message::Custom(MSG_SYNC_STEP_2_V2, update),
+  }
+}
+
+/// Returns the update message for the update that is encoded with the given version.
+pub fn update_message(update: Vec<u8>, version: EncoderVersion) -> Message {
+  match version {
+    EncoderVersion::V1 => Message::Sync(SyncMessage::Update(update)),
+    EncoderVersion::V2 => Message::Custom(MSG_SYNC_UPDATE_V2, update),
+  }
+}
+
 // In a client-server model. The client is the initiator of the connection. The server is the
 // responder. The client sends a sync-step-1 message to the server. The server responds with a
 // sync-step-2 message. The client then sends an update message to the server. The server applies
@@ -33,19 +66,81 @@ use yrs::{ReadTxn, StateVector, Transact, Update};
 // |        |<-(9) Broadcast Update
 // |        |             |
 // ********************************
+//
+// The peers send the updates with the v1 encoding unless they agreed on another
+// [EncoderVersion]. The client proposes its preferred version before the sync-step-1 message
+// and the server answers with the version it accepts. Each message records the encoding of its
+// update, so the messages that were sent before the answer can still be read.
 /// A implementation of y-sync [CollabSyncProtocol].
 #[derive(Clone)]
 pub struct DefaultSyncProtocol;
 
 impl CollabSyncProtocol for DefaultSyncProtocol {}
 
+/// A [CollabSyncProtocol] that negotiates the [EncoderVersion] of the updates with the remote.
+/// The clones share the negotiated [EncoderVersion], so one instance should be used for each
+/// connection.
+#[derive(Clone, Default)]
+pub struct VersionedSyncProtocol {
+  encoder_versions: Arc<RwLock<EncoderVersions>>,
+}
+
+#[derive(Default)]
+struct EncoderVersions {
+  preferred: EncoderVersion,
+  negotiated: EncoderVersion,
+}
+
+impl VersionedSyncProtocol {
+  /// Set the [EncoderVersion] that is proposed to the remote when the sync starts.
+  pub fn set_preferred_encoder_version(&self, version: EncoderVersion) {
+    self.encoder_versions.write().preferred = version;
+  }
+}
+
+impl CollabSyncProtocol for VersionedSyncProtocol {
+  fn preferred_encoder_version(&self) -> EncoderVersion {
+    self.encoder_versions.read().preferred
+  }
+
+  fn encoder_version(&self) -> EncoderVersion {
+    self.encoder_versions.read().negotiated
+  }
+
+  fn set_encoder_version(&self, version: EncoderVersion) {
+    self.encoder_versions.write().negotiated = version;
+  }
+}
+
 pub trait CollabSyncProtocol {
+  /// The [EncoderVersion] that is proposed to the remote when the sync starts.
+  fn preferred_encoder_version(&self) -> EncoderVersion {
+    EncoderVersion::V1
+  }
+
+  /// The [EncoderVersion] of the updates that are sent to the remote.
+  fn encoder_version(&self) -> EncoderVersion {
+    EncoderVersion::V1
+  }
+
+  /// Called when the remote agreed on the [EncoderVersion]. The default implementation ignores
+  /// it, so the updates are always sent with the v1 encoding.
+  fn set_encoder_version(&self, _version: EncoderVersion) {}
+
   fn start<E: Encoder>(&self, awareness: &Awareness, encoder: &mut E) -> Result<(), Error> {
     let (sv, update) = {
       let sv = awareness.doc().transact().state_vector();
       let update = awareness.update()?;
       (sv, update)
     };
+    let preferred = self.preferred_encoder_version();
+    if preferred != EncoderVersion::V1 {
+      Message::Custom(
+        MSG_ENCODER_VERSION,
+        vec![ENCODER_VERSION_PROPOSAL, preferred.to_u8()],
+      )
+      .encode(encoder);
+    }
     Message::Sync(SyncMessage::SyncStep1(sv)).encode(encoder);
     Message::Awareness(update).encode(encoder);
     Ok(())
@@ -58,8 +153,10 @@ pub trait CollabSyncProtocol {
     awareness: &Awareness,
     sv: StateVector,
   ) -> Result<Option<Message>, Error> {
-    let update = awareness.doc().transact().encode_state_as_update_v1(&sv);
-    Ok(Some(Message::Sync(SyncMessage::SyncStep2(update))))
+    let doc = awareness.doc();
+    let version = self.encoder_version();
+    let update = version.encode_state_as_update(&doc.transact(), &sv);
+    Ok(Some(sync_step2_message(update, version)))
   }
 
   /// Handle reply for a sync-step-1 send from this replica previously. By default just apply
@@ -89,6 +186,25 @@ pub trait CollabSyncProtocol {
     self.handle_sync_step2(origin, awareness, update)
   }
 
+  /// Handle the [MSG_ENCODER_VERSION] message. The proposed version is accepted if it's known,
+  /// and the answer carries the version that this side actually uses after the proposal.
+  fn handle_encoder_version(&self, data: &[u8]) -> Result<Option<Message>, Error> {
+    match data {
+      [ENCODER_VERSION_PROPOSAL, version] => {
+        self.set_encoder_version(EncoderVersion::from_u8(*version).unwrap_or_default());
+        Ok(Some(Message::Custom(
+          MSG_ENCODER_VERSION,
+          vec![ENCODER_VERSION_ANSWER, self.encoder_version().to_u8()],
+        )))
+      },
+      [ENCODER_VERSION_ANSWER, version] => {
+        self.set_encoder_version(EncoderVersion::from_u8(*version).unwrap_or_default());
+        Ok(None)
+      },
+      _ => Err(Error::Unsupported(MSG_ENCODER_VERSION)),
+    }
+  }
+
   fn handle_auth(
     &self,
     _awareness: &Awareness,
@@ -142,6 +258,14 @@ pub async fn handle_msg<P: CollabSyncProtocol>(
     Message::Sync(msg) => match msg {
       SyncMessage::SyncStep1(sv) => {
         let collab = collab.lock();
+        // The deleted items that the remote side missed were discarded by the compaction, so
+        // it's synced with the full state instead of the missing updates.
+        let sv = if collab.is_behind_compaction_point(&sv) {
+          tracing::debug!("The remote is behind the compaction point, sync with the full state");
+          StateVector::default()
+        } else {
+          sv
+        };
         protocol.handle_sync_step1(collab.get_awareness(), sv)
       },
       SyncMessage::SyncStep2(update) => {
@@ -173,6 +297,23 @@ pub async fn handle_msg<P: CollabSyncProtocol>(
       let mut collab = collab.lock();
       protocol.handle_awareness_update(collab.get_mut_awareness(), update)
     },
+    Message::Custom(MSG_ENCODER_VERSION, data) => protocol.handle_encoder_version(&data),
+    Message::Custom(MSG_SYNC_STEP_2_V2, update) => {
+      let mut collab = collab.lock();
+      protocol.handle_sync_step2(
+        origin,
+        collab.get_mut_awareness(),
+        Update::decode_v2(&update)?,
+      )
+    },
+    Message::Custom(MSG_SYNC_UPDATE_V2, update) => {
+      let mut collab = collab.lock();
+      protocol.handle_update(
+        origin,
+        collab.get_mut_awareness(),
+        Update::decode_v2(&update)?,
+      )
+    },
     Message::Custom(tag, data) => {
       let mut collab = collab.lock();
       protocol.missing_handle(collab.get_mut_awareness(), tag, data)
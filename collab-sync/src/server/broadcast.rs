@@ -1,10 +1,13 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
 use collab::core::collab::MutexCollab;
+use collab::core::encoding::EncoderVersion;
 use futures_util::{SinkExt, StreamExt};
 use lib0::encoding::Write;
 
 use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::CollabPlugin;
 use tokio::select;
 use tokio::sync::broadcast::error::SendError;
 use tokio::sync::broadcast::{channel, Sender};
@@ -15,13 +18,13 @@ use y_sync::awareness::{Awareness, AwarenessUpdate};
 use y_sync::sync::{Message, MessageReader, SyncMessage, MSG_SYNC, MSG_SYNC_UPDATE};
 use yrs::updates::decoder::DecoderV1;
 use yrs::updates::encoder::{Encode, Encoder, EncoderV1};
-use yrs::{ReadTxn, UpdateSubscription};
+use yrs::{ReadTxn, Transaction, TransactionMut, UpdateSubscription};
 
 use crate::error::SyncError;
 use crate::msg::{
   CSAwarenessUpdate, CSServerAck, CSServerBroadcast, CSServerResponse, CollabMessage,
 };
-use crate::protocol::{handle_msg, DefaultSyncProtocol};
+use crate::protocol::{handle_msg, update_message, CollabSyncProtocol, VersionedSyncProtocol};
 
 /// A broadcast can be used to propagate updates produced by yrs [yrs::Doc] and [Awareness]
 /// to subscribes. One broadcast can be used to propagate updates for a single document with
@@ -30,11 +33,13 @@ pub struct CollabBroadcast {
   object_id: String,
   collab: MutexCollab,
   sender: Sender<CollabMessage>,
+  /// The [CollabPlugin::plugin_id] of the [BroadcastPlugin], see [CollabBroadcast::detach].
+  plugin_id: String,
 
   #[allow(dead_code)]
   awareness_sub: awareness::UpdateSubscription,
   #[allow(dead_code)]
-  doc_sub: UpdateSubscription,
+  doc_sub: Option<UpdateSubscription>,
 }
 
 impl CollabBroadcast {
@@ -44,27 +49,38 @@ impl CollabBroadcast {
   ///
   /// The overflow of the incoming events that needs to be propagates will be buffered up to a
   /// provided `buffer_capacity` size.
+  ///
+  /// The updates are received through a plugin, so the updates that the
+  /// [Collab](collab::preclude::Collab) holds back from the plugins are not broadcast. The
+  /// plugins only receive the updates after the collab is initialized, so the updates of an
+  /// uninitialized collab are observed from the document until then.
   pub fn new(object_id: &str, collab: MutexCollab, buffer_capacity: usize) -> Self {
     let object_id = object_id.to_owned();
     let (sender, _) = channel(buffer_capacity);
+    let plugin = BroadcastPlugin::new(sender.clone());
+    let plugin_id = plugin.plugin_id();
     let (doc_sub, awareness_sub) = {
       let mut mutex_collab = collab.lock();
+      let doc_sub = if mutex_collab.get_state().is_uninitialized() {
+        let cloned_oid = object_id.clone();
+        let sink = sender.clone();
+        let did_init = plugin.did_init.clone();
+        let doc_sub = mutex_collab
+          .get_mut_awareness()
+          .doc_mut()
+          .observe_update_v1(move |txn, event| {
+            if !did_init.load(Ordering::SeqCst) {
+              send_update(&sink, &cloned_oid, txn, &event.update);
+            }
+          })
+          .unwrap();
+        Some(doc_sub)
+      } else {
+        None
+      };
 
-      // Observer the document's update and broadcast it to all subscribers.
-      let cloned_oid = object_id.clone();
-      let sink = sender.clone();
-      let doc_sub = mutex_collab
-        .get_mut_awareness()
-        .doc_mut()
-        .observe_update_v1(move |txn, event| {
-          let origin = CollabOrigin::from(txn);
-          let payload = gen_update_message(&event.update);
-          let msg = CSServerBroadcast::new(origin, cloned_oid.clone(), payload);
-          if let Err(_e) = sink.send(msg.into()) {
-            tracing::trace!("Broadcast group is closed");
-          }
-        })
-        .unwrap();
+      // Broadcast the updates of the document to all subscribers.
+      mutex_collab.add_plugin(Arc::new(plugin));
 
       let sink = sender.clone();
       let cloned_oid = object_id.clone();
@@ -87,11 +103,19 @@ impl CollabBroadcast {
       object_id,
       collab,
       sender,
+      plugin_id,
       awareness_sub,
       doc_sub,
     }
   }
 
+  /// Remove the plugin that forwards the updates of the document to this broadcast. Call it
+  /// when the broadcast group is closed but the collab is kept, otherwise the plugin stays in
+  /// the collab until the collab is dropped.
+  pub fn detach(&self) {
+    self.collab.lock().remove_plugin(&self.plugin_id);
+  }
+
   /// Returns a reference to an underlying [MutexCollab] instance.
   pub fn collab(&self) -> &MutexCollab {
     &self.collab
@@ -127,10 +151,15 @@ impl CollabBroadcast {
   {
     tracing::trace!("[💭Server]: new subscriber");
     let sink = Arc::new(Mutex::new(sink));
+    // Each subscriber negotiates its own encoder version. The clones share it, so the sink task
+    // encodes the broadcast updates with the version negotiated by the stream task.
+    let protocol = VersionedSyncProtocol::default();
+
     // Receive a update from the document observer and forward the applied update to all
     // connected subscribers using its Sink.
     let sink_task = {
       let sink = sink.clone();
+      let protocol = protocol.clone();
       let mut receiver = self.sender.subscribe();
       tokio::spawn(async move {
         while let Ok(msg) = receiver.recv().await {
@@ -141,6 +170,7 @@ impl CollabBroadcast {
             }
           }
 
+          let msg = encode_broadcast(msg, protocol.encoder_version());
           tracing::trace!("[💭Server]: {}", msg);
           let mut sink = sink.lock().await;
           if let Err(e) = sink.send(msg).await {
@@ -183,7 +213,7 @@ impl CollabBroadcast {
           for msg in reader {
             match msg {
               Ok(msg) => {
-                let resp = handle_msg(&origin, &DefaultSyncProtocol, &collab, msg).await?;
+                let resp = handle_msg(&origin, &protocol, &collab, msg).await?;
                 // Send the response to the corresponding client
                 if let Some(resp) = resp {
                   let msg =
@@ -223,6 +253,57 @@ impl CollabBroadcast {
   }
 }
 
+/// Sends the updates of the document to the subscribers of the [CollabBroadcast]. It's a
+/// plugin, so the updates that the [Collab](collab::preclude::Collab) holds back from the
+/// plugins, for example, the updates of a quarantined collab, are not broadcast either.
+struct BroadcastPlugin {
+  /// Each broadcast has its own plugin, so the id must be unique in the collab.
+  id: u64,
+  sender: Sender<CollabMessage>,
+  /// Set when the collab is initialized. After that, the updates are received by the plugin
+  /// instead of the observer of the document.
+  did_init: Arc<AtomicBool>,
+}
+
+impl BroadcastPlugin {
+  fn new(sender: Sender<CollabMessage>) -> Self {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    Self {
+      id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+      sender,
+      did_init: Default::default(),
+    }
+  }
+}
+
+impl CollabPlugin for BroadcastPlugin {
+  fn plugin_id(&self) -> String {
+    format!("{}-{}", std::any::type_name::<Self>(), self.id)
+  }
+
+  fn did_init(&self, _awareness: &Awareness, _object_id: &str, _txn: &Transaction) {
+    self.did_init.store(true, Ordering::SeqCst);
+  }
+
+  fn receive_update(&self, object_id: &str, txn: &TransactionMut, update: &[u8]) {
+    send_update(&self.sender, object_id, txn, update);
+  }
+}
+
+fn send_update(
+  sender: &Sender<CollabMessage>,
+  object_id: &str,
+  txn: &TransactionMut,
+  update: &[u8],
+) {
+  let origin = CollabOrigin::from(txn);
+  let payload = gen_update_message(update);
+  let msg = CSServerBroadcast::new(origin, object_id.to_string(), payload);
+  if let Err(_e) = sender.send(msg.into()) {
+    tracing::trace!("Broadcast group is closed");
+  }
+}
+
 fn encode_server_sv(collab: &MutexCollab) -> Vec<u8> {
   let mut encoder = EncoderV1::new();
   let sv = collab.lock().transact().state_vector();
@@ -262,6 +343,44 @@ fn gen_update_message(update: &[u8]) -> Vec<u8> {
   encoder.to_vec()
 }
 
+/// Re-encode the updates of the [CSServerBroadcast] with the given version. The broadcast is
+/// created with the v1 updates, and each subscriber receives them with the version that it
+/// negotiated. The other messages are returned as they are.
+fn encode_broadcast(msg: CollabMessage, version: EncoderVersion) -> CollabMessage {
+  if version == EncoderVersion::V1 || !matches!(msg, CollabMessage::ServerBroadcast(_)) {
+    return msg;
+  }
+  let (origin, payload) = match (msg.origin(), msg.payload()) {
+    (Some(origin), Some(payload)) => (origin.clone(), payload.clone()),
+    _ => return msg,
+  };
+
+  let mut decoder = DecoderV1::from(payload.as_ref());
+  let mut encoder = EncoderV1::new();
+  for reader_msg in MessageReader::new(&mut decoder) {
+    let reader_msg = match reader_msg {
+      Ok(Message::Sync(SyncMessage::Update(update))) => {
+        match EncoderVersion::V1.convert_update(&update, version) {
+          Ok(update) => update_message(update, version),
+          Err(e) => {
+            // The v1 update can be read by every peer, so send the broadcast as it is.
+            tracing::error!(
+              "[🔴Server]: encode broadcast with {:?} failed: {}",
+              version,
+              e
+            );
+            return msg;
+          },
+        }
+      },
+      Ok(reader_msg) => reader_msg,
+      Err(_) => return msg,
+    };
+    reader_msg.encode(&mut encoder);
+  }
+  CSServerBroadcast::new(origin, msg.object_id().to_string(), encoder.to_vec()).into()
+}
+
 fn gen_awareness_update_message(
   awareness: &Awareness,
   event: &awareness::Event,
@@ -7,7 +7,7 @@ use yrs::block::Prelim;
 use yrs::{Array, ArrayRef, MapPrelim, MapRef, ReadTxn, Transact, Transaction, TransactionMut};
 
 use crate::preclude::{CollabContext, MapRefWrapper, YrsValue};
-use crate::util::insert_json_value_to_array_ref;
+use crate::serde::{push_to_array_ref, to_any};
 
 #[derive(Clone)]
 pub struct ArrayRefWrapper {
@@ -40,12 +40,25 @@ impl ArrayRefWrapper {
     });
   }
 
+  /// Serialize the value and push it to the end of the array. If the value is a sequence, each
+  /// of its items is pushed instead.
   pub fn push_json_with_txn<T: Serialize>(&self, txn: &mut TransactionMut, value: T) -> Result<()> {
-    let value = serde_json::to_value(value)?;
-    insert_json_value_to_array_ref(txn, &self.array_ref, &value);
+    match to_any(&value)? {
+      Any::Array(items) => {
+        for item in items.iter() {
+          push_to_array_ref(txn, &self.array_ref, item)?;
+        }
+      },
+      _ => push_to_array_ref(txn, &self.array_ref, &value)?,
+    }
     Ok(())
   }
 
+  pub fn push_map_with_txn(&self, txn: &mut TransactionMut) -> MapRefWrapper {
+    let map_ref = self.array_ref.insert_map_with_txn(txn);
+    MapRefWrapper::new(map_ref, self.collab_ctx.clone())
+  }
+
   pub fn to_map_refs(&self) -> Vec<MapRefWrapper> {
     let txn = self.array_ref.transact();
     self.to_map_refs_with_txn(&txn)
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use yrs::block::ClientID;
+use yrs::types::Value;
+use yrs::{Array, Doc, Map, MapRef, ReadTxn, TransactionMut};
+
+use crate::core::item_index::{ItemIndex, Parent};
+use crate::core::origin::{CollabClient, CollabOrigin};
+use crate::core::permission::CollabPermission;
+use crate::error::CollabError;
+
+/// The root map that stores the [CollabClient] of each yrs client id that changed the document.
+pub const AUTHORS_SECTION: &str = "authors";
+
+/// The author of a piece of content. The [CollabClient] is None if the client that wrote the
+/// content didn't record itself, for example, a client that uses an older version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+  pub client_id: ClientID,
+  pub client: Option<CollabClient>,
+}
+
+/// A consecutive range of a text that was written by the same author. The `index` and `len`
+/// are counted in bytes, the same as the [Text](yrs::Text) of a [Collab](crate::preclude::Collab).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBlame {
+  pub index: u32,
+  pub len: u32,
+  pub author: Author,
+}
+
+/// Records the [CollabClient] of the local yrs client id into the [AUTHORS_SECTION]. It's
+/// done once, after the first local transaction that writes the content of the local client,
+/// and the mapping is written in the same transaction, so the plugins store it together with
+/// that change.
+///
+/// The recording is disabled by default. Yrs uses a new client id for each [Doc], so each
+/// session that writes to the document adds an entry, and the [AUTHORS_SECTION] keeps growing
+/// for as long as the document is edited. Enable it only for the documents that use the blame,
+/// see [Collab::set_author_recording](crate::preclude::Collab::set_author_recording).
+pub(crate) struct AuthorRegistry {
+  client_id: ClientID,
+  authors: MapRef,
+  permission: Arc<CollabPermission>,
+  enabled: AtomicBool,
+  registered: AtomicBool,
+}
+
+impl AuthorRegistry {
+  pub(crate) fn new(doc: &Doc, permission: Arc<CollabPermission>) -> Self {
+    Self {
+      client_id: doc.client_id(),
+      authors: doc.get_or_insert_map(AUTHORS_SECTION),
+      permission,
+      enabled: AtomicBool::new(false),
+      registered: AtomicBool::new(false),
+    }
+  }
+
+  pub(crate) fn set_enabled(&self, enabled: bool) {
+    self.enabled.store(enabled, Ordering::SeqCst);
+  }
+
+  /// Records the local client if the given local transaction has written any content. It's
+  /// called after each transaction of the document.
+  pub(crate) fn register(&self, txn: &mut TransactionMut, local_origin: &CollabOrigin) {
+    if !self.enabled.load(Ordering::SeqCst)
+      || self.registered.load(Ordering::SeqCst)
+      || self.permission.is_read_only()
+      || txn.after_state().get(&self.client_id) == txn.before_state().get(&self.client_id)
+      || &CollabOrigin::from(&*txn) != local_origin
+    {
+      return;
+    }
+    if let CollabOrigin::Client(client) = local_origin {
+      if let Ok(value) = serde_json::to_string(client) {
+        let client_id = self.client_id.to_string();
+        if self.authors.get(txn, &client_id).is_none() {
+          self.authors.insert(txn, client_id, value);
+        }
+        self.registered.store(true, Ordering::SeqCst);
+      }
+    }
+  }
+
+  pub(crate) fn get_authors<T: ReadTxn>(&self, txn: &T) -> HashMap<ClientID, CollabClient> {
+    self
+      .authors
+      .iter(txn)
+      .flat_map(|(client_id, value)| {
+        let client_id = client_id.parse::<ClientID>().ok()?;
+        let client = serde_json::from_str::<CollabClient>(&value.to_string(txn)).ok()?;
+        Some((client_id, client))
+      })
+      .collect()
+  }
+}
+
+/// Returns the blame of each entry of the map.
+pub(crate) fn blame_map(
+  index: &ItemIndex,
+  map: &Parent,
+  authors: &HashMap<ClientID, CollabClient>,
+) -> HashMap<String, Author> {
+  index
+    .map_entries(map)
+    .map(|(key, id)| (key.to_string(), author_of(id.client, authors)))
+    .collect()
+}
+
+/// Returns the blame of each element of the array.
+pub(crate) fn blame_array(
+  index: &ItemIndex,
+  array: &Parent,
+  authors: &HashMap<ClientID, CollabClient>,
+) -> Vec<Author> {
+  index
+    .sequence(array)
+    .iter()
+    .flat_map(|item| (0..item.len).map(move |_| author_of(item.id.client, authors)))
+    .collect()
+}
+
+/// Returns the consecutive runs of the text that were written by the same author.
+pub(crate) fn blame_text(
+  index: &ItemIndex,
+  text: &Parent,
+  authors: &HashMap<ClientID, CollabClient>,
+) -> Vec<TextBlame> {
+  let mut runs: Vec<TextBlame> = vec![];
+  let mut offset = 0;
+  for item in index.sequence(text) {
+    match runs.last_mut() {
+      Some(run) if run.author.client_id == item.id.client => run.len += item.len,
+      _ => runs.push(TextBlame {
+        index: offset,
+        len: item.len,
+        author: author_of(item.id.client, authors),
+      }),
+    }
+    offset += item.len;
+  }
+  runs
+}
+
+fn author_of(client_id: ClientID, authors: &HashMap<ClientID, CollabClient>) -> Author {
+  Author {
+    client_id,
+    client: authors.get(&client_id).cloned(),
+  }
+}
+
+/// Resolve the map, the array or the text at the given path of the `data` section.
+pub(crate) fn resolve_path<T: ReadTxn>(
+  txn: &T,
+  data: &MapRef,
+  path: &[&str],
+) -> Result<Value, CollabError> {
+  let not_found = || CollabError::BlamePathNotFound(path.join("/"));
+  let mut value = Value::YMap(data.clone());
+  for segment in path {
+    value = match &value {
+      Value::YMap(map_ref) => map_ref.get(txn, segment),
+      Value::YArray(array_ref) => segment
+        .parse::<u32>()
+        .ok()
+        .and_then(|position| array_ref.get(txn, position)),
+      _ => None,
+    }
+    .ok_or_else(not_found)?;
+  }
+  Ok(value)
+}
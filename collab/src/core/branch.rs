@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use yrs::types::ToJson;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, StateVector, Transact, Update};
+
+use crate::core::collab::{Collab, MutexCollab, DATA_SECTION};
+use crate::error::CollabError;
+use crate::preclude::JsonValue;
+
+/// The [BranchMeta] records where a branch was forked from. It can be persisted, see
+/// [BranchMeta::to_vec], and used to reopen the branch with
+/// [CollabBuilder::build_branch_with_meta](crate::preclude::CollabBuilder::build_branch_with_meta).
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BranchMeta {
+  /// The object id of the parent [Collab].
+  pub parent_object_id: String,
+  /// The encoded [StateVector] of the parent at fork time. After each merge, it will be
+  /// replaced by the state vector of the parent after merging.
+  pub parent_state_vector: Vec<u8>,
+  pub created_at: i64,
+}
+
+impl BranchMeta {
+  pub fn new(parent_object_id: &str, parent_state_vector: &StateVector) -> Self {
+    let created_at = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|duration| duration.as_secs() as i64)
+      .unwrap_or_default();
+    Self {
+      parent_object_id: parent_object_id.to_string(),
+      parent_state_vector: parent_state_vector.encode_v1(),
+      created_at,
+    }
+  }
+
+  pub fn to_vec(&self) -> Result<Vec<u8>, CollabError> {
+    let data = serde_json::to_vec(self)?;
+    Ok(data)
+  }
+
+  pub fn from_slice(data: &[u8]) -> Result<Self, CollabError> {
+    let meta = serde_json::from_slice(data)?;
+    Ok(meta)
+  }
+}
+
+/// A [CollabBranch] is an isolated copy of a parent [Collab]. The changes made on the branch
+/// are not visible to the parent until [CollabBranch::merge_into] is called, which makes it
+/// possible to edit a document offline, for example, drafts or suggested changes.
+///
+/// The branch has its own object id, so it can be persisted by adding the disk plugin to the
+/// [CollabBuilder](crate::preclude::CollabBuilder) that creates the branch.
+pub struct CollabBranch {
+  collab: MutexCollab,
+  meta: RwLock<BranchMeta>,
+}
+
+impl CollabBranch {
+  pub(crate) fn new(collab: MutexCollab, meta: BranchMeta) -> Self {
+    Self {
+      collab,
+      meta: RwLock::new(meta),
+    }
+  }
+
+  /// Returns the [MutexCollab] of the branch. All the changes should be made on this collab.
+  pub fn collab(&self) -> &MutexCollab {
+    &self.collab
+  }
+
+  pub fn object_id(&self) -> String {
+    self.collab.lock().object_id.clone()
+  }
+
+  pub fn meta(&self) -> BranchMeta {
+    self.meta.read().clone()
+  }
+
+  /// Encode the changes that were made on the branch since it was forked or last merged.
+  pub fn encode_merge_update(&self) -> Result<Vec<u8>, CollabError> {
+    let state_vector = StateVector::decode_v1(&self.meta.read().parent_state_vector)?;
+    let collab = self.collab.lock();
+    let txn = collab.transact();
+    Ok(txn.encode_state_as_update_v1(&state_vector))
+  }
+
+  /// Returns the [MergePreview] of merging the branch into the given parent. The parent is not
+  /// changed.
+  pub fn preview_merge(&self, parent: &Collab) -> Result<MergePreview, CollabError> {
+    self.check_parent(parent)?;
+    let merge_update = self.encode_merge_update()?;
+    let parent_state = {
+      let txn = parent.transact();
+      txn.encode_state_as_update_v1(&StateVector::default())
+    };
+
+    let doc = Doc::new();
+    let data = doc.get_or_insert_map(DATA_SECTION);
+    {
+      let mut txn = doc.transact_mut();
+      txn.apply_update(Update::decode_v1(&parent_state)?);
+      txn.apply_update(Update::decode_v1(&merge_update)?);
+    }
+
+    let before = parent.to_json_value();
+    let after = {
+      let txn = doc.transact();
+      serde_json::to_value(data.to_json(&txn))?
+    };
+    let mut changes = vec![];
+    diff_json_value(&mut vec![], &before, &after, &mut changes);
+    Ok(MergePreview {
+      before,
+      after,
+      changes,
+    })
+  }
+
+  /// Merge the changes of the branch into the given parent. The update is applied with the
+  /// parent's origin, so the parent's plugins will treat it as a local update and persist or
+  /// sync it.
+  ///
+  /// Returns the updated [BranchMeta]. It's only updated in memory, the caller must save it,
+  /// for example, with `RocksdbDiskPlugin::save_branch`. Otherwise, the branch that is reopened
+  /// with the stale meta will merge the changes that were already merged again.
+  pub fn merge_into(&self, parent: &Collab) -> Result<BranchMeta, CollabError> {
+    self.check_parent(parent)?;
+    let update = Update::decode_v1(&self.encode_merge_update()?)?;
+    parent.with_transact_mut(|txn| txn.apply_update(update));
+
+    // The following merge only contains the changes that were made after this merge.
+    let state_vector = parent.transact().state_vector();
+    let mut meta = self.meta.write();
+    meta.parent_state_vector = state_vector.encode_v1();
+    Ok(meta.clone())
+  }
+
+  fn check_parent(&self, parent: &Collab) -> Result<(), CollabError> {
+    if parent.object_id != self.meta.read().parent_object_id {
+      return Err(CollabError::BranchParentMismatch);
+    }
+    Ok(())
+  }
+}
+
+/// The preview of merging a [CollabBranch] into its parent.
+#[derive(Debug, Clone)]
+pub struct MergePreview {
+  /// The json value of the parent's `data` section before merging.
+  pub before: JsonValue,
+  /// The json value of the parent's `data` section after merging.
+  pub after: JsonValue,
+  pub changes: Vec<MergeChange>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MergeChange {
+  Added(Vec<String>),
+  Removed(Vec<String>),
+  Modified(Vec<String>),
+}
+
+fn diff_json_value(
+  path: &mut Vec<String>,
+  before: &JsonValue,
+  after: &JsonValue,
+  changes: &mut Vec<MergeChange>,
+) {
+  match (before, after) {
+    (JsonValue::Object(before), JsonValue::Object(after)) => {
+      let keys = before
+        .keys()
+        .chain(after.keys())
+        .map(|key| (key.clone(), ()))
+        .collect::<BTreeMap<_, _>>();
+      for key in keys.into_keys() {
+        path.push(key.clone());
+        match (before.get(&key), after.get(&key)) {
+          (Some(before), Some(after)) => diff_json_value(path, before, after, changes),
+          (None, Some(_)) => changes.push(MergeChange::Added(path.clone())),
+          (Some(_), None) => changes.push(MergeChange::Removed(path.clone())),
+          (None, None) => {},
+        }
+        path.pop();
+      }
+    },
+    (before, after) => {
+      if before != after {
+        changes.push(MergeChange::Modified(path.clone()));
+      }
+    },
+  }
+}
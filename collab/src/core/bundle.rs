@@ -0,0 +1,187 @@
+use lib0::decoding::{Cursor, Read};
+use lib0::encoding::Write;
+use serde::{Deserialize, Serialize};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{ReadTxn, Snapshot, StateVector, Update};
+
+use crate::core::encoding::EncoderVersion;
+use crate::error::CollabError;
+use crate::util::timestamp_millis;
+
+// The layout of a bundle file:
+//
+//     MAGIC   FORMAT_VERSION   header   state   snapshot_count   snapshot*   checksum
+//
+// The header, the state and each snapshot are length-prefixed. The header is json, so new
+// fields can be added without changing the format version. The checksum is the md5 digest of
+// all the bytes before it.
+const BUNDLE_MAGIC: &[u8; 6] = b"COLLAB";
+const CHECKSUM_LEN: usize = 16;
+
+/// The version of the bundle layout. A bundle with a newer version is rejected.
+pub const BUNDLE_FORMAT_VERSION: u8 = 1;
+
+/// The file extension of the bundle files.
+pub const BUNDLE_FILE_EXTENSION: &str = "collab";
+
+/// Describes the content of a [CollabBundle].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleHeader {
+  pub object_id: String,
+  /// The type of the collab object, for example, "document" or "database". It's not used by the
+  /// [Collab](crate::preclude::Collab) itself, the application uses it to open the right
+  /// object after importing.
+  pub collab_type: String,
+  /// The encoding of the [CollabBundle::state].
+  pub encoder_version: EncoderVersion,
+  /// The unix timestamp, in milliseconds, when the bundle was created.
+  pub created_at: i64,
+}
+
+/// Configures the content of the bundle created by [Collab::export_bundle].
+///
+/// [Collab::export_bundle]: crate::preclude::Collab::export_bundle
+#[derive(Debug, Clone, Default)]
+pub struct BundleOptions {
+  pub collab_type: String,
+  /// The encoding of the state. Default is [EncoderVersion::V1].
+  pub encoder_version: EncoderVersion,
+  /// The snapshots that are kept in the bundle, so the history can still be viewed after
+  /// importing. Default is empty.
+  pub snapshots: Vec<Snapshot>,
+}
+
+impl BundleOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn collab_type(mut self, collab_type: &str) -> Self {
+    self.collab_type = collab_type.to_string();
+    self
+  }
+
+  pub fn encoder_version(mut self, encoder_version: EncoderVersion) -> Self {
+    self.encoder_version = encoder_version;
+    self
+  }
+
+  pub fn snapshots(mut self, snapshots: Vec<Snapshot>) -> Self {
+    self.snapshots = snapshots;
+    self
+  }
+}
+
+/// A self-describing file that contains the full state of a collab object. It's used to move an
+/// object between machines, for backups and for attaching to bug reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollabBundle {
+  pub header: BundleHeader,
+  /// The state of the document that is encoded with the [BundleHeader::encoder_version].
+  pub state: Vec<u8>,
+  /// The snapshots that are encoded with [Encode::encode_v1].
+  pub snapshots: Vec<Vec<u8>>,
+}
+
+impl CollabBundle {
+  /// Create a bundle from the state of the document.
+  pub fn from_txn<T: ReadTxn>(txn: &T, object_id: &str, options: BundleOptions) -> Self {
+    let header = BundleHeader {
+      object_id: object_id.to_string(),
+      collab_type: options.collab_type,
+      encoder_version: options.encoder_version,
+      created_at: timestamp_millis(),
+    };
+    let state = options
+      .encoder_version
+      .encode_state_as_update(txn, &StateVector::default());
+    let snapshots = options
+      .snapshots
+      .iter()
+      .map(|snapshot| snapshot.encode_v1())
+      .collect();
+    Self {
+      header,
+      state,
+      snapshots,
+    }
+  }
+
+  /// Encode the bundle into the bytes of a bundle file.
+  pub fn encode(&self) -> Result<Vec<u8>, CollabError> {
+    let header = serde_json::to_vec(&self.header)?;
+    let mut data = Vec::with_capacity(header.len() + self.state.len() + 64);
+    data.write_all(BUNDLE_MAGIC);
+    data.write_u8(BUNDLE_FORMAT_VERSION);
+    data.write_buf(&header);
+    data.write_buf(&self.state);
+    data.write_var(self.snapshots.len() as u32);
+    for snapshot in &self.snapshots {
+      data.write_buf(snapshot);
+    }
+    let checksum = md5::compute(&data);
+    data.write_all(checksum.as_ref());
+    Ok(data)
+  }
+
+  /// Decode the bytes of a bundle file. Returns [CollabError::BundleChecksumMismatch] if the
+  /// file was corrupted.
+  pub fn decode(data: &[u8]) -> Result<Self, CollabError> {
+    if data.len() < BUNDLE_MAGIC.len() + 1 + CHECKSUM_LEN || !data.starts_with(BUNDLE_MAGIC) {
+      return Err(CollabError::InvalidBundle(
+        "not a collab bundle file".to_string(),
+      ));
+    }
+    let (content, checksum) = data.split_at(data.len() - CHECKSUM_LEN);
+    if md5::compute(content).as_ref() != checksum {
+      return Err(CollabError::BundleChecksumMismatch);
+    }
+
+    let mut cursor = Cursor::new(&content[BUNDLE_MAGIC.len()..]);
+    let format_version = cursor.read_u8()?;
+    if format_version > BUNDLE_FORMAT_VERSION {
+      return Err(CollabError::InvalidBundle(format!(
+        "unsupported format version: {}",
+        format_version
+      )));
+    }
+    let header: BundleHeader = serde_json::from_slice(cursor.read_buf()?)?;
+    let state = cursor.read_buf()?.to_vec();
+    let snapshot_count: u32 = cursor.read_var()?;
+    let mut snapshots = Vec::with_capacity(snapshot_count as usize);
+    for _ in 0..snapshot_count {
+      snapshots.push(cursor.read_buf()?.to_vec());
+    }
+    Ok(Self {
+      header,
+      state,
+      snapshots,
+    })
+  }
+
+  /// Returns [CollabError::BundleObjectMismatch] if the bundle doesn't belong to the given
+  /// object.
+  pub fn validate_object_id(&self, object_id: &str) -> Result<(), CollabError> {
+    if self.header.object_id != object_id {
+      return Err(CollabError::BundleObjectMismatch {
+        expected: object_id.to_string(),
+        actual: self.header.object_id.clone(),
+      });
+    }
+    Ok(())
+  }
+
+  pub fn decode_state(&self) -> Result<Update, CollabError> {
+    Ok(self.header.encoder_version.decode_update(&self.state)?)
+  }
+
+  pub fn decode_snapshots(&self) -> Result<Vec<Snapshot>, CollabError> {
+    let snapshots = self
+      .snapshots
+      .iter()
+      .map(|snapshot| Snapshot::decode_v1(snapshot))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(snapshots)
+  }
+}
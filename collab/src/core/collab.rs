@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
@@ -7,23 +8,52 @@ use parking_lot::{Mutex, RwLock};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tokio::sync::watch;
-use y_sync::awareness::Awareness;
-use yrs::block::Prelim;
+use y_sync::awareness::{Awareness, UpdateSubscription as AwarenessUpdateSubscription};
+use yrs::block::{ClientID, Prelim};
 use yrs::types::map::MapEvent;
-use yrs::types::{ToJson, Value};
+use yrs::types::{DeepEventsSubscription, DeepObservable, ToJson, Value};
+use yrs::updates::decoder::Decode;
 use yrs::{
-  ArrayPrelim, ArrayRef, Doc, Map, MapPrelim, MapRef, Observable, Options, ReadTxn, Subscription,
-  Transact, Transaction, TransactionMut, UndoManager, Update, UpdateSubscription,
+  ArrayPrelim, ArrayRef, Doc, Map, MapPrelim, MapRef, Observable, Options, ReadTxn, Snapshot,
+  StateVector, Subscription, Transact, Transaction, TransactionMut, Update, UpdateSubscription,
 };
 
+use crate::core::blame::{
+  blame_array, blame_map, blame_text, resolve_path, Author, AuthorRegistry, TextBlame,
+};
+use crate::core::branch::{BranchMeta, CollabBranch};
+use crate::core::bundle::{BundleOptions, CollabBundle};
 use crate::core::collab_plugin::CollabPlugin;
-use crate::core::collab_state::{CollabState, State};
+use crate::core::collab_state::{CollabState, CollabStateChange, CollabStateHandle, State};
+use crate::core::compaction::{compact_doc, CompactionPoint, CompactionResult};
+use crate::core::item_index::{ItemIndex, Parent};
+use crate::core::json_patch::{
+  apply_json_patch, json_patch_from_events, JsonPatch, PatchOperation,
+};
 use crate::core::map_wrapper::{CustomMapRef, MapRefWrapper};
 use crate::core::origin::{CollabClient, CollabOrigin};
-use crate::core::transaction::TransactionRetry;
+use crate::core::permission::{
+  observe_permission, CollabPermission, PermissionGuard, PermissionPolicy, PermissionViolation,
+  PermissionViolationReceiver, PermissionViolationSender,
+};
+use crate::core::plain_text::{DefaultPlainTextExtractor, PlainTextExtractor};
+use crate::core::quarantine::{observe_remote_paths, Quarantine, QuarantinedUpdate};
+use crate::core::snapshot_view::{encode_state_from_snapshot, SnapshotView};
+use crate::core::stats::CollabStats;
+use crate::core::transaction::{
+  TransactionMetrics, TransactionMetricsSnapshot, TransactionRetry, TransactionRetryConfig,
+};
+use crate::core::undo::{
+  CollabUndoManager, UndoConfig, UndoStackItem, UndoStackNotifier, UndoStackReceiver,
+  UndoStackSender,
+};
 use crate::error::CollabError;
 use crate::preclude::{ArrayRefWrapper, JsonValue};
-use crate::util::insert_json_value_to_map_ref;
+use crate::presence::{
+  get_local_presence, get_peer_presences, set_local_presence, PeerPresence, Presence,
+  PresenceConfig, PresenceEvent, PresenceReceiver, PresenceSender,
+};
+use crate::serde::{from_map_ref, to_map_ref};
 
 pub const DATA_SECTION: &str = "data";
 
@@ -57,14 +87,52 @@ pub struct Collab {
 
   state: Arc<State>,
 
+  /// Used to convert the `data` section into plain text. See [Collab::to_plain_text].
+  plain_text_extractor: RwLock<Arc<dyn PlainTextExtractor>>,
+
   /// The [UndoManager] is used to undo and redo changes. By default, the [UndoManager]
-  /// is disabled. To enable it, call [Collab::enable_undo_manager].
-  undo_manager: Mutex<Option<UndoManager>>,
+  /// is disabled. To enable it, call [Collab::enable_undo_redo].
+  undo_manager: Mutex<Option<CollabUndoManager>>,
+
+  /// The undo managers of the nested types, keyed by the scope id. See [Collab::add_undo_scope].
+  undo_scopes: Mutex<HashMap<String, CollabUndoManager>>,
+
+  /// The retry policy of acquiring the transactions. See [Collab::set_transaction_retry_config].
+  txn_retry_config: Arc<RwLock<TransactionRetryConfig>>,
+  txn_metrics: Arc<TransactionMetrics>,
+
+  /// Notifies the changes of the undo and redo stacks. See [Collab::subscribe_undo_stack].
+  undo_stack_tx: UndoStackSender,
+
+  /// Notifies the presences of the peers. See [Collab::subscribe_presence].
+  presence_tx: PresenceSender,
+  presence_config: Arc<RwLock<PresenceConfig>>,
+
+  /// The read-only mode and the [PermissionPolicy] of the local client.
+  permission: Arc<CollabPermission>,
+  /// Records the [CollabClient] of the local client for the blame. See [Collab::blame_map].
+  authors: Arc<AuthorRegistry>,
+  #[allow(dead_code)]
+  authors_subscription: AfterTransactionSubscription,
+  permission_violation_tx: PermissionViolationSender,
+  permission_subscription: RwLock<Option<DeepEventsSubscription>>,
+  /// Reverts the local changes that are not allowed by the [CollabPermission].
+  permission_guard: Arc<PermissionGuard>,
+  /// Holds back the updates from the plugins after a remote update is rejected. See
+  /// [CollabPlugin::accept_remote_update].
+  quarantine: Arc<Quarantine>,
+  remote_paths_subscription: RwLock<Option<DeepEventsSubscription>>,
+  /// The point of the last [Collab::compact]. It's not part of the document, so it's not synced
+  /// to the peers, the disk plugins store it next to the document.
+  compaction_point: RwLock<Option<CompactionPoint>>,
 
   /// Just binding the data_subscription to the [Collab] struct to prevent it from
   /// being dropped.
   #[allow(dead_code)]
   data_subscription: MapSubscription,
+  /// Forwards the awareness updates to the plugins. See [CollabPlugin::receive_awareness_update].
+  #[allow(dead_code)]
+  awareness_subscription: AwarenessUpdateSubscription,
   update_subscription: RwLock<Option<UpdateSubscription>>,
   after_txn_subscription: RwLock<Option<AfterTransactionSubscription>>,
 }
@@ -86,10 +154,39 @@ impl Collab {
       ..Options::default()
     });
     let mut data = doc.get_or_insert_map(DATA_SECTION);
+    let permission = Arc::new(CollabPermission::default());
+    let permission_guard = Arc::new(PermissionGuard::new(
+      &doc,
+      &data,
+      origin.clone(),
+      permission.clone(),
+    ));
+    let authors = Arc::new(AuthorRegistry::new(&doc, permission.clone()));
     let undo_manager = Mutex::new(None);
     let plugins = Plugins::new(plugins);
-    let state = Arc::new(State::new(&object_id));
-    let awareness = Awareness::new(doc.clone());
+    let state = Arc::new(State::new(&object_id, plugins.clone()));
+    let mut awareness = Awareness::new(doc.clone());
+    let presence_tx = tokio::sync::broadcast::channel(100).0;
+    let presence_config = Arc::new(RwLock::new(PresenceConfig::default()));
+    let awareness_subscription = {
+      let plugins = plugins.clone();
+      let object_id = object_id.clone();
+      let presence_tx: PresenceSender = presence_tx.clone();
+      let presence_config = presence_config.clone();
+      awareness.on_update(move |awareness, event| {
+        plugins
+          .read()
+          .iter()
+          .for_each(|plugin| plugin.receive_awareness_update(&object_id, awareness, event));
+
+        if presence_tx.receiver_count() > 0 {
+          let _ = presence_tx.send(PresenceEvent {
+            peers: get_peer_presences(awareness, &presence_config.read()),
+            removed: event.removed().to_vec(),
+          });
+        }
+      })
+    };
 
     let cloned_state = state.clone();
     let local_origin = origin.clone();
@@ -105,25 +202,321 @@ impl Collab {
       }
     });
 
+    let authors_subscription = {
+      let authors = authors.clone();
+      let local_origin = origin.clone();
+      doc
+        .observe_after_transaction(move |txn| authors.register(txn, &local_origin))
+        .unwrap()
+    };
+
     Self {
       origin,
       object_id,
       doc,
       undo_manager,
+      undo_scopes: Default::default(),
+      undo_stack_tx: tokio::sync::broadcast::channel(100).0,
+      presence_tx,
+      presence_config,
+      permission,
+      authors,
+      authors_subscription,
+      permission_violation_tx: tokio::sync::broadcast::channel(100).0,
+      permission_subscription: Default::default(),
+      permission_guard,
+      quarantine: Default::default(),
+      remote_paths_subscription: Default::default(),
+      compaction_point: Default::default(),
+      txn_retry_config: Default::default(),
+      txn_metrics: Default::default(),
       awareness,
       data,
       plugins,
       state,
+      plain_text_extractor: RwLock::new(Arc::new(DefaultPlainTextExtractor)),
       data_subscription,
+      awareness_subscription,
       update_subscription: Default::default(),
       after_txn_subscription: Default::default(),
     }
   }
 
-  pub fn subscribe_state_change(&self) -> watch::Receiver<CollabState> {
+  /// Subscribe to the state of the [Collab]. The [CollabStateChange] carries the reason of the
+  /// change, for example, the error message of [CollabState::PersistError].
+  pub fn subscribe_state_change(&self) -> watch::Receiver<CollabStateChange> {
     self.state.notifier.subscribe()
   }
 
+  /// Returns the current state of the [Collab].
+  pub fn get_state(&self) -> CollabState {
+    self.state.get()
+  }
+
+  /// Returns a [CollabStateHandle] that can be used to publish the state of the [Collab]. The
+  /// plugins receive it in [CollabPlugin::set_state_handle].
+  pub fn state_handle(&self) -> CollabStateHandle {
+    CollabStateHandle::new(&self.state)
+  }
+
+  /// In the read-only mode, the local transactions are rejected by [Collab::try_transact_mut]
+  /// and [Collab::try_with_transact_mut]. The remote updates are still applied.
+  ///
+  /// The local changes that are made anyway, for example, by [Collab::with_transact_mut] or the
+  /// [MapRefWrapper], are reported by [Collab::subscribe_permission_violation] and reverted by a
+  /// local transaction right after them. Both updates are passed to the plugins, so the
+  /// persisted and the synced state stay in line with the document.
+  pub fn set_read_only(&self, read_only: bool) {
+    self.permission.set_read_only(read_only);
+  }
+
+  pub fn is_read_only(&self) -> bool {
+    self.permission.is_read_only()
+  }
+
+  /// Restrict the paths of the `data` section that can be changed by the local client.
+  ///
+  /// [Collab::apply_json_patch] rejects a denied path with [CollabError::PermissionDenied]
+  /// before changing anything. The other local transactions are checked after the change: the
+  /// transaction that changed a denied path is reported by
+  /// [Collab::subscribe_permission_violation] and reverted like in the read-only mode, see
+  /// [Collab::set_read_only].
+  pub fn set_permission_policy(&self, policy: Option<PermissionPolicy>) {
+    self.permission.set_policy(policy);
+  }
+
+  pub fn subscribe_permission_violation(&self) -> PermissionViolationReceiver {
+    self.permission_violation_tx.subscribe()
+  }
+
+  /// Returns true if a remote update was rejected by [CollabPlugin::accept_remote_update]. The
+  /// document keeps the changes, but the remote updates are neither persisted nor sent to the
+  /// remote until [Collab::release_quarantine] is called. The local updates are passed to the
+  /// plugins as usual. Closing the collab releases the held updates, see [Collab::close].
+  pub fn is_quarantined(&self) -> bool {
+    self.quarantine.is_active()
+  }
+
+  /// End the quarantine and pass the held updates to the plugins in the order they were
+  /// applied, for example, after the application repaired the data. Returns the released
+  /// updates.
+  pub fn release_quarantine(&self) -> Vec<QuarantinedUpdate> {
+    let updates = self.quarantine.release();
+    for update in updates.iter() {
+      // The plugins read the origin of the update from the transaction.
+      let txn = self.doc.transact_mut_with(update.origin.clone());
+      dispatch_update(
+        &self.plugins,
+        &self.object_id,
+        &self.origin,
+        &update.origin,
+        &txn,
+        &update.update,
+      );
+    }
+    updates
+  }
+
+  /// Record the [CollabClient] of the local client in the document, so the content it writes
+  /// can be attributed to it by [Collab::blame_map], [Collab::blame_array] and
+  /// [Collab::blame_text]. It's disabled by default.
+  ///
+  /// The client is recorded once, in the first local transaction that changes the document.
+  /// Each session has a new yrs client id, so each session that writes to the document adds
+  /// an entry to the authors of the document, which are never removed.
+  pub fn set_author_recording(&self, enabled: bool) {
+    self.authors.set_enabled(enabled);
+  }
+
+  /// Returns the [CollabClient] of each yrs client id that changed the document and recorded
+  /// itself. See [Collab::set_author_recording].
+  pub fn get_authors(&self) -> HashMap<ClientID, CollabClient> {
+    let txn = self.transact();
+    self.authors.get_authors(&txn)
+  }
+
+  /// Returns the author of each entry of the map at the given path of the `data` section. An
+  /// empty path is the `data` section itself. The author of an entry is the client that set the
+  /// current value of the entry.
+  pub fn blame_map(&self, path: &[&str]) -> Result<HashMap<String, Author>, CollabError> {
+    self.blame(path, |value| matches!(value, Value::YMap(_)), blame_map)
+  }
+
+  /// Returns the author of each element of the array at the given path of the `data` section.
+  /// The author of an element is the client that inserted it.
+  pub fn blame_array(&self, path: &[&str]) -> Result<Vec<Author>, CollabError> {
+    self.blame(path, |value| matches!(value, Value::YArray(_)), blame_array)
+  }
+
+  /// Returns the runs of the text at the given path of the `data` section that were inserted
+  /// by the same author.
+  pub fn blame_text(&self, path: &[&str]) -> Result<Vec<TextBlame>, CollabError> {
+    self.blame(path, |value| matches!(value, Value::YText(_)), blame_text)
+  }
+
+  fn blame<T>(
+    &self,
+    path: &[&str],
+    is_expected_type: impl Fn(&Value) -> bool,
+    blame: impl Fn(&ItemIndex, &Parent, &HashMap<ClientID, CollabClient>) -> T,
+  ) -> Result<T, CollabError> {
+    let not_found = || CollabError::BlamePathNotFound(path.join("/"));
+    let txn = self.transact();
+    if !is_expected_type(&resolve_path(&txn, &self.data, path)?) {
+      return Err(not_found());
+    }
+    let index = ItemIndex::from_txn(&txn);
+    let parent = index
+      .resolve_path(DATA_SECTION, path)
+      .ok_or_else(not_found)?;
+    Ok(blame(&index, &parent, &self.authors.get_authors(&txn)))
+  }
+
+  /// Returns the size and the garbage of the document, and the numbers that are collected by
+  /// the plugins, for example, the number of the updates on disk.
+  pub fn stats(&self) -> Result<CollabStats, CollabError> {
+    let mut stats = {
+      let txn = self.transact();
+      CollabStats::from_txn(&txn, &self.authors)
+    };
+    self
+      .plugins
+      .read()
+      .iter()
+      .for_each(|plugin| plugin.collect_stats(&self.object_id, &mut stats));
+    Ok(stats)
+  }
+
+  /// Discard the content of the items that were deleted before the given [Snapshot] and keep
+  /// the history after it. The document is rebuilt with the garbage collection enabled up to
+  /// the snapshot, and the plugins receive the rebuilt document with the [CompactionPoint] by
+  /// [CollabPlugin::did_compact]. The disk plugin replaces the stored state with it, so the
+  /// document is smaller when it's opened again. The collab keeps its current document, yrs
+  /// can't discard the deleted items of a document that was created without the garbage
+  /// collection.
+  ///
+  /// The peers that are behind the point are synced with the full state, see
+  /// [Collab::is_behind_compaction_point]. Snapshots that were taken before the point can't be
+  /// viewed anymore after the document is reopened.
+  pub fn compact(&self, snapshot: &Snapshot) -> Result<CompactionResult, CollabError> {
+    self.check_writable()?;
+    let (size_before, compacted_doc) = {
+      let txn = self.transact();
+      let size_before = txn.encode_state_as_update_v1(&StateVector::default()).len();
+      (
+        size_before,
+        compact_doc(&txn, self.doc.client_id(), snapshot)?,
+      )
+    };
+    let size_after = compacted_doc
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default())
+      .len();
+    let point = CompactionPoint::new(&snapshot.state_map);
+    *self.compaction_point.write() = Some(point.clone());
+    tracing::debug!(
+      "[🦀Collab] {} compacted from {} to {} bytes",
+      self.object_id,
+      size_before,
+      size_after
+    );
+    self
+      .plugins
+      .read()
+      .iter()
+      .for_each(|plugin| plugin.did_compact(&self.object_id, &compacted_doc, &point));
+
+    Ok(CompactionResult {
+      point,
+      size_before,
+      size_after,
+    })
+  }
+
+  /// Returns the [CompactionPoint] of the last [Collab::compact]. It's restored from the
+  /// plugins when the collab is initialized, see [CollabPlugin::get_compaction_point]. Returns
+  /// None if the document was never compacted.
+  pub fn get_compaction_point(&self) -> Option<CompactionPoint> {
+    self.compaction_point.read().clone()
+  }
+
+  /// Returns true if the peer with the given [StateVector] is behind the last
+  /// [Collab::compact]. See [CompactionPoint::is_ahead_of].
+  pub fn is_behind_compaction_point(&self, state_vector: &StateVector) -> bool {
+    self
+      .compaction_point
+      .read()
+      .as_ref()
+      .map(|point| point.is_ahead_of(state_vector))
+      .unwrap_or(false)
+  }
+
+  /// Export the full state of the document as the bytes of a [CollabBundle] file.
+  pub fn export_bundle(&self, options: BundleOptions) -> Result<Vec<u8>, CollabError> {
+    let txn = self.transact();
+    CollabBundle::from_txn(&txn, &self.object_id, options).encode()
+  }
+
+  /// Apply the state in the bytes of a [CollabBundle] file to the document. The bundle is
+  /// rejected if its checksum doesn't match or it belongs to another object. The state is
+  /// merged with the current document, so importing the same bundle twice is a no-op.
+  pub fn import_bundle(&self, data: &[u8]) -> Result<CollabBundle, CollabError> {
+    let bundle = CollabBundle::decode(data)?;
+    bundle.validate_object_id(&self.object_id)?;
+    let update = bundle.decode_state()?;
+    {
+      let mut txn = self.try_transact_mut()?;
+      txn.apply_update(update);
+    }
+    Ok(bundle)
+  }
+
+  pub fn set_presence_config(&self, config: PresenceConfig) {
+    *self.presence_config.write() = config;
+  }
+
+  /// Returns the [Presence] of the local client. Returns None if the presence is not set.
+  pub fn get_presence(&self) -> Option<Presence> {
+    get_local_presence(&self.awareness)
+  }
+
+  /// Update the [Presence] of the local client. The presence is created from the origin of the
+  /// [Collab] if it's not set yet. The [Presence::last_active] time is refreshed after the
+  /// update.
+  pub fn update_presence<F>(&mut self, f: F) -> Result<Presence, CollabError>
+  where
+    F: FnOnce(&mut Presence),
+  {
+    let mut presence = match self.get_presence() {
+      Some(presence) => presence,
+      None => match &self.origin {
+        CollabOrigin::Client(client) => Presence::new(client.clone()),
+        _ => return Err(CollabError::PresenceRequiresClient),
+      },
+    };
+    f(&mut presence);
+    presence.touch();
+    set_local_presence(&mut self.awareness, &presence)?;
+    Ok(presence)
+  }
+
+  /// Remove the [Presence] of the local client. The peers will receive it in
+  /// [PresenceEvent::removed].
+  pub fn clear_presence(&mut self) {
+    self.awareness.clean_local_state();
+  }
+
+  /// Returns the presences of the peers, excluding the local client.
+  pub fn get_peer_presences(&self) -> Vec<PeerPresence> {
+    get_peer_presences(&self.awareness, &self.presence_config.read())
+  }
+
+  /// Subscribe to the presences of the peers. A [PresenceEvent] is sent every time the awareness
+  /// is updated, either locally or by the remote peers.
+  pub fn subscribe_presence(&self) -> PresenceReceiver {
+    self.presence_tx.subscribe()
+  }
+
   /// Returns the [Doc] associated with the [Collab].
   pub fn get_doc(&self) -> &Doc {
     &self.doc
@@ -138,17 +531,81 @@ impl Collab {
     &mut self.awareness
   }
 
-  /// Add a plugin to the [Collab]. The plugin's callbacks will be called in the order they are added.
+  /// Add a plugin to the [Collab]. The plugin's callbacks will be called in the order of
+  /// [CollabPlugin::priority], and then in the order they are added.
   pub fn add_plugin(&mut self, plugin: Arc<dyn CollabPlugin>) {
-    self.plugins.write().push(plugin);
+    self.plugins.insert(plugin);
   }
 
-  /// Add plugins to the [Collab]. The plugin's callbacks will be called in the order they are added.
+  /// Add plugins to the [Collab]. The plugin's callbacks will be called in the order of
+  /// [CollabPlugin::priority], and then in the order they are added.
   pub fn add_plugins(&mut self, plugins: Vec<Arc<dyn CollabPlugin>>) {
-    let mut write_guard = self.plugins.write();
     for plugin in plugins {
-      write_guard.push(plugin);
+      self.plugins.insert(plugin);
+    }
+  }
+
+  /// Remove the plugin with the given [CollabPlugin::plugin_id]. The plugin is flushed and
+  /// closed before it's returned.
+  pub fn remove_plugin(&self, plugin_id: &str) -> Option<Arc<dyn CollabPlugin>> {
+    let plugin = {
+      let mut write_guard = self.plugins.write();
+      let index = write_guard
+        .iter()
+        .position(|plugin| plugin.plugin_id() == plugin_id)?;
+      write_guard.remove(index)
+    };
+    plugin.flush(&self.object_id, &self.doc);
+    plugin.will_close(&self.object_id);
+    Some(plugin)
+  }
+
+  /// Returns the ids of the plugins in the order they are called.
+  pub fn plugin_ids(&self) -> Vec<String> {
+    self
+      .plugins
+      .read()
+      .iter()
+      .map(|plugin| plugin.plugin_id())
+      .collect()
+  }
+
+  /// Ask the plugins to write their pending state. See [CollabPlugin::flush].
+  pub fn flush(&self) {
+    self
+      .plugins
+      .read()
+      .iter()
+      .for_each(|plugin| plugin.flush(&self.object_id, &self.doc));
+  }
+
+  /// Flush and close the plugins, then set the state to [CollabState::Closed]. It's called when
+  /// the [Collab] is dropped. Calling it more than once has no effect.
+  ///
+  /// The updates held by the quarantine are released first, so the plugins persist them before
+  /// they are closed. Otherwise, the local updates applied after them couldn't be integrated when
+  /// the collab is opened again.
+  pub fn close(&self) {
+    if self.state.get().is_closed() {
+      return;
     }
+    if self.is_quarantined() {
+      let updates = self.release_quarantine();
+      tracing::warn!(
+        "[🦀Collab]: release {} quarantined updates of {} before closing",
+        updates.len(),
+        self.object_id
+      );
+    }
+    self.flush();
+    self
+      .state
+      .set_with_reason(CollabState::Closed, "The collab was closed");
+    self
+      .plugins
+      .read()
+      .iter()
+      .for_each(|plugin| plugin.will_close(&self.object_id));
   }
 
   /// When calling this method, the [Collab]'s doc will be initialized with the plugins. The plugin's
@@ -161,6 +618,9 @@ impl Collab {
     }
 
     self.state.set(CollabState::Loading);
+    self.plugins.read().iter().for_each(|plugin| {
+      plugin.set_state_handle(&self.object_id, self.state_handle());
+    });
     {
       let mut txn = self.transact_mut();
       self
@@ -170,16 +630,40 @@ impl Collab {
         .for_each(|plugin| plugin.init(&self.object_id, &mut txn));
       drop(txn);
     }
+    if let Some(point) = self
+      .plugins
+      .read()
+      .iter()
+      .find_map(|plugin| plugin.get_compaction_point(&self.object_id))
+    {
+      *self.compaction_point.write() = Some(point);
+    }
 
     let (update_subscription, after_txn_subscription) = observe_doc(
       &self.doc,
       self.object_id.clone(),
       self.plugins.clone(),
       self.origin.clone(),
+      self.permission.clone(),
+      self.permission_violation_tx.clone(),
+      self.permission_guard.clone(),
+      self.quarantine.clone(),
     );
 
     *self.update_subscription.write() = Some(update_subscription);
     *self.after_txn_subscription.write() = Some(after_txn_subscription);
+    *self.permission_subscription.write() = Some(observe_permission(
+      &self.data,
+      self.origin.clone(),
+      self.permission.clone(),
+      self.permission_guard.clone(),
+    ));
+    *self.remote_paths_subscription.write() = Some(observe_remote_paths(
+      &self.data,
+      self.origin.clone(),
+      self.permission_guard.clone(),
+      self.quarantine.clone(),
+    ));
 
     {
       let txn = self.doc.transact();
@@ -189,7 +673,11 @@ impl Collab {
         .iter()
         .for_each(|plugin| plugin.did_init(&self.awareness, &self.object_id, &txn));
     }
-    self.state.set(CollabState::Initialized);
+    // Keep the state that was published by the plugins while loading, for example,
+    // [CollabState::PersistError] or [CollabState::Syncing].
+    if self.state.get() == CollabState::Loading {
+      self.state.set(CollabState::Initialized);
+    }
   }
 
   pub fn observer_data<F>(&mut self, f: F) -> MapSubscription
@@ -199,6 +687,21 @@ impl Collab {
     self.data.observe(f)
   }
 
+  /// Observe the changes of the `data` section and convert them into [JsonPatch]. Applying
+  /// the patch to the json value of the `data` section before the transaction will get the
+  /// json value after the transaction.
+  pub fn observe_json_patch<F>(&mut self, f: F) -> DeepEventsSubscription
+  where
+    F: Fn(&TransactionMut, &JsonPatch) + 'static,
+  {
+    self.data.observe_deep(move |txn, events| {
+      let patch = json_patch_from_events(txn, events);
+      if !patch.is_empty() {
+        f(txn, &patch);
+      }
+    })
+  }
+
   pub fn get(&self, key: &str) -> Option<Value> {
     let txn = self.doc.transact();
     self.data.get(&txn, key)
@@ -237,8 +740,9 @@ impl Collab {
             .insert(txn, key, MapPrelim::<lib0::any::Any>::new()),
         );
       }
-      let value = serde_json::to_value(&value).unwrap();
-      insert_json_value_to_map_ref(key, &value, map.unwrap(), txn);
+      if let Err(e) = to_map_ref(txn, &map.unwrap(), key, &value) {
+        tracing::error!("🔴insert {} failed: {:?}", key, e);
+      }
     });
   }
 
@@ -249,11 +753,7 @@ impl Collab {
     }
     let txn = self.transact();
     let map = self.get_map_with_txn(&txn, path)?;
-    drop(txn);
-
-    let json_str = map.to_json();
-    let object = serde_json::from_str::<T>(&json_str).ok()?;
-    Some(object)
+    from_map_ref(&txn, &map).ok()
   }
 
   pub fn insert_map_with_txn(&self, txn: &mut TransactionMut, key: &str) -> MapRefWrapper {
@@ -361,13 +861,50 @@ impl Collab {
     }
   }
 
+  /// Apply the RFC 6902 JSON Patch to the `data` section in a single transaction. The patch is
+  /// checked against the json value of the `data` section first, so nothing will be changed if
+  /// any operation fails.
+  pub fn apply_json_patch(&self, patch: &JsonPatch) -> Result<(), CollabError> {
+    // The `from` of a move is removed, and the `test` changes nothing.
+    let changed_paths = patch
+      .iter()
+      .flat_map(|operation| match operation {
+        PatchOperation::Move { from, path } => vec![from.clone(), path.clone()],
+        PatchOperation::Test { .. } => vec![],
+        PatchOperation::Add { path, .. }
+        | PatchOperation::Remove { path }
+        | PatchOperation::Replace { path, .. }
+        | PatchOperation::Copy { path, .. } => vec![path.clone()],
+      })
+      .collect();
+    self.permission.check_paths(changed_paths)?;
+
+    let mut json_value = self.to_json_value();
+    patch.apply_to_json_value(&mut json_value)?;
+    self.with_transact_mut(|txn| apply_json_patch(txn, &self.data, patch))
+  }
+
   pub fn to_json(&self) -> lib0::any::Any {
     let txn = self.transact();
     self.data.to_json(&txn)
   }
 
+  /// Set the [PlainTextExtractor] that used to convert the `data` section into plain text.
+  /// Each collab type can register its own extractor. For example, the document only returns
+  /// the text of its blocks.
+  pub fn set_plain_text_extractor(&self, extractor: Arc<dyn PlainTextExtractor>) {
+    *self.plain_text_extractor.write() = extractor;
+  }
+
+  /// Returns the plain text of the `data` section. If the registered [PlainTextExtractor] can't
+  /// handle the data, the [DefaultPlainTextExtractor] will be used.
   pub fn to_plain_text(&self) -> String {
-    "".to_string()
+    let txn = self.transact();
+    let extractor = self.plain_text_extractor.read().clone();
+    extractor
+      .extract(&txn, &self.data)
+      .or_else(|| DefaultPlainTextExtractor.extract(&txn, &self.data))
+      .unwrap_or_default()
   }
 
   pub fn to_json_value(&self) -> JsonValue {
@@ -375,18 +912,58 @@ impl Collab {
     serde_json::to_value(&self.data.to_json(&txn)).unwrap()
   }
 
+  /// Capture the [Snapshot] of the current state of the document. The snapshot can be encoded
+  /// with [Encode::encode_v1](yrs::updates::encoder::Encode::encode_v1) and stored, then used
+  /// to read the document as it was at this point. See [Collab::snapshot_view].
+  pub fn snapshot(&self) -> Snapshot {
+    self.transact().snapshot()
+  }
+
+  /// Encode the state of the document as it was at the given [Snapshot].
+  pub fn encode_state_from_snapshot(&self, snapshot: &Snapshot) -> Result<Vec<u8>, CollabError> {
+    let txn = self.transact();
+    encode_state_from_snapshot(&txn, snapshot)
+  }
+
+  /// Returns a read-only [SnapshotView] of the document as it was at the given [Snapshot].
+  /// Unlike restoring a snapshot, the current document is not changed.
+  pub fn snapshot_view(&self, snapshot: &Snapshot) -> Result<SnapshotView, CollabError> {
+    let encoded_state = self.encode_state_from_snapshot(snapshot)?;
+    SnapshotView::from_encoded_state(&encoded_state)
+  }
+
+  /// Returns the json value of the `data` section as it was at the given [Snapshot].
+  pub fn to_json_value_at(&self, snapshot: &Snapshot) -> Result<JsonValue, CollabError> {
+    Ok(self.snapshot_view(snapshot)?.to_json_value())
+  }
+
   pub fn enable_undo_redo(&mut self) {
+    self.enable_undo_redo_with_config(UndoConfig::default());
+  }
+
+  /// Enable the [UndoManager](yrs::UndoManager) of the `data` section with the given
+  /// [UndoConfig]. The changes of the collab's own origin are always tracked.
+  pub fn enable_undo_redo_with_config(&mut self, config: UndoConfig) {
     if self.undo_manager.lock().is_some() {
       tracing::warn!("Undo manager already enabled");
       return;
     }
     // a frequent case includes establishing a new transaction for every user key stroke. Meanwhile
     // we may decide to use different granularity of undo/redo actions. These are grouped together
-    // on time-based ranges (configurable in undo::Options, which is 500ms by default).
-    let mut undo_manager =
-      UndoManager::with_options(&self.doc, &self.data, yrs::undo::Options::default());
-    undo_manager.include_origin(self.origin.clone());
-    *self.undo_manager.lock() = Some(undo_manager);
+    // on time-based ranges (configurable in UndoConfig, which is 500ms by default).
+    let scope = Value::YMap(self.data.clone());
+    let notifier = UndoStackNotifier::new(None, self.undo_stack_tx.clone());
+    match CollabUndoManager::new(
+      &self.doc,
+      &scope,
+      vec![],
+      self.origin.clone().into(),
+      config,
+      notifier,
+    ) {
+      Ok(undo_manager) => *self.undo_manager.lock() = Some(undo_manager),
+      Err(err) => tracing::error!("Failed to enable undo manager: {}", err),
+    }
   }
 
   /// Undo the previous change.
@@ -418,19 +995,178 @@ impl Collab {
   pub fn undo(&mut self) -> Result<bool, CollabError> {
     match &mut *self.undo_manager.lock() {
       None => Err(CollabError::UndoManagerNotEnabled),
-      Some(mgr) => mgr.undo().map_err(|e| CollabError::Internal(Box::new(e))),
+      Some(mgr) => mgr.undo(),
     }
   }
 
   pub fn redo(&mut self) -> Result<bool, CollabError> {
     match &mut *self.undo_manager.lock() {
       None => Err(CollabError::UndoManagerNotEnabled),
-      Some(mgr) => mgr.redo().map_err(|e| CollabError::Internal(Box::new(e))),
+      Some(mgr) => mgr.redo(),
     }
   }
 
+  /// Add an undo scope that only tracks the changes of the map, array or text at the given path.
+  /// Each scope has its own undo stack, for example, one per document block subtree or per
+  /// database view. Adding a scope with an existing id replaces the previous scope.
+  pub fn add_undo_scope<P: Into<Path>>(
+    &self,
+    scope_id: &str,
+    path: P,
+    config: UndoConfig,
+  ) -> Result<(), CollabError> {
+    let path = path.into();
+    let scope = self.get_undo_scope_ref(path.clone())?;
+    let notifier = UndoStackNotifier::new(Some(scope_id.to_string()), self.undo_stack_tx.clone());
+    let undo_manager = CollabUndoManager::new(
+      &self.doc,
+      &scope,
+      path.to_vec(),
+      self.origin.clone().into(),
+      config,
+      notifier,
+    )?;
+    self
+      .undo_scopes
+      .lock()
+      .insert(scope_id.to_string(), undo_manager);
+    Ok(())
+  }
+
+  /// Extend the undo scope with the map, array or text at the given path.
+  pub fn expand_undo_scope<P: Into<Path>>(
+    &self,
+    scope_id: &str,
+    path: P,
+  ) -> Result<(), CollabError> {
+    let path = path.into();
+    let scope = self.get_undo_scope_ref(path.clone())?;
+    self.with_undo_scope(scope_id, |mgr| mgr.expand_scope(&scope, path.to_vec()))
+  }
+
+  /// Remove the undo scope. Returns false if the scope doesn't exist.
+  pub fn remove_undo_scope(&self, scope_id: &str) -> bool {
+    self.undo_scopes.lock().remove(scope_id).is_some()
+  }
+
+  pub fn can_undo_in_scope(&self, scope_id: &str) -> bool {
+    self
+      .with_undo_scope(scope_id, |mgr| Ok(mgr.can_undo()))
+      .unwrap_or(false)
+  }
+
+  pub fn can_redo_in_scope(&self, scope_id: &str) -> bool {
+    self
+      .with_undo_scope(scope_id, |mgr| Ok(mgr.can_redo()))
+      .unwrap_or(false)
+  }
+
+  pub fn undo_in_scope(&self, scope_id: &str) -> Result<bool, CollabError> {
+    self.with_undo_scope(scope_id, |mgr| mgr.undo())
+  }
+
+  pub fn redo_in_scope(&self, scope_id: &str) -> Result<bool, CollabError> {
+    self.with_undo_scope(scope_id, |mgr| mgr.redo())
+  }
+
+  /// Subscribe to the changes of the undo and redo stacks of the `data` section and of all the
+  /// undo scopes. The [UndoStackEvent](crate::core::undo::UndoStackEvent) carries the id of the
+  /// scope and the lengths of the stacks after the change.
+  pub fn subscribe_undo_stack(&self) -> UndoStackReceiver {
+    self.undo_stack_tx.subscribe()
+  }
+
+  /// Returns the number of items in the undo stack. Returns 0 if the UndoManager is not enabled.
+  pub fn undo_stack_len(&self) -> usize {
+    self
+      .undo_manager
+      .lock()
+      .as_ref()
+      .map(|mgr| mgr.undo_stack_len())
+      .unwrap_or(0)
+  }
+
+  /// Returns the number of items in the redo stack. Returns 0 if the UndoManager is not enabled.
+  pub fn redo_stack_len(&self) -> usize {
+    self
+      .undo_manager
+      .lock()
+      .as_ref()
+      .map(|mgr| mgr.redo_stack_len())
+      .unwrap_or(0)
+  }
+
+  /// Returns the items of the undo stack. The last item is the one that is undone next.
+  pub fn undo_stack_items(&self) -> Vec<UndoStackItem> {
+    self
+      .undo_manager
+      .lock()
+      .as_ref()
+      .map(|mgr| mgr.undo_stack())
+      .unwrap_or_default()
+  }
+
+  /// Returns the items of the redo stack. The last item is the one that is redone next.
+  pub fn redo_stack_items(&self) -> Vec<UndoStackItem> {
+    self
+      .undo_manager
+      .lock()
+      .as_ref()
+      .map(|mgr| mgr.redo_stack())
+      .unwrap_or_default()
+  }
+
+  pub fn undo_stack_items_in_scope(
+    &self,
+    scope_id: &str,
+  ) -> Result<Vec<UndoStackItem>, CollabError> {
+    self.with_undo_scope(scope_id, |mgr| Ok(mgr.undo_stack()))
+  }
+
+  pub fn redo_stack_items_in_scope(
+    &self,
+    scope_id: &str,
+  ) -> Result<Vec<UndoStackItem>, CollabError> {
+    self.with_undo_scope(scope_id, |mgr| Ok(mgr.redo_stack()))
+  }
+
+  fn with_undo_scope<F, T>(&self, scope_id: &str, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut CollabUndoManager) -> Result<T, CollabError>,
+  {
+    match self.undo_scopes.lock().get_mut(scope_id) {
+      None => Err(CollabError::UndoScopeNotFound(scope_id.to_string())),
+      Some(mgr) => f(mgr),
+    }
+  }
+
+  fn get_undo_scope_ref<P: Into<Path>>(&self, path: P) -> Result<Value, CollabError> {
+    let path = path.into();
+    let path_str = path.join("/");
+    let txn = self.transact();
+    self
+      .get_ref_from_path_with_txn(&txn, path)
+      .ok_or_else(|| CollabError::InvalidUndoScope(format!("{} does not exist", path_str)))
+  }
+
+  /// Set the retry policy of acquiring the transactions of this [Collab], including the
+  /// transactions acquired by the [MapRefWrapper] and the [ArrayRefWrapper] of this [Collab].
+  pub fn set_transaction_retry_config(&self, config: TransactionRetryConfig) {
+    *self.txn_retry_config.write() = config;
+  }
+
+  /// Returns the contention metrics of acquiring the transactions of this [Collab].
+  pub fn transaction_metrics(&self) -> TransactionMetricsSnapshot {
+    self.txn_metrics.snapshot()
+  }
+
+  fn txn_retry(&self) -> TransactionRetry {
+    TransactionRetry::with_config(&self.doc, &self.txn_retry_config.read())
+      .with_metrics(self.txn_metrics.clone())
+  }
+
   pub fn transact(&self) -> Transaction {
-    TransactionRetry::new(&self.doc).get_read_txn()
+    self.txn_retry().get_read_txn()
   }
 
   pub fn try_transaction(&self) -> Result<Transaction, CollabError> {
@@ -442,8 +1178,59 @@ impl Collab {
 
   /// Returns a transaction that can mutate the document. This transaction will carry the
   /// origin of the current user.
+  ///
+  /// The transaction is not rejected in the read-only mode. Use [Collab::try_transact_mut] to
+  /// reject the local changes of a read-only collab. The denied changes of the transaction are
+  /// reverted when the next write transaction is acquired, see [Collab::set_read_only].
   pub fn transact_mut(&self) -> TransactionMut {
-    TransactionRetry::new(&self.doc).get_write_txn_with(self.origin.clone())
+    self.permission_guard.revert();
+    self.txn_retry().get_write_txn_with(self.origin.clone())
+  }
+
+  /// Like [Collab::transact_mut], but returns [CollabError::ReadOnly] if the collab is in the
+  /// read-only mode, or an error if the transaction can't be acquired before the timeout.
+  pub fn try_transact_mut(&self) -> Result<TransactionMut, CollabError> {
+    self.check_writable()?;
+    self.permission_guard.revert();
+    self.txn_retry().try_get_write_txn_with(self.origin.clone())
+  }
+
+  /// Like [Collab::with_transact_mut], but returns [CollabError::ReadOnly] if the collab is in
+  /// the read-only mode.
+  pub fn try_with_transact_mut<F, T>(&self, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut TransactionMut) -> T,
+  {
+    let mut txn = self.try_transact_mut()?;
+    let ret = f(&mut txn);
+    drop(txn);
+    self.permission_guard.revert();
+    Ok(ret)
+  }
+
+  /// The async version of [Collab::transact]. It yields to the runtime while the transaction
+  /// is held by others instead of blocking the thread. Returns an error on timeout.
+  pub async fn transact_async(&self) -> Result<Transaction, CollabError> {
+    self.txn_retry().get_read_txn_async().await
+  }
+
+  /// The async version of [Collab::try_transact_mut]. It yields to the runtime while the
+  /// transaction is held by others instead of blocking the thread. Returns an error on timeout,
+  /// or [CollabError::ReadOnly] if the collab is in the read-only mode.
+  pub async fn transact_mut_async(&self) -> Result<TransactionMut, CollabError> {
+    self.check_writable()?;
+    self.permission_guard.revert();
+    self
+      .txn_retry()
+      .get_write_txn_with_async(self.origin.clone())
+      .await
+  }
+
+  fn check_writable(&self) -> Result<(), CollabError> {
+    if self.permission.is_read_only() {
+      return Err(CollabError::ReadOnly);
+    }
+    Ok(())
   }
 
   /// Returns a transaction that can mutate the document. This transaction will carry the
@@ -451,59 +1238,119 @@ impl Collab {
   ///
   /// If applying the remote update, please use the `transact_mut` of `doc`. Ot
   /// update will send to remote that the remote already has.
+  ///
+  /// The denied changes are reverted right after the transaction, see [Collab::set_read_only].
   pub fn with_transact_mut<F, T>(&self, f: F) -> T
   where
     F: FnOnce(&mut TransactionMut) -> T,
   {
-    let mut txn = TransactionRetry::new(&self.doc).get_write_txn_with(self.origin.clone());
+    let mut txn = self.transact_mut();
     let ret = f(&mut txn);
     drop(txn);
+    self.permission_guard.revert();
     ret
   }
 
-  fn map_wrapper_with(&self, map_ref: MapRef) -> MapRefWrapper {
-    MapRefWrapper::new(
-      map_ref,
-      CollabContext::new(self.origin.clone(), self.plugins.clone(), self.doc.clone()),
+  /// Returns a [CollabContext] that shares the document and the transaction settings of this
+  /// [Collab]. It doesn't borrow the [Collab], so the transactions can be acquired
+  /// asynchronously without holding the lock of the [MutexCollab].
+  pub fn context(&self) -> CollabContext {
+    CollabContext::new(
+      self.origin.clone(),
+      self.plugins.clone(),
+      self.doc.clone(),
+      self.txn_retry_config.clone(),
+      self.txn_metrics.clone(),
+      self.permission.clone(),
+      self.permission_guard.clone(),
     )
   }
+
+  fn map_wrapper_with(&self, map_ref: MapRef) -> MapRefWrapper {
+    MapRefWrapper::new(map_ref, self.context())
+  }
   fn array_wrapper_with(&self, array_ref: ArrayRef) -> ArrayRefWrapper {
-    ArrayRefWrapper::new(
-      array_ref,
-      CollabContext::new(self.origin.clone(), self.plugins.clone(), self.doc.clone()),
-    )
+    ArrayRefWrapper::new(array_ref, self.context())
+  }
+}
+
+impl Drop for Collab {
+  fn drop(&mut self) {
+    self.close();
   }
 }
 
 /// Observe a document for updates.
 /// Use the uid and the device_id to verify that the update is local or remote.
 /// If the update is local, the plugins will be notified.
+#[allow(clippy::too_many_arguments)]
 fn observe_doc(
   doc: &Doc,
   oid: String,
   plugins: Plugins,
   local_origin: CollabOrigin,
+  permission: Arc<CollabPermission>,
+  permission_violation_tx: PermissionViolationSender,
+  permission_guard: Arc<PermissionGuard>,
+  quarantine: Arc<Quarantine>,
 ) -> (UpdateSubscription, AfterTransactionSubscription) {
   let cloned_oid = oid.clone();
   let cloned_plugins = plugins.clone();
   let update_sub = doc
     .observe_update_v1(move |txn, event| {
-      // If the origin of the txn is none, it means that the update is coming from a remote source.
-      cloned_plugins.read().iter().for_each(|plugin| {
-        plugin.receive_update(&cloned_oid, txn, &event.update);
-
-        let remote_origin = CollabOrigin::from(txn);
-        if remote_origin == local_origin {
-          tracing::trace!("[🦀Collab]: did apply local {} update", local_origin);
-          plugin.receive_local_update(&local_origin, &cloned_oid, &event.update);
-        } else {
-          tracing::trace!(
-            "[🦀Collab]: {} did apply remote {} update",
+      // The revert of the denied changes is a change of the local client.
+      let origin = if permission_guard.is_revert(txn) {
+        local_origin.clone()
+      } else {
+        CollabOrigin::from(txn)
+      };
+      // The denied local changes are still passed to the plugins, the guard reverts them with
+      // the next local update. The denied paths are collected by the permission observer, which
+      // runs before the update is emitted.
+      if origin == local_origin {
+        let denied_paths = permission.take_pending_denied_paths();
+        if !denied_paths.is_empty() {
+          tracing::warn!(
+            "[🦀Collab]: {} is not allowed to change {:?} of {}",
             local_origin,
-            remote_origin,
+            denied_paths,
+            cloned_oid
           );
+          let _ = permission_violation_tx.send(PermissionViolation {
+            origin: local_origin.clone(),
+            paths: denied_paths,
+          });
+        }
+      } else {
+        // Every plugin checks the update, so each of them can report what it rejects.
+        let changed_paths = quarantine.take_pending_remote_paths();
+        let mut rejected = false;
+        for plugin in cloned_plugins.read().iter() {
+          if !plugin.accept_remote_update(&origin, &cloned_oid, txn, &changed_paths) {
+            rejected = true;
+          }
         }
-      });
+        if rejected && !quarantine.is_active() {
+          tracing::warn!(
+            "[🦀Collab]: quarantine {} after the rejected update from {}",
+            cloned_oid,
+            origin
+          );
+          quarantine.activate();
+        }
+      }
+
+      if quarantine.hold(&local_origin, &origin, &event.update) {
+        return;
+      }
+      dispatch_update(
+        &cloned_plugins,
+        &cloned_oid,
+        &local_origin,
+        &origin,
+        txn,
+        &event.update,
+      );
     })
     .unwrap();
 
@@ -519,6 +1366,31 @@ fn observe_doc(
   (update_sub, after_txn_sub)
 }
 
+/// Pass the update to the plugins. If the origin is none, it means that the update is coming
+/// from a remote source.
+fn dispatch_update(
+  plugins: &Plugins,
+  oid: &str,
+  local_origin: &CollabOrigin,
+  origin: &CollabOrigin,
+  txn: &TransactionMut,
+  update: &[u8],
+) {
+  plugins.read().iter().for_each(|plugin| {
+    plugin.receive_update(oid, txn, update);
+    if origin == local_origin {
+      tracing::trace!("[🦀Collab]: did apply local {} update", local_origin);
+      plugin.receive_local_update(local_origin, oid, update);
+    } else {
+      tracing::trace!(
+        "[🦀Collab]: {} did apply remote {} update",
+        local_origin,
+        origin,
+      );
+    }
+  });
+}
+
 impl Display for Collab {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     f.write_str(&serde_json::to_string(self).unwrap())?;
@@ -532,6 +1404,11 @@ pub struct CollabBuilder {
   device_id: String,
   plugins: Vec<Arc<dyn CollabPlugin>>,
   object_id: String,
+  plain_text_extractor: Option<Arc<dyn PlainTextExtractor>>,
+  txn_retry_config: Option<TransactionRetryConfig>,
+  read_only: bool,
+  permission_policy: Option<PermissionPolicy>,
+  author_recording: bool,
 }
 
 impl CollabBuilder {
@@ -542,6 +1419,11 @@ impl CollabBuilder {
       plugins: vec![],
       object_id: object_id.to_string(),
       device_id: "".to_string(),
+      plain_text_extractor: None,
+      txn_retry_config: None,
+      read_only: false,
+      permission_policy: None,
+      author_recording: false,
     }
   }
 
@@ -561,6 +1443,37 @@ impl CollabBuilder {
     self
   }
 
+  pub fn with_plain_text_extractor<T>(mut self, extractor: T) -> Self
+  where
+    T: PlainTextExtractor,
+  {
+    self.plain_text_extractor = Some(Arc::new(extractor));
+    self
+  }
+
+  pub fn with_transaction_retry_config(mut self, config: TransactionRetryConfig) -> Self {
+    self.txn_retry_config = Some(config);
+    self
+  }
+
+  /// See [Collab::set_read_only].
+  pub fn with_read_only(mut self, read_only: bool) -> Self {
+    self.read_only = read_only;
+    self
+  }
+
+  /// See [Collab::set_permission_policy].
+  pub fn with_permission_policy(mut self, policy: PermissionPolicy) -> Self {
+    self.permission_policy = Some(policy);
+    self
+  }
+
+  /// See [Collab::set_author_recording].
+  pub fn with_author_recording(mut self, enabled: bool) -> Self {
+    self.author_recording = enabled;
+    self
+  }
+
   pub fn build_with_updates(self, updates: Vec<Update>) -> MutexCollab {
     let collab = self.build();
     collab.lock().with_transact_mut(|txn| {
@@ -571,12 +1484,48 @@ impl CollabBuilder {
     collab
   }
 
+  /// Fork the given parent [Collab] into a [CollabBranch]. The branch uses the object id of
+  /// this builder, so the plugins, for example, the disk plugin, will store the branch under
+  /// its own object id. The branch records the parent's state vector at fork time, which is
+  /// used to calculate the changes when merging the branch back into the parent.
+  ///
+  /// Like [CollabBuilder::build_with_updates], the returned collab is not initialized.
+  pub fn build_branch(self, parent: &Collab) -> Result<CollabBranch, CollabError> {
+    let (parent_state, parent_state_vector) = {
+      let txn = parent.transact();
+      (
+        txn.encode_state_as_update_v1(&StateVector::default()),
+        txn.state_vector(),
+      )
+    };
+    let meta = BranchMeta::new(&parent.object_id, &parent_state_vector);
+    let update = Update::decode_v1(&parent_state)?;
+    let collab = self.build_with_updates(vec![update]);
+    Ok(CollabBranch::new(collab, meta))
+  }
+
+  /// Reopen a [CollabBranch] with the given [BranchMeta]. The content of the branch should be
+  /// loaded by the plugins, for example, the disk plugin, when initializing the collab.
+  pub fn build_branch_with_meta(self, meta: BranchMeta) -> CollabBranch {
+    CollabBranch::new(self.build(), meta)
+  }
+
   pub fn build(self) -> MutexCollab {
     let origin = CollabOrigin::Client(CollabClient {
       uid: self.uid,
       device_id: self.device_id,
     });
-    MutexCollab::new(origin, &self.object_id, self.plugins)
+    let collab = MutexCollab::new(origin, &self.object_id, self.plugins);
+    if let Some(extractor) = self.plain_text_extractor {
+      collab.lock().set_plain_text_extractor(extractor);
+    }
+    if let Some(config) = self.txn_retry_config {
+      collab.lock().set_transaction_retry_config(config);
+    }
+    collab.lock().set_read_only(self.read_only);
+    collab.lock().set_permission_policy(self.permission_policy);
+    collab.lock().set_author_recording(self.author_recording);
+    collab
   }
 }
 
@@ -586,30 +1535,109 @@ pub struct CollabContext {
   doc: Doc,
   #[allow(dead_code)]
   plugins: Plugins,
+  txn_retry_config: Arc<RwLock<TransactionRetryConfig>>,
+  txn_metrics: Arc<TransactionMetrics>,
+  permission: Arc<CollabPermission>,
+  permission_guard: Arc<PermissionGuard>,
 }
 
 impl CollabContext {
-  fn new(origin: CollabOrigin, plugins: Plugins, doc: Doc) -> Self {
+  fn new(
+    origin: CollabOrigin,
+    plugins: Plugins,
+    doc: Doc,
+    txn_retry_config: Arc<RwLock<TransactionRetryConfig>>,
+    txn_metrics: Arc<TransactionMetrics>,
+    permission: Arc<CollabPermission>,
+    permission_guard: Arc<PermissionGuard>,
+  ) -> Self {
     Self {
       origin,
       plugins,
       doc,
+      txn_retry_config,
+      txn_metrics,
+      permission,
+      permission_guard,
     }
   }
 
+  fn txn_retry(&self) -> TransactionRetry {
+    TransactionRetry::with_config(&self.doc, &self.txn_retry_config.read())
+      .with_metrics(self.txn_metrics.clone())
+  }
+
   pub fn transact(&self) -> Transaction {
-    TransactionRetry::new(&self.doc).get_read_txn()
+    self.txn_retry().get_read_txn()
   }
 
+  /// Like [Collab::with_transact_mut], the denied changes are reverted right after the
+  /// transaction.
   pub fn with_transact_mut<F, T>(&self, f: F) -> T
   where
     F: FnOnce(&mut TransactionMut) -> T,
   {
-    let mut txn = TransactionRetry::new(&self.doc).get_write_txn_with(self.origin.clone());
+    self.permission_guard.revert();
+    let mut txn = self.txn_retry().get_write_txn_with(self.origin.clone());
     let ret = f(&mut txn);
     drop(txn);
+    self.permission_guard.revert();
     ret
   }
+
+  /// Like [CollabContext::with_transact_mut], but returns [CollabError::ReadOnly] if the collab
+  /// is in the read-only mode, or an error if the transaction can't be acquired before the
+  /// timeout.
+  pub fn try_with_transact_mut<F, T>(&self, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut TransactionMut) -> T,
+  {
+    self.check_writable()?;
+    self.permission_guard.revert();
+    let mut txn = self
+      .txn_retry()
+      .try_get_write_txn_with(self.origin.clone())?;
+    let ret = f(&mut txn);
+    drop(txn);
+    self.permission_guard.revert();
+    Ok(ret)
+  }
+
+  fn check_writable(&self) -> Result<(), CollabError> {
+    if self.permission.is_read_only() {
+      return Err(CollabError::ReadOnly);
+    }
+    Ok(())
+  }
+
+  /// The async version of [CollabContext::transact]. It yields to the runtime while the
+  /// transaction is held by others instead of blocking the thread. Returns an error on timeout.
+  pub async fn transact_async(&self) -> Result<Transaction, CollabError> {
+    self.txn_retry().get_read_txn_async().await
+  }
+
+  /// The async version of [CollabContext::with_transact_mut]. It yields to the runtime while
+  /// the transaction is held by others instead of blocking the thread. Returns an error on
+  /// timeout, or [CollabError::ReadOnly] if the collab is in the read-only mode.
+  pub async fn transact_mut_async(&self) -> Result<TransactionMut, CollabError> {
+    self.check_writable()?;
+    self.permission_guard.revert();
+    self
+      .txn_retry()
+      .get_write_txn_with_async(self.origin.clone())
+      .await
+  }
+
+  pub async fn with_transact_mut_async<F, T>(&self, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut TransactionMut) -> T,
+  {
+    let mut txn = self.transact_mut_async().await?;
+    let ret = f(&mut txn);
+    drop(txn);
+    self.permission_guard.revert();
+    Ok(ret)
+  }
 }
 
 #[derive(Clone)]
@@ -658,9 +1686,22 @@ impl DerefMut for Path {
 pub struct Plugins(Arc<RwLock<Vec<Arc<dyn CollabPlugin>>>>);
 
 impl Plugins {
-  pub fn new(plugins: Vec<Arc<dyn CollabPlugin>>) -> Plugins {
+  pub fn new(mut plugins: Vec<Arc<dyn CollabPlugin>>) -> Plugins {
+    // The sort is stable, so the plugins with the same priority keep their order.
+    plugins.sort_by_key(|plugin| std::cmp::Reverse(plugin.priority()));
     Self(Arc::new(RwLock::new(plugins)))
   }
+
+  /// Insert the plugin after the plugins that have the same or higher priority.
+  pub fn insert(&self, plugin: Arc<dyn CollabPlugin>) {
+    let mut write_guard = self.0.write();
+    let priority = plugin.priority();
+    let index = write_guard
+      .iter()
+      .position(|other| other.priority() < priority)
+      .unwrap_or(write_guard.len());
+    write_guard.insert(index, plugin);
+  }
 }
 
 impl Deref for Plugins {
@@ -687,6 +1728,40 @@ impl MutexCollab {
   pub fn to_json_value(&self) -> JsonValue {
     self.0.lock().to_json_value()
   }
+
+  pub fn to_plain_text(&self) -> String {
+    self.0.lock().to_plain_text()
+  }
+
+  pub fn snapshot(&self) -> Snapshot {
+    self.0.lock().snapshot()
+  }
+
+  pub fn to_json_value_at(&self, snapshot: &Snapshot) -> Result<JsonValue, CollabError> {
+    self.0.lock().to_json_value_at(snapshot)
+  }
+
+  /// Acquire a read transaction without blocking the thread and call `f` with it. The lock of
+  /// the collab is only held while cloning its [CollabContext], not while waiting for the
+  /// transaction.
+  pub async fn with_transact_async<F, T>(&self, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&Transaction) -> T,
+  {
+    let context = self.0.lock().context();
+    let txn = context.transact_async().await?;
+    Ok(f(&txn))
+  }
+
+  /// Like [MutexCollab::with_transact_async], but acquires a write transaction. Returns
+  /// [CollabError::ReadOnly] if the collab is in the read-only mode.
+  pub async fn with_transact_mut_async<F, T>(&self, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut TransactionMut) -> T,
+  {
+    let context = self.0.lock().context();
+    context.with_transact_mut_async(f).await
+  }
 }
 
 impl Deref for MutexCollab {
@@ -1,11 +1,34 @@
 use std::sync::Arc;
 
-use y_sync::awareness::Awareness;
-use yrs::{Transaction, TransactionMut};
+use y_sync::awareness::{Awareness, Event as AwarenessEvent};
+use yrs::{Doc, Transaction, TransactionMut};
 
+use crate::core::collab_state::{CollabStateChange, CollabStateHandle};
+use crate::core::compaction::CompactionPoint;
 use crate::core::origin::CollabOrigin;
+use crate::core::stats::CollabStats;
 
 pub trait CollabPlugin: Send + Sync + 'static {
+  /// The id of the plugin. It's used to remove the plugin by [Collab::remove_plugin]. Default is
+  /// the type name of the plugin, override it if the same type of plugin is added more than once.
+  ///
+  /// [Collab::remove_plugin]: crate::preclude::Collab::remove_plugin
+  fn plugin_id(&self) -> String {
+    std::any::type_name::<Self>().to_string()
+  }
+
+  /// The plugins with higher priority are called first. The plugins with the same priority are
+  /// called in the order they are added. Default is 0.
+  fn priority(&self) -> i32 {
+    0
+  }
+
+  /// Called before [CollabPlugin::init]. The plugin can keep the [CollabStateHandle] to publish
+  /// the state of the [Collab](crate::preclude::Collab), for example, the sync or the persistence
+  /// state. The handle doesn't keep the collab alive, it should be released in
+  /// [CollabPlugin::will_close].
+  fn set_state_handle(&self, _object_id: &str, _handle: CollabStateHandle) {}
+
   /// Called when the plugin is initialized.
   /// The will apply the updates to the current [TransactionMut] which will restore the state of
   /// the document.
@@ -21,8 +44,74 @@ pub trait CollabPlugin: Send + Sync + 'static {
   /// Called when the plugin receives a local update
   fn receive_local_update(&self, _origin: &CollabOrigin, _object_id: &str, _update: &[u8]) {}
 
+  /// Called when an update that is applied by the remote, for example, by the sync plugin, is
+  /// emitted, before any plugin receives it. The `origin` is the origin of the [TransactionMut]
+  /// that applied the update and the `changed_paths` are the JSON Pointers of the values of the
+  /// `data` section that it changed.
+  ///
+  /// Returns false to reject the update. The update is already applied to the document, so the
+  /// collab is quarantined instead: the update, and every remote update after it, is held back
+  /// from the plugins until [Collab::release_quarantine] is called.
+  ///
+  /// [Collab::release_quarantine]: crate::preclude::Collab::release_quarantine
+  fn accept_remote_update(
+    &self,
+    _origin: &CollabOrigin,
+    _object_id: &str,
+    _txn: &TransactionMut,
+    _changed_paths: &[String],
+  ) -> bool {
+    true
+  }
+
   /// Called after each [TransactionMut]
   fn after_transaction(&self, _object_id: &str, _txn: &mut TransactionMut) {}
+
+  /// Called when the [Awareness] of the collab is updated, either by the local client or by
+  /// applying the remote awareness update. The [AwarenessEvent] contains the ids of the added,
+  /// updated and removed clients.
+  fn receive_awareness_update(
+    &self,
+    _object_id: &str,
+    _awareness: &Awareness,
+    _event: &AwarenessEvent,
+  ) {
+  }
+
+  /// Called when the state of the collab changes, including the changes published by the other
+  /// plugins through the [CollabStateHandle].
+  fn did_change_state(&self, _object_id: &str, _change: &CollabStateChange) {}
+
+  /// Called when [Collab::flush] is called, when the plugin is removed and before the collab
+  /// is closed. The plugin should write its pending state, for example, the updates that are
+  /// not saved or sent yet.
+  ///
+  /// [Collab::flush]: crate::preclude::Collab::flush
+  fn flush(&self, _object_id: &str, _doc: &Doc) {}
+
+  /// Called by [Collab::stats] to add the numbers that are only known by the plugin, for
+  /// example, the number of the updates that are stored on disk.
+  ///
+  /// [Collab::stats]: crate::preclude::Collab::stats
+  fn collect_stats(&self, _object_id: &str, _stats: &mut CollabStats) {}
+
+  /// Called by [Collab::compact] with the compacted document, in which the deleted items before
+  /// the [CompactionPoint] were discarded. The disk plugin should replace the stored state with
+  /// it, because the stored updates still contain the deleted items, and store the point, so
+  /// it can be returned by [CollabPlugin::get_compaction_point] when the document is reopened.
+  ///
+  /// [Collab::compact]: crate::preclude::Collab::compact
+  fn did_compact(&self, _object_id: &str, _compacted_doc: &Doc, _point: &CompactionPoint) {}
+
+  /// Called after [CollabPlugin::init] to restore the [CompactionPoint] that was stored by
+  /// [CollabPlugin::did_compact]. The first point that is returned by the plugins is used.
+  fn get_compaction_point(&self, _object_id: &str) -> Option<CompactionPoint> {
+    None
+  }
+
+  /// Called after [CollabPlugin::flush] when the collab is closed or dropped, or when the plugin
+  /// is removed. The plugin will not receive any callbacks after this.
+  fn will_close(&self, _object_id: &str) {}
 }
 
 /// Implement the [CollabPlugin] trait for Box<T> and Arc<T> where T implements CollabPlugin.
@@ -30,6 +119,18 @@ impl<T> CollabPlugin for Box<T>
 where
   T: CollabPlugin,
 {
+  fn plugin_id(&self) -> String {
+    (**self).plugin_id()
+  }
+
+  fn priority(&self) -> i32 {
+    (**self).priority()
+  }
+
+  fn set_state_handle(&self, object_id: &str, handle: CollabStateHandle) {
+    (**self).set_state_handle(object_id, handle)
+  }
+
   fn init(&self, object_id: &str, txn: &mut TransactionMut) {
     (**self).init(object_id, txn)
   }
@@ -41,12 +142,75 @@ where
   fn receive_update(&self, object_id: &str, txn: &TransactionMut, update: &[u8]) {
     (**self).receive_update(object_id, txn, update)
   }
+
+  fn receive_local_update(&self, origin: &CollabOrigin, object_id: &str, update: &[u8]) {
+    (**self).receive_local_update(origin, object_id, update)
+  }
+
+  fn accept_remote_update(
+    &self,
+    origin: &CollabOrigin,
+    object_id: &str,
+    txn: &TransactionMut,
+    changed_paths: &[String],
+  ) -> bool {
+    (**self).accept_remote_update(origin, object_id, txn, changed_paths)
+  }
+
+  fn after_transaction(&self, object_id: &str, txn: &mut TransactionMut) {
+    (**self).after_transaction(object_id, txn)
+  }
+
+  fn receive_awareness_update(
+    &self,
+    object_id: &str,
+    awareness: &Awareness,
+    event: &AwarenessEvent,
+  ) {
+    (**self).receive_awareness_update(object_id, awareness, event)
+  }
+
+  fn did_change_state(&self, object_id: &str, change: &CollabStateChange) {
+    (**self).did_change_state(object_id, change)
+  }
+
+  fn flush(&self, object_id: &str, doc: &Doc) {
+    (**self).flush(object_id, doc)
+  }
+
+  fn collect_stats(&self, object_id: &str, stats: &mut CollabStats) {
+    (**self).collect_stats(object_id, stats)
+  }
+
+  fn did_compact(&self, object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    (**self).did_compact(object_id, compacted_doc, point)
+  }
+
+  fn get_compaction_point(&self, object_id: &str) -> Option<CompactionPoint> {
+    (**self).get_compaction_point(object_id)
+  }
+
+  fn will_close(&self, object_id: &str) {
+    (**self).will_close(object_id)
+  }
 }
 
 impl<T> CollabPlugin for Arc<T>
 where
   T: CollabPlugin,
 {
+  fn plugin_id(&self) -> String {
+    (**self).plugin_id()
+  }
+
+  fn priority(&self) -> i32 {
+    (**self).priority()
+  }
+
+  fn set_state_handle(&self, object_id: &str, handle: CollabStateHandle) {
+    (**self).set_state_handle(object_id, handle)
+  }
+
   fn init(&self, object_id: &str, txn: &mut TransactionMut) {
     (**self).init(object_id, txn)
   }
@@ -58,4 +222,55 @@ where
   fn receive_update(&self, object_id: &str, txn: &TransactionMut, update: &[u8]) {
     (**self).receive_update(object_id, txn, update)
   }
+
+  fn receive_local_update(&self, origin: &CollabOrigin, object_id: &str, update: &[u8]) {
+    (**self).receive_local_update(origin, object_id, update)
+  }
+
+  fn accept_remote_update(
+    &self,
+    origin: &CollabOrigin,
+    object_id: &str,
+    txn: &TransactionMut,
+    changed_paths: &[String],
+  ) -> bool {
+    (**self).accept_remote_update(origin, object_id, txn, changed_paths)
+  }
+
+  fn after_transaction(&self, object_id: &str, txn: &mut TransactionMut) {
+    (**self).after_transaction(object_id, txn)
+  }
+
+  fn receive_awareness_update(
+    &self,
+    object_id: &str,
+    awareness: &Awareness,
+    event: &AwarenessEvent,
+  ) {
+    (**self).receive_awareness_update(object_id, awareness, event)
+  }
+
+  fn did_change_state(&self, object_id: &str, change: &CollabStateChange) {
+    (**self).did_change_state(object_id, change)
+  }
+
+  fn flush(&self, object_id: &str, doc: &Doc) {
+    (**self).flush(object_id, doc)
+  }
+
+  fn collect_stats(&self, object_id: &str, stats: &mut CollabStats) {
+    (**self).collect_stats(object_id, stats)
+  }
+
+  fn did_compact(&self, object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    (**self).did_compact(object_id, compacted_doc, point)
+  }
+
+  fn get_compaction_point(&self, object_id: &str) -> Option<CompactionPoint> {
+    (**self).get_compaction_point(object_id)
+  }
+
+  fn will_close(&self, object_id: &str) {
+    (**self).will_close(object_id)
+  }
 }
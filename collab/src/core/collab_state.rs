@@ -1,8 +1,10 @@
 use parking_lot::RwLock;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use tokio::sync::watch;
 
-#[derive(Clone, Debug)]
+use crate::core::collab::Plugins;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CollabState {
   /// The [Collab] is not initialized yet. Call [Collab::initialize] to initialize
   Uninitialized,
@@ -19,6 +21,18 @@ pub enum CollabState {
   RootChanged,
   /// The [Collab] is initialized and ready to use.
   Initialized,
+  /// The local changes are being sent to the remote or the remote changes are being loaded.
+  Syncing,
+  /// All the local changes were acknowledged by the remote.
+  Synced,
+  /// The connection to the remote was lost. The local changes are kept and will be sent when
+  /// the connection is back.
+  Offline,
+  /// Saving the changes to the local storage failed. The error is carried by the
+  /// [CollabStateChange::reason].
+  PersistError,
+  /// The [Collab] was closed and must not be used anymore.
+  Closed,
 }
 
 impl CollabState {
@@ -29,25 +43,61 @@ impl CollabState {
   pub fn is_root_changed(&self) -> bool {
     matches!(self, CollabState::RootChanged)
   }
+
+  pub fn is_synced(&self) -> bool {
+    matches!(self, CollabState::Synced)
+  }
+
+  pub fn is_closed(&self) -> bool {
+    matches!(self, CollabState::Closed)
+  }
+}
+
+/// The value that is sent to the subscribers of the state. See [Collab::subscribe_state_change].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollabStateChange {
+  pub state: CollabState,
+  /// Describes why the state changed, for example, the error message of the
+  /// [CollabState::PersistError]. Empty if no reason was given.
+  pub reason: String,
+}
+
+impl CollabStateChange {
+  pub fn new(state: CollabState, reason: impl Into<String>) -> Self {
+    Self {
+      state,
+      reason: reason.into(),
+    }
+  }
 }
 
 pub struct State {
   object_id: String,
-  inner: Arc<RwLock<CollabState>>,
-  pub(crate) notifier: Arc<watch::Sender<CollabState>>,
+  inner: Arc<RwLock<CollabStateChange>>,
+  pub(crate) notifier: Arc<watch::Sender<CollabStateChange>>,
+  /// The plugins are notified by [CollabPlugin::did_change_state] when the state changes.
+  ///
+  /// [CollabPlugin::did_change_state]: crate::core::collab_plugin::CollabPlugin::did_change_state
+  plugins: Plugins,
 }
 
 impl State {
-  pub fn new(object_id: &str) -> Self {
-    let (state_notifier, _) = watch::channel(CollabState::Uninitialized);
+  pub fn new(object_id: &str, plugins: Plugins) -> Self {
+    let initial = CollabStateChange::new(CollabState::Uninitialized, "");
+    let (state_notifier, _) = watch::channel(initial.clone());
     Self {
       object_id: object_id.to_string(),
-      inner: Arc::new(RwLock::new(CollabState::Uninitialized)),
+      inner: Arc::new(RwLock::new(initial)),
       notifier: Arc::new(state_notifier),
+      plugins,
     }
   }
 
   pub fn get(&self) -> CollabState {
+    self.inner.read().state.clone()
+  }
+
+  pub fn get_change(&self) -> CollabStateChange {
     self.inner.read().clone()
   }
 
@@ -56,13 +106,58 @@ impl State {
   }
 
   pub fn set(&self, state: CollabState) {
+    self.set_with_reason(state, "");
+  }
+
+  pub fn set_with_reason(&self, state: CollabState, reason: impl Into<String>) {
+    let change = CollabStateChange::new(state, reason);
     tracing::trace!(
       "[🦀Collab] {} state did change from {:?} to {:?}",
       self.object_id,
-      self.inner.read(),
-      state
+      self.inner.read().state,
+      change
     );
-    *self.inner.write() = state.clone();
-    let _ = self.notifier.send(state);
+    *self.inner.write() = change.clone();
+    // The state is usually published by the plugins while the plugins are being iterated.
+    self
+      .plugins
+      .read_recursive()
+      .iter()
+      .for_each(|plugin| plugin.did_change_state(&self.object_id, &change));
+    let _ = self.notifier.send(change);
+  }
+}
+
+/// A handle that is used by the plugins to publish the state of the [Collab]. For example, the
+/// sync plugin publishes [CollabState::Syncing] and [CollabState::Synced], and the disk plugin
+/// publishes [CollabState::PersistError].
+///
+/// The handle doesn't keep the [Collab] alive. The [State] holds the plugins, so a strong
+/// reference would form a cycle. After the [Collab] is dropped, [CollabStateHandle::get] returns
+/// [CollabState::Closed] and [CollabStateHandle::set] does nothing.
+#[derive(Clone)]
+pub struct CollabStateHandle(Weak<State>);
+
+impl CollabStateHandle {
+  pub(crate) fn new(state: &Arc<State>) -> Self {
+    Self(Arc::downgrade(state))
+  }
+
+  pub fn get(&self) -> CollabState {
+    match self.0.upgrade() {
+      Some(state) => state.get(),
+      None => CollabState::Closed,
+    }
+  }
+
+  pub fn set(&self, state: CollabState, reason: impl Into<String>) {
+    if let Some(inner) = self.0.upgrade() {
+      inner.set_with_reason(state, reason);
+    }
+  }
+
+  /// Returns `None` if the [Collab] was dropped.
+  pub fn subscribe(&self) -> Option<watch::Receiver<CollabStateChange>> {
+    self.0.upgrade().map(|state| state.notifier.subscribe())
   }
 }
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use yrs::block::ClientID;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, Options, ReadTxn, Snapshot, StateVector, Transact, Update};
+
+use crate::core::snapshot_view::encode_state_from_snapshot;
+use crate::error::CollabError;
+use crate::util::timestamp_millis;
+
+/// Records where the history of a document was cut by [Collab::compact]. The deleted items
+/// before the point are discarded, so the document can't be read at a snapshot that was taken
+/// before it. The point is not part of the document, the disk plugins store it next to the
+/// document, see [CollabPlugin::did_compact].
+///
+/// [CollabPlugin::did_compact]: crate::preclude::CollabPlugin::did_compact
+/// [Collab::compact]: crate::preclude::Collab::compact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionPoint {
+  /// The encoded [StateVector] of the snapshot that the document was compacted up to.
+  pub state_vector: Vec<u8>,
+  /// The unix timestamp, in milliseconds, when the document was compacted.
+  pub compacted_at: i64,
+}
+
+impl CompactionPoint {
+  pub fn new(state_vector: &StateVector) -> Self {
+    Self {
+      state_vector: state_vector.encode_v1(),
+      compacted_at: timestamp_millis(),
+    }
+  }
+
+  pub fn to_vec(&self) -> Result<Vec<u8>, CollabError> {
+    let data = serde_json::to_vec(self)?;
+    Ok(data)
+  }
+
+  pub fn from_slice(data: &[u8]) -> Result<Self, CollabError> {
+    let point = serde_json::from_slice(data)?;
+    Ok(point)
+  }
+
+  pub fn state_vector(&self) -> Result<StateVector, CollabError> {
+    Ok(StateVector::decode_v1(&self.state_vector)?)
+  }
+
+  /// Returns true if the peer with the given [StateVector] didn't receive all the changes that
+  /// were made before the point. The peer should be synced with the full state instead of the
+  /// missing updates.
+  pub fn is_ahead_of(&self, state_vector: &StateVector) -> bool {
+    match self.state_vector() {
+      Ok(point) => point
+        .iter()
+        .any(|(client_id, clock)| state_vector.get(client_id) < *clock),
+      // The point can't be compared, assume the peer is behind to stay on the safe side.
+      Err(_) => true,
+    }
+  }
+}
+
+/// The result of [Collab::compact](crate::preclude::Collab::compact).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionResult {
+  pub point: CompactionPoint,
+  /// The size, in bytes, of the encoded document state before the compaction.
+  pub size_before: usize,
+  /// The size, in bytes, of the encoded state of the compacted document.
+  pub size_after: usize,
+}
+
+/// Rebuild the document with the garbage collection enabled up to the given [Snapshot]. The
+/// content of the items that were deleted before the snapshot is discarded, and the changes
+/// after it are kept as they are, including their deleted items. The discarded items stay in
+/// the document as tombstones without content, so the items that refer to them, for example, a
+/// map entry that was removed before the snapshot and set again after it, are kept.
+///
+/// The returned [Doc] uses the given client id, so it can replace the stored state of the
+/// document.
+pub(crate) fn compact_doc<T: ReadTxn>(
+  txn: &T,
+  client_id: ClientID,
+  snapshot: &Snapshot,
+) -> Result<Doc, CollabError> {
+  // Applying the state to a doc that runs the garbage collection discards the deleted items.
+  let gc_doc = Doc::with_options(Options {
+    skip_gc: false,
+    ..Options::default()
+  });
+  {
+    let encoded_state = encode_state_from_snapshot(txn, snapshot)?;
+    let mut gc_txn = gc_doc.transact_mut();
+    gc_txn.apply_update(Update::decode_v1(&encoded_state)?);
+  }
+  let compacted_state = gc_doc
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+
+  let doc = Doc::with_options(Options {
+    client_id,
+    skip_gc: true,
+    ..Options::default()
+  });
+  {
+    let mut doc_txn = doc.transact_mut();
+    doc_txn.apply_update(Update::decode_v1(&compacted_state)?);
+  }
+  {
+    let recent_updates = txn.encode_state_as_update_v1(&snapshot.state_map);
+    let mut doc_txn = doc.transact_mut();
+    doc_txn.apply_update(Update::decode_v1(&recent_updates)?);
+  }
+  Ok(doc)
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{ReadTxn, StateVector, Update};
+
+/// The encoding of the yrs updates. The [EncoderVersion::V2] is much smaller for the documents
+/// that contain a lot of text, but it can only be read by the peers that support it. The
+/// [Collab](crate::preclude::Collab) always passes the v1 updates to the plugins, so each layer,
+/// for example, the disk plugin or the sync protocol, chooses its own encoding and converts the
+/// updates with [EncoderVersion::convert_update].
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum EncoderVersion {
+  #[default]
+  V1,
+  V2,
+}
+
+impl EncoderVersion {
+  pub fn to_u8(self) -> u8 {
+    match self {
+      EncoderVersion::V1 => 1,
+      EncoderVersion::V2 => 2,
+    }
+  }
+
+  /// Returns None if the version is unknown, for example, it was written by a newer version.
+  pub fn from_u8(value: u8) -> Option<Self> {
+    match value {
+      1 => Some(EncoderVersion::V1),
+      2 => Some(EncoderVersion::V2),
+      _ => None,
+    }
+  }
+
+  /// Encode the changes that are not included in the given [StateVector].
+  pub fn encode_state_as_update<T: ReadTxn>(self, txn: &T, state_vector: &StateVector) -> Vec<u8> {
+    match self {
+      EncoderVersion::V1 => txn.encode_state_as_update_v1(state_vector),
+      EncoderVersion::V2 => txn.encode_state_as_update_v2(state_vector),
+    }
+  }
+
+  pub fn encode_update(self, update: &Update) -> Vec<u8> {
+    match self {
+      EncoderVersion::V1 => update.encode_v1(),
+      EncoderVersion::V2 => update.encode_v2(),
+    }
+  }
+
+  pub fn decode_update(self, data: &[u8]) -> Result<Update, lib0::error::Error> {
+    match self {
+      EncoderVersion::V1 => Update::decode_v1(data),
+      EncoderVersion::V2 => Update::decode_v2(data),
+    }
+  }
+
+  /// Merge the updates that are encoded with this version into one update.
+  pub fn merge_updates(self, updates: &[&[u8]]) -> Result<Vec<u8>, lib0::error::Error> {
+    match self {
+      EncoderVersion::V1 => yrs::merge_updates_v1(updates),
+      EncoderVersion::V2 => yrs::merge_updates_v2(updates),
+    }
+  }
+
+  /// Re-encode the update that is encoded with this version with the given version. The data is
+  /// returned as it is if the versions are the same.
+  pub fn convert_update(
+    self,
+    data: &[u8],
+    to: EncoderVersion,
+  ) -> Result<Vec<u8>, lib0::error::Error> {
+    if self == to {
+      return Ok(data.to_vec());
+    }
+    let update = self.decode_update(data)?;
+    Ok(to.encode_update(&update))
+  }
+}
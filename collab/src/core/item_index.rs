@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use lib0::decoding::Read;
+use lib0::error::Error;
+use yrs::block::{
+  ClientID, ItemContent, BLOCK_GC_REF_NUMBER, BLOCK_SKIP_REF_NUMBER, HAS_ORIGIN, HAS_PARENT_SUB,
+  HAS_RIGHT_ORIGIN,
+};
+use yrs::types::{TYPE_REFS_ARRAY, TYPE_REFS_MAP};
+use yrs::updates::decoder::{Decode, Decoder, DecoderV1};
+use yrs::{DeleteSet, OffsetKind, ReadTxn, StateVector, ID};
+
+/// The type that contains an item: a root type of the document, or a nested type that is
+/// identified by the id of the item that holds it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Parent {
+  Root(Arc<str>),
+  Item(ID),
+}
+
+/// A block of the block store. The `len` is counted in the clock of the client.
+pub(crate) struct BlockInfo {
+  pub(crate) id: ID,
+  pub(crate) len: u32,
+  pub(crate) deleted: bool,
+}
+
+/// A visible piece of an array or a text. The `len` is the number of the elements of an array
+/// or the number of the bytes of a text. An embed or a nested type counts as one.
+pub(crate) struct SequenceItem {
+  pub(crate) id: ID,
+  pub(crate) len: u32,
+}
+
+/// An index of the items of a document. Yrs doesn't expose its block store, so the state of the
+/// document is encoded and decoded again, the same way as an update, and the items of the
+/// arrays and the texts are put in order with the rules that yrs uses to integrate them.
+pub(crate) struct ItemIndex {
+  /// The blocks of the document, including the deleted ones.
+  pub(crate) blocks: Vec<BlockInfo>,
+  /// The id of the item that holds the current value of each key, grouped by the map.
+  map_entries: HashMap<Parent, HashMap<Arc<str>, ID>>,
+  /// The visible items of each array and text in order.
+  sequences: HashMap<Parent, Vec<SequenceItem>>,
+  /// The type ref of each nested type that is not deleted, see [TYPE_REFS_MAP].
+  type_refs: HashMap<ID, u8>,
+}
+
+impl ItemIndex {
+  pub(crate) fn from_txn<T: ReadTxn>(txn: &T) -> Self {
+    let update = txn.encode_state_as_update_v1(&StateVector::default());
+    // The state is encoded by yrs itself, so it's always decoded.
+    let (blocks, delete_set) = decode_state(&update).unwrap_or_default();
+    let infos = blocks
+      .iter()
+      .map(|block| match block {
+        DecodedBlock::GC(id, len) => BlockInfo {
+          id: *id,
+          len: *len,
+          deleted: true,
+        },
+        DecodedBlock::Item(item) => BlockInfo {
+          id: item.id,
+          len: item.len,
+          deleted: delete_set.is_deleted(&item.id),
+        },
+      })
+      .collect();
+
+    let mut integration = Integration::new(blocks, &delete_set);
+    integration.integrate_all();
+    let mut index = Self {
+      blocks: infos,
+      map_entries: HashMap::new(),
+      sequences: HashMap::new(),
+      type_refs: HashMap::new(),
+    };
+    integration.collect_into(&mut index);
+    index
+  }
+
+  /// Returns the nested type at the given path of a root map. The segments of the path are the
+  /// keys of the maps and the positions of the arrays.
+  pub(crate) fn resolve_path(&self, root: &str, path: &[&str]) -> Option<Parent> {
+    let mut parent = Parent::Root(Arc::from(root));
+    let mut type_ref = TYPE_REFS_MAP;
+    for segment in path {
+      let id = match type_ref {
+        TYPE_REFS_MAP => *self.map_entries.get(&parent)?.get(*segment)?,
+        TYPE_REFS_ARRAY => self.array_element(&parent, segment.parse().ok()?)?,
+        _ => return None,
+      };
+      type_ref = *self.type_refs.get(&id)?;
+      parent = Parent::Item(id);
+    }
+    Some(parent)
+  }
+
+  /// Returns the id of the item that holds the current value of each key of the map.
+  pub(crate) fn map_entries(&self, parent: &Parent) -> impl Iterator<Item = (&str, &ID)> {
+    self
+      .map_entries
+      .get(parent)
+      .into_iter()
+      .flat_map(|entries| entries.iter().map(|(key, id)| (key.as_ref(), id)))
+  }
+
+  /// Returns the visible items of the array or the text in order.
+  pub(crate) fn sequence(&self, parent: &Parent) -> &[SequenceItem] {
+    self
+      .sequences
+      .get(parent)
+      .map(|items| items.as_slice())
+      .unwrap_or_default()
+  }
+
+  /// Returns the id of the item that holds the element at the given position of the array.
+  /// A nested type is always held by an item of its own.
+  fn array_element(&self, parent: &Parent, position: u32) -> Option<ID> {
+    let mut remaining = position;
+    for item in self.sequence(parent) {
+      if remaining < item.len {
+        return Some(ID::new(item.id.client, item.id.clock + remaining));
+      }
+      remaining -= item.len;
+    }
+    None
+  }
+}
+
+enum DecodedBlock {
+  GC(ID, u32),
+  Item(DecodedItem),
+}
+
+struct DecodedItem {
+  id: ID,
+  len: u32,
+  origin: Option<ID>,
+  right_origin: Option<ID>,
+  /// None if the parent is the same as the parent of the origin or the right origin.
+  parent: Option<Parent>,
+  parent_sub: Option<Arc<str>>,
+  content: ItemContent,
+}
+
+/// Decodes the blocks and the delete set of an update v1, the same as yrs does.
+fn decode_state(update: &[u8]) -> Result<(Vec<DecodedBlock>, DeleteSet), Error> {
+  let mut decoder = DecoderV1::from(update);
+  let mut blocks = vec![];
+  let clients_len: u32 = decoder.read_var()?;
+  for _ in 0..clients_len {
+    let blocks_len: u32 = decoder.read_var()?;
+    let client = decoder.read_client()?;
+    let mut clock: u32 = decoder.read_var()?;
+    for _ in 0..blocks_len {
+      let id = ID::new(client, clock);
+      let info = decoder.read_info()?;
+      match info {
+        BLOCK_SKIP_REF_NUMBER => {
+          let len: u32 = decoder.read_var()?;
+          clock += len;
+        },
+        BLOCK_GC_REF_NUMBER => {
+          let len = decoder.read_len()?;
+          clock += len;
+          blocks.push(DecodedBlock::GC(id, len));
+        },
+        info => {
+          let item = decode_item(id, info, &mut decoder)?;
+          clock += item.len;
+          blocks.push(DecodedBlock::Item(item));
+        },
+      }
+    }
+  }
+  let delete_set = DeleteSet::decode(&mut decoder)?;
+  Ok((blocks, delete_set))
+}
+
+fn decode_item<D: Decoder>(id: ID, info: u8, decoder: &mut D) -> Result<DecodedItem, Error> {
+  let cant_copy_parent_info = info & (HAS_ORIGIN | HAS_RIGHT_ORIGIN) == 0;
+  let origin = if info & HAS_ORIGIN != 0 {
+    Some(decoder.read_left_id()?)
+  } else {
+    None
+  };
+  let right_origin = if info & HAS_RIGHT_ORIGIN != 0 {
+    Some(decoder.read_right_id()?)
+  } else {
+    None
+  };
+  let parent = if cant_copy_parent_info {
+    if decoder.read_parent_info()? {
+      Some(Parent::Root(Arc::from(decoder.read_string()?)))
+    } else {
+      Some(Parent::Item(decoder.read_left_id()?))
+    }
+  } else {
+    None
+  };
+  let parent_sub = if cant_copy_parent_info && info & HAS_PARENT_SUB != 0 {
+    Some(Arc::from(decoder.read_string()?))
+  } else {
+    None
+  };
+  let content = ItemContent::decode(decoder, info)?;
+  Ok(DecodedItem {
+    id,
+    len: content.len(OffsetKind::Utf16),
+    origin,
+    right_origin,
+    parent,
+    parent_sub,
+    content,
+  })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+  Pending,
+  InProgress,
+  Integrated,
+  /// The item can't be integrated, for example, its parent was garbage collected.
+  Dropped,
+}
+
+enum PieceContent {
+  Text(String),
+  Type(u8),
+  Countable,
+  NotCountable,
+}
+
+/// A part of an item. The items are split at the origins of the other items and at the ranges
+/// of the delete set, so each piece is either deleted or not as a whole, and the YATA rules
+/// can be applied to the pieces the same as yrs applies them to its split items.
+struct Piece {
+  id: ID,
+  len: u32,
+  origin: Option<ID>,
+  right_origin: Option<ID>,
+  parent: Option<Parent>,
+  parent_sub: Option<Arc<str>>,
+  content: PieceContent,
+  deleted: bool,
+  state: State,
+  right: Option<usize>,
+  has_left: bool,
+}
+
+enum Slot {
+  Piece(usize),
+  GC,
+}
+
+struct Integration {
+  pieces: Vec<Piece>,
+  /// The `(clock, end, slot)` of the pieces and the garbage collected blocks of each client,
+  /// sorted by the clock.
+  slots: HashMap<ClientID, Vec<(u32, u32, Slot)>>,
+  /// The first piece of each array and text.
+  starts: HashMap<Parent, usize>,
+}
+
+impl Integration {
+  fn new(blocks: Vec<DecodedBlock>, delete_set: &DeleteSet) -> Self {
+    let mut cuts: HashMap<ClientID, Vec<u32>> = HashMap::new();
+    for block in &blocks {
+      if let DecodedBlock::Item(item) = block {
+        if let Some(origin) = item.origin {
+          cuts
+            .entry(origin.client)
+            .or_default()
+            .push(origin.clock + 1);
+        }
+        if let Some(right_origin) = item.right_origin {
+          cuts
+            .entry(right_origin.client)
+            .or_default()
+            .push(right_origin.clock);
+        }
+      }
+    }
+    for (client, range) in delete_set.iter() {
+      let client_cuts = cuts.entry(*client).or_default();
+      for range in range.iter() {
+        client_cuts.push(range.start);
+        client_cuts.push(range.end);
+      }
+    }
+    for client_cuts in cuts.values_mut() {
+      client_cuts.sort_unstable();
+      client_cuts.dedup();
+    }
+
+    let mut integration = Self {
+      pieces: vec![],
+      slots: HashMap::new(),
+      starts: HashMap::new(),
+    };
+    for block in blocks {
+      match block {
+        DecodedBlock::GC(id, len) => {
+          integration
+            .slots
+            .entry(id.client)
+            .or_default()
+            .push((id.clock, id.clock + len, Slot::GC))
+        },
+        DecodedBlock::Item(item) => {
+          let no_cuts = vec![];
+          let client_cuts = cuts.get(&item.id.client).unwrap_or(&no_cuts);
+          integration.push_item(item, client_cuts, delete_set);
+        },
+      }
+    }
+    for slots in integration.slots.values_mut() {
+      slots.sort_unstable_by_key(|(clock, _, _)| *clock);
+    }
+    integration
+  }
+
+  /// Splits the item at the cuts that are inside of it.
+  fn push_item(&mut self, item: DecodedItem, cuts: &[u32], delete_set: &DeleteSet) {
+    let start = item.id.clock;
+    let end = start + item.len;
+    let first = cuts.partition_point(|cut| *cut <= start);
+    let mut bounds = vec![start];
+    bounds.extend(cuts[first..].iter().take_while(|cut| **cut < end));
+    bounds.push(end);
+
+    let text = match &item.content {
+      ItemContent::String(content) => Some(content.as_str()),
+      _ => None,
+    };
+    for (i, range) in bounds.windows(2).enumerate() {
+      let id = ID::new(item.id.client, range[0]);
+      let content = match (&item.content, text) {
+        (_, Some(text)) => {
+          PieceContent::Text(utf16_slice(text, range[0] - start, range[1] - start))
+        },
+        (ItemContent::Type(branch), _) => PieceContent::Type(branch.type_ref()),
+        (content, _) if content.is_countable() => PieceContent::Countable,
+        _ => PieceContent::NotCountable,
+      };
+      let origin = if i == 0 {
+        item.origin
+      } else {
+        Some(ID::new(item.id.client, range[0] - 1))
+      };
+      self.slots.entry(id.client).or_default().push((
+        range[0],
+        range[1],
+        Slot::Piece(self.pieces.len()),
+      ));
+      self.pieces.push(Piece {
+        id,
+        len: range[1] - range[0],
+        origin,
+        right_origin: item.right_origin,
+        parent: item.parent.clone(),
+        parent_sub: item.parent_sub.clone(),
+        content,
+        deleted: delete_set.is_deleted(&id),
+        state: State::Pending,
+        right: None,
+        has_left: false,
+      });
+    }
+  }
+
+  fn find(&self, id: &ID) -> Option<&Slot> {
+    let slots = self.slots.get(&id.client)?;
+    let i = slots.partition_point(|(_, end, _)| *end <= id.clock);
+    match slots.get(i) {
+      Some((clock, _, slot)) if *clock <= id.clock => Some(slot),
+      _ => None,
+    }
+  }
+
+  fn find_piece(&self, id: &ID) -> Option<usize> {
+    match self.find(id)? {
+      Slot::Piece(i) => Some(*i),
+      Slot::GC => None,
+    }
+  }
+
+  /// Integrates the pieces in an order where the origins of a piece are integrated before it.
+  fn integrate_all(&mut self) {
+    for i in 0..self.pieces.len() {
+      let mut stack = vec![i];
+      while let Some(&top) = stack.last() {
+        if !matches!(self.pieces[top].state, State::Pending | State::InProgress) {
+          stack.pop();
+          continue;
+        }
+        self.pieces[top].state = State::InProgress;
+        let piece = &self.pieces[top];
+        let dependency = [piece.origin, piece.right_origin]
+          .iter()
+          .flatten()
+          .flat_map(|id| self.find_piece(id))
+          .find(|dependency| self.pieces[*dependency].state == State::Pending);
+        match dependency {
+          Some(dependency) => stack.push(dependency),
+          None => {
+            stack.pop();
+            self.integrate(top);
+          },
+        }
+      }
+    }
+  }
+
+  /// Returns the integrated piece of the origin. None if there's no origin, and an error if the
+  /// origin can't be found.
+  fn integrated_piece(&self, id: Option<ID>) -> Result<Option<usize>, ()> {
+    match id {
+      None => Ok(None),
+      Some(id) => match self.find_piece(&id) {
+        Some(i) if self.pieces[i].state == State::Integrated => Ok(Some(i)),
+        _ => Err(()),
+      },
+    }
+  }
+
+  fn integrate(&mut self, i: usize) {
+    let (left, right) = match (
+      self.integrated_piece(self.pieces[i].origin),
+      self.integrated_piece(self.pieces[i].right_origin),
+    ) {
+      (Ok(left), Ok(right)) => (left, right),
+      _ => {
+        self.pieces[i].state = State::Dropped;
+        return;
+      },
+    };
+    if self.pieces[i].parent.is_none() {
+      match left.or(right) {
+        Some(neighbour) => {
+          self.pieces[i].parent = self.pieces[neighbour].parent.clone();
+          self.pieces[i].parent_sub = self.pieces[neighbour].parent_sub.clone();
+        },
+        None => {
+          self.pieces[i].state = State::Dropped;
+          return;
+        },
+      }
+    }
+    let parent = match &self.pieces[i].parent {
+      Some(Parent::Item(id)) if self.find_piece(id).is_none() => None,
+      parent => parent.clone(),
+    };
+    let parent = match parent {
+      None => {
+        self.pieces[i].state = State::Dropped;
+        return;
+      },
+      Some(parent) => parent,
+    };
+    self.pieces[i].state = State::Integrated;
+    // Only one value of a key is not deleted, so the order of the map entries is not needed.
+    if self.pieces[i].parent_sub.is_some() {
+      return;
+    }
+
+    let left = self.resolve_conflicts(i, &parent, left, right);
+    let next = match left {
+      Some(left) => self.pieces[left].right.replace(i),
+      None => self.starts.insert(parent, i),
+    };
+    self.pieces[i].has_left = left.is_some();
+    self.pieces[i].right = next;
+    if let Some(next) = next {
+      self.pieces[next].has_left = true;
+    }
+  }
+
+  /// Returns the piece that the piece is inserted after, see `Item::integrate` of yrs.
+  fn resolve_conflicts(
+    &self,
+    i: usize,
+    parent: &Parent,
+    mut left: Option<usize>,
+    right: Option<usize>,
+  ) -> Option<usize> {
+    let piece = &self.pieces[i];
+    let left_right = match left {
+      Some(left) => self.pieces[left].right,
+      None => self.starts.get(parent).copied(),
+    };
+    let right_has_left = right.map(|right| self.pieces[right].has_left);
+    let conflict = match left {
+      None => right_has_left != Some(false),
+      Some(_) => left_right != right,
+    };
+    if !conflict {
+      return left;
+    }
+
+    let mut conflicting = HashSet::new();
+    let mut before_origin = HashSet::new();
+    let mut o = left_right;
+    while let Some(current) = o {
+      if Some(current) == right {
+        break;
+      }
+      before_origin.insert(current);
+      conflicting.insert(current);
+      let other = &self.pieces[current];
+      if other.origin == piece.origin {
+        if other.id.client < piece.id.client {
+          left = Some(current);
+          conflicting.clear();
+        } else if other.right_origin == piece.right_origin {
+          break;
+        }
+      } else {
+        match other.origin.and_then(|origin| self.find_piece(&origin)) {
+          Some(origin) if before_origin.contains(&origin) => {
+            if !conflicting.contains(&origin) {
+              left = Some(current);
+              conflicting.clear();
+            }
+          },
+          _ => break,
+        }
+      }
+      o = other.right;
+    }
+    left
+  }
+
+  fn collect_into(self, index: &mut ItemIndex) {
+    for piece in &self.pieces {
+      if piece.state != State::Integrated || piece.deleted {
+        continue;
+      }
+      if let PieceContent::Type(type_ref) = piece.content {
+        index.type_refs.insert(piece.id, type_ref);
+      }
+      if let (Some(parent), Some(key)) = (&piece.parent, &piece.parent_sub) {
+        index
+          .map_entries
+          .entry(parent.clone())
+          .or_default()
+          .insert(key.clone(), piece.id);
+      }
+    }
+
+    for (parent, start) in &self.starts {
+      let mut items = vec![];
+      let mut next = Some(*start);
+      while let Some(i) = next {
+        let piece = &self.pieces[i];
+        let len = match &piece.content {
+          _ if piece.deleted => 0,
+          PieceContent::Text(text) => text.len() as u32,
+          PieceContent::Type(_) => 1,
+          PieceContent::Countable => piece.len,
+          PieceContent::NotCountable => 0,
+        };
+        if len > 0 {
+          items.push(SequenceItem { id: piece.id, len });
+        }
+        next = piece.right;
+      }
+      index.sequences.insert(parent.clone(), items);
+    }
+  }
+}
+
+/// Returns the part of the string between the given offsets, which are counted in UTF-16 code
+/// units like the clocks of the items.
+fn utf16_slice(text: &str, start: u32, end: u32) -> String {
+  let mut offset = 0;
+  let mut slice = String::new();
+  for c in text.chars() {
+    if offset >= end {
+      break;
+    }
+    if offset >= start {
+      slice.push(c);
+    }
+    offset += c.len_utf16() as u32;
+  }
+  slice
+}
@@ -0,0 +1,492 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+use yrs::types::{Change, EntryChange, Event, Events, PathSegment, ToJson, Value};
+use yrs::{Array, Map, MapRef, ReadTxn, TransactionMut};
+
+use crate::error::CollabError;
+use crate::preclude::JsonValue;
+use crate::util::{insert_json_value_at_index, insert_json_value_with_key, JsonInsertConfig};
+
+/// A JSON Patch operation that is defined in [RFC 6902](https://tools.ietf.org/html/rfc6902).
+/// The `path` and `from` are JSON Pointers ([RFC 6901](https://tools.ietf.org/html/rfc6901))
+/// that relative to the `data` section of the [Collab](crate::preclude::Collab).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+  Add { path: String, value: JsonValue },
+  Remove { path: String },
+  Replace { path: String, value: JsonValue },
+  Move { from: String, path: String },
+  Copy { from: String, path: String },
+  Test { path: String, value: JsonValue },
+}
+
+/// A JSON Patch document, which is a list of [PatchOperation]s. It's serialized as a json array,
+/// for example:
+/// `[{"op": "add", "path": "/name", "value": "nathan"}, {"op": "remove", "path": "/level"}]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+impl JsonPatch {
+  pub fn from_json_value(value: JsonValue) -> Result<Self, CollabError> {
+    let patch = serde_json::from_value(value)?;
+    Ok(patch)
+  }
+
+  pub fn to_json_value(&self) -> JsonValue {
+    serde_json::to_value(self).unwrap()
+  }
+
+  /// Apply the patch to the given json value. The json value is left in an undefined state if
+  /// any operation fails.
+  pub fn apply_to_json_value(&self, value: &mut JsonValue) -> Result<(), CollabError> {
+    for operation in self.iter() {
+      apply_operation(value, operation)?;
+    }
+    Ok(())
+  }
+}
+
+impl Deref for JsonPatch {
+  type Target = Vec<PatchOperation>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl From<Vec<PatchOperation>> for JsonPatch {
+  fn from(operations: Vec<PatchOperation>) -> Self {
+    Self(operations)
+  }
+}
+
+/// Apply the patch to the given [MapRef] within the transaction. The operations are applied one
+/// by one, so the [MapRef] might be partially changed if any operation fails. Use
+/// [Collab::apply_json_patch](crate::preclude::Collab::apply_json_patch) to apply the patch
+/// atomically.
+///
+/// The json values are converted by [insert_json_value_with_key]. Moving or copying a value
+/// inserts a new value that converted from its json representation.
+pub fn apply_json_patch(
+  txn: &mut TransactionMut,
+  root: &MapRef,
+  patch: &JsonPatch,
+) -> Result<(), CollabError> {
+  let mut target = YrsPatchTarget { txn, root };
+  for operation in patch.iter() {
+    apply_operation(&mut target, operation)?;
+  }
+  Ok(())
+}
+
+/// Convert the events that received by the deep observer into [JsonPatch]. The paths of the
+/// operations are relative to the observed [MapRef].
+///
+/// The changes of the text are emitted as `replace` operation with the whole text.
+pub fn json_patch_from_events(txn: &TransactionMut, events: &Events) -> JsonPatch {
+  let mut operations = vec![];
+  for event in events.iter() {
+    let path = event
+      .path()
+      .iter()
+      .map(|segment| match segment {
+        PathSegment::Key(key) => key.to_string(),
+        PathSegment::Index(index) => index.to_string(),
+      })
+      .collect::<Vec<String>>();
+
+    match event {
+      Event::Map(event) => {
+        let mut changes = event.keys(txn).iter().collect::<Vec<_>>();
+        changes.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, change) in changes {
+          let path = to_json_pointer(path.iter().chain(std::iter::once(&key.to_string())));
+          let operation = match change {
+            EntryChange::Inserted(value) => PatchOperation::Add {
+              path,
+              value: yrs_value_to_json(txn, value),
+            },
+            EntryChange::Updated(_, value) => PatchOperation::Replace {
+              path,
+              value: yrs_value_to_json(txn, value),
+            },
+            EntryChange::Removed(_) => PatchOperation::Remove { path },
+          };
+          operations.push(operation);
+        }
+      },
+      Event::Array(event) => {
+        let mut index = 0;
+        for change in event.delta(txn) {
+          match change {
+            Change::Retain(len) => index += len,
+            Change::Removed(len) => {
+              let path = to_json_pointer(path.iter().chain(std::iter::once(&index.to_string())));
+              for _ in 0..*len {
+                operations.push(PatchOperation::Remove { path: path.clone() });
+              }
+            },
+            Change::Added(values) => {
+              for value in values {
+                let path = to_json_pointer(path.iter().chain(std::iter::once(&index.to_string())));
+                operations.push(PatchOperation::Add {
+                  path,
+                  value: yrs_value_to_json(txn, value),
+                });
+                index += 1;
+              }
+            },
+          }
+        }
+      },
+      _ => operations.push(PatchOperation::Replace {
+        path: to_json_pointer(path.iter()),
+        value: yrs_value_to_json(txn, &event.target()),
+      }),
+    }
+  }
+  JsonPatch(operations)
+}
+
+/// Parse the JSON Pointer into a list of reference tokens. The empty pointer refers to the
+/// whole document.
+pub fn parse_json_pointer(pointer: &str) -> Result<Vec<String>, CollabError> {
+  if pointer.is_empty() {
+    return Ok(vec![]);
+  }
+  match pointer.strip_prefix('/') {
+    None => Err(CollabError::InvalidJsonPatch(format!(
+      "The json pointer:{} must start with '/'",
+      pointer
+    ))),
+    Some(pointer) => Ok(
+      pointer
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect(),
+    ),
+  }
+}
+
+pub fn to_json_pointer<'a>(tokens: impl Iterator<Item = &'a String>) -> String {
+  tokens
+    .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+    .collect()
+}
+
+/// Returns the JSON Pointers of the values that are changed by the event. The paths are
+/// prefixed with the `scope_path`, which is the path of the observed type. For a map, each
+/// changed key is a path, for the other types, the path of the type itself.
+pub(crate) fn touched_paths_from_event(
+  txn: &TransactionMut,
+  event: &Event,
+  scope_path: &[String],
+) -> Vec<String> {
+  let mut path = scope_path.to_vec();
+  path.extend(event.path().into_iter().map(|segment| match segment {
+    PathSegment::Key(key) => key.to_string(),
+    PathSegment::Index(index) => index.to_string(),
+  }));
+  match event {
+    Event::Map(event) => event
+      .keys(txn)
+      .keys()
+      .map(|key| {
+        let mut path = path.clone();
+        path.push(key.to_string());
+        to_json_pointer(path.iter())
+      })
+      .collect(),
+    _ => vec![to_json_pointer(path.iter())],
+  }
+}
+
+/// The json value or the [MapRef] that the [PatchOperation] is applied to.
+trait PatchTarget {
+  fn get(&self, path: &[String]) -> Result<JsonValue, CollabError>;
+  fn add(&mut self, path: &[String], value: &JsonValue) -> Result<(), CollabError>;
+  fn remove(&mut self, path: &[String]) -> Result<(), CollabError>;
+}
+
+fn apply_operation<T: PatchTarget>(
+  target: &mut T,
+  operation: &PatchOperation,
+) -> Result<(), CollabError> {
+  match operation {
+    PatchOperation::Add { path, value } => target.add(&parse_json_pointer(path)?, value),
+    PatchOperation::Remove { path } => target.remove(&parse_json_pointer(path)?),
+    PatchOperation::Replace { path, value } => {
+      let path = parse_json_pointer(path)?;
+      if !path.is_empty() {
+        target.remove(&path)?;
+      }
+      target.add(&path, value)
+    },
+    PatchOperation::Move { from, path } => {
+      let from = parse_json_pointer(from)?;
+      let path = parse_json_pointer(path)?;
+      if from == path {
+        return Ok(());
+      }
+      if path.starts_with(&from) {
+        return Err(CollabError::InvalidJsonPatch(format!(
+          "Can't move {} into one of its children",
+          to_json_pointer(from.iter())
+        )));
+      }
+      let value = target.get(&from)?;
+      target.remove(&from)?;
+      target.add(&path, &value)
+    },
+    PatchOperation::Copy { from, path } => {
+      let value = target.get(&parse_json_pointer(from)?)?;
+      target.add(&parse_json_pointer(path)?, &value)
+    },
+    PatchOperation::Test { path, value } => {
+      let current = target.get(&parse_json_pointer(path)?)?;
+      if json_value_eq(&current, value) {
+        Ok(())
+      } else {
+        Err(CollabError::InvalidJsonPatch(format!(
+          "Test {} failed, expected: {}, actual: {}",
+          path, value, current
+        )))
+      }
+    },
+  }
+}
+
+impl PatchTarget for JsonValue {
+  fn get(&self, path: &[String]) -> Result<JsonValue, CollabError> {
+    let mut value = self;
+    for token in path {
+      value = match value {
+        JsonValue::Object(object) => object.get(token),
+        JsonValue::Array(array) => array.get(parse_index(token, array.len())?),
+        _ => None,
+      }
+      .ok_or_else(|| path_not_found(path))?;
+    }
+    Ok(value.clone())
+  }
+
+  fn add(&mut self, path: &[String], value: &JsonValue) -> Result<(), CollabError> {
+    let (last, parent_path) = match path.split_last() {
+      None => {
+        check_root_value(value)?;
+        *self = value.clone();
+        return Ok(());
+      },
+      Some(last) => last,
+    };
+
+    let mut parent = &mut *self;
+    for token in parent_path {
+      parent = match parent {
+        JsonValue::Object(object) => object.get_mut(token),
+        JsonValue::Array(array) => {
+          let index = parse_index(token, array.len())?;
+          array.get_mut(index)
+        },
+        _ => None,
+      }
+      .ok_or_else(|| path_not_found(path))?;
+    }
+
+    match parent {
+      JsonValue::Object(object) => {
+        object.insert(last.clone(), value.clone());
+      },
+      JsonValue::Array(array) => {
+        let index = parse_insert_index(last, array.len())?;
+        array.insert(index, value.clone());
+      },
+      _ => return Err(path_not_found(path)),
+    }
+    Ok(())
+  }
+
+  fn remove(&mut self, path: &[String]) -> Result<(), CollabError> {
+    let (last, parent_path) = path.split_last().ok_or_else(remove_root_error)?;
+    let mut parent = &mut *self;
+    for token in parent_path {
+      parent = match parent {
+        JsonValue::Object(object) => object.get_mut(token),
+        JsonValue::Array(array) => {
+          let index = parse_index(token, array.len())?;
+          array.get_mut(index)
+        },
+        _ => None,
+      }
+      .ok_or_else(|| path_not_found(path))?;
+    }
+
+    match parent {
+      JsonValue::Object(object) => object.remove(last).map(|_| ()),
+      JsonValue::Array(array) => {
+        let index = parse_index(last, array.len())?;
+        if index < array.len() {
+          array.remove(index);
+          Some(())
+        } else {
+          None
+        }
+      },
+      _ => None,
+    }
+    .ok_or_else(|| path_not_found(path))
+  }
+}
+
+struct YrsPatchTarget<'a, 'doc> {
+  txn: &'a mut TransactionMut<'doc>,
+  root: &'a MapRef,
+}
+
+impl<'a, 'doc> YrsPatchTarget<'a, 'doc> {
+  fn get_value(&self, path: &[String]) -> Result<Value, CollabError> {
+    let txn = &*self.txn;
+    let mut value = Value::YMap(self.root.clone());
+    for token in path {
+      value = match value {
+        Value::YMap(map_ref) => map_ref.get(txn, token),
+        Value::YArray(array_ref) => {
+          let index = parse_index(token, array_ref.len(txn) as usize)?;
+          array_ref.get(txn, index as u32)
+        },
+        _ => None,
+      }
+      .ok_or_else(|| path_not_found(path))?;
+    }
+    Ok(value)
+  }
+}
+
+impl<'a, 'doc> PatchTarget for YrsPatchTarget<'a, 'doc> {
+  fn get(&self, path: &[String]) -> Result<JsonValue, CollabError> {
+    let value = self.get_value(path)?;
+    Ok(yrs_value_to_json(&*self.txn, &value))
+  }
+
+  fn add(&mut self, path: &[String], value: &JsonValue) -> Result<(), CollabError> {
+    let (last, parent_path) = match path.split_last() {
+      None => {
+        check_root_value(value)?;
+        self.root.clear(self.txn);
+        let config = JsonInsertConfig::default();
+        for (key, value) in value.as_object().unwrap() {
+          insert_json_value_with_key(self.txn, self.root, key, value, &config);
+        }
+        return Ok(());
+      },
+      Some(last) => last,
+    };
+
+    let config = JsonInsertConfig::default();
+    match self.get_value(parent_path)? {
+      Value::YMap(map_ref) => insert_json_value_with_key(self.txn, &map_ref, last, value, &config),
+      Value::YArray(array_ref) => {
+        let index = parse_insert_index(last, array_ref.len(self.txn) as usize)?;
+        insert_json_value_at_index(self.txn, &array_ref, index as u32, value, &config);
+      },
+      _ => return Err(path_not_found(path)),
+    }
+    Ok(())
+  }
+
+  fn remove(&mut self, path: &[String]) -> Result<(), CollabError> {
+    let (last, parent_path) = path.split_last().ok_or_else(remove_root_error)?;
+    match self.get_value(parent_path)? {
+      Value::YMap(map_ref) => map_ref.remove(self.txn, last).map(|_| ()),
+      Value::YArray(array_ref) => {
+        let len = array_ref.len(self.txn);
+        let index = parse_index(last, len as usize)? as u32;
+        if index < len {
+          array_ref.remove(self.txn, index);
+          Some(())
+        } else {
+          None
+        }
+      },
+      _ => None,
+    }
+    .ok_or_else(|| path_not_found(path))
+  }
+}
+
+fn yrs_value_to_json<T: ReadTxn>(txn: &T, value: &Value) -> JsonValue {
+  serde_json::to_value(value.to_json(txn)).unwrap_or_default()
+}
+
+/// Compare two json values. The numbers are compared by their f64 value, because the integer
+/// might be stored as float in the document.
+fn json_value_eq(a: &JsonValue, b: &JsonValue) -> bool {
+  match (a, b) {
+    (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64() == b.as_f64(),
+    (JsonValue::Array(a), JsonValue::Array(b)) => {
+      a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_value_eq(a, b))
+    },
+    (JsonValue::Object(a), JsonValue::Object(b)) => {
+      a.len() == b.len()
+        && a
+          .iter()
+          .all(|(key, a)| b.get(key).map(|b| json_value_eq(a, b)).unwrap_or(false))
+    },
+    (a, b) => a == b,
+  }
+}
+
+/// The `data` section of the collab is a map, so the root can only be replaced by an object.
+fn check_root_value(value: &JsonValue) -> Result<(), CollabError> {
+  if value.is_object() {
+    Ok(())
+  } else {
+    Err(CollabError::InvalidJsonPatch(
+      "The root can only be replaced by an object".to_string(),
+    ))
+  }
+}
+
+/// Parse the array index. The `-` refers to the position after the last element, which is only
+/// valid when adding a value.
+fn parse_insert_index(token: &str, len: usize) -> Result<usize, CollabError> {
+  let index = if token == "-" {
+    len
+  } else {
+    parse_index(token, len)?
+  };
+  if index > len {
+    return Err(CollabError::InvalidJsonPatch(format!(
+      "The index:{} is out of bounds",
+      index
+    )));
+  }
+  Ok(index)
+}
+
+fn parse_index(token: &str, len: usize) -> Result<usize, CollabError> {
+  let is_valid = !token.is_empty()
+    && token.chars().all(|c| c.is_ascii_digit())
+    && (token == "0" || !token.starts_with('0'));
+  match token.parse::<usize>() {
+    Ok(index) if is_valid => Ok(index),
+    _ => Err(CollabError::InvalidJsonPatch(format!(
+      "Invalid array index:{}, the len of the array is {}",
+      token, len
+    ))),
+  }
+}
+
+fn path_not_found(path: &[String]) -> CollabError {
+  CollabError::InvalidJsonPatch(format!(
+    "The path:{} doesn't exist",
+    to_json_pointer(path.iter())
+  ))
+}
+
+fn remove_root_error() -> CollabError {
+  CollabError::InvalidJsonPatch("The root can't be removed".to_string())
+}
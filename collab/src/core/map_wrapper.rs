@@ -12,7 +12,7 @@ use yrs::{
 use crate::core::array_wrapper::ArrayRefWrapper;
 use crate::core::text_wrapper::TextRefWrapper;
 use crate::preclude::*;
-use crate::util::lib0_any_to_json_value;
+use crate::serde::{from_yrs_value, to_map_ref, to_map_ref_with_key};
 
 pub trait CustomMapRef {
   fn from_map_ref(map_ref: MapRefWrapper) -> Self;
@@ -105,16 +105,18 @@ impl MapRefWrapper {
   }
 
   pub fn insert_json<T: Serialize>(&self, key: &str, value: T) {
-    let value = serde_json::to_value(&value).unwrap();
     self.collab_ctx.with_transact_mut(|txn| {
-      insert_json_value_to_map_ref(key, &value, self.map_ref.clone(), txn);
+      if let Err(e) = to_map_ref(txn, &self.map_ref, key, &value) {
+        tracing::error!("🔴insert {} failed: {:?}", key, e);
+      }
     });
   }
 
+  /// Serialize the value and insert it with the given key. The structs and maps are inserted
+  /// as [MapRef] and the sequences are inserted as [ArrayRef]. The existing value is replaced.
   pub fn insert_json_with_txn<T: Serialize>(&self, txn: &mut TransactionMut, key: &str, value: T) {
-    let value = serde_json::to_value(&value).unwrap();
-    if let Some(map_ref) = self.get_map_with_txn(txn, key) {
-      insert_json_value_to_map_ref(key, &value, map_ref.into_inner(), txn);
+    if let Err(e) = to_map_ref_with_key(txn, &self.map_ref, key, &value) {
+      tracing::error!("🔴insert {} failed: {:?}", key, e);
     }
   }
 
@@ -122,10 +124,35 @@ impl MapRefWrapper {
     self.get_json_with_txn(&self.collab_ctx.transact(), key)
   }
 
-  pub fn get_json_with_txn<T: DeserializeOwned>(&self, txn: &Transaction, key: &str) -> Option<T> {
-    let map_ref = self.get_map_with_txn(txn, key)?;
-    let json_value = lib0_any_to_json_value(map_ref.into_inner().to_json(txn)).ok()?;
-    serde_json::from_value::<T>(json_value).ok()
+  pub fn get_json_with_txn<T: DeserializeOwned, R: ReadTxn>(
+    &self,
+    txn: &R,
+    key: &str,
+  ) -> Option<T> {
+    let value = self.map_ref.get(txn, key)?;
+    from_yrs_value(txn, value).ok()
+  }
+
+  /// Observe the value with the given key. The callback is called when the value is inserted,
+  /// replaced or removed, and when the content of its nested map, array or text is changed.
+  pub fn observe_key<F>(&mut self, key: &str, f: F) -> DeepEventsSubscription
+  where
+    F: Fn(&TransactionMut) + 'static,
+  {
+    let key = key.to_string();
+    self.map_ref.observe_deep(move |txn, events| {
+      let is_changed = events.iter().any(|event| match event.path().front() {
+        Some(PathSegment::Key(path_key)) => path_key.as_ref() == key.as_str(),
+        Some(PathSegment::Index(_)) => false,
+        None => match event {
+          Event::Map(event) => event.keys(txn).contains_key(key.as_str()),
+          _ => false,
+        },
+      });
+      if is_changed {
+        f(txn);
+      }
+    })
   }
 
   pub fn transact(&self) -> Transaction {
@@ -1,11 +1,25 @@
 pub mod any_array;
 pub mod any_map;
 pub mod array_wrapper;
+pub mod blame;
+pub mod branch;
+pub mod bundle;
 pub mod collab;
 pub mod collab_plugin;
 mod collab_serde;
 pub mod collab_state;
+pub mod compaction;
+pub mod encoding;
+mod item_index;
+pub mod json_patch;
 pub mod map_wrapper;
 pub mod origin;
+pub mod permission;
+pub mod plain_text;
+pub mod quarantine;
+pub mod schema;
+pub mod snapshot_view;
+pub mod stats;
 pub mod text_wrapper;
 pub mod transaction;
+pub mod undo;
@@ -0,0 +1,270 @@
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::broadcast;
+use yrs::types::{DeepEventsSubscription, DeepObservable};
+use yrs::undo::Options;
+use yrs::{Doc, MapRef, Origin, TransactionMut, UndoManager};
+
+use crate::core::json_patch::touched_paths_from_event;
+use crate::core::origin::CollabOrigin;
+use crate::error::CollabError;
+
+pub type PermissionViolationSender = broadcast::Sender<PermissionViolation>;
+pub type PermissionViolationReceiver = broadcast::Receiver<PermissionViolation>;
+
+/// Decides which paths of the `data` section can be changed by the local client. The paths are
+/// JSON Pointers, for example, `/views`. A rule applies to the path itself and all the paths
+/// under it, and the most specific rule wins.
+///
+/// For example, a commenter can be restricted to the comments with:
+/// `PermissionPolicy::new().deny_by_default().allow("/comments")`
+#[derive(Debug, Clone)]
+pub struct PermissionPolicy {
+  rules: Vec<PermissionRule>,
+  default_allow: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRule {
+  pub path: String,
+  pub allow: bool,
+}
+
+impl Default for PermissionPolicy {
+  fn default() -> Self {
+    Self {
+      rules: vec![],
+      default_allow: true,
+    }
+  }
+}
+
+impl PermissionPolicy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn allow(mut self, path: &str) -> Self {
+    self.rules.push(PermissionRule {
+      path: path.to_string(),
+      allow: true,
+    });
+    self
+  }
+
+  pub fn deny(mut self, path: &str) -> Self {
+    self.rules.push(PermissionRule {
+      path: path.to_string(),
+      allow: false,
+    });
+    self
+  }
+
+  /// Deny the paths that are not matched by any rule. By default, they are allowed.
+  pub fn deny_by_default(mut self) -> Self {
+    self.default_allow = false;
+    self
+  }
+
+  /// Returns true if the local client can change the value at the given path. Changing a value
+  /// is denied if any denied path is under it, for example, replacing `/views` is denied when
+  /// `/views/1` is denied.
+  pub fn is_allowed(&self, path: &str) -> bool {
+    let allow = self
+      .rules
+      .iter()
+      .filter(|rule| is_same_or_under(path, &rule.path))
+      .max_by_key(|rule| rule.path.len())
+      .map(|rule| rule.allow)
+      .unwrap_or(self.default_allow);
+
+    allow
+      && !self
+        .rules
+        .iter()
+        .any(|rule| !rule.allow && rule.path != path && is_same_or_under(&rule.path, path))
+  }
+}
+
+fn is_same_or_under(path: &str, parent: &str) -> bool {
+  match path.strip_prefix(parent) {
+    None => false,
+    Some(rest) => rest.is_empty() || rest.starts_with('/') || parent.ends_with('/'),
+  }
+}
+
+/// Sent when a local transaction changed the paths that are not allowed by the read-only mode
+/// or the [PermissionPolicy]. The change is reverted by a local transaction right after it, see
+/// [Collab::subscribe_permission_violation].
+///
+/// [Collab::subscribe_permission_violation]: crate::preclude::Collab::subscribe_permission_violation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionViolation {
+  pub origin: CollabOrigin,
+  /// The JSON Pointers of the changed values that are not allowed.
+  pub paths: Vec<String>,
+}
+
+/// The permission of the local client. It's shared with the observers of the document.
+#[derive(Default)]
+pub(crate) struct CollabPermission {
+  read_only: AtomicBool,
+  policy: RwLock<Option<PermissionPolicy>>,
+  /// The denied paths changed by the current local transaction. They are collected by the
+  /// observer of the `data` section, checked by the [PermissionGuard] after the transaction and
+  /// taken when the update of the transaction is emitted.
+  pending_denied_paths: Mutex<Vec<String>>,
+}
+
+impl CollabPermission {
+  pub(crate) fn is_read_only(&self) -> bool {
+    self.read_only.load(Ordering::SeqCst)
+  }
+
+  pub(crate) fn set_read_only(&self, read_only: bool) {
+    self.read_only.store(read_only, Ordering::SeqCst);
+  }
+
+  pub(crate) fn set_policy(&self, policy: Option<PermissionPolicy>) {
+    *self.policy.write() = policy;
+  }
+
+  /// Returns an error if the local client is not allowed to change any of the given paths.
+  pub(crate) fn check_paths(&self, paths: Vec<String>) -> Result<(), CollabError> {
+    if self.is_read_only() {
+      return Err(CollabError::ReadOnly);
+    }
+    let denied_paths = self.denied_paths(paths);
+    if !denied_paths.is_empty() {
+      return Err(CollabError::PermissionDenied(denied_paths));
+    }
+    Ok(())
+  }
+
+  fn has_pending_denied_paths(&self) -> bool {
+    !self.pending_denied_paths.lock().is_empty()
+  }
+
+  /// Returns the denied paths changed by the current local transaction.
+  pub(crate) fn take_pending_denied_paths(&self) -> Vec<String> {
+    std::mem::take(&mut *self.pending_denied_paths.lock())
+  }
+
+  /// Returns the paths that the local client is not allowed to change.
+  fn denied_paths(&self, paths: Vec<String>) -> Vec<String> {
+    if self.is_read_only() {
+      return paths;
+    }
+    match self.policy.read().as_ref() {
+      None => vec![],
+      Some(policy) => paths
+        .into_iter()
+        .filter(|path| !policy.is_allowed(path))
+        .collect(),
+    }
+  }
+}
+
+/// Collect the paths of the `data` section that are changed by the local transactions and not
+/// allowed by the [CollabPermission]. The remote updates are always applied, and so is the
+/// revert of the [PermissionGuard].
+pub(crate) fn observe_permission(
+  data: &MapRef,
+  local_origin: CollabOrigin,
+  permission: Arc<CollabPermission>,
+  guard: Arc<PermissionGuard>,
+) -> DeepEventsSubscription {
+  data.clone().observe_deep(move |txn, events| {
+    if guard.is_revert(txn) || CollabOrigin::from(txn) != local_origin {
+      return;
+    }
+    let mut paths = vec![];
+    for event in events.iter() {
+      for path in touched_paths_from_event(txn, event, &[]) {
+        if !paths.contains(&path) {
+          paths.push(path);
+        }
+      }
+    }
+    let denied_paths = permission.denied_paths(paths);
+    if !denied_paths.is_empty() {
+      let mut pending_paths = permission.pending_denied_paths.lock();
+      for path in denied_paths {
+        if !pending_paths.contains(&path) {
+          pending_paths.push(path);
+        }
+      }
+    }
+  })
+}
+
+/// Reverts the local changes of the `data` section that are not allowed by the
+/// [CollabPermission]. Such a change can't be rejected before it's applied, for example, when
+/// it's made by a [MapRefWrapper](crate::preclude::MapRefWrapper). Its update is passed to the
+/// plugins like any other local update, and the guard undoes the change with a local
+/// transaction of its own, which is passed to the plugins too. So the persisted and the synced
+/// state always follow the document.
+///
+/// The revert can't be made inside the transaction, it's made when the collab acquires the
+/// next write transaction or releases the one of [Collab::with_transact_mut].
+///
+/// [Collab::with_transact_mut]: crate::preclude::Collab::with_transact_mut
+pub(crate) struct PermissionGuard {
+  origin: Origin,
+  undo_manager: Mutex<UndoManager>,
+}
+
+impl PermissionGuard {
+  pub(crate) fn new(
+    doc: &Doc,
+    data: &MapRef,
+    local_origin: CollabOrigin,
+    permission: Arc<CollabPermission>,
+  ) -> Self {
+    let cloned_origin = local_origin.clone();
+    let options = Options {
+      // Every denied transaction is reverted on its own.
+      capture_timeout_millis: 0,
+      capture_transaction: Rc::new(move |txn: &TransactionMut| {
+        CollabOrigin::from(txn) == cloned_origin && permission.has_pending_denied_paths()
+      }),
+      ..Options::default()
+    };
+    let mut undo_manager = UndoManager::with_options(doc, data, options);
+    undo_manager.include_origin(local_origin);
+    Self {
+      origin: undo_manager.as_origin(),
+      undo_manager: Mutex::new(undo_manager),
+    }
+  }
+
+  /// Returns true if the transaction reverts the denied changes.
+  pub(crate) fn is_revert(&self, txn: &TransactionMut) -> bool {
+    txn.origin() == Some(&self.origin)
+  }
+
+  /// Revert the denied changes that were not reverted yet. The revert is retried later if the
+  /// document is held by another transaction.
+  pub(crate) fn revert(&self) {
+    let mut undo_manager = self.undo_manager.lock();
+    while undo_manager.can_undo() {
+      match undo_manager.undo() {
+        Ok(true) => {},
+        Ok(false) => break,
+        Err(e) => {
+          tracing::warn!("[🦀Collab]: revert the denied changes failed: {}", e);
+          break;
+        },
+      }
+    }
+  }
+}
+
+// The [UndoManager] is only used while holding the lock, and its callbacks are only called by
+// the transactions of the document, like the [MutexCollab](crate::preclude::MutexCollab).
+unsafe impl Send for PermissionGuard {}
+
+unsafe impl Sync for PermissionGuard {}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use lib0::any::Any;
+use yrs::types::Value;
+use yrs::{Array, ArrayRef, GetString, Map, MapRef, ReadTxn, Transaction};
+
+/// A [PlainTextExtractor] converts the `data` section of a [Collab](crate::preclude::Collab)
+/// into human readable text. The text is used to generate the snapshot diffs, the search index
+/// and the previews.
+///
+/// Each collab type (document, folder, database, ...) can provide its own extractor by calling
+/// [Collab::set_plain_text_extractor](crate::preclude::Collab::set_plain_text_extractor). If no
+/// extractor was registered, the [DefaultPlainTextExtractor] will be used.
+pub trait PlainTextExtractor: Send + Sync + 'static {
+  /// Returns the plain text of the given `data` map. Returning `None` means the extractor doesn't
+  /// understand the data and the caller should fall back to the [DefaultPlainTextExtractor].
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String>;
+}
+
+impl<T> PlainTextExtractor for Arc<T>
+where
+  T: PlainTextExtractor,
+{
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    (**self).extract(txn, data)
+  }
+}
+
+/// The [DefaultPlainTextExtractor] walks the `data` section recursively. Each scalar value of a
+/// map is written as `key: value`, each element of an array and each [TextRef](yrs::TextRef) is
+/// written as its own line. The keys of the maps are sorted so that the output is stable, which
+/// is required to calculate the diff between two snapshots.
+#[derive(Default, Clone)]
+pub struct DefaultPlainTextExtractor;
+
+impl PlainTextExtractor for DefaultPlainTextExtractor {
+  fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+    let mut lines = vec![];
+    map_to_plain_text(txn, data, &mut lines);
+    Some(lines.join("\n"))
+  }
+}
+
+/// Append the plain text of the given [MapRef] to the `lines`.
+pub fn map_to_plain_text<T: ReadTxn>(txn: &T, map_ref: &MapRef, lines: &mut Vec<String>) {
+  let mut entries = map_ref
+    .iter(txn)
+    .map(|(key, value)| (key.to_string(), value))
+    .collect::<Vec<_>>();
+  entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+  for (key, value) in entries {
+    match value {
+      Value::Any(any) => {
+        if let Some(text) = any_to_plain_text(&any) {
+          lines.push(format!("{}: {}", key, text));
+        }
+      },
+      value => value_to_plain_text(txn, value, lines),
+    }
+  }
+}
+
+/// Append the plain text of the given [ArrayRef] to the `lines`.
+pub fn array_to_plain_text<T: ReadTxn>(txn: &T, array_ref: &ArrayRef, lines: &mut Vec<String>) {
+  for value in array_ref.iter(txn) {
+    value_to_plain_text(txn, value, lines);
+  }
+}
+
+/// Append the plain text of the given [Value] to the `lines`.
+pub fn value_to_plain_text<T: ReadTxn>(txn: &T, value: Value, lines: &mut Vec<String>) {
+  match value {
+    Value::Any(any) => {
+      if let Some(text) = any_to_plain_text(&any) {
+        lines.push(text);
+      }
+    },
+    Value::YText(text_ref) => {
+      let text = text_ref.get_string(txn);
+      if !text.is_empty() {
+        lines.push(text);
+      }
+    },
+    Value::YArray(array_ref) => array_to_plain_text(txn, &array_ref, lines),
+    Value::YMap(map_ref) => map_to_plain_text(txn, &map_ref, lines),
+    _ => {},
+  }
+}
+
+/// Returns the plain text of the given [Any]. Returns `None` if the value doesn't contain any
+/// readable content, for example, `null` or a binary buffer.
+pub fn any_to_plain_text(any: &Any) -> Option<String> {
+  match any {
+    Any::Null | Any::Undefined | Any::Buffer(_) => None,
+    Any::Bool(value) => Some(value.to_string()),
+    Any::Number(value) => Some(value.to_string()),
+    Any::BigInt(value) => Some(value.to_string()),
+    Any::String(value) => Some(value.to_string()),
+    Any::Array(values) => {
+      let values = values
+        .iter()
+        .flat_map(any_to_plain_text)
+        .collect::<Vec<_>>();
+      if values.is_empty() {
+        None
+      } else {
+        Some(values.join(" "))
+      }
+    },
+    Any::Map(values) => {
+      let mut entries = values.iter().collect::<Vec<_>>();
+      entries.sort_by(|a, b| a.0.cmp(b.0));
+      let values = entries
+        .into_iter()
+        .flat_map(|(key, value)| any_to_plain_text(value).map(|text| format!("{}: {}", key, text)))
+        .collect::<Vec<_>>();
+      if values.is_empty() {
+        None
+      } else {
+        Some(values.join(", "))
+      }
+    },
+  }
+}
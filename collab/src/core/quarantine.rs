@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use yrs::types::{DeepEventsSubscription, DeepObservable};
+use yrs::MapRef;
+
+use crate::core::json_patch::touched_paths_from_event;
+use crate::core::origin::CollabOrigin;
+use crate::core::permission::PermissionGuard;
+
+/// An update that was held back from the plugins while the collab was quarantined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedUpdate {
+  /// The origin of the transaction that applied the update.
+  pub origin: CollabOrigin,
+  /// The encoded update v1.
+  pub update: Vec<u8>,
+}
+
+/// The collab is quarantined when a plugin rejects a remote update by
+/// [CollabPlugin::accept_remote_update]. The update is already applied to the document, so the
+/// quarantine holds back the update, and every remote update after it, from the plugins. They
+/// are neither persisted nor sent to the remote until [Collab::release_quarantine] is called or
+/// the collab is closed. The local updates are not held back.
+///
+/// [CollabPlugin::accept_remote_update]: crate::preclude::CollabPlugin::accept_remote_update
+/// [Collab::release_quarantine]: crate::preclude::Collab::release_quarantine
+#[derive(Default)]
+pub(crate) struct Quarantine {
+  active: AtomicBool,
+  updates: Mutex<Vec<QuarantinedUpdate>>,
+  /// The paths of the `data` section that are changed by the current remote transaction.
+  pending_remote_paths: Mutex<Vec<String>>,
+}
+
+impl Quarantine {
+  pub(crate) fn is_active(&self) -> bool {
+    self.active.load(Ordering::SeqCst)
+  }
+
+  pub(crate) fn activate(&self) {
+    self.active.store(true, Ordering::SeqCst);
+  }
+
+  /// Keep the remote update if the collab is quarantined. Returns true if the update is kept.
+  pub(crate) fn hold(
+    &self,
+    local_origin: &CollabOrigin,
+    origin: &CollabOrigin,
+    update: &[u8],
+  ) -> bool {
+    if !self.is_active() || origin == local_origin {
+      return false;
+    }
+    self.updates.lock().push(QuarantinedUpdate {
+      origin: origin.clone(),
+      update: update.to_vec(),
+    });
+    true
+  }
+
+  /// End the quarantine and returns the updates that were held back, in the order they were
+  /// applied.
+  pub(crate) fn release(&self) -> Vec<QuarantinedUpdate> {
+    let mut updates = self.updates.lock();
+    self.active.store(false, Ordering::SeqCst);
+    std::mem::take(&mut *updates)
+  }
+
+  pub(crate) fn take_pending_remote_paths(&self) -> Vec<String> {
+    std::mem::take(&mut *self.pending_remote_paths.lock())
+  }
+}
+
+/// Collect the paths of the `data` section that are changed by the remote transactions. They
+/// are passed to [CollabPlugin::accept_remote_update](crate::preclude::CollabPlugin::accept_remote_update)
+/// when the update is emitted, see `observe_doc`.
+pub(crate) fn observe_remote_paths(
+  data: &MapRef,
+  local_origin: CollabOrigin,
+  permission_guard: Arc<PermissionGuard>,
+  quarantine: Arc<Quarantine>,
+) -> DeepEventsSubscription {
+  data.clone().observe_deep(move |txn, events| {
+    if permission_guard.is_revert(txn) || CollabOrigin::from(txn) == local_origin {
+      return;
+    }
+    let mut pending_paths = quarantine.pending_remote_paths.lock();
+    for event in events.iter() {
+      for path in touched_paths_from_event(txn, event, &[]) {
+        if !pending_paths.contains(&path) {
+          pending_paths.push(path);
+        }
+      }
+    }
+  })
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use lib0::any::Any;
+use yrs::types::Value;
+use yrs::{Array, Map, MapRef, ReadTxn};
+
+use crate::core::collab::DATA_SECTION;
+use crate::core::json_patch::{parse_json_pointer, to_json_pointer};
+
+/// The expected shape of a value in the `data` section of a [Collab](crate::preclude::Collab).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaType {
+  /// Any value, including the nested maps and arrays.
+  Any,
+  String,
+  /// An integer that is stored as `lib0Any::BigInt`, for example, the timestamps.
+  Integer,
+  /// An integer or a float.
+  Number,
+  Bool,
+  /// A collaborative text.
+  Text,
+  /// An array that every item matches the given type.
+  Array(Box<SchemaType>),
+  /// A map with the known fields. The fields that are not defined in the [MapSchema] are
+  /// allowed, so the older clients can open the data written by the newer ones.
+  Map(MapSchema),
+  /// A map that every value matches the given type. The keys are usually ids, for example,
+  /// the views of the folder.
+  MapOf(Box<SchemaType>),
+}
+
+impl SchemaType {
+  pub fn array(item: SchemaType) -> Self {
+    Self::Array(Box::new(item))
+  }
+
+  pub fn map_of(value: SchemaType) -> Self {
+    Self::MapOf(Box::new(value))
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      SchemaType::Any => "any",
+      SchemaType::String => "string",
+      SchemaType::Integer => "integer",
+      SchemaType::Number => "number",
+      SchemaType::Bool => "bool",
+      SchemaType::Text => "text",
+      SchemaType::Array(_) => "array",
+      SchemaType::Map(_) | SchemaType::MapOf(_) => "map",
+    }
+  }
+}
+
+impl From<MapSchema> for SchemaType {
+  fn from(schema: MapSchema) -> Self {
+    Self::Map(schema)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+  pub key: String,
+  pub ty: SchemaType,
+  pub required: bool,
+}
+
+/// Describes the fields of a map. For example:
+/// `MapSchema::new().required("id", SchemaType::String).optional("name", SchemaType::String)`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapSchema {
+  fields: Vec<SchemaField>,
+}
+
+impl MapSchema {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The field must exist and match the given type.
+  pub fn required<T: Into<SchemaType>>(mut self, key: &str, ty: T) -> Self {
+    self.fields.push(SchemaField {
+      key: key.to_string(),
+      ty: ty.into(),
+      required: true,
+    });
+    self
+  }
+
+  /// The field can be missing or null. If it exists, it must match the given type.
+  pub fn optional<T: Into<SchemaType>>(mut self, key: &str, ty: T) -> Self {
+    self.fields.push(SchemaField {
+      key: key.to_string(),
+      ty: ty.into(),
+      required: false,
+    });
+    self
+  }
+
+  pub fn fields(&self) -> &[SchemaField] {
+    &self.fields
+  }
+
+  pub fn get(&self, key: &str) -> Option<&SchemaField> {
+    self.fields.iter().find(|field| field.key == key)
+  }
+}
+
+/// The schema of the `data` section of a kind of collab, for example, the folder or the
+/// document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollabSchema {
+  pub collab_type: String,
+  pub root: MapSchema,
+}
+
+impl CollabSchema {
+  pub fn new(collab_type: &str, root: MapSchema) -> Self {
+    Self {
+      collab_type: collab_type.to_string(),
+      root,
+    }
+  }
+
+  /// Validate the `data` section of the document that the transaction belongs to. Returns an
+  /// empty vec if the data matches the schema.
+  pub fn validate<T: ReadTxn>(&self, txn: &T) -> Vec<SchemaViolation> {
+    match txn.get_map(DATA_SECTION) {
+      None => self.validate_map(txn, None),
+      Some(data) => self.validate_map(txn, Some(&data)),
+    }
+  }
+
+  /// Validate only the values at the given JSON Pointers of the `data` section, for example,
+  /// the paths that were changed by a transaction. The value at each path is validated with its
+  /// nested values. The paths that are not described by the schema are skipped.
+  pub fn validate_paths<T: ReadTxn>(&self, txn: &T, paths: &[String]) -> Vec<SchemaViolation> {
+    let data = match txn.get_map(DATA_SECTION) {
+      None => return self.validate_map(txn, None),
+      Some(data) => data,
+    };
+    let root = SchemaType::Map(self.root.clone());
+    let mut violations = vec![];
+    for pointer in paths {
+      let tokens = match parse_json_pointer(pointer) {
+        Ok(tokens) => tokens,
+        Err(_) => continue,
+      };
+      let mut path_violations = vec![];
+      validate_path(
+        txn,
+        &root,
+        Value::YMap(data.clone()),
+        &tokens,
+        &mut vec![],
+        &mut path_violations,
+      );
+      for violation in path_violations {
+        if !violations.contains(&violation) {
+          violations.push(violation);
+        }
+      }
+    }
+    violations
+  }
+
+  /// Validate the given map against the root of the schema.
+  pub fn validate_map<T: ReadTxn>(&self, txn: &T, data: Option<&MapRef>) -> Vec<SchemaViolation> {
+    let mut violations = vec![];
+    let entries = data
+      .map(|data| {
+        data
+          .iter(txn)
+          .map(|(key, value)| (key.to_string(), value))
+          .collect()
+      })
+      .unwrap_or_default();
+    validate_entries(txn, &self.root, entries, &mut vec![], &mut violations);
+    violations
+  }
+}
+
+/// Keeps the [CollabSchema] of each collab type.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+  schemas: HashMap<String, Arc<CollabSchema>>,
+}
+
+impl SchemaRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Register the schema. It replaces the schema of the same collab type.
+  pub fn register(&mut self, schema: CollabSchema) {
+    self
+      .schemas
+      .insert(schema.collab_type.clone(), Arc::new(schema));
+  }
+
+  pub fn with_schema(mut self, schema: CollabSchema) -> Self {
+    self.register(schema);
+    self
+  }
+
+  pub fn get(&self, collab_type: &str) -> Option<Arc<CollabSchema>> {
+    self.schemas.get(collab_type).cloned()
+  }
+
+  pub fn collab_types(&self) -> Vec<String> {
+    self.schemas.keys().cloned().collect()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaViolationKind {
+  /// The required field doesn't exist.
+  Missing,
+  /// The value doesn't match the type of the schema. The `actual` is the type of the value,
+  /// for example, `string` or `map`.
+  TypeMismatch { expected: String, actual: String },
+}
+
+/// A value of the `data` section that doesn't match the [CollabSchema].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+  /// The JSON Pointer of the value, for example, `/folder/views/v1/name`.
+  pub path: String,
+  pub kind: SchemaViolationKind,
+}
+
+impl Display for SchemaViolation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.kind {
+      SchemaViolationKind::Missing => write!(f, "{} is missing", self.path),
+      SchemaViolationKind::TypeMismatch { expected, actual } => {
+        write!(f, "{} expects {} but found {}", self.path, expected, actual)
+      },
+    }
+  }
+}
+
+fn validate_entries<T: ReadTxn>(
+  txn: &T,
+  schema: &MapSchema,
+  entries: HashMap<String, Value>,
+  path: &mut Vec<String>,
+  violations: &mut Vec<SchemaViolation>,
+) {
+  for field in schema.fields() {
+    path.push(field.key.clone());
+    match entries.get(&field.key).filter(|value| !is_null(value)) {
+      None => {
+        if field.required {
+          violations.push(SchemaViolation {
+            path: to_json_pointer(path.iter()),
+            kind: SchemaViolationKind::Missing,
+          });
+        }
+      },
+      Some(value) => validate_value(txn, &field.ty, value, path, violations),
+    }
+    path.pop();
+  }
+}
+
+/// Walks down the given tokens from the value and validates the value at the end of them.
+fn validate_path<T: ReadTxn>(
+  txn: &T,
+  ty: &SchemaType,
+  value: Value,
+  tokens: &[String],
+  path: &mut Vec<String>,
+  violations: &mut Vec<SchemaViolation>,
+) {
+  let (token, rest) = match tokens.split_first() {
+    None => return validate_value(txn, ty, &value, path, violations),
+    Some(split) => split,
+  };
+  let (child_ty, required) = match ty {
+    SchemaType::Map(schema) => match schema.get(token) {
+      Some(field) => (&field.ty, field.required),
+      None => return,
+    },
+    SchemaType::MapOf(value_ty) => (value_ty.as_ref(), false),
+    SchemaType::Array(item_ty) => (item_ty.as_ref(), false),
+    _ => return,
+  };
+
+  path.push(token.clone());
+  match child_value(txn, &value, token).filter(|value| !is_null(value)) {
+    Some(child) => validate_path(txn, child_ty, child, rest, path, violations),
+    // A missing parent of the path is reported by the change that removed it.
+    None if rest.is_empty() && required => violations.push(SchemaViolation {
+      path: to_json_pointer(path.iter()),
+      kind: SchemaViolationKind::Missing,
+    }),
+    None => {},
+  }
+  path.pop();
+}
+
+fn child_value<T: ReadTxn>(txn: &T, value: &Value, token: &str) -> Option<Value> {
+  match value {
+    Value::YMap(map_ref) => map_ref.get(txn, token),
+    Value::YArray(array_ref) => array_ref.get(txn, token.parse().ok()?),
+    Value::Any(Any::Map(map)) => map.get(token).cloned().map(Value::Any),
+    Value::Any(Any::Array(items)) => items
+      .get(token.parse::<usize>().ok()?)
+      .cloned()
+      .map(Value::Any),
+    _ => None,
+  }
+}
+
+fn validate_value<T: ReadTxn>(
+  txn: &T,
+  ty: &SchemaType,
+  value: &Value,
+  path: &mut Vec<String>,
+  violations: &mut Vec<SchemaViolation>,
+) {
+  let is_matched = match ty {
+    SchemaType::Any => true,
+    SchemaType::String => matches!(value, Value::Any(Any::String(_))),
+    SchemaType::Integer => matches!(value, Value::Any(Any::BigInt(_))),
+    SchemaType::Number => matches!(value, Value::Any(Any::BigInt(_) | Any::Number(_))),
+    SchemaType::Bool => matches!(value, Value::Any(Any::Bool(_))),
+    SchemaType::Text => matches!(value, Value::YText(_)),
+    SchemaType::Array(item_ty) => match array_items(txn, value) {
+      None => false,
+      Some(items) => {
+        for (index, item) in items.iter().enumerate() {
+          path.push(index.to_string());
+          validate_value(txn, item_ty, item, path, violations);
+          path.pop();
+        }
+        true
+      },
+    },
+    SchemaType::Map(schema) => match map_entries(txn, value) {
+      None => false,
+      Some(entries) => {
+        validate_entries(txn, schema, entries, path, violations);
+        true
+      },
+    },
+    SchemaType::MapOf(value_ty) => match map_entries(txn, value) {
+      None => false,
+      Some(entries) => {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, value) in entries {
+          path.push(key);
+          validate_value(txn, value_ty, &value, path, violations);
+          path.pop();
+        }
+        true
+      },
+    },
+  };
+
+  if !is_matched {
+    violations.push(SchemaViolation {
+      path: to_json_pointer(path.iter()),
+      kind: SchemaViolationKind::TypeMismatch {
+        expected: ty.name().to_string(),
+        actual: value_type_name(value).to_string(),
+      },
+    });
+  }
+}
+
+/// The maps can be stored as the [MapRef] or as the `lib0Any::Map`.
+fn map_entries<T: ReadTxn>(txn: &T, value: &Value) -> Option<HashMap<String, Value>> {
+  match value {
+    Value::YMap(map_ref) => Some(
+      map_ref
+        .iter(txn)
+        .map(|(key, value)| (key.to_string(), value))
+        .collect(),
+    ),
+    Value::Any(Any::Map(map)) => Some(
+      map
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::Any(value.clone())))
+        .collect(),
+    ),
+    _ => None,
+  }
+}
+
+/// The arrays can be stored as the [ArrayRef](yrs::ArrayRef) or as the `lib0Any::Array`.
+fn array_items<T: ReadTxn>(txn: &T, value: &Value) -> Option<Vec<Value>> {
+  match value {
+    Value::YArray(array_ref) => Some(array_ref.iter(txn).collect()),
+    Value::Any(Any::Array(items)) => {
+      Some(items.iter().map(|item| Value::Any(item.clone())).collect())
+    },
+    _ => None,
+  }
+}
+
+fn is_null(value: &Value) -> bool {
+  matches!(value, Value::Any(Any::Null | Any::Undefined))
+}
+
+fn value_type_name(value: &Value) -> &'static str {
+  match value {
+    Value::Any(any) => match any {
+      Any::Null | Any::Undefined => "null",
+      Any::Bool(_) => "bool",
+      Any::Number(_) => "number",
+      Any::BigInt(_) => "integer",
+      Any::String(_) => "string",
+      Any::Buffer(_) => "buffer",
+      Any::Array(_) => "array",
+      Any::Map(_) => "map",
+    },
+    Value::YText(_) => "text",
+    Value::YArray(_) => "array",
+    Value::YMap(_) => "map",
+    Value::YXmlElement(_) | Value::YXmlFragment(_) | Value::YXmlText(_) => "xml",
+    Value::YDoc(_) => "doc",
+  }
+}
@@ -0,0 +1,74 @@
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+use yrs::types::ToJson;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::{Encoder, EncoderV1};
+use yrs::{Doc, MapRef, ReadTxn, Snapshot, Transact, Update};
+
+use crate::core::collab::DATA_SECTION;
+use crate::error::CollabError;
+use crate::preclude::JsonValue;
+
+/// A [SnapshotView] is a read-only view of a [Collab](crate::preclude::Collab) as it was at a
+/// given [Snapshot]. It's built from the history that is kept in the document, so the
+/// [Collab] itself is not changed.
+///
+/// The [Collab] keeps the deleted items (the `skip_gc` option is true), which makes it possible
+/// to read the document at any past snapshot.
+pub struct SnapshotView {
+  doc: Doc,
+  data: MapRef,
+}
+
+impl SnapshotView {
+  /// Create a [SnapshotView] from the given document state. The state must be encoded with
+  /// [encode_state_from_snapshot].
+  pub fn from_encoded_state(encoded_state: &[u8]) -> Result<Self, CollabError> {
+    let doc = Doc::new();
+    let data = doc.get_or_insert_map(DATA_SECTION);
+    {
+      let update = Update::decode_v1(encoded_state)?;
+      let mut txn = doc.transact_mut();
+      txn.apply_update(update);
+    }
+    Ok(Self { doc, data })
+  }
+
+  pub fn to_json(&self) -> lib0::any::Any {
+    let txn = self.doc.transact();
+    self.data.to_json(&txn)
+  }
+
+  pub fn to_json_value(&self) -> JsonValue {
+    serde_json::to_value(self.to_json()).unwrap()
+  }
+}
+
+/// Encode the state of the document as it was at the given [Snapshot]. Return an error if the
+/// document doesn't contain the history of the snapshot.
+pub fn encode_state_from_snapshot<T: ReadTxn>(
+  txn: &T,
+  snapshot: &Snapshot,
+) -> Result<Vec<u8>, CollabError> {
+  let mut encoded_state = vec![];
+  // Encoding a snapshot that is not part of the document's history might panic. So catch the
+  // panic and return an error instead.
+  let result = {
+    let mut wrapper = AssertUnwindSafe(&mut encoded_state);
+    let wrapper_txn = AssertUnwindSafe(txn);
+    panic::catch_unwind(move || {
+      let mut encoder = EncoderV1::new();
+      wrapper_txn
+        .encode_state_from_snapshot(snapshot, &mut encoder)
+        .map(|_| **wrapper = encoder.to_vec())
+        .map_err(|e| format!("{:?}", e))
+    })
+  };
+
+  match result {
+    Ok(Ok(_)) => Ok(encoded_state),
+    Ok(Err(e)) => Err(CollabError::InvalidSnapshot(e)),
+    Err(e) => Err(CollabError::InvalidSnapshot(format!("{:?}", e))),
+  }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use yrs::block::ClientID;
+use yrs::updates::encoder::Encode;
+use yrs::{ReadTxn, StateVector};
+
+use crate::core::blame::AuthorRegistry;
+use crate::core::item_index::ItemIndex;
+use crate::core::origin::CollabClient;
+
+/// The size and the garbage of a [Collab](crate::preclude::Collab). The documents never run the
+/// garbage collection, so the deleted items are kept forever. Use these numbers to decide when
+/// to compact or snapshot a document. See [Collab::stats](crate::preclude::Collab::stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollabStats {
+  /// The size, in bytes, of the document state that is encoded with the update v1 format.
+  pub encoded_state_size: usize,
+  /// The size, in bytes, of the encoded state vector.
+  pub state_vector_size: usize,
+  /// The number of the blocks in the block store, including the deleted ones. Yrs merges the
+  /// consecutive items of a client into one block, for example, the characters typed one by
+  /// one, and splits a block when a part of it is deleted, so it's not the number of the
+  /// inserted values.
+  pub block_count: usize,
+  /// The number of the deleted blocks, including the garbage collected ones.
+  pub deleted_block_count: usize,
+  /// The number of the ranges in the delete set.
+  pub delete_set_range_count: usize,
+  /// The total length of the deleted content.
+  pub deleted_len: u64,
+  /// The contribution of each client, sorted by the client id.
+  pub clients: Vec<ClientStats>,
+  /// The number of the updates that are stored on disk but not merged into the document yet.
+  /// It's reported by the disk plugin, None if there is no disk plugin.
+  pub disk_update_count: Option<usize>,
+}
+
+impl CollabStats {
+  pub(crate) fn from_txn<T: ReadTxn>(txn: &T, authors: &AuthorRegistry) -> Self {
+    let authors = authors.get_authors(txn);
+    let index = ItemIndex::from_txn(txn);
+    let mut delete_set_range_count = 0;
+    let mut clients: HashMap<ClientID, ClientStats> = HashMap::new();
+    let mut prev_block = None;
+    for block in &index.blocks {
+      let stats = clients
+        .entry(block.id.client)
+        .or_insert_with(|| ClientStats::new(block.id.client, &authors));
+      stats.block_count += 1;
+      stats.content_len += block.len as u64;
+      if block.deleted {
+        stats.deleted_block_count += 1;
+        stats.deleted_len += block.len as u64;
+        // The deleted blocks next to each other are merged into one range of the delete set.
+        if prev_block != Some((block.id.client, true)) {
+          delete_set_range_count += 1;
+        }
+      }
+      prev_block = Some((block.id.client, block.deleted));
+    }
+    let mut clients = clients.into_values().collect::<Vec<_>>();
+    clients.sort_by_key(|stats| stats.client_id);
+
+    Self {
+      encoded_state_size: txn.encode_state_as_update_v1(&StateVector::default()).len(),
+      state_vector_size: txn.state_vector().encode_v1().len(),
+      block_count: clients.iter().map(|client| client.block_count).sum(),
+      deleted_block_count: clients
+        .iter()
+        .map(|client| client.deleted_block_count)
+        .sum(),
+      delete_set_range_count,
+      deleted_len: clients.iter().map(|client| client.deleted_len).sum(),
+      clients,
+      disk_update_count: None,
+    }
+  }
+
+  /// The ratio of the deleted content to all the content. Zero if the document is empty.
+  pub fn deleted_ratio(&self) -> f64 {
+    let content_len: u64 = self.clients.iter().map(|client| client.content_len).sum();
+    if content_len == 0 {
+      return 0.0;
+    }
+    self.deleted_len as f64 / content_len as f64
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientStats {
+  pub client_id: ClientID,
+  /// None if the client didn't record itself. See [Collab::get_authors].
+  ///
+  /// [Collab::get_authors]: crate::preclude::Collab::get_authors
+  pub client: Option<CollabClient>,
+  /// The number of the blocks of the client. See [CollabStats::block_count].
+  pub block_count: usize,
+  /// The total length of the content that was inserted by the client, including the deleted.
+  pub content_len: u64,
+  pub deleted_block_count: usize,
+  /// The total length of the content of the client that was deleted.
+  pub deleted_len: u64,
+}
+
+impl ClientStats {
+  fn new(client_id: ClientID, authors: &HashMap<ClientID, CollabClient>) -> Self {
+    Self {
+      client_id,
+      client: authors.get(&client_id).cloned(),
+      block_count: 0,
+      content_len: 0,
+      deleted_block_count: 0,
+      deleted_len: 0,
+    }
+  }
+}
@@ -1,24 +1,142 @@
-use crate::preclude::{CollabContext, YrsDelta};
+use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+
+use lib0::any::Any;
+use parking_lot::Mutex;
+use serde_json::{json, Map as JsonMap};
 use yrs::types::text::{TextEvent, YChange};
-use yrs::types::{Attrs, Delta};
-use yrs::{ReadTxn, Subscription, Text, TextRef, Transaction, TransactionMut};
+use yrs::types::{Attrs, Delta, ToJson};
+use yrs::{Observable, ReadTxn, Subscription, Text, TextRef, Transaction, TransactionMut};
+
+use crate::error::CollabError;
+use crate::preclude::{CollabContext, JsonValue, YrsValue};
+
 pub type TextSubscriptionCallback = Arc<dyn Fn(&TransactionMut, &TextEvent)>;
 pub type TextSubscription = Subscription<TextSubscriptionCallback>;
 
+/// A Quill-compatible change of a text. The lengths are counted in UTF-16 code units, the same
+/// as the `String.length` in JavaScript, and an embed counts as one.
+#[derive(Debug, Clone, PartialEq)]
 pub enum TextDelta {
   Inserted(String, Attrs),
 
+  /// Inserts an embed, for example, an image or a formula. The content is the value of the
+  /// Quill `insert` object.
+  InsertedEmbed(Any, Attrs),
+
   /// Determines a change that resulted in removing a consecutive range of characters.
   Deleted(u32),
 
   /// Determines a number of consecutive unchanged characters. Used to recognize non-edited spaces
   /// between [Delta::Inserted] and/or [Delta::Deleted] chunks. Can contain an optional set of
-  /// attributes, which have been used to format an existing piece of text.
+  /// attributes, which have been used to format an existing piece of text. An attribute with
+  /// the [Any::Null] value removes the attribute.
   Retain(u32, Attrs),
 }
 
+impl TextDelta {
+  /// Convert the [Delta] of a [TextEvent] into the [TextDelta]s, so the changes can be applied
+  /// to an editor. The lengths of the [Delta] are counted in the offsets of the document, so
+  /// they are converted into UTF-16 code units with `before`, the content of the text before
+  /// the transaction as returned by [TextRefWrapper::get_delta_with_txn].
+  /// [TextRefWrapper::observe_delta] keeps track of the content.
+  pub fn from_event(
+    txn: &TransactionMut,
+    event: &TextEvent,
+    before: &[TextDelta],
+  ) -> Vec<TextDelta> {
+    let mut cursor = TextCursor::from_deltas(before);
+    event
+      .delta(txn)
+      .iter()
+      .map(|delta| match delta {
+        Delta::Inserted(value, attrs) => insert_delta(txn, value, attrs_or_default(attrs)),
+        Delta::Deleted(len) => TextDelta::Deleted(cursor.advance_offset(*len)),
+        Delta::Retain(len, attrs) => {
+          TextDelta::Retain(cursor.advance_offset(*len), attrs_or_default(attrs))
+        },
+      })
+      .collect()
+  }
+
+  /// Returns the Quill operation of the delta, for example, `{"insert": "abc"}`.
+  pub fn to_json_value(&self) -> JsonValue {
+    let (mut op, attrs) = match self {
+      TextDelta::Inserted(content, attrs) => (json!({ "insert": content }), Some(attrs)),
+      TextDelta::InsertedEmbed(embed, attrs) => (json!({ "insert": embed }), Some(attrs)),
+      TextDelta::Deleted(len) => (json!({ "delete": len }), None),
+      TextDelta::Retain(len, attrs) => (json!({ "retain": len }), Some(attrs)),
+    };
+    if let Some(attrs) = attrs.filter(|attrs| !attrs.is_empty()) {
+      let attributes = attrs
+        .iter()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .collect::<JsonMap<_, _>>();
+      op["attributes"] = JsonValue::Object(attributes);
+    }
+    op
+  }
+
+  /// Parse the Quill operation. Returns [CollabError::InvalidTextDelta] if the value is not a
+  /// valid operation.
+  pub fn from_json_value(value: &JsonValue) -> Result<TextDelta, CollabError> {
+    let invalid = || CollabError::InvalidTextDelta(value.to_string());
+    let op = value.as_object().ok_or_else(invalid)?;
+    let attrs = match op.get("attributes") {
+      None | Some(JsonValue::Null) => Attrs::new(),
+      Some(JsonValue::Object(attributes)) => {
+        let mut attrs = Attrs::new();
+        for (key, value) in attributes {
+          attrs.insert(
+            Arc::from(key.as_str()),
+            serde_json::from_value(value.clone())?,
+          );
+        }
+        attrs
+      },
+      Some(_) => return Err(invalid()),
+    };
+    if let Some(insert) = op.get("insert") {
+      match insert {
+        JsonValue::String(content) => Ok(TextDelta::Inserted(content.clone(), attrs)),
+        JsonValue::Object(_) => Ok(TextDelta::InsertedEmbed(
+          serde_json::from_value(insert.clone())?,
+          attrs,
+        )),
+        _ => Err(invalid()),
+      }
+    } else if let Some(len) = op.get("delete") {
+      let len = len.as_u64().ok_or_else(invalid)?;
+      Ok(TextDelta::Deleted(len as u32))
+    } else if let Some(len) = op.get("retain") {
+      let len = len.as_u64().ok_or_else(invalid)?;
+      Ok(TextDelta::Retain(len as u32, attrs))
+    } else {
+      Err(invalid())
+    }
+  }
+}
+
+/// Returns the Quill delta, `{"ops": [...]}`, of the given [TextDelta]s.
+pub fn text_deltas_to_json(deltas: &[TextDelta]) -> JsonValue {
+  let ops = deltas.iter().map(|delta| delta.to_json_value()).collect();
+  json!({ "ops": JsonValue::Array(ops) })
+}
+
+/// Parse the Quill delta. Both `{"ops": [...]}` and the array of operations are accepted.
+pub fn text_deltas_from_json(value: &JsonValue) -> Result<Vec<TextDelta>, CollabError> {
+  let ops = match value {
+    JsonValue::Array(ops) => ops,
+    JsonValue::Object(object) => match object.get("ops") {
+      Some(JsonValue::Array(ops)) => ops,
+      _ => return Err(CollabError::InvalidTextDelta(value.to_string())),
+    },
+    _ => return Err(CollabError::InvalidTextDelta(value.to_string())),
+  };
+  ops.iter().map(TextDelta::from_json_value).collect()
+}
+
 pub struct TextRefWrapper {
   text_ref: TextRef,
   collab_ctx: CollabContext,
@@ -43,33 +161,64 @@ impl TextRefWrapper {
     self.collab_ctx.with_transact_mut(f)
   }
 
-  pub fn get_delta_with_txn<T: ReadTxn>(&self, txn: &T) -> Vec<Delta> {
-    let changes = self.text_ref.diff(txn, YChange::identity);
-    let mut deltas = vec![];
-    for change in changes {
-      let delta = YrsDelta::Inserted(change.insert, change.attributes);
-      deltas.push(delta);
-    }
-    deltas
+  /// Returns the content of the text as a list of [TextDelta::Inserted] and
+  /// [TextDelta::InsertedEmbed], which is the Quill representation of a document.
+  pub fn get_delta_with_txn<T: ReadTxn>(&self, txn: &T) -> Vec<TextDelta> {
+    get_delta(txn, &self.text_ref)
+  }
+
+  /// Subscribes to the changes of the text as [TextDelta]s, see [TextDelta::from_event]. The
+  /// subscription keeps a copy of the content of the text and refreshes it after each change.
+  pub fn observe_delta<F>(&mut self, f: F) -> TextSubscription
+  where
+    F: Fn(&TransactionMut, Vec<TextDelta>) + 'static,
+  {
+    let content = {
+      let txn = self.transact();
+      Mutex::new(self.get_delta_with_txn(&txn))
+    };
+    self.text_ref.observe(move |txn, event| {
+      let mut content = content.lock();
+      let deltas = TextDelta::from_event(txn, event, &content);
+      *content = get_delta(txn, event.target());
+      drop(content);
+      f(txn, deltas);
+    })
   }
 
+  /// Apply the Quill delta to the text. The [TextDelta::Retain] and [TextDelta::Deleted] lengths
+  /// are counted in UTF-16 code units, and they are converted into the offsets of the document.
   pub fn apply_delta_with_txn(&self, txn: &mut TransactionMut, delta: Vec<TextDelta>) {
+    let mut cursor = TextCursor::new(self.text_ref.diff(txn, YChange::identity));
+    // The index of the document, see [TextCursor].
     let mut index = 0;
     for d in delta {
       match d {
+        // The content is always inserted with its attributes, even if they are empty, so it
+        // doesn't inherit the format of the preceding content.
         TextDelta::Inserted(content, attrs) => {
-          let value = content.to_string();
-          let len = value.len() as u32;
-          self.text_ref.insert(txn, index, &value);
-          self.text_ref.format(txn, index, len, attrs);
-          index += len;
+          self
+            .text_ref
+            .insert_with_attributes(txn, index, &content, attrs);
+          index += content.len() as u32;
+        },
+        TextDelta::InsertedEmbed(embed, attrs) => {
+          self
+            .text_ref
+            .insert_embed_with_attributes(txn, index, embed, attrs);
+          index += 1;
         },
         TextDelta::Deleted(len) => {
-          self.text_ref.remove_range(txn, index, len);
-          index += len;
+          let len = cursor.advance(len);
+          if len > 0 {
+            self.text_ref.remove_range(txn, index, len);
+          }
         },
         TextDelta::Retain(len, attrs) => {
-          self.text_ref.format(txn, index, len, attrs);
+          let len = cursor.advance(len);
+          if !attrs.is_empty() && len > 0 {
+            self.text_ref.format(txn, index, len, attrs);
+          }
           index += len;
         },
       }
@@ -77,6 +226,133 @@ impl TextRefWrapper {
   }
 }
 
+fn get_delta<T: ReadTxn>(txn: &T, text_ref: &TextRef) -> Vec<TextDelta> {
+  text_ref
+    .diff(txn, YChange::identity)
+    .into_iter()
+    .map(|change| insert_delta(txn, &change.insert, attrs_or_default(&change.attributes)))
+    .collect()
+}
+
+fn attrs_or_default(attrs: &Option<Box<Attrs>>) -> Attrs {
+  attrs
+    .as_ref()
+    .map(|attrs| *attrs.clone())
+    .unwrap_or_default()
+}
+
+fn insert_delta<T: ReadTxn>(txn: &T, value: &YrsValue, attrs: Attrs) -> TextDelta {
+  match value {
+    YrsValue::Any(Any::String(content)) => TextDelta::Inserted(content.to_string(), attrs),
+    YrsValue::Any(embed) => TextDelta::InsertedEmbed(embed.clone(), attrs),
+    other => TextDelta::InsertedEmbed(other.to_json(txn), attrs),
+  }
+}
+
+/// Walks the content of a text that existed before a change. The collab documents use the
+/// [OffsetKind::Bytes](yrs::OffsetKind::Bytes), so the UTF-16 lengths of the Quill delta are
+/// converted into UTF-8 byte lengths, and the other way around. An embed is one unit in both.
+struct TextCursor {
+  chunks: VecDeque<TextChunk>,
+}
+
+enum TextChunk {
+  Text(String),
+  Embed,
+}
+
+impl TextCursor {
+  fn new<D>(diffs: Vec<yrs::types::text::Diff<D>>) -> Self {
+    let chunks = diffs
+      .into_iter()
+      .map(|diff| match diff.insert {
+        YrsValue::Any(Any::String(content)) => TextChunk::Text(content.to_string()),
+        _ => TextChunk::Embed,
+      })
+      .collect();
+    Self { chunks }
+  }
+
+  /// Walks the content that is given as [TextDelta::Inserted] and [TextDelta::InsertedEmbed].
+  fn from_deltas(deltas: &[TextDelta]) -> Self {
+    let chunks = deltas
+      .iter()
+      .flat_map(|delta| match delta {
+        TextDelta::Inserted(content, _) => Some(TextChunk::Text(content.clone())),
+        TextDelta::InsertedEmbed(_, _) => Some(TextChunk::Embed),
+        TextDelta::Deleted(_) | TextDelta::Retain(_, _) => None,
+      })
+      .collect();
+    Self { chunks }
+  }
+
+  /// Skip the given number of UTF-16 code units and returns the number of the document offsets
+  /// that were skipped. A character that is split by the given length is skipped as a whole.
+  fn advance(&mut self, utf16_len: u32) -> u32 {
+    let mut remaining = utf16_len as usize;
+    let mut offset = 0;
+    while remaining > 0 {
+      match self.chunks.pop_front() {
+        None => break,
+        Some(TextChunk::Embed) => {
+          remaining -= 1;
+          offset += 1;
+        },
+        Some(TextChunk::Text(content)) => {
+          let mut byte_len = 0;
+          for c in content.chars() {
+            if remaining == 0 {
+              break;
+            }
+            remaining = remaining.saturating_sub(c.len_utf16());
+            byte_len += c.len_utf8();
+          }
+          offset += byte_len;
+          if byte_len < content.len() {
+            self
+              .chunks
+              .push_front(TextChunk::Text(content[byte_len..].to_string()));
+          }
+        },
+      }
+    }
+    offset as u32
+  }
+
+  /// Skip the given number of the document offsets and returns the number of UTF-16 code units
+  /// that were skipped.
+  fn advance_offset(&mut self, offset: u32) -> u32 {
+    let mut remaining = offset as usize;
+    let mut utf16_len = 0;
+    while remaining > 0 {
+      match self.chunks.pop_front() {
+        None => break,
+        Some(TextChunk::Embed) => {
+          remaining -= 1;
+          utf16_len += 1;
+        },
+        Some(TextChunk::Text(content)) => {
+          let mut byte_len = 0;
+          for c in content.chars() {
+            if remaining == 0 {
+              break;
+            }
+            remaining = remaining.saturating_sub(c.len_utf8());
+            byte_len += c.len_utf8();
+            utf16_len += c.len_utf16();
+          }
+          if byte_len < content.len() {
+            self
+              .chunks
+              .push_front(TextChunk::Text(content[byte_len..].to_string()));
+          }
+        },
+      }
+    }
+    utf16_len as u32
+  }
+}
+
 impl Deref for TextRefWrapper {
   type Target = TextRef;
 
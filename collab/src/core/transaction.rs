@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
@@ -6,39 +8,151 @@ use yrs::{Doc, Transact, Transaction, TransactionMut};
 use crate::core::origin::CollabOrigin;
 use crate::error::CollabError;
 
+/// Configures how the [TransactionRetry] retries to acquire a transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionRetryConfig {
+  /// Give up retrying after this duration. Default is `2` seconds.
+  pub timeout: Duration,
+  /// The interval between two retries. Default is `50` milliseconds.
+  pub retry_interval: Duration,
+}
+
+impl Default for TransactionRetryConfig {
+  fn default() -> Self {
+    Self {
+      timeout: Duration::from_secs(2),
+      retry_interval: Duration::from_millis(50),
+    }
+  }
+}
+
+impl TransactionRetryConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+    self.retry_interval = retry_interval;
+    self
+  }
+}
+
+/// Records the contention of acquiring the transactions of a document.
+#[derive(Debug, Default)]
+pub struct TransactionMetrics {
+  acquired: AtomicU64,
+  contended: AtomicU64,
+  retries: AtomicU64,
+  timeouts: AtomicU64,
+  wait_micros: AtomicU64,
+}
+
+impl TransactionMetrics {
+  pub fn snapshot(&self) -> TransactionMetricsSnapshot {
+    TransactionMetricsSnapshot {
+      acquired: self.acquired.load(Ordering::Relaxed),
+      contended: self.contended.load(Ordering::Relaxed),
+      retries: self.retries.load(Ordering::Relaxed),
+      timeouts: self.timeouts.load(Ordering::Relaxed),
+      total_wait: Duration::from_micros(self.wait_micros.load(Ordering::Relaxed)),
+    }
+  }
+
+  pub fn reset(&self) {
+    self.acquired.store(0, Ordering::Relaxed);
+    self.contended.store(0, Ordering::Relaxed);
+    self.retries.store(0, Ordering::Relaxed);
+    self.timeouts.store(0, Ordering::Relaxed);
+    self.wait_micros.store(0, Ordering::Relaxed);
+  }
+
+  fn record(&self, retries: u64, wait: Duration, is_timeout: bool) {
+    self.acquired.fetch_add(1, Ordering::Relaxed);
+    if retries > 0 {
+      self.contended.fetch_add(1, Ordering::Relaxed);
+      self.retries.fetch_add(retries, Ordering::Relaxed);
+      self
+        .wait_micros
+        .fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+    }
+    if is_timeout {
+      self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionMetricsSnapshot {
+  /// The number of acquisitions, including the ones that timed out.
+  pub acquired: u64,
+  /// The number of acquisitions that needed at least one retry.
+  pub contended: u64,
+  /// The total number of retries.
+  pub retries: u64,
+  /// The number of acquisitions that timed out.
+  pub timeouts: u64,
+  /// The total time spent waiting for the contended transactions.
+  pub total_wait: Duration,
+}
+
 /// TransactionRetry is a wrapper of Transaction and TransactionMut.
 /// It will retry to get a transaction if fail to require the transaction.
 /// The default timeout is `2` seconds and the default retry interval is `50` milliseconds.
 /// Most of the time, it will get the transaction in the first try.
+///
+/// The `*_async` methods yield to the runtime between the retries instead of blocking the
+/// thread, and return an error instead of falling back to the blocking acquisition.
 pub struct TransactionRetry<'a> {
   timeout: Duration,
   doc: &'a Doc,
   start: Instant,
   retry_interval: Duration,
+  retries: u64,
+  metrics: Option<Arc<TransactionMetrics>>,
 }
 
 impl<'a> TransactionRetry<'a> {
   pub fn new(doc: &'a Doc) -> Self {
+    Self::with_config(doc, &TransactionRetryConfig::default())
+  }
+
+  pub fn with_config(doc: &'a Doc, config: &TransactionRetryConfig) -> Self {
     Self {
-      timeout: Duration::from_secs(2),
-      retry_interval: Duration::from_millis(50),
+      timeout: config.timeout,
+      retry_interval: config.retry_interval,
       doc,
       start: Instant::now(),
+      retries: 0,
+      metrics: None,
     }
   }
 
+  /// Record the contention of the acquisition into the [TransactionMetrics].
+  pub fn with_metrics(mut self, metrics: Arc<TransactionMetrics>) -> Self {
+    self.metrics = Some(metrics);
+    self
+  }
+
   pub fn get_read_txn(&mut self) -> Transaction<'a> {
     while self.start.elapsed() < self.timeout {
       match self.doc.try_transact() {
         Ok(txn) => {
+          self.record(false);
           return txn;
         },
         Err(_e) => {
+          self.retries += 1;
           sleep(self.retry_interval);
         },
       }
     }
     tracing::warn!("[Txn]: acquire read txn timeout");
+    self.record(true);
     self.doc.transact()
   }
 
@@ -46,14 +160,17 @@ impl<'a> TransactionRetry<'a> {
     while self.start.elapsed() < self.timeout {
       match self.doc.try_transact_mut_with(origin.clone()) {
         Ok(txn) => {
+          self.record(false);
           return txn;
         },
         Err(_e) => {
+          self.retries += 1;
           sleep(self.retry_interval);
         },
       }
     }
     tracing::warn!("[Txn]: acquire write txn timeout");
+    self.record(true);
     self.doc.transact_mut_with(origin)
   }
 
@@ -64,14 +181,54 @@ impl<'a> TransactionRetry<'a> {
     while self.start.elapsed() < self.timeout {
       match self.doc.try_transact_mut_with(origin.clone()) {
         Ok(txn) => {
+          self.record(false);
           return Ok(txn);
         },
         Err(_e) => {
+          self.retries += 1;
           sleep(self.retry_interval);
         },
       }
     }
     tracing::warn!("[Txn]: acquire write txn timeout");
+    self.record(true);
     Err(CollabError::AcquiredWriteTxnFail)
   }
+
+  pub async fn get_read_txn_async(&mut self) -> Result<Transaction<'a>, CollabError> {
+    while self.start.elapsed() < self.timeout {
+      if let Ok(txn) = self.doc.try_transact() {
+        self.record(false);
+        return Ok(txn);
+      }
+      self.retries += 1;
+      tokio::time::sleep(self.retry_interval).await;
+    }
+    tracing::warn!("[Txn]: acquire read txn timeout");
+    self.record(true);
+    Err(CollabError::AcquiredReadTxnFail)
+  }
+
+  pub async fn get_write_txn_with_async(
+    &mut self,
+    origin: CollabOrigin,
+  ) -> Result<TransactionMut<'a>, CollabError> {
+    while self.start.elapsed() < self.timeout {
+      if let Ok(txn) = self.doc.try_transact_mut_with(origin.clone()) {
+        self.record(false);
+        return Ok(txn);
+      }
+      self.retries += 1;
+      tokio::time::sleep(self.retry_interval).await;
+    }
+    tracing::warn!("[Txn]: acquire write txn timeout");
+    self.record(true);
+    Err(CollabError::AcquiredWriteTxnFail)
+  }
+
+  fn record(&self, is_timeout: bool) {
+    if let Some(metrics) = &self.metrics {
+      metrics.record(self.retries, self.start.elapsed(), is_timeout);
+    }
+  }
 }
@@ -0,0 +1,416 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+use yrs::types::{DeepEventsSubscription, DeepObservable, Value};
+use yrs::undo::{EventKind, Options, UndoEventSubscription};
+use yrs::{Doc, Origin, Subscription, TransactionMut, UndoManager};
+
+use crate::core::json_patch::touched_paths_from_event;
+use crate::core::origin::CollabOrigin;
+use crate::error::CollabError;
+use crate::preclude::JsonValue;
+use crate::util::timestamp_millis;
+
+type AfterTransactionSubscription = Subscription<Arc<dyn Fn(&mut TransactionMut)>>;
+
+pub type UndoStackSender = broadcast::Sender<UndoStackEvent>;
+pub type UndoStackReceiver = broadcast::Receiver<UndoStackEvent>;
+
+/// The metadata that is stored with each undo stack item.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStackItemMeta {
+  /// The selection or the cursor that returned by the [UndoConfig::with_selection_provider]
+  /// when the stack item was created.
+  pub selection: Option<JsonValue>,
+}
+
+pub type SelectionProvider = Arc<dyn Fn() -> Option<JsonValue> + Send + Sync>;
+pub type SelectionRestorer = Arc<dyn Fn(JsonValue) + Send + Sync>;
+
+/// Configures the [UndoManager] of the [Collab](crate::preclude::Collab) or of an undo scope.
+#[derive(Clone)]
+pub struct UndoConfig {
+  /// The changes that are made within this duration are grouped into one stack item.
+  /// Default is 500ms.
+  pub capture_timeout_millis: u64,
+  /// The changes of these origins are tracked besides the changes of the collab's own origin.
+  pub tracked_origins: Vec<Origin>,
+  selection_provider: Option<SelectionProvider>,
+  selection_restorer: Option<SelectionRestorer>,
+}
+
+impl Default for UndoConfig {
+  fn default() -> Self {
+    Self {
+      capture_timeout_millis: 500,
+      tracked_origins: vec![],
+      selection_provider: None,
+      selection_restorer: None,
+    }
+  }
+}
+
+impl UndoConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn capture_timeout_millis(mut self, capture_timeout_millis: u64) -> Self {
+    self.capture_timeout_millis = capture_timeout_millis;
+    self
+  }
+
+  pub fn track_origin<O: Into<Origin>>(mut self, origin: O) -> Self {
+    self.tracked_origins.push(origin.into());
+    self
+  }
+
+  /// The provider is called when a stack item is created. The returned selection is stored in
+  /// the [UndoStackItemMeta] of the item.
+  pub fn with_selection_provider<F>(mut self, provider: F) -> Self
+  where
+    F: Fn() -> Option<JsonValue> + Send + Sync + 'static,
+  {
+    self.selection_provider = Some(Arc::new(provider));
+    self
+  }
+
+  /// The restorer is called with the stored selection when a stack item is undone or redone.
+  pub fn with_selection_restorer<F>(mut self, restorer: F) -> Self
+  where
+    F: Fn(JsonValue) + Send + Sync + 'static,
+  {
+    self.selection_restorer = Some(Arc::new(restorer));
+    self
+  }
+}
+
+/// Describes an item of the undo or the redo stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoStackItem {
+  /// The origin of the transaction that created the item.
+  pub origin: CollabOrigin,
+  /// The unix timestamp, in seconds, when the item was created or last extended.
+  pub timestamp: i64,
+  /// The json pointers of the values that were changed by the item. The pointers are relative
+  /// to the `data` section, the same as the paths of the [JsonPatch](crate::preclude::JsonPatch).
+  pub touched_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoStackKind {
+  Undo,
+  Redo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoStackChange {
+  /// A new item was pushed onto the stack.
+  Pushed(UndoStackKind, UndoStackItem),
+  /// The changes were merged into the top item of the stack because they happened within the
+  /// capture timeout.
+  Updated(UndoStackKind, UndoStackItem),
+  /// The top item of the stack was popped by undo or redo.
+  Popped(UndoStackKind, UndoStackItem),
+  /// The stack was cleared. The redo stack is cleared when a new change is made.
+  Cleared(UndoStackKind),
+}
+
+/// Sent to the [UndoStackReceiver] whenever the undo or the redo stack changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoStackEvent {
+  /// The id of the undo scope. [None] for the undo manager of the whole `data` section.
+  pub scope_id: Option<String>,
+  pub change: UndoStackChange,
+  pub undo_len: usize,
+  pub redo_len: usize,
+}
+
+/// Mirrors the stacks of the [UndoManager], which doesn't expose its stack items. Each item
+/// keeps its [UndoStackItemMeta], because the stack items of the [UndoManager] don't carry any
+/// metadata.
+#[derive(Default)]
+struct UndoStacks {
+  undo: Vec<(UndoStackItem, UndoStackItemMeta)>,
+  redo: Vec<(UndoStackItem, UndoStackItemMeta)>,
+  /// The paths that were touched by the current transaction. They are collected by the deep
+  /// observers of the scope and drained when the stack item is pushed or updated.
+  pending_paths: Vec<String>,
+}
+
+impl UndoStacks {
+  fn stack_mut(&mut self, kind: UndoStackKind) -> &mut Vec<(UndoStackItem, UndoStackItemMeta)> {
+    match kind {
+      UndoStackKind::Undo => &mut self.undo,
+      UndoStackKind::Redo => &mut self.redo,
+    }
+  }
+
+  fn items(&self, kind: UndoStackKind) -> Vec<UndoStackItem> {
+    let stack = match kind {
+      UndoStackKind::Undo => &self.undo,
+      UndoStackKind::Redo => &self.redo,
+    };
+    stack.iter().map(|(item, _)| item.clone()).collect()
+  }
+}
+
+/// Notifies the [UndoStackReceiver]s about the changes of the stacks of one scope.
+#[derive(Clone)]
+pub(crate) struct UndoStackNotifier {
+  scope_id: Option<String>,
+  sender: UndoStackSender,
+}
+
+impl UndoStackNotifier {
+  pub(crate) fn new(scope_id: Option<String>, sender: UndoStackSender) -> Self {
+    Self { scope_id, sender }
+  }
+
+  fn notify(&self, stacks: &UndoStacks, change: UndoStackChange) {
+    // Sending fails if there is no receiver, which is fine.
+    let _ = self.sender.send(UndoStackEvent {
+      scope_id: self.scope_id.clone(),
+      change,
+      undo_len: stacks.undo.len(),
+      redo_len: stacks.redo.len(),
+    });
+  }
+}
+
+/// A [CollabUndoManager] wraps the [UndoManager] of a scope. The scope can be the whole `data`
+/// section or the nested types, for example, a block subtree of the document.
+pub(crate) struct CollabUndoManager {
+  undo_manager: UndoManager,
+  stacks: Arc<Mutex<UndoStacks>>,
+  /// Set while undoing or redoing. The items pushed at that time are moved between the stacks
+  /// instead of being new changes.
+  is_undoing: Arc<AtomicBool>,
+  #[allow(dead_code)]
+  subscriptions: Vec<UndoEventSubscription>,
+  #[allow(dead_code)]
+  path_subscriptions: Vec<DeepEventsSubscription>,
+  #[allow(dead_code)]
+  after_txn_subscription: Option<AfterTransactionSubscription>,
+}
+
+impl CollabUndoManager {
+  /// Create a [CollabUndoManager] that tracks the changes of the scope. The scope must be a map,
+  /// an array or a text. The `scope_path` is the path of the scope in the `data` section.
+  pub(crate) fn new(
+    doc: &Doc,
+    scope: &Value,
+    scope_path: Vec<String>,
+    local_origin: Origin,
+    config: UndoConfig,
+    notifier: UndoStackNotifier,
+  ) -> Result<Self, CollabError> {
+    let options = Options {
+      capture_timeout_millis: config.capture_timeout_millis,
+      ..Options::default()
+    };
+    let mut undo_manager = match scope {
+      Value::YMap(map_ref) => UndoManager::with_options(doc, map_ref, options),
+      Value::YArray(array_ref) => UndoManager::with_options(doc, array_ref, options),
+      Value::YText(text_ref) => UndoManager::with_options(doc, text_ref, options),
+      _ => return Err(invalid_scope()),
+    };
+    undo_manager.include_origin(local_origin);
+    for origin in config.tracked_origins {
+      undo_manager.include_origin(origin);
+    }
+
+    let stacks = Arc::new(Mutex::new(UndoStacks::default()));
+    let is_undoing = Arc::new(AtomicBool::new(false));
+    let mut subscriptions = vec![];
+    let provider = config.selection_provider;
+    let cloned_stacks = stacks.clone();
+    let cloned_is_undoing = is_undoing.clone();
+    let cloned_notifier = notifier.clone();
+    subscriptions.push(undo_manager.observe_item_added(move |_, event| {
+      let meta = UndoStackItemMeta {
+        selection: provider.as_ref().and_then(|provider| provider()),
+      };
+      let kind = pushed_stack_kind(event.kind);
+      let mut stacks = cloned_stacks.lock();
+      let touched_paths = std::mem::take(&mut stacks.pending_paths);
+      let item = UndoStackItem {
+        origin: event
+          .origin
+          .as_ref()
+          .map(CollabOrigin::from)
+          .unwrap_or(CollabOrigin::Empty),
+        timestamp: timestamp_millis() / 1000,
+        touched_paths,
+      };
+      // A new change clears the redo stack. The changes made by undo or redo don't.
+      if kind == UndoStackKind::Undo
+        && !cloned_is_undoing.load(Ordering::SeqCst)
+        && !stacks.redo.is_empty()
+      {
+        stacks.redo.clear();
+        cloned_notifier.notify(&stacks, UndoStackChange::Cleared(UndoStackKind::Redo));
+      }
+      stacks.stack_mut(kind).push((item.clone(), meta));
+      cloned_notifier.notify(&stacks, UndoStackChange::Pushed(kind, item));
+    }));
+
+    let cloned_stacks = stacks.clone();
+    let cloned_notifier = notifier.clone();
+    subscriptions.push(undo_manager.observe_item_updated(move |_, event| {
+      let kind = pushed_stack_kind(event.kind);
+      let mut stacks = cloned_stacks.lock();
+      let mut touched_paths = std::mem::take(&mut stacks.pending_paths);
+      if let Some((item, _)) = stacks.stack_mut(kind).last_mut() {
+        touched_paths.retain(|path| !item.touched_paths.contains(path));
+        item.touched_paths.extend(touched_paths);
+        item.timestamp = timestamp_millis() / 1000;
+        let item = item.clone();
+        cloned_notifier.notify(&stacks, UndoStackChange::Updated(kind, item));
+      }
+    }));
+
+    let restorer = config.selection_restorer;
+    let cloned_stacks = stacks.clone();
+    subscriptions.push(undo_manager.observe_item_popped(move |_, event| {
+      let kind = popped_stack_kind(event.kind);
+      let popped = {
+        let mut stacks = cloned_stacks.lock();
+        let popped = stacks.stack_mut(kind).pop();
+        if let Some((item, _)) = &popped {
+          notifier.notify(&stacks, UndoStackChange::Popped(kind, item.clone()));
+        }
+        popped
+      };
+      if let (Some(restorer), Some((_, meta))) = (&restorer, popped) {
+        if let Some(selection) = meta.selection {
+          restorer(selection);
+        }
+      }
+    }));
+
+    // The after transaction callbacks are called in the order they are registered, so the
+    // pending paths of the transactions that are not tracked by the [UndoManager] are dropped
+    // here.
+    let cloned_stacks = stacks.clone();
+    let after_txn_subscription = doc
+      .observe_after_transaction(move |_| {
+        cloned_stacks.lock().pending_paths.clear();
+      })
+      .ok();
+
+    let mut this = Self {
+      undo_manager,
+      stacks,
+      is_undoing,
+      subscriptions,
+      path_subscriptions: vec![],
+      after_txn_subscription,
+    };
+    this.observe_touched_paths(scope, scope_path);
+    Ok(this)
+  }
+
+  pub(crate) fn expand_scope(
+    &mut self,
+    scope: &Value,
+    scope_path: Vec<String>,
+  ) -> Result<(), CollabError> {
+    match scope {
+      Value::YMap(map_ref) => self.undo_manager.expand_scope(map_ref),
+      Value::YArray(array_ref) => self.undo_manager.expand_scope(array_ref),
+      Value::YText(text_ref) => self.undo_manager.expand_scope(text_ref),
+      _ => return Err(invalid_scope()),
+    }
+    self.observe_touched_paths(scope, scope_path);
+    Ok(())
+  }
+
+  pub(crate) fn can_undo(&self) -> bool {
+    self.undo_manager.can_undo()
+  }
+
+  pub(crate) fn can_redo(&self) -> bool {
+    self.undo_manager.can_redo()
+  }
+
+  pub(crate) fn undo(&mut self) -> Result<bool, CollabError> {
+    self.is_undoing.store(true, Ordering::SeqCst);
+    let result = self.undo_manager.undo();
+    self.is_undoing.store(false, Ordering::SeqCst);
+    result.map_err(|e| CollabError::Internal(Box::new(e)))
+  }
+
+  pub(crate) fn redo(&mut self) -> Result<bool, CollabError> {
+    self.is_undoing.store(true, Ordering::SeqCst);
+    let result = self.undo_manager.redo();
+    self.is_undoing.store(false, Ordering::SeqCst);
+    result.map_err(|e| CollabError::Internal(Box::new(e)))
+  }
+
+  /// Returns the items of the undo stack. The last item is the one that is undone next.
+  pub(crate) fn undo_stack(&self) -> Vec<UndoStackItem> {
+    self.stacks.lock().items(UndoStackKind::Undo)
+  }
+
+  /// Returns the items of the redo stack. The last item is the one that is redone next.
+  pub(crate) fn redo_stack(&self) -> Vec<UndoStackItem> {
+    self.stacks.lock().items(UndoStackKind::Redo)
+  }
+
+  pub(crate) fn undo_stack_len(&self) -> usize {
+    self.stacks.lock().undo.len()
+  }
+
+  pub(crate) fn redo_stack_len(&self) -> usize {
+    self.stacks.lock().redo.len()
+  }
+
+  /// Collect the paths that are changed within the scope into the pending paths.
+  fn observe_touched_paths(&mut self, scope: &Value, scope_path: Vec<String>) {
+    let stacks = self.stacks.clone();
+    let callback = move |txn: &TransactionMut, events: &yrs::types::Events| {
+      let mut stacks = stacks.lock();
+      for event in events.iter() {
+        let touched_paths = touched_paths_from_event(txn, event, &scope_path);
+        for touched_path in touched_paths {
+          if !stacks.pending_paths.contains(&touched_path) {
+            stacks.pending_paths.push(touched_path);
+          }
+        }
+      }
+    };
+    let subscription = match scope.clone() {
+      Value::YMap(mut map_ref) => map_ref.observe_deep(callback),
+      Value::YArray(mut array_ref) => array_ref.observe_deep(callback),
+      Value::YText(mut text_ref) => text_ref.observe_deep(callback),
+      _ => return,
+    };
+    self.path_subscriptions.push(subscription);
+  }
+}
+
+/// The stack that an added or updated item belongs to. The kind of the event is the operation
+/// that created the item: the items created by undoing are pushed onto the redo stack, and the
+/// other changes, including the redone ones, onto the undo stack.
+fn pushed_stack_kind(kind: EventKind) -> UndoStackKind {
+  match kind {
+    EventKind::Undo => UndoStackKind::Redo,
+    EventKind::Redo => UndoStackKind::Undo,
+  }
+}
+
+/// The stack that a popped item belongs to. Undoing pops the undo stack and redoing pops the
+/// redo stack.
+fn popped_stack_kind(kind: EventKind) -> UndoStackKind {
+  match kind {
+    EventKind::Undo => UndoStackKind::Undo,
+    EventKind::Redo => UndoStackKind::Redo,
+  }
+}
+
+fn invalid_scope() -> CollabError {
+  CollabError::InvalidUndoScope("The scope must be a map, an array or a text".to_string())
+}
@@ -9,9 +9,72 @@ pub enum CollabError {
   #[error("Get write txn failed")]
   AcquiredWriteTxnFail,
 
+  #[error("Get read txn failed")]
+  AcquiredReadTxnFail,
+
+  #[error("The collab is read-only")]
+  ReadOnly,
+
+  #[error("The local client is not allowed to change {0:?}")]
+  PermissionDenied(Vec<String>),
+
   #[error("UndoManager is not enabled")]
   UndoManagerNotEnabled,
 
+  #[error("Undo scope not found: {0}")]
+  UndoScopeNotFound(String),
+
+  #[error("Invalid undo scope: {0}")]
+  InvalidUndoScope(String),
+
+  #[error("No map, array or text at the path: {0}")]
+  BlamePathNotFound(String),
+
+  #[error("The presence requires a client origin")]
+  PresenceRequiresClient,
+
+  #[error(transparent)]
+  Yrs(#[from] lib0::error::Error),
+
+  #[error("Invalid snapshot: {0}")]
+  InvalidSnapshot(String),
+
+  #[error("The branch doesn't belong to the collab")]
+  BranchParentMismatch,
+
+  #[error("Invalid json patch: {0}")]
+  InvalidJsonPatch(String),
+
+  #[error("Invalid text delta: {0}")]
+  InvalidTextDelta(String),
+
+  #[error("Invalid bundle: {0}")]
+  InvalidBundle(String),
+
+  #[error("The checksum of the bundle doesn't match its content")]
+  BundleChecksumMismatch,
+
+  #[error("The bundle belongs to {actual}, expected {expected}")]
+  BundleObjectMismatch { expected: String, actual: String },
+
+  #[error("Unknown value {value} of {ty}")]
+  UnknownEnumValue { ty: String, value: i64 },
+
+  #[error("Yrs serde error: {0}")]
+  YrsSerde(String),
+
   #[error("Internal failure: {0}")]
   Internal(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
+
+impl serde::ser::Error for CollabError {
+  fn custom<T: std::fmt::Display>(msg: T) -> Self {
+    CollabError::YrsSerde(msg.to_string())
+  }
+}
+
+impl serde::de::Error for CollabError {
+  fn custom<T: std::fmt::Display>(msg: T) -> Self {
+    CollabError::YrsSerde(msg.to_string())
+  }
+}
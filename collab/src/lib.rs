@@ -2,6 +2,8 @@ pub mod error;
 mod util;
 
 pub mod core;
+pub mod presence;
+pub mod serde;
 
 pub mod preclude {
   pub use lib0::any::Any as lib0Any;
@@ -15,10 +17,40 @@ pub mod preclude {
   pub use yrs::*;
 
   pub use crate::core::array_wrapper::ArrayRefWrapper;
+  pub use crate::core::blame::{Author, TextBlame};
+  pub use crate::core::bundle::{BundleHeader, BundleOptions, CollabBundle};
   pub use crate::core::collab::{Collab, CollabBuilder, CollabContext};
   pub use crate::core::collab_plugin::CollabPlugin;
+  pub use crate::core::compaction::{CompactionPoint, CompactionResult};
+  pub use crate::core::encoding::EncoderVersion;
+  pub use crate::core::json_patch::{JsonPatch, PatchOperation};
   pub use crate::core::map_wrapper::CustomMapRef;
   pub use crate::core::map_wrapper::{MapRefExtension, MapRefWrapper};
-  pub use crate::core::text_wrapper::TextRefWrapper;
-  pub use crate::util::insert_json_value_to_map_ref;
+  pub use crate::core::permission::{
+    PermissionPolicy, PermissionRule, PermissionViolation, PermissionViolationReceiver,
+  };
+  pub use crate::core::plain_text::PlainTextExtractor;
+  pub use crate::core::quarantine::QuarantinedUpdate;
+  pub use crate::core::schema::{
+    CollabSchema, MapSchema, SchemaField, SchemaRegistry, SchemaType, SchemaViolation,
+    SchemaViolationKind,
+  };
+  pub use crate::core::stats::{ClientStats, CollabStats};
+  pub use crate::core::text_wrapper::{
+    text_deltas_from_json, text_deltas_to_json, TextDelta, TextRefWrapper,
+  };
+  pub use crate::core::undo::{
+    UndoConfig, UndoStackChange, UndoStackEvent, UndoStackItem, UndoStackItemMeta, UndoStackKind,
+    UndoStackReceiver,
+  };
+  pub use crate::presence::{
+    PeerPresence, Presence, PresenceConfig, PresenceEvent, PresencePosition, PresenceReceiver,
+    PresenceSelection,
+  };
+  #[allow(deprecated)]
+  pub use crate::util::lib0_any_to_json_value;
+  pub use crate::util::{
+    insert_json_value_to_array_ref, insert_json_value_to_array_ref_with_config,
+    insert_json_value_to_map_ref, insert_json_value_to_map_ref_with_config, JsonInsertConfig,
+  };
 }
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use y_sync::awareness::Awareness;
+use yrs::block::ClientID;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Assoc, IndexedSequence, ReadTxn, StickyIndex, TextRef, TransactionMut};
+
+use crate::core::origin::CollabClient;
+use crate::error::CollabError;
+use crate::util::timestamp_millis;
+
+pub type PresenceSender = broadcast::Sender<PresenceEvent>;
+pub type PresenceReceiver = broadcast::Receiver<PresenceEvent>;
+
+/// Configures how the presences of the peers are interpreted. See [Collab::set_presence_config].
+///
+/// [Collab::set_presence_config]: crate::preclude::Collab::set_presence_config
+#[derive(Debug, Clone)]
+pub struct PresenceConfig {
+  /// The peer is considered idle if it was not active for this duration. Default is `5` minutes.
+  pub idle_timeout: Duration,
+}
+
+impl Default for PresenceConfig {
+  fn default() -> Self {
+    Self {
+      idle_timeout: Duration::from_secs(5 * 60),
+    }
+  }
+}
+
+impl PresenceConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+    self.idle_timeout = idle_timeout;
+    self
+  }
+}
+
+/// The typed presence of a client. It is stored as the json state of the client in the
+/// [Awareness].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+  pub user: CollabClient,
+  /// The name that is shown to the other peers.
+  #[serde(default)]
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cursor: Option<PresencePosition>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub selection: Option<PresenceSelection>,
+  /// The unix timestamp, in milliseconds, when the client was last active.
+  pub last_active: i64,
+}
+
+impl Presence {
+  pub fn new(user: CollabClient) -> Self {
+    Self {
+      user,
+      name: "".to_string(),
+      cursor: None,
+      selection: None,
+      last_active: timestamp_millis(),
+    }
+  }
+
+  pub fn with_name(mut self, name: &str) -> Self {
+    self.name = name.to_string();
+    self
+  }
+
+  /// Refresh the [Presence::last_active] time.
+  pub fn touch(&mut self) {
+    self.last_active = timestamp_millis();
+  }
+
+  pub fn is_idle(&self, idle_timeout: Duration) -> bool {
+    timestamp_millis() - self.last_active > idle_timeout.as_millis() as i64
+  }
+}
+
+/// A position in a text that is anchored to the content instead of the index. The position
+/// keeps pointing to the same character when the text before it is changed by the other peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresencePosition(Vec<u8>);
+
+impl PresencePosition {
+  /// Create a position that is anchored to the character at the `index` of the `text`. Returns
+  /// None if the index is out of the range of the text.
+  pub fn new(txn: &mut TransactionMut, text: &TextRef, index: u32) -> Option<Self> {
+    let sticky_index = text.sticky_index(txn, index, Assoc::After)?;
+    Some(Self(sticky_index.encode_v1()))
+  }
+
+  /// Returns the current index of the position. Returns None if the text that the position
+  /// belongs to doesn't exist in the document.
+  pub fn resolve<T: ReadTxn>(&self, txn: &T) -> Result<Option<u32>, CollabError> {
+    let sticky_index = StickyIndex::decode_v1(&self.0)?;
+    Ok(sticky_index.get_offset(txn).map(|offset| offset.index))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceSelection {
+  /// The position where the selection starts.
+  pub anchor: PresencePosition,
+  /// The position where the selection ends. It can be before the anchor.
+  pub head: PresencePosition,
+}
+
+/// The presence of a peer that is connected to the same collab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerPresence {
+  pub client_id: ClientID,
+  pub presence: Presence,
+  pub is_idle: bool,
+}
+
+/// Sent when the awareness of the peers changed. See [Collab::subscribe_presence].
+///
+/// [Collab::subscribe_presence]: crate::preclude::Collab::subscribe_presence
+#[derive(Debug, Clone)]
+pub struct PresenceEvent {
+  /// The presences of all the peers, excluding the local client.
+  pub peers: Vec<PeerPresence>,
+  /// The peers that left.
+  pub removed: Vec<ClientID>,
+}
+
+/// Returns the [Presence] of the local client. Returns None if the local state is not set or is
+/// not a [Presence].
+pub fn get_local_presence(awareness: &Awareness) -> Option<Presence> {
+  let state = awareness.local_state()?;
+  serde_json::from_str(state).ok()
+}
+
+pub fn set_local_presence(
+  awareness: &mut Awareness,
+  presence: &Presence,
+) -> Result<(), CollabError> {
+  let state = serde_json::to_string(presence)?;
+  awareness.set_local_state(state);
+  Ok(())
+}
+
+/// Returns the presences of the peers, excluding the local client. The states of the peers that
+/// are not a [Presence] are ignored.
+pub fn get_peer_presences(awareness: &Awareness, config: &PresenceConfig) -> Vec<PeerPresence> {
+  let local_client_id = awareness.client_id();
+  let mut peers = awareness
+    .clients()
+    .iter()
+    .filter(|(client_id, _)| **client_id != local_client_id)
+    .flat_map(|(client_id, state)| {
+      let presence = serde_json::from_str::<Presence>(state).ok()?;
+      Some(PeerPresence {
+        client_id: *client_id,
+        is_idle: presence.is_idle(config.idle_timeout),
+        presence,
+      })
+    })
+    .collect::<Vec<_>>();
+  peers.sort_by_key(|peer| peer.client_id);
+  peers
+}
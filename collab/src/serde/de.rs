@@ -0,0 +1,345 @@
+use std::vec::IntoIter;
+
+use lib0::any::Any;
+use serde::de::value::StringDeserializer;
+use serde::de::{
+  DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+  VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserializer};
+use yrs::types::Value;
+use yrs::{Array, ArrayRef, GetString, Map, MapRef, ReadTxn};
+
+use crate::error::CollabError;
+
+/// Deserialize the value from [Any].
+pub fn from_any<T: DeserializeOwned>(any: &Any) -> Result<T, CollabError> {
+  T::deserialize(AnyDeserializer(any.clone()))
+}
+
+/// Deserialize the value from the yrs [Value]. The [MapRef], [ArrayRef] and
+/// [TextRef](yrs::TextRef) are read within the transaction directly.
+pub fn from_yrs_value<T: DeserializeOwned, R: ReadTxn>(
+  txn: &R,
+  value: Value,
+) -> Result<T, CollabError> {
+  T::deserialize(YrsDeserializer { txn, value })
+}
+
+pub fn from_map_ref<T: DeserializeOwned, R: ReadTxn>(
+  txn: &R,
+  map_ref: &MapRef,
+) -> Result<T, CollabError> {
+  from_yrs_value(txn, Value::YMap(map_ref.clone()))
+}
+
+pub fn from_array_ref<T: DeserializeOwned, R: ReadTxn>(
+  txn: &R,
+  array_ref: &ArrayRef,
+) -> Result<T, CollabError> {
+  from_yrs_value(txn, Value::YArray(array_ref.clone()))
+}
+
+/// The [AnyDeserializer] deserializes the value from [Any].
+pub struct AnyDeserializer(pub Any);
+
+impl<'de> Deserializer<'de> for AnyDeserializer {
+  type Error = CollabError;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    match self.0 {
+      Any::Null | Any::Undefined => visitor.visit_unit(),
+      Any::Bool(v) => visitor.visit_bool(v),
+      Any::Number(v) => visit_number(v, visitor),
+      Any::BigInt(v) => visitor.visit_i64(v),
+      Any::String(v) => visitor.visit_string(v.into_string()),
+      Any::Buffer(v) => visitor.visit_byte_buf(v.into_vec()),
+      Any::Array(values) => visitor.visit_seq(SeqDeserializer {
+        iter: values
+          .into_vec()
+          .into_iter()
+          .map(AnyDeserializer)
+          .collect::<Vec<_>>()
+          .into_iter(),
+      }),
+      Any::Map(map) => visitor.visit_map(MapDeserializer {
+        iter: (*map)
+          .into_iter()
+          .map(|(key, value)| (key, AnyDeserializer(value)))
+          .collect::<Vec<_>>()
+          .into_iter(),
+        value: None,
+      }),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    match self.0 {
+      Any::Null | Any::Undefined => visitor.visit_none(),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    match self.0 {
+      Any::String(variant) => visitor.visit_enum(EnumDeserializer::<AnyDeserializer> {
+        variant: variant.into_string(),
+        value: None,
+      }),
+      Any::Map(map) if map.len() == 1 => {
+        let (variant, value) = (*map).into_iter().next().unwrap();
+        visitor.visit_enum(EnumDeserializer {
+          variant,
+          value: Some(AnyDeserializer(value)),
+        })
+      },
+      _ => Err(invalid_enum()),
+    }
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf unit unit_struct seq tuple
+    tuple_struct map struct identifier ignored_any
+  }
+}
+
+/// The [YrsDeserializer] deserializes the value from the yrs [Value] within the transaction.
+struct YrsDeserializer<'a, R: ReadTxn> {
+  txn: &'a R,
+  value: Value,
+}
+
+impl<'de, 'a, R: ReadTxn> Deserializer<'de> for YrsDeserializer<'a, R> {
+  type Error = CollabError;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    let txn = self.txn;
+    match self.value {
+      Value::Any(any) => AnyDeserializer(any).deserialize_any(visitor),
+      Value::YText(text_ref) => visitor.visit_string(text_ref.get_string(txn)),
+      Value::YArray(array_ref) => visitor.visit_seq(SeqDeserializer {
+        iter: array_ref
+          .iter(txn)
+          .map(|value| YrsDeserializer { txn, value })
+          .collect::<Vec<_>>()
+          .into_iter(),
+      }),
+      Value::YMap(map_ref) => visitor.visit_map(MapDeserializer {
+        iter: map_ref
+          .iter(txn)
+          .map(|(key, value)| (key.to_string(), YrsDeserializer { txn, value }))
+          .collect::<Vec<_>>()
+          .into_iter(),
+        value: None,
+      }),
+      _ => Err(CollabError::YrsSerde(
+        "Deserializing the xml or sub document is not supported".to_string(),
+      )),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+    match self.value {
+      Value::Any(any) => AnyDeserializer(any).deserialize_option(visitor),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    name: &'static str,
+    variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    let txn = self.txn;
+    match self.value {
+      Value::Any(any) => AnyDeserializer(any).deserialize_enum(name, variants, visitor),
+      Value::YText(text_ref) => visitor.visit_enum(EnumDeserializer::<AnyDeserializer> {
+        variant: text_ref.get_string(txn),
+        value: None,
+      }),
+      Value::YMap(map_ref) if map_ref.len(txn) == 1 => {
+        let (variant, value) = map_ref.iter(txn).next().unwrap();
+        visitor.visit_enum(EnumDeserializer {
+          variant: variant.to_string(),
+          value: Some(YrsDeserializer { txn, value }),
+        })
+      },
+      _ => Err(invalid_enum()),
+    }
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf unit unit_struct seq tuple
+    tuple_struct map struct identifier ignored_any
+  }
+}
+
+/// The integers might be stored as [Any::Number], for example, the values that are converted
+/// from json by [Any::from_json]. So visit the number as integer if it has no fractional part.
+fn visit_number<'de, V: Visitor<'de>>(v: f64, visitor: V) -> Result<V::Value, CollabError> {
+  if v.fract() == 0.0 && v >= i64::MIN as f64 && v <= i64::MAX as f64 {
+    visitor.visit_i64(v as i64)
+  } else {
+    visitor.visit_f64(v)
+  }
+}
+
+fn invalid_enum() -> CollabError {
+  CollabError::YrsSerde("The enum must be a string or a map with a single entry".to_string())
+}
+
+struct SeqDeserializer<D> {
+  iter: IntoIter<D>,
+}
+
+impl<'de, D> SeqAccess<'de> for SeqDeserializer<D>
+where
+  D: Deserializer<'de, Error = CollabError>,
+{
+  type Error = CollabError;
+
+  fn next_element_seed<T: DeserializeSeed<'de>>(
+    &mut self,
+    seed: T,
+  ) -> Result<Option<T::Value>, Self::Error> {
+    match self.iter.next() {
+      None => Ok(None),
+      Some(deserializer) => seed.deserialize(deserializer).map(Some),
+    }
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.iter.len())
+  }
+}
+
+struct MapDeserializer<D> {
+  iter: IntoIter<(String, D)>,
+  value: Option<D>,
+}
+
+impl<'de, D> MapAccess<'de> for MapDeserializer<D>
+where
+  D: Deserializer<'de, Error = CollabError>,
+{
+  type Error = CollabError;
+
+  fn next_key_seed<K: DeserializeSeed<'de>>(
+    &mut self,
+    seed: K,
+  ) -> Result<Option<K::Value>, Self::Error> {
+    match self.iter.next() {
+      None => Ok(None),
+      Some((key, value)) => {
+        self.value = Some(value);
+        let key: StringDeserializer<CollabError> = key.into_deserializer();
+        seed.deserialize(key).map(Some)
+      },
+    }
+  }
+
+  fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+    match self.value.take() {
+      None => Err(CollabError::YrsSerde(
+        "next_value_seed called before next_key_seed".to_string(),
+      )),
+      Some(value) => seed.deserialize(value),
+    }
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.iter.len())
+  }
+}
+
+struct EnumDeserializer<D> {
+  variant: String,
+  value: Option<D>,
+}
+
+impl<'de, D> EnumAccess<'de> for EnumDeserializer<D>
+where
+  D: Deserializer<'de, Error = CollabError>,
+{
+  type Error = CollabError;
+  type Variant = VariantDeserializer<D>;
+
+  fn variant_seed<V: DeserializeSeed<'de>>(
+    self,
+    seed: V,
+  ) -> Result<(V::Value, Self::Variant), Self::Error> {
+    let variant: StringDeserializer<CollabError> = self.variant.into_deserializer();
+    let value = seed.deserialize(variant)?;
+    Ok((value, VariantDeserializer { value: self.value }))
+  }
+}
+
+struct VariantDeserializer<D> {
+  value: Option<D>,
+}
+
+impl<D> VariantDeserializer<D> {
+  fn into_value(self) -> Result<D, CollabError> {
+    self
+      .value
+      .ok_or_else(|| CollabError::YrsSerde("The enum variant has no value".to_string()))
+  }
+}
+
+impl<'de, D> VariantAccess<'de> for VariantDeserializer<D>
+where
+  D: Deserializer<'de, Error = CollabError>,
+{
+  type Error = CollabError;
+
+  fn unit_variant(self) -> Result<(), Self::Error> {
+    match self.value {
+      None => Ok(()),
+      Some(value) => <() as serde::Deserialize>::deserialize(value),
+    }
+  }
+
+  fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+    seed.deserialize(self.into_value()?)
+  }
+
+  fn tuple_variant<V: Visitor<'de>>(
+    self,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    self.into_value()?.deserialize_seq(visitor)
+  }
+
+  fn struct_variant<V: Visitor<'de>>(
+    self,
+    _fields: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    self.into_value()?.deserialize_map(visitor)
+  }
+}
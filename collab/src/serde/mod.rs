@@ -0,0 +1,12 @@
+//! Serialize and deserialize the Rust data structures into the yrs types directly, without
+//! converting them into json first.
+//!
+//! ```ignore
+//! collab.with_transact_mut(|txn| to_map_ref_with_key(txn, &map_ref, "person", &person))?;
+//! let person: Person = from_map_ref(&collab.transact(), &person_map_ref)?;
+//! ```
+mod de;
+mod ser;
+
+pub use de::*;
+pub use ser::*;
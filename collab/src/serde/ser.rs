@@ -0,0 +1,922 @@
+use std::collections::HashMap;
+
+use lib0::any::Any;
+use serde::ser::{
+  Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+  SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Serialize, Serializer};
+use yrs::{Array, ArrayPrelim, ArrayRef, Map, MapPrelim, MapRef, TransactionMut};
+
+use crate::error::CollabError;
+
+/// Serialize the value into [Any]. The structs and maps are serialized as [Any::Map] and the
+/// sequences are serialized as [Any::Array].
+pub fn to_any<T: Serialize + ?Sized>(value: &T) -> Result<Any, CollabError> {
+  value.serialize(AnySerializer)
+}
+
+/// Serialize the value into the [MapRef] with the given key. The structs and maps are inserted
+/// as [MapRef], the sequences are inserted as [ArrayRef] and the other values are inserted as
+/// [Any].
+pub fn to_map_ref_with_key<T: Serialize + ?Sized>(
+  txn: &mut TransactionMut,
+  map_ref: &MapRef,
+  key: &str,
+  value: &T,
+) -> Result<(), CollabError> {
+  value.serialize(YrsSerializer {
+    txn,
+    slot: Slot::Key(map_ref.clone(), key.to_string()),
+  })
+}
+
+/// Serialize the value into the [MapRef]. If the value is a struct or a map, its fields will be
+/// inserted into the [MapRef] directly. Otherwise, the value will be inserted with the given
+/// key. It's the same as [insert_json_value_to_map_ref](crate::preclude::insert_json_value_to_map_ref)
+/// but without converting the value into json.
+pub fn to_map_ref<T: Serialize + ?Sized>(
+  txn: &mut TransactionMut,
+  map_ref: &MapRef,
+  key: &str,
+  value: &T,
+) -> Result<(), CollabError> {
+  value.serialize(YrsSerializer {
+    txn,
+    slot: Slot::Fields(map_ref.clone(), key.to_string()),
+  })
+}
+
+/// Serialize the value and push it to the end of the [ArrayRef].
+pub fn push_to_array_ref<T: Serialize + ?Sized>(
+  txn: &mut TransactionMut,
+  array_ref: &ArrayRef,
+  value: &T,
+) -> Result<(), CollabError> {
+  let index = array_ref.len(txn);
+  to_array_ref_at_index(txn, array_ref, index, value)
+}
+
+/// Serialize the value and insert it into the [ArrayRef] at the given index.
+pub fn to_array_ref_at_index<T: Serialize + ?Sized>(
+  txn: &mut TransactionMut,
+  array_ref: &ArrayRef,
+  index: u32,
+  value: &T,
+) -> Result<(), CollabError> {
+  value.serialize(YrsSerializer {
+    txn,
+    slot: Slot::Index(array_ref.clone(), index),
+  })
+}
+
+/// The place that the [YrsSerializer] writes the value to.
+enum Slot {
+  /// Insert the value into the map with the key.
+  Key(MapRef, String),
+  /// Insert the fields of the struct or map into the map. Other values are inserted with the key.
+  Fields(MapRef, String),
+  /// Insert the value into the array at the index.
+  Index(ArrayRef, u32),
+}
+
+/// The [YrsSerializer] writes the value into the [Slot] within the transaction. Unlike
+/// [AnySerializer], the nested structs and sequences are written as [MapRef] and [ArrayRef].
+struct YrsSerializer<'a, 'doc> {
+  txn: &'a mut TransactionMut<'doc>,
+  slot: Slot,
+}
+
+impl<'a, 'doc> YrsSerializer<'a, 'doc> {
+  fn insert_any(self, any: Any) -> Result<(), CollabError> {
+    match self.slot {
+      Slot::Key(map_ref, key) | Slot::Fields(map_ref, key) => {
+        map_ref.insert(self.txn, key, any);
+      },
+      Slot::Index(array_ref, index) => {
+        array_ref.insert(self.txn, index, any);
+      },
+    }
+    Ok(())
+  }
+
+  fn insert_map(self) -> YrsMapSerializer<'a, 'doc> {
+    let map_ref = match self.slot {
+      Slot::Key(map_ref, key) => map_ref.insert(self.txn, key, MapPrelim::<Any>::new()),
+      Slot::Fields(map_ref, _) => map_ref,
+      Slot::Index(array_ref, index) => array_ref.insert(self.txn, index, MapPrelim::<Any>::new()),
+    };
+    YrsMapSerializer {
+      txn: self.txn,
+      map_ref,
+      key: None,
+    }
+  }
+
+  fn insert_array(self) -> YrsSeqSerializer<'a, 'doc> {
+    let array = ArrayPrelim::<Vec<Any>, Any>::from(vec![]);
+    let array_ref = match self.slot {
+      Slot::Key(map_ref, key) | Slot::Fields(map_ref, key) => map_ref.insert(self.txn, key, array),
+      Slot::Index(array_ref, index) => array_ref.insert(self.txn, index, array),
+    };
+    YrsSeqSerializer {
+      txn: self.txn,
+      array_ref,
+    }
+  }
+
+  /// The enum variant with value is written as a map with a single entry, which is the same as
+  /// the externally tagged representation of serde_json.
+  fn insert_variant(self, variant: &'static str) -> YrsSerializer<'a, 'doc> {
+    let map = self.insert_map();
+    YrsSerializer {
+      txn: map.txn,
+      slot: Slot::Key(map.map_ref, variant.to_string()),
+    }
+  }
+}
+
+impl<'a, 'doc> Serializer for YrsSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+  type SerializeSeq = YrsSeqSerializer<'a, 'doc>;
+  type SerializeTuple = YrsSeqSerializer<'a, 'doc>;
+  type SerializeTupleStruct = YrsSeqSerializer<'a, 'doc>;
+  type SerializeTupleVariant = YrsSeqSerializer<'a, 'doc>;
+  type SerializeMap = YrsMapSerializer<'a, 'doc>;
+  type SerializeStruct = YrsMapSerializer<'a, 'doc>;
+  type SerializeStructVariant = YrsMapSerializer<'a, 'doc>;
+
+  fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_bool(v)?)
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_i64(v)?)
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_u64(v)?)
+  }
+
+  fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_f64(v as f64)
+  }
+
+  fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_f64(v)?)
+  }
+
+  fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_char(v)?)
+  }
+
+  fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_str(v)?)
+  }
+
+  fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(AnySerializer.serialize_bytes(v)?)
+  }
+
+  fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(Any::Null)
+  }
+
+  fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(Any::Null)
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+    self.insert_any(Any::Null)
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+  ) -> Result<Self::Ok, Self::Error> {
+    self.serialize_str(variant)
+  }
+
+  fn serialize_newtype_struct<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self.insert_variant(variant))
+  }
+
+  fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+    Ok(self.insert_array())
+  }
+
+  fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+    Ok(self.insert_array())
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+    Ok(self.insert_array())
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+    Ok(self.insert_variant(variant).insert_array())
+  }
+
+  fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+    Ok(self.insert_map())
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStruct, Self::Error> {
+    Ok(self.insert_map())
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, Self::Error> {
+    Ok(self.insert_variant(variant).insert_map())
+  }
+}
+
+struct YrsSeqSerializer<'a, 'doc> {
+  txn: &'a mut TransactionMut<'doc>,
+  array_ref: ArrayRef,
+}
+
+impl<'a, 'doc> YrsSeqSerializer<'a, 'doc> {
+  fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), CollabError> {
+    push_to_array_ref(self.txn, &self.array_ref, value)
+  }
+}
+
+impl<'a, 'doc> SerializeSeq for YrsSeqSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+impl<'a, 'doc> SerializeTuple for YrsSeqSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+impl<'a, 'doc> SerializeTupleStruct for YrsSeqSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+impl<'a, 'doc> SerializeTupleVariant for YrsSeqSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+struct YrsMapSerializer<'a, 'doc> {
+  txn: &'a mut TransactionMut<'doc>,
+  map_ref: MapRef,
+  key: Option<String>,
+}
+
+impl<'a, 'doc> SerializeMap for YrsMapSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+    self.key = Some(key.serialize(MapKeySerializer)?);
+    Ok(())
+  }
+
+  fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    let key = self.key.take().ok_or_else(|| {
+      CollabError::YrsSerde("serialize_value called before serialize_key".to_string())
+    })?;
+    to_map_ref_with_key(self.txn, &self.map_ref, &key, value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+impl<'a, 'doc> SerializeStruct for YrsMapSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), Self::Error> {
+    to_map_ref_with_key(self.txn, &self.map_ref, key, value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+impl<'a, 'doc> SerializeStructVariant for YrsMapSerializer<'a, 'doc> {
+  type Ok = ();
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), Self::Error> {
+    to_map_ref_with_key(self.txn, &self.map_ref, key, value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(())
+  }
+}
+
+/// The [AnySerializer] serializes the value into [Any].
+pub struct AnySerializer;
+
+impl Serializer for AnySerializer {
+  type Ok = Any;
+  type Error = CollabError;
+  type SerializeSeq = AnySeqSerializer;
+  type SerializeTuple = AnySeqSerializer;
+  type SerializeTupleStruct = AnySeqSerializer;
+  type SerializeTupleVariant = AnySeqSerializer;
+  type SerializeMap = AnyMapSerializer;
+  type SerializeStruct = AnyMapSerializer;
+  type SerializeStructVariant = AnyMapSerializer;
+
+  fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Bool(v))
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::BigInt(v))
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_i64(v as i64)
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+    match i64::try_from(v) {
+      Ok(v) => Ok(Any::BigInt(v)),
+      Err(_) => Ok(Any::Number(v as f64)),
+    }
+  }
+
+  fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+    self.serialize_f64(v as f64)
+  }
+
+  fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Number(v))
+  }
+
+  fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::String(v.to_string().into_boxed_str()))
+  }
+
+  fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::String(v.into()))
+  }
+
+  fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Buffer(v.into()))
+  }
+
+  fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Null)
+  }
+
+  fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Null)
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+    Ok(Any::Null)
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+  ) -> Result<Self::Ok, Self::Error> {
+    self.serialize_str(variant)
+  }
+
+  fn serialize_newtype_struct<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    let mut map = HashMap::new();
+    map.insert(variant.to_string(), value.serialize(self)?);
+    Ok(Any::Map(Box::new(map)))
+  }
+
+  fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+    Ok(AnySeqSerializer {
+      variant: None,
+      values: Vec::with_capacity(len.unwrap_or_default()),
+    })
+  }
+
+  fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    len: usize,
+  ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    len: usize,
+  ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+    Ok(AnySeqSerializer {
+      variant: Some(variant),
+      values: Vec::with_capacity(len),
+    })
+  }
+
+  fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+    Ok(AnyMapSerializer {
+      variant: None,
+      map: HashMap::new(),
+      key: None,
+    })
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    len: usize,
+  ) -> Result<Self::SerializeStruct, Self::Error> {
+    self.serialize_map(Some(len))
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, Self::Error> {
+    Ok(AnyMapSerializer {
+      variant: Some(variant),
+      map: HashMap::new(),
+      key: None,
+    })
+  }
+}
+
+/// Wrap the value into a map with a single entry if the value belongs to an enum variant.
+fn wrap_variant(variant: Option<&'static str>, value: Any) -> Any {
+  match variant {
+    None => value,
+    Some(variant) => {
+      let mut map = HashMap::new();
+      map.insert(variant.to_string(), value);
+      Any::Map(Box::new(map))
+    },
+  }
+}
+
+pub struct AnySeqSerializer {
+  variant: Option<&'static str>,
+  values: Vec<Any>,
+}
+
+impl AnySeqSerializer {
+  fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), CollabError> {
+    self.values.push(value.serialize(AnySerializer)?);
+    Ok(())
+  }
+
+  fn into_any(self) -> Any {
+    wrap_variant(self.variant, Any::Array(self.values.into_boxed_slice()))
+  }
+}
+
+impl SerializeSeq for AnySeqSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+impl SerializeTuple for AnySeqSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+impl SerializeTupleStruct for AnySeqSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+impl SerializeTupleVariant for AnySeqSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+pub struct AnyMapSerializer {
+  variant: Option<&'static str>,
+  map: HashMap<String, Any>,
+  key: Option<String>,
+}
+
+impl AnyMapSerializer {
+  fn into_any(self) -> Any {
+    wrap_variant(self.variant, Any::Map(Box::new(self.map)))
+  }
+}
+
+impl SerializeMap for AnyMapSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+    self.key = Some(key.serialize(MapKeySerializer)?);
+    Ok(())
+  }
+
+  fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+    let key = self.key.take().ok_or_else(|| {
+      CollabError::YrsSerde("serialize_value called before serialize_key".to_string())
+    })?;
+    self.map.insert(key, value.serialize(AnySerializer)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+impl SerializeStruct for AnyMapSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), Self::Error> {
+    self
+      .map
+      .insert(key.to_string(), value.serialize(AnySerializer)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+impl SerializeStructVariant for AnyMapSerializer {
+  type Ok = Any;
+  type Error = CollabError;
+
+  fn serialize_field<T: ?Sized + Serialize>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), Self::Error> {
+    self
+      .map
+      .insert(key.to_string(), value.serialize(AnySerializer)?);
+    Ok(())
+  }
+
+  fn end(self) -> Result<Self::Ok, Self::Error> {
+    Ok(self.into_any())
+  }
+}
+
+/// The keys of the yrs map must be strings. Like serde_json, the numbers, chars and unit
+/// variants are converted into strings.
+struct MapKeySerializer;
+
+fn key_must_be_string() -> CollabError {
+  CollabError::YrsSerde("The key of the map must be a string".to_string())
+}
+
+impl Serializer for MapKeySerializer {
+  type Ok = String;
+  type Error = CollabError;
+  type SerializeSeq = Impossible<String, CollabError>;
+  type SerializeTuple = Impossible<String, CollabError>;
+  type SerializeTupleStruct = Impossible<String, CollabError>;
+  type SerializeTupleVariant = Impossible<String, CollabError>;
+  type SerializeMap = Impossible<String, CollabError>;
+  type SerializeStruct = Impossible<String, CollabError>;
+  type SerializeStructVariant = Impossible<String, CollabError>;
+
+  fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+    Ok(v.to_string())
+  }
+
+  fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+  ) -> Result<Self::Ok, Self::Error> {
+    Ok(variant.to_string())
+  }
+
+  fn serialize_newtype_struct<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    _variant: &'static str,
+    _value: &T,
+  ) -> Result<Self::Ok, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStruct, Self::Error> {
+    Err(key_must_be_string())
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, Self::Error> {
+    Err(key_must_be_string())
+  }
+}
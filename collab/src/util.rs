@@ -1,62 +1,160 @@
 use anyhow::Result;
 use lib0::any::Any;
 use serde_json::Value as JsonValue;
-use yrs::{Array, ArrayPrelim, ArrayRef, Map, MapPrelim, MapRef, TransactionMut};
+use yrs::{Array, ArrayPrelim, ArrayRef, Map, MapPrelim, MapRef, TextPrelim, TransactionMut};
 
+/// Configures how the json values are converted into yrs values.
+#[derive(Debug, Clone, Default)]
+pub struct JsonInsertConfig {
+  /// Store the json strings as [TextRef](yrs::TextRef) instead of [Any::String], which makes
+  /// the strings collaboratively editable. Default is [false].
+  pub string_as_text: bool,
+}
+
+impl JsonInsertConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn string_as_text(mut self, string_as_text: bool) -> Self {
+    self.string_as_text = string_as_text;
+    self
+  }
+}
+
+/// Insert the json value into the [MapRef]. If the value is an object, its fields will be
+/// inserted into the [MapRef] directly. Otherwise, the value will be inserted with the given key.
 pub fn insert_json_value_to_map_ref(
   key: &str,
   value: &JsonValue,
   map_ref: MapRef,
   txn: &mut TransactionMut,
 ) {
-  if value.is_object() {
-    value
-      .as_object()
-      .unwrap()
-      .into_iter()
-      .for_each(|(key, value)| {
-        let new_map_ref = if value.is_object() {
-          map_ref.insert(txn, key.as_str(), MapPrelim::<Any>::new());
-          map_ref
-            .get(txn, key)
-            .map(|value| value.to_ymap().unwrap())
-            .unwrap()
-        } else {
-          map_ref.clone()
-        };
-        insert_json_value_to_map_ref(key, value, new_map_ref, txn);
-      });
-  } else if value.is_array() {
-    map_ref.insert(txn, key, ArrayPrelim::<Vec<Any>, Any>::from(vec![]));
-    let array_ref = map_ref
-      .get(txn, key)
-      .map(|value| value.to_yarray().unwrap())
-      .unwrap();
-    insert_json_value_to_array_ref(txn, &array_ref, value);
-  } else {
-    match json_value_to_lib0_any(value.clone()) {
-      Ok(value) => {
-        map_ref.insert(txn, key, value);
-      },
-      Err(e) => tracing::error!("🔴{:?}", e),
-    }
+  insert_json_value_to_map_ref_with_config(key, value, map_ref, txn, &JsonInsertConfig::default())
+}
+
+pub fn insert_json_value_to_map_ref_with_config(
+  key: &str,
+  value: &JsonValue,
+  map_ref: MapRef,
+  txn: &mut TransactionMut,
+  config: &JsonInsertConfig,
+) {
+  match value {
+    JsonValue::Object(object) => {
+      for (key, value) in object {
+        insert_json_value_with_key(txn, &map_ref, key, value, config);
+      }
+    },
+    _ => insert_json_value_with_key(txn, &map_ref, key, value, config),
   }
 }
 
+/// Push the json value to the end of the [ArrayRef]. If the value is an array, each element
+/// will be pushed.
 pub fn insert_json_value_to_array_ref(
   txn: &mut TransactionMut,
   array_ref: &ArrayRef,
   value: &JsonValue,
 ) {
-  // Only support string
-  let values = value.as_array().unwrap();
-  let values = values
-    .iter()
-    .flat_map(|value| value.as_str())
-    .collect::<Vec<_>>();
+  insert_json_value_to_array_ref_with_config(txn, array_ref, value, &JsonInsertConfig::default())
+}
+
+pub fn insert_json_value_to_array_ref_with_config(
+  txn: &mut TransactionMut,
+  array_ref: &ArrayRef,
+  value: &JsonValue,
+  config: &JsonInsertConfig,
+) {
+  match value {
+    JsonValue::Array(values) => {
+      for value in values {
+        let index = array_ref.len(txn);
+        insert_json_value_at_index(txn, array_ref, index, value, config);
+      }
+    },
+    _ => {
+      let index = array_ref.len(txn);
+      insert_json_value_at_index(txn, array_ref, index, value, config);
+    },
+  }
+}
 
-  for value in values {
-    array_ref.push_back(txn, value);
+/// Insert the json value into the [MapRef] with the given key. The objects are inserted as
+/// [MapRef], the arrays are inserted as [ArrayRef] and the other values are inserted as [Any].
+pub fn insert_json_value_with_key(
+  txn: &mut TransactionMut,
+  map_ref: &MapRef,
+  key: &str,
+  value: &JsonValue,
+  config: &JsonInsertConfig,
+) {
+  match value {
+    JsonValue::Object(object) => {
+      let map_ref = map_ref.insert(txn, key, MapPrelim::<Any>::new());
+      for (key, value) in object {
+        insert_json_value_with_key(txn, &map_ref, key, value, config);
+      }
+    },
+    JsonValue::Array(values) => {
+      let array_ref = map_ref.insert(txn, key, ArrayPrelim::<Vec<Any>, Any>::from(vec![]));
+      for (index, value) in values.iter().enumerate() {
+        insert_json_value_at_index(txn, &array_ref, index as u32, value, config);
+      }
+    },
+    JsonValue::String(s) if config.string_as_text => {
+      map_ref.insert(txn, key, TextPrelim::new(s.as_str()));
+    },
+    _ => {
+      map_ref.insert(txn, key, json_scalar_to_lib0_any(value));
+    },
+  }
+}
+
+/// Insert the json value into the [ArrayRef] at the given index. The conversion is the same as
+/// [insert_json_value_with_key].
+pub fn insert_json_value_at_index(
+  txn: &mut TransactionMut,
+  array_ref: &ArrayRef,
+  index: u32,
+  value: &JsonValue,
+  config: &JsonInsertConfig,
+) {
+  match value {
+    JsonValue::Object(object) => {
+      let map_ref = array_ref.insert(txn, index, MapPrelim::<Any>::new());
+      for (key, value) in object {
+        insert_json_value_with_key(txn, &map_ref, key, value, config);
+      }
+    },
+    JsonValue::Array(values) => {
+      let child = array_ref.insert(txn, index, ArrayPrelim::<Vec<Any>, Any>::from(vec![]));
+      for (index, value) in values.iter().enumerate() {
+        insert_json_value_at_index(txn, &child, index as u32, value, config);
+      }
+    },
+    JsonValue::String(s) if config.string_as_text => {
+      array_ref.insert(txn, index, TextPrelim::new(s.as_str()));
+    },
+    _ => {
+      array_ref.insert(txn, index, json_scalar_to_lib0_any(value));
+    },
+  }
+}
+
+/// Convert the json scalar into [Any]. The integers are stored as [Any::BigInt], so they are
+/// converted back to integers instead of floats.
+fn json_scalar_to_lib0_any(value: &JsonValue) -> Any {
+  match value {
+    JsonValue::Null => Any::Null,
+    JsonValue::Bool(b) => Any::Bool(*b),
+    JsonValue::Number(number) => match number.as_i64() {
+      Some(i) => Any::BigInt(i),
+      None => Any::Number(number.as_f64().unwrap_or_default()),
+    },
+    JsonValue::String(s) => Any::String(s.clone().into_boxed_str()),
+    // The objects and arrays are converted into [MapRef] and [ArrayRef] by the callers.
+    _ => json_value_to_lib0_any(value.clone()).unwrap_or(Any::Null),
   }
 }
 
@@ -65,6 +163,17 @@ pub fn json_value_to_lib0_any(json_value: JsonValue) -> Result<Any> {
   Ok(value)
 }
 
+/// Returns the milliseconds elapsed since the unix epoch.
+pub(crate) fn timestamp_millis() -> i64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|duration| duration.as_millis() as i64)
+    .unwrap_or_default()
+}
+
+#[deprecated(
+  note = "use collab::serde::from_any to read the value without converting it into json"
+)]
 pub fn lib0_any_to_json_value(any: Any) -> Result<JsonValue> {
   let json_value = serde_json::to_value(&any)?;
   Ok(json_value)
@@ -0,0 +1,188 @@
+use collab::core::collab::Collab;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::error::CollabError;
+use collab::preclude::{
+  lib0Any, Array, ArrayPrelim, MapPrelim, MapRefExtension, ReadTxn, StateVector, Text, TextPrelim,
+  Transact, Update, YrsValue,
+};
+use yrs::updates::decoder::Decode;
+
+fn client_collab(uid: i64) -> Collab {
+  let origin = CollabOrigin::Client(CollabClient::new(uid, &uid.to_string()));
+  let collab = Collab::new_with_client(origin, "1", vec![]);
+  collab.set_author_recording(true);
+  collab.initialize();
+  collab
+}
+
+/// Apply all the changes of `from` to `to` as a remote update.
+fn sync(from: &Collab, to: &Collab) {
+  let update = from
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  let mut txn = to.get_doc().transact_mut_with(CollabOrigin::Server);
+  txn.apply_update(Update::decode_v1(&update).unwrap());
+}
+
+fn uid_of(author: &collab::preclude::Author) -> i64 {
+  author.client.as_ref().unwrap().uid
+}
+
+#[tokio::test]
+async fn blame_map_entry_test() {
+  let collab_1 = client_collab(1);
+  let collab_2 = client_collab(2);
+  collab_1.insert("title", "a");
+  collab_1.insert("views", MapPrelim::<lib0Any>::new());
+  sync(&collab_1, &collab_2);
+
+  collab_2.insert("title", "b");
+  collab_2.insert("desc", "c");
+  sync(&collab_2, &collab_1);
+
+  let authors = collab_1.get_authors();
+  assert_eq!(authors.len(), 2);
+  assert!(authors.values().any(|client| client.uid == 1));
+  assert!(authors.values().any(|client| client.uid == 2));
+
+  let blame = collab_1.blame_map(&[]).unwrap();
+  assert_eq!(blame.len(), 3);
+  assert_eq!(uid_of(&blame["title"]), 2);
+  assert_eq!(uid_of(&blame["desc"]), 2);
+  assert_eq!(uid_of(&blame["views"]), 1);
+}
+
+#[tokio::test]
+async fn blame_nested_map_entry_test() {
+  let collab_1 = client_collab(1);
+  let collab_2 = client_collab(2);
+  collab_1.insert("views", MapPrelim::<lib0Any>::new());
+  sync(&collab_1, &collab_2);
+
+  collab_2.with_transact_mut(|txn| {
+    let views = collab_2.get_map_with_txn(&*txn, vec!["views"]).unwrap();
+    views.insert_str_with_txn(txn, "1", "my view");
+  });
+  sync(&collab_2, &collab_1);
+
+  let blame = collab_1.blame_map(&["views"]).unwrap();
+  assert_eq!(uid_of(&blame["1"]), 2);
+}
+
+#[tokio::test]
+async fn blame_array_element_test() {
+  let collab_1 = client_collab(1);
+  let collab_2 = client_collab(2);
+  collab_1.insert("list", ArrayPrelim::from(vec!["a", "b"]));
+  sync(&collab_1, &collab_2);
+
+  collab_2.with_transact_mut(|txn| {
+    if let Some(YrsValue::YArray(list)) = collab_2.get_with_txn(&*txn, "list") {
+      list.push_back(txn, "c");
+    }
+  });
+  sync(&collab_2, &collab_1);
+
+  let blame = collab_1.blame_array(&["list"]).unwrap();
+  let uids = blame.iter().map(uid_of).collect::<Vec<_>>();
+  assert_eq!(uids, vec![1, 1, 2]);
+}
+
+#[tokio::test]
+async fn blame_text_run_test() {
+  let collab_1 = client_collab(1);
+  let collab_2 = client_collab(2);
+  collab_1.insert("text", TextPrelim::new("hello"));
+  sync(&collab_1, &collab_2);
+
+  collab_2.with_transact_mut(|txn| {
+    if let Some(YrsValue::YText(text)) = collab_2.get_with_txn(&*txn, "text") {
+      text.push(txn, " world");
+    }
+  });
+  sync(&collab_2, &collab_1);
+
+  let runs = collab_1.blame_text(&["text"]).unwrap();
+  assert_eq!(runs.len(), 2);
+  assert_eq!(
+    (runs[0].index, runs[0].len, uid_of(&runs[0].author)),
+    (0, 5, 1)
+  );
+  assert_eq!(
+    (runs[1].index, runs[1].len, uid_of(&runs[1].author)),
+    (5, 6, 2)
+  );
+}
+
+#[tokio::test]
+async fn blame_text_after_partial_delete_test() {
+  let collab_1 = client_collab(1);
+  let collab_2 = client_collab(2);
+  collab_1.insert("text", TextPrelim::new("héllo world"));
+  sync(&collab_1, &collab_2);
+
+  // Delete a range that starts in the middle of the item of the first client.
+  collab_2.with_transact_mut(|txn| {
+    if let Some(YrsValue::YText(text)) = collab_2.get_with_txn(&*txn, "text") {
+      text.remove_range(txn, 3, 5);
+      text.insert(txn, 3, "p! ");
+    }
+  });
+  sync(&collab_2, &collab_1);
+
+  let runs = collab_1.blame_text(&["text"]).unwrap();
+  let runs = runs
+    .iter()
+    .map(|run| (run.index, run.len, uid_of(&run.author)))
+    .collect::<Vec<_>>();
+  // The text is "hé" + "p! " + "orld", and the indexes are counted in bytes.
+  assert_eq!(runs, vec![(0, 3, 1), (3, 3, 2), (6, 4, 1)]);
+}
+
+#[tokio::test]
+async fn no_op_txn_does_not_record_author_test() {
+  let collab = client_collab(1);
+  collab.with_transact_mut(|_| {});
+  assert!(collab.get_authors().is_empty());
+  assert_eq!(collab.stats().unwrap().block_count, 0);
+
+  collab.insert("title", "a");
+  assert_eq!(collab.get_authors().len(), 1);
+}
+
+#[tokio::test]
+async fn plain_transact_mut_records_author_test() {
+  let collab = client_collab(1);
+  {
+    let mut txn = collab.transact_mut();
+    collab.insert_with_txn(&mut txn, "title", "a");
+  }
+  assert_eq!(collab.get_authors().len(), 1);
+  let blame = collab.blame_map(&[]).unwrap();
+  assert_eq!(uid_of(&blame["title"]), 1);
+}
+
+#[tokio::test]
+async fn author_recording_is_disabled_by_default_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "a");
+  assert!(collab.get_authors().is_empty());
+
+  let blame = collab.blame_map(&[]).unwrap();
+  assert_eq!(blame["title"].client, None);
+}
+
+#[tokio::test]
+async fn blame_invalid_path_test() {
+  let collab = client_collab(1);
+  collab.insert("title", "a");
+  assert!(matches!(
+    collab.blame_map(&["title"]),
+    Err(CollabError::BlamePathNotFound(_))
+  ));
+  assert!(matches!(
+    collab.blame_array(&["not_exist"]),
+    Err(CollabError::BlamePathNotFound(_))
+  ));
+}
@@ -0,0 +1,83 @@
+use collab::core::branch::MergeChange;
+use collab::core::collab::CollabBuilder;
+use serde_json::json;
+
+#[test]
+fn merge_branch_into_parent_test() {
+  let parent = CollabBuilder::new(1, "1").build();
+  parent.initial();
+  parent.lock().insert("title", "hello");
+  parent.lock().insert("desc", "my document");
+
+  let branch = CollabBuilder::new(1, "1_draft")
+    .build_branch(&parent.lock())
+    .unwrap();
+  branch.collab().initial();
+  assert_eq!(branch.meta().parent_object_id, "1");
+  assert_eq!(branch.collab().to_json_value(), parent.to_json_value());
+
+  // The changes on the branch are isolated from the parent
+  branch.collab().lock().insert("title", "hello world");
+  branch.collab().lock().remove("desc");
+  assert_eq!(
+    parent.to_json_value(),
+    json!({ "title": "hello", "desc": "my document" })
+  );
+
+  let preview = branch.preview_merge(&parent.lock()).unwrap();
+  assert_eq!(preview.after, json!({ "title": "hello world" }));
+  assert_eq!(
+    preview.changes,
+    vec![
+      MergeChange::Removed(vec!["desc".to_string()]),
+      MergeChange::Modified(vec!["title".to_string()]),
+    ]
+  );
+
+  branch.merge_into(&parent.lock()).unwrap();
+  assert_eq!(parent.to_json_value(), json!({ "title": "hello world" }));
+}
+
+#[test]
+fn merge_branch_with_concurrent_parent_changes_test() {
+  let parent = CollabBuilder::new(1, "1").build();
+  parent.initial();
+  parent.lock().insert("title", "hello");
+
+  let branch = CollabBuilder::new(1, "1_draft")
+    .build_branch(&parent.lock())
+    .unwrap();
+  branch.collab().initial();
+  branch.collab().lock().insert("desc", "draft");
+  parent.lock().insert("cover", "image");
+
+  branch.merge_into(&parent.lock()).unwrap();
+  assert_eq!(
+    parent.to_json_value(),
+    json!({ "title": "hello", "desc": "draft", "cover": "image" })
+  );
+
+  // Merge again only applies the new changes
+  assert!(branch
+    .preview_merge(&parent.lock())
+    .unwrap()
+    .changes
+    .is_empty());
+  branch.collab().lock().insert("desc", "final");
+  branch.merge_into(&parent.lock()).unwrap();
+  assert_eq!(parent.to_json_value()["desc"], json!("final"));
+}
+
+#[test]
+fn merge_branch_into_other_collab_test() {
+  let parent = CollabBuilder::new(1, "1").build();
+  parent.initial();
+  let other = CollabBuilder::new(1, "2").build();
+  other.initial();
+
+  let branch = CollabBuilder::new(1, "1_draft")
+    .build_branch(&parent.lock())
+    .unwrap();
+  assert!(branch.preview_merge(&other.lock()).is_err());
+  assert!(branch.merge_into(&other.lock()).is_err());
+}
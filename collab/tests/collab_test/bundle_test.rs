@@ -0,0 +1,91 @@
+use collab::core::bundle::{BundleOptions, CollabBundle};
+use collab::core::collab::Collab;
+use collab::error::CollabError;
+use collab::preclude::EncoderVersion;
+use serde_json::json;
+
+#[test]
+fn export_and_import_bundle_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  let snapshot = collab.snapshot();
+  collab.insert("title", "hello world");
+
+  let data = collab
+    .export_bundle(
+      BundleOptions::new()
+        .collab_type("document")
+        .snapshots(vec![snapshot]),
+    )
+    .unwrap();
+
+  let other = Collab::new(2, "1", vec![]);
+  other.initialize();
+  let bundle = other.import_bundle(&data).unwrap();
+  assert_eq!(bundle.header.object_id, "1");
+  assert_eq!(bundle.header.collab_type, "document");
+  assert_eq!(other.to_json_value(), json!({ "title": "hello world" }));
+
+  let snapshots = bundle.decode_snapshots().unwrap();
+  assert_eq!(snapshots.len(), 1);
+  assert_eq!(
+    other.to_json_value_at(&snapshots[0]).unwrap(),
+    json!({ "title": "hello" })
+  );
+}
+
+#[test]
+fn export_v2_bundle_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello world");
+  let data = collab
+    .export_bundle(BundleOptions::new().encoder_version(EncoderVersion::V2))
+    .unwrap();
+  let bundle = CollabBundle::decode(&data).unwrap();
+  assert_eq!(bundle.header.encoder_version, EncoderVersion::V2);
+
+  let other = Collab::new(2, "1", vec![]);
+  other.initialize();
+  other.import_bundle(&data).unwrap();
+  assert_eq!(other.to_json_value(), json!({ "title": "hello world" }));
+}
+
+#[test]
+fn import_corrupted_bundle_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello world");
+  let mut data = collab.export_bundle(BundleOptions::new()).unwrap();
+  let index = data.len() / 2;
+  data[index] ^= 0xff;
+
+  let other = Collab::new(2, "1", vec![]);
+  other.initialize();
+  assert!(matches!(
+    other.import_bundle(&data),
+    Err(CollabError::BundleChecksumMismatch)
+  ));
+  assert!(matches!(
+    other.import_bundle(b"not a bundle"),
+    Err(CollabError::InvalidBundle(_))
+  ));
+  assert_eq!(other.to_json_value(), json!({}));
+}
+
+#[test]
+fn import_bundle_of_other_object_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello world");
+  let data = collab.export_bundle(BundleOptions::new()).unwrap();
+
+  let other = Collab::new(1, "2", vec![]);
+  other.initialize();
+  assert!(matches!(
+    other.import_bundle(&data),
+    Err(CollabError::BundleObjectMismatch { .. })
+  ));
+  assert_eq!(other.to_json_value(), json!({}));
+}
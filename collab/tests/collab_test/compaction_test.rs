@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use collab::core::collab::{Collab, CollabBuilder, MutexCollab};
+use collab::error::CollabError;
+use collab::preclude::{
+  CollabPlugin, CompactionPoint, Doc, ReadTxn, StateVector, Transact, Update,
+};
+use parking_lot::Mutex;
+use serde_json::json;
+use yrs::updates::decoder::Decode;
+
+#[derive(Default)]
+struct CompactedStatePlugin {
+  state: Mutex<Option<Vec<u8>>>,
+  point: Mutex<Option<CompactionPoint>>,
+}
+
+impl CompactedStatePlugin {
+  fn open(&self) -> MutexCollab {
+    let state = self.state.lock().clone().unwrap();
+    let collab = CollabBuilder::new(1, "1").build();
+    collab.lock().with_transact_mut(|txn| {
+      txn.apply_update(Update::decode_v1(&state).unwrap());
+    });
+    collab
+  }
+}
+
+impl CollabPlugin for CompactedStatePlugin {
+  fn did_compact(&self, _object_id: &str, compacted_doc: &Doc, point: &CompactionPoint) {
+    let state = compacted_doc
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default());
+    *self.state.lock() = Some(state);
+    *self.point.lock() = Some(point.clone());
+  }
+
+  fn get_compaction_point(&self, _object_id: &str) -> Option<CompactionPoint> {
+    self.point.lock().clone()
+  }
+}
+
+#[test]
+fn compact_discards_deleted_items_test() {
+  let plugin = Arc::new(CompactedStatePlugin::default());
+  let collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  for i in 0..10 {
+    collab.insert("title", "a".repeat(100 + i).as_str());
+  }
+  let snapshot = collab.snapshot();
+  let result = collab.compact(&snapshot).unwrap();
+  assert!(result.size_after < result.size_before);
+  assert_eq!(collab.get_compaction_point(), Some(result.point));
+  assert_eq!(collab.to_json_value(), json!({ "title": "a".repeat(109) }));
+  assert_eq!(
+    plugin.state.lock().as_ref().map(|state| state.len()),
+    Some(result.size_after)
+  );
+
+  let compacted = plugin.open();
+  assert_eq!(
+    compacted.lock().to_json_value(),
+    json!({ "title": "a".repeat(109) })
+  );
+}
+
+#[test]
+fn compact_keeps_history_after_snapshot_test() {
+  let plugin = Arc::new(CompactedStatePlugin::default());
+  let mut collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  collab.insert("title", "hello world");
+  let compaction_snapshot = collab.snapshot();
+
+  collab.insert("desc", "my document");
+  let recent_snapshot = collab.snapshot();
+  collab.remove("desc");
+  collab.compact(&compaction_snapshot).unwrap();
+
+  let compacted = plugin.open();
+  assert_eq!(
+    compacted.lock().to_json_value(),
+    json!({ "title": "hello world" })
+  );
+  assert_eq!(
+    compacted.lock().to_json_value_at(&recent_snapshot).unwrap(),
+    json!({ "title": "hello world", "desc": "my document" })
+  );
+  assert_eq!(
+    collab.to_json_value_at(&recent_snapshot).unwrap(),
+    json!({ "title": "hello world", "desc": "my document" })
+  );
+}
+
+#[test]
+fn compact_keeps_entry_set_again_after_snapshot_test() {
+  let plugin = Arc::new(CompactedStatePlugin::default());
+  let mut collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  collab.insert("desc", "my document");
+  collab.remove("desc");
+  let snapshot = collab.snapshot();
+
+  // The new entry refers to the removed one, which was deleted before the snapshot.
+  collab.insert("desc", "my new document");
+  collab.compact(&snapshot).unwrap();
+
+  let expected = json!({ "title": "hello", "desc": "my new document" });
+  assert_eq!(collab.to_json_value(), expected);
+  let compacted = plugin.open();
+  assert_eq!(compacted.lock().to_json_value(), expected);
+}
+
+#[test]
+fn compaction_point_is_restored_on_reopen_test() {
+  let plugin = Arc::new(CompactedStatePlugin::default());
+  let collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  let old_state_vector = collab.transact().state_vector();
+  collab.insert("title", "hello world");
+  let snapshot = collab.snapshot();
+  let result = collab.compact(&snapshot).unwrap();
+  drop(collab);
+
+  let reopened = Collab::new(1, "1", vec![plugin]);
+  assert_eq!(reopened.get_compaction_point(), None);
+  reopened.initialize();
+  assert_eq!(reopened.get_compaction_point(), Some(result.point));
+  assert!(reopened.is_behind_compaction_point(&old_state_vector));
+}
+
+#[test]
+fn peer_behind_compaction_point_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  let old_state_vector = collab.transact().state_vector();
+  assert!(!collab.is_behind_compaction_point(&StateVector::default()));
+
+  collab.insert("title", "hello world");
+  let snapshot = collab.snapshot();
+  collab.compact(&snapshot).unwrap();
+
+  let state_vector = collab.transact().state_vector();
+  assert!(!collab.is_behind_compaction_point(&state_vector));
+  assert!(collab.is_behind_compaction_point(&old_state_vector));
+  assert!(collab.is_behind_compaction_point(&StateVector::default()));
+}
+
+#[test]
+fn compact_read_only_collab_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello");
+  collab.set_read_only(true);
+  let snapshot = collab.snapshot();
+  assert!(matches!(
+    collab.compact(&snapshot),
+    Err(CollabError::ReadOnly)
+  ));
+  assert_eq!(collab.get_compaction_point(), None);
+}
@@ -0,0 +1,58 @@
+use collab::core::collab::Collab;
+use collab::core::encoding::EncoderVersion;
+use collab::preclude::{ReadTxn, StateVector};
+use serde_json::json;
+
+#[test]
+fn encoder_version_u8_test() {
+  for version in [EncoderVersion::V1, EncoderVersion::V2] {
+    assert_eq!(EncoderVersion::from_u8(version.to_u8()), Some(version));
+  }
+  assert_eq!(EncoderVersion::from_u8(0), None);
+  assert_eq!(EncoderVersion::default(), EncoderVersion::V1);
+}
+
+#[test]
+fn convert_update_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  collab.insert("title", "hello world");
+
+  let v1 = EncoderVersion::V1.encode_state_as_update(&collab.transact(), &StateVector::default());
+  let v2 = EncoderVersion::V1
+    .convert_update(&v1, EncoderVersion::V2)
+    .unwrap();
+  assert_ne!(v1, v2);
+
+  let other = Collab::new(1, "1", vec![]);
+  other.initialize();
+  other.with_transact_mut(|txn| {
+    txn.apply_update(EncoderVersion::V2.decode_update(&v2).unwrap());
+  });
+  assert_eq!(other.to_json_value(), json!({ "title": "hello world" }));
+}
+
+#[test]
+fn merge_v2_updates_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let sv = collab.transact().state_vector();
+  collab.insert("title", "hello");
+  let first = EncoderVersion::V2.encode_state_as_update(&collab.transact(), &sv);
+  let sv = collab.transact().state_vector();
+  collab.insert("desc", "world");
+  let second = EncoderVersion::V2.encode_state_as_update(&collab.transact(), &sv);
+
+  let merged = EncoderVersion::V2
+    .merge_updates(&[first.as_slice(), second.as_slice()])
+    .unwrap();
+  let other = Collab::new(1, "1", vec![]);
+  other.initialize();
+  other.with_transact_mut(|txn| {
+    txn.apply_update(EncoderVersion::V2.decode_update(&merged).unwrap());
+  });
+  assert_eq!(
+    other.to_json_value(),
+    json!({ "title": "hello", "desc": "world" })
+  );
+}
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use collab::core::collab::MutexCollab;
 use collab::core::origin::CollabOrigin;
-use collab::core::transaction::TransactionRetry;
+use collab::core::transaction::{TransactionRetry, TransactionRetryConfig};
 use collab::error::CollabError;
 use collab::preclude::{Collab, MapRefWrapper};
-use yrs::{Map, Observable};
+use yrs::{Map, Observable, Transact};
 
 use crate::helper::{setup_log, Person, Position};
 
@@ -210,3 +211,67 @@ async fn undo_second_insert_text() {
 
   assert!(!collab.can_undo());
 }
+
+#[tokio::test]
+async fn async_write_txn_yields_until_released_test() {
+  let collab = MutexCollab::new(CollabOrigin::Empty, "1", vec![]);
+  collab.initial();
+  let doc = collab.lock().get_doc().clone();
+  let data = doc.get_or_insert_map("data");
+  let (tx, rx) = tokio::sync::oneshot::channel();
+  let handle = tokio::task::spawn_blocking(move || {
+    let txn = doc.transact_mut();
+    tx.send(()).unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    drop(txn);
+  });
+  rx.await.unwrap();
+
+  // The lock of the collab is not held while waiting for the transaction.
+  let wait_txn = collab.with_transact_mut_async(|txn| {
+    data.insert(txn, "name", "nathan");
+  });
+  let check_lock = async {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(collab.try_lock().is_some());
+  };
+  let (result, _) = tokio::join!(wait_txn, check_lock);
+  result.unwrap();
+  handle.await.unwrap();
+
+  let collab = collab.lock();
+  let metrics = collab.transaction_metrics();
+  assert!(metrics.contended >= 1);
+  assert!(metrics.retries >= 1);
+  assert_eq!(metrics.timeouts, 0);
+  assert_eq!(
+    collab.get("name").unwrap().to_string(&collab.transact()),
+    "nathan"
+  );
+}
+
+#[tokio::test]
+async fn async_write_txn_timeout_test() {
+  let collab = MutexCollab::new(CollabOrigin::Empty, "1", vec![]);
+  collab.lock().set_transaction_retry_config(
+    TransactionRetryConfig::new()
+      .timeout(Duration::from_millis(100))
+      .retry_interval(Duration::from_millis(10)),
+  );
+  let doc = collab.lock().get_doc().clone();
+  let (tx, rx) = tokio::sync::oneshot::channel();
+  let handle = tokio::task::spawn_blocking(move || {
+    let txn = doc.transact_mut();
+    tx.send(()).unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+    drop(txn);
+  });
+  rx.await.unwrap();
+
+  let result = collab.with_transact_mut_async(|_| ()).await;
+  assert!(matches!(result, Err(CollabError::AcquiredWriteTxnFail)));
+  assert_eq!(collab.lock().transaction_metrics().timeouts, 1);
+  handle.await.unwrap();
+
+  assert!(collab.with_transact_async(|_| ()).await.is_ok());
+}
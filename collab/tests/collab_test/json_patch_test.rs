@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use collab::preclude::{Collab, JsonPatch, PatchOperation};
+use parking_lot::Mutex;
+use serde_json::json;
+
+fn patch(value: serde_json::Value) -> JsonPatch {
+  JsonPatch::from_json_value(value).unwrap()
+}
+
+#[tokio::test]
+async fn apply_json_patch_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab
+    .apply_json_patch(&patch(json!([
+      { "op": "add", "path": "/person", "value": { "name": "nathan", "tags": ["a", "b"] } },
+      { "op": "add", "path": "/person/level", "value": 3 },
+      { "op": "replace", "path": "/person/name", "value": "lucas" },
+      { "op": "add", "path": "/person/tags/1", "value": { "id": 1 } },
+      { "op": "add", "path": "/person/tags/-", "value": "c" },
+      { "op": "remove", "path": "/person/tags/0" },
+      { "op": "copy", "from": "/person/name", "path": "/owner" },
+      { "op": "move", "from": "/person/level", "path": "/level" },
+      { "op": "test", "path": "/level", "value": 3 },
+    ])))
+    .unwrap();
+
+  assert_eq!(
+    collab.to_json_value(),
+    json!({
+      "person": { "name": "lucas", "tags": [{ "id": 1 }, "b", "c"] },
+      "owner": "lucas",
+      "level": 3,
+    })
+  );
+}
+
+#[tokio::test]
+async fn apply_json_patch_with_escaped_path_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab
+    .apply_json_patch(&patch(json!([
+      { "op": "add", "path": "/a~1b", "value": 1 },
+      { "op": "add", "path": "/c~0d", "value": 2 },
+    ])))
+    .unwrap();
+  assert_eq!(collab.to_json_value(), json!({ "a/b": 1, "c~d": 2 }));
+}
+
+#[tokio::test]
+async fn apply_failed_json_patch_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.insert("name", "nathan");
+
+  // The patch is applied atomically, so the first operation is not applied.
+  for value in [
+    json!([
+      { "op": "add", "path": "/level", "value": 3 },
+      { "op": "test", "path": "/name", "value": "lucas" },
+    ]),
+    json!([
+      { "op": "add", "path": "/level", "value": 3 },
+      { "op": "remove", "path": "/not_exist" },
+    ]),
+    json!([
+      { "op": "add", "path": "/level", "value": 3 },
+      { "op": "add", "path": "/a/b", "value": 1 },
+    ]),
+    json!([{ "op": "replace", "path": "", "value": [1, 2] }]),
+    json!([{ "op": "add", "path": "name", "value": 1 }]),
+  ] {
+    assert!(collab.apply_json_patch(&patch(value)).is_err());
+    assert_eq!(collab.to_json_value(), json!({ "name": "nathan" }));
+  }
+}
+
+#[tokio::test]
+async fn json_patch_serde_test() {
+  let value = json!([
+    { "op": "add", "path": "/name", "value": "nathan" },
+    { "op": "move", "from": "/name", "path": "/owner" },
+  ]);
+  let json_patch = patch(value.clone());
+  assert_eq!(
+    json_patch.0,
+    vec![
+      PatchOperation::Add {
+        path: "/name".to_string(),
+        value: json!("nathan"),
+      },
+      PatchOperation::Move {
+        from: "/name".to_string(),
+        path: "/owner".to_string(),
+      },
+    ]
+  );
+  assert_eq!(json_patch.to_json_value(), value);
+}
+
+#[tokio::test]
+async fn observe_json_patch_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab
+    .apply_json_patch(&patch(json!([
+      { "op": "add", "path": "/person", "value": { "name": "nathan", "tags": ["a", "b"] } },
+      { "op": "add", "path": "/level", "value": 1 },
+    ])))
+    .unwrap();
+
+  let patches = Arc::new(Mutex::new(vec![]));
+  let cloned_patches = patches.clone();
+  let _subscription = collab.observe_json_patch(move |_, patch| {
+    cloned_patches.lock().push(patch.clone());
+  });
+
+  let before = collab.to_json_value();
+  collab
+    .apply_json_patch(&patch(json!([
+      { "op": "replace", "path": "/person/name", "value": "lucas" },
+      { "op": "add", "path": "/person/tags/1", "value": "c" },
+      { "op": "remove", "path": "/person/tags/0" },
+      { "op": "remove", "path": "/level" },
+      { "op": "add", "path": "/owner", "value": { "id": 1 } },
+    ])))
+    .unwrap();
+  collab.insert("title", "hello world");
+
+  // Replay the observed patches on the json value before changing
+  let patches = patches.lock();
+  assert_eq!(patches.len(), 2);
+  let mut json_value = before;
+  for patch in patches.iter() {
+    patch.apply_to_json_value(&mut json_value).unwrap();
+  }
+  assert_eq!(json_value, collab.to_json_value());
+  assert_eq!(
+    json_value,
+    json!({
+      "person": { "name": "lucas", "tags": ["c", "b"] },
+      "owner": { "id": 1 },
+      "title": "hello world",
+    })
+  );
+}
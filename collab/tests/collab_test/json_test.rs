@@ -0,0 +1,87 @@
+use collab::preclude::{
+  insert_json_value_to_map_ref_with_config, lib0Any, Array, Collab, JsonInsertConfig, Map,
+  MapRefWrapper, YrsValue,
+};
+use serde_json::json;
+
+fn test_json_value() -> serde_json::Value {
+  json!({
+    "name": "nathan",
+    "level": 3,
+    "score": -1.5,
+    "is_admin": false,
+    "avatar": null,
+    "tags": ["a", 1, 2.5, true, null],
+    "tasks": [
+      { "id": 1, "title": "write tests", "sub_tasks": [{ "id": 2, "done": true }] },
+      { "id": 3, "title": "review", "sub_tasks": [] }
+    ],
+    "matrix": [[1, 2], [3, [4, "5"]], []],
+    "position": { "title": "developer", "history": [{ "title": "intern" }] }
+  })
+}
+
+#[tokio::test]
+async fn insert_json_round_trip_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.insert_json_with_path(vec![], "person", test_json_value());
+  assert_eq!(collab.to_json_value()["person"], test_json_value());
+}
+
+#[tokio::test]
+async fn map_ref_insert_json_round_trip_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let map_ref = collab.with_transact_mut(|txn| collab.insert_map_with_txn(txn, "person"));
+  map_ref.insert_json("person", test_json_value());
+  assert_eq!(map_ref.to_json_value(), test_json_value());
+}
+
+#[tokio::test]
+async fn array_ref_push_json_round_trip_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.with_transact_mut(|txn| {
+    let array_ref = collab.create_array_with_txn::<lib0Any>(txn, "items", vec![]);
+    array_ref
+      .push_json_with_txn(txn, json!([1, "a", [true, null], { "id": 1 }]))
+      .unwrap();
+    array_ref
+      .push_json_with_txn(txn, json!({ "id": 2, "tags": ["b"] }))
+      .unwrap();
+  });
+  assert_eq!(
+    collab.to_json_value(),
+    json!({ "items": [1, "a", [true, null], { "id": 1 }, { "id": 2, "tags": ["b"] }] })
+  );
+}
+
+#[tokio::test]
+async fn insert_json_with_string_as_text_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let map_ref: MapRefWrapper =
+    collab.with_transact_mut(|txn| collab.insert_map_with_txn(txn, "document"));
+  let value = json!({ "title": "hello", "blocks": [{ "text": "world" }] });
+  map_ref.with_transact_mut(|txn| {
+    insert_json_value_to_map_ref_with_config(
+      "document",
+      &value,
+      map_ref.clone().into_inner(),
+      txn,
+      &JsonInsertConfig::new().string_as_text(true),
+    )
+  });
+
+  assert_eq!(map_ref.to_json_value(), value);
+  let txn = collab.transact();
+  assert!(matches!(
+    map_ref.get(&txn, "title"),
+    Some(YrsValue::YText(_))
+  ));
+  let block = map_ref
+    .get_array_ref_with_txn(&txn, "blocks")
+    .unwrap()
+    .get(&txn, 0)
+    .unwrap()
+    .to_ymap()
+    .unwrap();
+  assert!(matches!(block.get(&txn, "text"), Some(YrsValue::YText(_))));
+}
@@ -1,5 +1,23 @@
+mod blame_test;
+mod branch_test;
+mod bundle_test;
+mod compaction_test;
+mod encoding_test;
 mod helper;
 mod insert_test;
+mod json_patch_test;
+mod json_test;
+mod permission_test;
+mod plain_text_test;
+mod plugin_test;
+mod presence_test;
 mod restore_test;
+mod schema_test;
+mod serde_test;
+mod snapshot_view_test;
+mod state_test;
+mod stats_test;
 mod struct_define;
+mod text_delta_test;
+mod undo_test;
 mod update_test;
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use collab::core::collab::{Collab, CollabBuilder};
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::error::CollabError;
+use collab::preclude::{
+  lib0Any, JsonPatch, JsonValue, MapPrelim, MapRefExtension, MapRefWrapper, PermissionPolicy,
+  Transact,
+};
+use serde_json::json;
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+use crate::helper::CollabStateCachePlugin;
+
+#[tokio::test]
+async fn read_only_reject_local_txn_test() {
+  let mutex_collab = CollabBuilder::new(1, "1").with_read_only(true).build();
+  let context = {
+    let collab = mutex_collab.lock();
+    collab.initialize();
+    assert!(collab.is_read_only());
+
+    let result = collab.try_with_transact_mut(|txn| collab.insert_with_txn(txn, "1", "a"));
+    assert!(matches!(result, Err(CollabError::ReadOnly)));
+    let context = collab.context();
+    let result = context.try_with_transact_mut(|txn| collab.insert_with_txn(txn, "1", "a"));
+    assert!(matches!(result, Err(CollabError::ReadOnly)));
+    context
+  };
+  assert!(matches!(
+    context.transact_mut_async().await,
+    Err(CollabError::ReadOnly)
+  ));
+
+  let collab = mutex_collab.lock();
+  assert!(collab.get("1").is_none());
+
+  collab.set_read_only(false);
+  collab
+    .try_with_transact_mut(|txn| collab.insert_with_txn(txn, "1", "a"))
+    .unwrap();
+  assert_eq!(collab.to_json_value(), json!({ "1": "a" }));
+}
+
+#[tokio::test]
+async fn read_only_reject_async_txn_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.set_read_only(true);
+  collab.initialize();
+  assert!(matches!(
+    collab.transact_mut_async().await,
+    Err(CollabError::ReadOnly)
+  ));
+
+  collab.set_read_only(false);
+  let mut txn = collab.transact_mut_async().await.unwrap();
+  collab.insert_with_txn(&mut txn, "1", "a");
+  drop(txn);
+  assert_eq!(collab.to_json_value(), json!({ "1": "a" }));
+}
+
+#[tokio::test]
+async fn read_only_apply_remote_update_test() {
+  let remote_collab = Collab::new(2, "1", vec![]);
+  remote_collab.insert("1", "a");
+  let update = remote_collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+
+  let plugin = CollabStateCachePlugin::new();
+  let collab = Collab::new(1, "1", vec![Arc::new(plugin.clone())]);
+  collab.set_read_only(true);
+  collab.initialize();
+  let mut rx = collab.subscribe_permission_violation();
+
+  // The local change is reported and reverted.
+  collab.insert("2", "b");
+  let violation = rx.try_recv().unwrap();
+  assert_eq!(
+    violation.origin,
+    CollabOrigin::Client(CollabClient::new(1, ""))
+  );
+  assert_eq!(violation.paths, vec!["/2".to_string()]);
+  assert_eq!(collab.to_json_value(), json!({}));
+  assert_eq!(restore(&plugin), json!({}));
+
+  {
+    let mut txn = collab.get_doc().transact_mut_with(CollabOrigin::Server);
+    txn.apply_update(Update::decode_v1(&update).unwrap());
+  }
+  assert_eq!(collab.to_json_value(), json!({ "1": "a" }));
+  assert_eq!(restore(&plugin), json!({ "1": "a" }));
+  assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn permission_policy_violation_test() {
+  let collab = CollabBuilder::new(1, "1")
+    .with_permission_policy(PermissionPolicy::new().deny("/workspaces"))
+    .build();
+  let collab = collab.lock();
+  collab.initialize();
+  let mut rx = collab.subscribe_permission_violation();
+
+  collab.insert("views", MapPrelim::<lib0Any>::new());
+  assert!(rx.try_recv().is_err());
+
+  collab.insert("workspaces", MapPrelim::<lib0Any>::new());
+  let violation = rx.try_recv().unwrap();
+  assert_eq!(violation.paths, vec!["/workspaces".to_string()]);
+  assert_eq!(collab.to_json_value(), json!({ "views": {} }));
+
+  // The json patch is rejected before changing anything.
+  let patch = JsonPatch::from_json_value(json!([
+    { "op": "add", "path": "/workspaces", "value": {} }
+  ]))
+  .unwrap();
+  assert!(matches!(
+    collab.apply_json_patch(&patch),
+    Err(CollabError::PermissionDenied(paths)) if paths == vec!["/workspaces".to_string()]
+  ));
+  assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn denied_change_is_reverted_on_the_plugins_test() {
+  let plugin = CollabStateCachePlugin::new();
+  let collab = Collab::new(1, "1", vec![Arc::new(plugin.clone())]);
+  collab.initialize();
+  collab.insert("views", MapPrelim::<lib0Any>::new());
+  collab.insert("workspaces", MapPrelim::<lib0Any>::new());
+  let workspaces = collab
+    .get_map_with_path::<MapRefWrapper>(vec!["workspaces".to_string()])
+    .unwrap();
+  let views = collab
+    .get_map_with_path::<MapRefWrapper>(vec!["views".to_string()])
+    .unwrap();
+
+  collab.set_permission_policy(Some(PermissionPolicy::new().deny("/workspaces")));
+  let mut rx = collab.subscribe_permission_violation();
+
+  // The wrapper can't reject the change, so it's reverted after the transaction.
+  workspaces.insert("1", "my workspace");
+  let violation = rx.try_recv().unwrap();
+  assert_eq!(violation.paths, vec!["/workspaces/1".to_string()]);
+
+  // The allowed change is kept.
+  views.insert("1", "my view");
+  assert!(rx.try_recv().is_err());
+
+  // The change of a plain transaction is reverted when the next transaction is acquired.
+  {
+    let mut txn = collab.transact_mut();
+    workspaces.insert_with_txn(&mut txn, "2", "my workspace");
+  }
+  collab.with_transact_mut(|_| {});
+  assert_eq!(
+    rx.try_recv().unwrap().paths,
+    vec!["/workspaces/2".to_string()]
+  );
+
+  let expected = json!({ "views": { "1": "my view" }, "workspaces": {} });
+  assert_eq!(collab.to_json_value(), expected);
+  // The plugins received the denied changes and their reverts.
+  assert_eq!(restore(&plugin), expected);
+}
+
+/// Returns the json value of the document that is restored from the updates of the plugin.
+fn restore(plugin: &CollabStateCachePlugin) -> JsonValue {
+  let collab = CollabBuilder::new(1, "1").build_with_updates(plugin.get_updates().unwrap());
+  let json = collab.lock().to_json_value();
+  json
+}
+
+#[test]
+fn permission_policy_rule_test() {
+  let policy = PermissionPolicy::new()
+    .deny_by_default()
+    .allow("/views")
+    .deny("/views/private");
+  // Replacing the views would change the denied private views.
+  assert!(!policy.is_allowed("/views"));
+  assert!(policy.is_allowed("/views/1/name"));
+  assert!(!policy.is_allowed("/views/private"));
+  assert!(!policy.is_allowed("/views/private/name"));
+  assert!(!policy.is_allowed("/views_meta"));
+  assert!(!policy.is_allowed("/workspaces"));
+  // Replacing the whole data section would change the denied paths.
+  assert!(!PermissionPolicy::new().deny("/workspaces").is_allowed(""));
+}
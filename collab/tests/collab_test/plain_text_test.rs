@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use collab::core::plain_text::PlainTextExtractor;
+use collab::preclude::*;
+
+use crate::helper::{Person, Position};
+
+#[tokio::test]
+async fn default_plain_text_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.insert("title", "my document");
+  collab.insert_json_with_path(
+    vec![],
+    "person",
+    Person {
+      name: "nathan".to_string(),
+      position: Position {
+        title: "developer".to_string(),
+        level: 3,
+      },
+    },
+  );
+  collab.with_transact_mut(|txn| {
+    let text = collab.insert_with_txn(txn, "text", TextPrelim::new(""));
+    text.insert(txn, 0, "hello world");
+  });
+
+  assert_eq!(
+    collab.to_plain_text(),
+    "name: nathan\nlevel: 3\ntitle: developer\nhello world\ntitle: my document"
+  );
+}
+
+#[tokio::test]
+async fn custom_plain_text_extractor_test() {
+  struct TitleExtractor;
+  impl PlainTextExtractor for TitleExtractor {
+    fn extract(&self, txn: &Transaction, data: &MapRef) -> Option<String> {
+      data.get_str_with_txn(txn, "title")
+    }
+  }
+
+  let collab = Collab::new(1, "1", vec![]);
+  collab.set_plain_text_extractor(Arc::new(TitleExtractor));
+  collab.insert("description", "hello world");
+  // The extractor can't handle the data, fallback to the default extractor
+  assert_eq!(collab.to_plain_text(), "description: hello world");
+
+  collab.insert("title", "my document");
+  assert_eq!(collab.to_plain_text(), "my document");
+}
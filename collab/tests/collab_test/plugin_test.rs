@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use collab::core::collab_state::{CollabState, CollabStateChange};
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, CollabPlugin};
+use parking_lot::Mutex;
+use y_sync::awareness::{Awareness, Event as AwarenessEvent};
+use yrs::{Doc, TransactionMut};
+
+type CallLog = Arc<Mutex<Vec<String>>>;
+
+struct LifecyclePlugin {
+  name: String,
+  priority: i32,
+  log: CallLog,
+}
+
+impl LifecyclePlugin {
+  fn new(name: &str, priority: i32, log: CallLog) -> Arc<Self> {
+    Arc::new(Self {
+      name: name.to_string(),
+      priority,
+      log,
+    })
+  }
+}
+
+impl CollabPlugin for LifecyclePlugin {
+  fn plugin_id(&self) -> String {
+    self.name.clone()
+  }
+
+  fn priority(&self) -> i32 {
+    self.priority
+  }
+
+  fn init(&self, _object_id: &str, _txn: &mut TransactionMut) {
+    self.log.lock().push(format!("{}:init", self.name));
+  }
+
+  fn flush(&self, _object_id: &str, _doc: &Doc) {
+    self.log.lock().push(format!("{}:flush", self.name));
+  }
+
+  fn will_close(&self, _object_id: &str) {
+    self.log.lock().push(format!("{}:will_close", self.name));
+  }
+}
+
+#[tokio::test]
+async fn plugin_priority_order_test() {
+  let log = CallLog::default();
+  let mut collab = Collab::new(
+    1,
+    "1",
+    vec![
+      LifecyclePlugin::new("a", 0, log.clone()),
+      LifecyclePlugin::new("b", 10, log.clone()),
+    ],
+  );
+  collab.add_plugin(LifecyclePlugin::new("c", 10, log.clone()));
+  collab.add_plugin(LifecyclePlugin::new("d", -1, log.clone()));
+  assert_eq!(collab.plugin_ids(), vec!["b", "c", "a", "d"]);
+
+  collab.initialize();
+  assert_eq!(*log.lock(), vec!["b:init", "c:init", "a:init", "d:init"]);
+}
+
+#[tokio::test]
+async fn remove_plugin_test() {
+  let log = CallLog::default();
+  let collab = Collab::new(
+    1,
+    "1",
+    vec![
+      LifecyclePlugin::new("a", 0, log.clone()),
+      LifecyclePlugin::new("b", 0, log.clone()),
+    ],
+  );
+  assert!(collab.remove_plugin("not_exist").is_none());
+  let removed = collab.remove_plugin("a").unwrap();
+  assert_eq!(removed.plugin_id(), "a");
+  assert_eq!(collab.plugin_ids(), vec!["b"]);
+  assert_eq!(*log.lock(), vec!["a:flush", "a:will_close"]);
+}
+
+#[tokio::test]
+async fn close_collab_flush_plugins_test() {
+  let log = CallLog::default();
+  let collab = Collab::new(1, "1", vec![LifecyclePlugin::new("a", 0, log.clone())]);
+  collab.flush();
+  assert_eq!(*log.lock(), vec!["a:flush"]);
+
+  log.lock().clear();
+  collab.close();
+  // Dropping a closed collab doesn't close the plugins again.
+  drop(collab);
+  assert_eq!(*log.lock(), vec!["a:flush", "a:will_close"]);
+}
+
+#[tokio::test]
+async fn drop_collab_close_plugins_test() {
+  let log = CallLog::default();
+  let collab = Collab::new(1, "1", vec![LifecyclePlugin::new("a", 0, log.clone())]);
+  drop(collab);
+  assert_eq!(*log.lock(), vec!["a:flush", "a:will_close"]);
+}
+
+#[derive(Default)]
+struct ObserverPlugin {
+  states: Mutex<Vec<CollabState>>,
+  awareness_states: Mutex<Vec<String>>,
+  local_updates: Mutex<usize>,
+}
+
+impl CollabPlugin for ObserverPlugin {
+  fn receive_local_update(&self, _origin: &CollabOrigin, _object_id: &str, _update: &[u8]) {
+    *self.local_updates.lock() += 1;
+  }
+
+  fn receive_awareness_update(
+    &self,
+    _object_id: &str,
+    awareness: &Awareness,
+    _event: &AwarenessEvent,
+  ) {
+    if let Some(state) = awareness.local_state() {
+      self.awareness_states.lock().push(state.to_string());
+    }
+  }
+
+  fn did_change_state(&self, _object_id: &str, change: &CollabStateChange) {
+    self.states.lock().push(change.state.clone());
+  }
+}
+
+#[tokio::test]
+async fn plugin_receive_state_change_test() {
+  let plugin = Arc::new(ObserverPlugin::default());
+  let collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  collab
+    .state_handle()
+    .set(CollabState::PersistError, "disk is full");
+  collab.close();
+  assert_eq!(
+    *plugin.states.lock(),
+    vec![
+      CollabState::Loading,
+      CollabState::Initialized,
+      CollabState::PersistError,
+      CollabState::Closed
+    ]
+  );
+}
+
+#[tokio::test]
+async fn plugin_receive_awareness_update_test() {
+  let plugin = Arc::new(ObserverPlugin::default());
+  let mut collab = Collab::new(1, "1", vec![plugin.clone()]);
+  collab.initialize();
+  collab
+    .get_mut_awareness()
+    .set_local_state(r#"{"name":"nathan"}"#);
+  assert_eq!(
+    *plugin.awareness_states.lock(),
+    vec![r#"{"name":"nathan"}"#.to_string()]
+  );
+}
+
+#[tokio::test]
+async fn boxed_plugin_forward_local_update_test() {
+  let plugin = Arc::new(ObserverPlugin::default());
+  let boxed: Box<Arc<ObserverPlugin>> = Box::new(plugin.clone());
+  let collab = Collab::new(1, "1", vec![Arc::new(boxed)]);
+  collab.initialize();
+  collab.insert("1", "a");
+  assert_eq!(*plugin.local_updates.lock(), 1);
+}
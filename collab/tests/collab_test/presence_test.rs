@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use collab::core::collab::Collab;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::error::CollabError;
+use collab::preclude::{PresenceConfig, PresencePosition, PresenceSelection, Text, TextPrelim};
+
+fn client_collab(uid: i64, device_id: &str) -> Collab {
+  let origin = CollabOrigin::Client(CollabClient::new(uid, device_id));
+  let collab = Collab::new_with_client(origin, "1", vec![]);
+  collab.initialize();
+  collab
+}
+
+fn sync_awareness(from: &Collab, to: &mut Collab) {
+  let update = from.get_awareness().update().unwrap();
+  to.get_mut_awareness().apply_update(update).unwrap();
+}
+
+#[tokio::test]
+async fn set_local_presence_test() {
+  let mut collab = client_collab(1, "a");
+  assert!(collab.get_presence().is_none());
+
+  let presence = collab
+    .update_presence(|presence| presence.name = "nathan".to_string())
+    .unwrap();
+  assert_eq!(presence.user, CollabClient::new(1, "a"));
+  assert_eq!(collab.get_presence().unwrap(), presence);
+
+  collab.clear_presence();
+  assert!(collab.get_presence().is_none());
+}
+
+#[tokio::test]
+async fn server_origin_presence_test() {
+  let mut collab = Collab::new_with_client(CollabOrigin::Server, "1", vec![]);
+  let result = collab.update_presence(|_| {});
+  assert!(matches!(result, Err(CollabError::PresenceRequiresClient)));
+}
+
+#[tokio::test]
+async fn peer_presence_test() {
+  let mut collab_1 = client_collab(1, "a");
+  let mut collab_2 = client_collab(2, "b");
+  let mut rx = collab_2.subscribe_presence();
+
+  collab_1
+    .update_presence(|presence| presence.name = "nathan".to_string())
+    .unwrap();
+  sync_awareness(&collab_1, &mut collab_2);
+
+  let peers = collab_2.get_peer_presences();
+  assert_eq!(peers.len(), 1);
+  assert_eq!(peers[0].presence.user, CollabClient::new(1, "a"));
+  assert_eq!(peers[0].presence.name, "nathan");
+  assert!(!peers[0].is_idle);
+
+  let event = rx.recv().await.unwrap();
+  assert_eq!(event.peers, peers);
+  assert!(event.removed.is_empty());
+
+  // The local presence is not one of the peers.
+  assert!(collab_1.get_peer_presences().is_empty());
+}
+
+#[tokio::test]
+async fn idle_peer_presence_test() {
+  let mut collab_1 = client_collab(1, "a");
+  let mut collab_2 = client_collab(2, "b");
+  collab_2.set_presence_config(PresenceConfig::new().idle_timeout(Duration::from_millis(100)));
+
+  collab_1.update_presence(|_| {}).unwrap();
+  sync_awareness(&collab_1, &mut collab_2);
+  assert!(!collab_2.get_peer_presences()[0].is_idle);
+
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(collab_2.get_peer_presences()[0].is_idle);
+}
+
+#[tokio::test]
+async fn presence_cursor_follow_text_changes_test() {
+  let mut collab = client_collab(1, "a");
+  let text = collab.insert("text", TextPrelim::new("hello world"));
+  let (cursor, selection) = collab.with_transact_mut(|txn| {
+    let cursor = PresencePosition::new(txn, &text, 6).unwrap();
+    let selection = PresenceSelection {
+      anchor: PresencePosition::new(txn, &text, 0).unwrap(),
+      head: PresencePosition::new(txn, &text, 5).unwrap(),
+    };
+    (cursor, selection)
+  });
+  collab
+    .update_presence(|presence| {
+      presence.cursor = Some(cursor);
+      presence.selection = Some(selection);
+    })
+    .unwrap();
+
+  collab.with_transact_mut(|txn| text.insert(txn, 0, "abc "));
+
+  let presence = collab.get_presence().unwrap();
+  let txn = collab.transact();
+  assert_eq!(presence.cursor.unwrap().resolve(&txn).unwrap(), Some(10));
+  let selection = presence.selection.unwrap();
+  assert_eq!(selection.anchor.resolve(&txn).unwrap(), Some(4));
+  assert_eq!(selection.head.resolve(&txn).unwrap(), Some(9));
+}
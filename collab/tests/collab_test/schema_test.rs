@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use collab::core::collab::MutexCollab;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::{
+  lib0Any, Collab, CollabPlugin, CollabSchema, Map, MapPrelim, MapSchema, SchemaRegistry,
+  SchemaType, SchemaViolation, SchemaViolationKind, Transact, TransactionMut,
+};
+use parking_lot::Mutex;
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+fn view_schema() -> CollabSchema {
+  let view = MapSchema::new()
+    .required("id", SchemaType::String)
+    .optional("name", SchemaType::String)
+    .optional("created_at", SchemaType::Integer);
+  CollabSchema::new(
+    "folder",
+    MapSchema::new()
+      .required("views", SchemaType::map_of(view.into()))
+      .optional("labels", SchemaType::array(SchemaType::String)),
+  )
+}
+
+fn type_mismatch(path: &str, expected: &str, actual: &str) -> SchemaViolation {
+  SchemaViolation {
+    path: path.to_string(),
+    kind: SchemaViolationKind::TypeMismatch {
+      expected: expected.to_string(),
+      actual: actual.to_string(),
+    },
+  }
+}
+
+#[test]
+fn validate_matched_data_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.with_transact_mut(|txn| {
+    let views = collab.insert_with_txn(txn, "views", MapPrelim::<lib0Any>::new());
+    let view = views.insert(txn, "v1", MapPrelim::<lib0Any>::new());
+    view.insert(txn, "id", "v1");
+    view.insert(txn, "created_at", lib0Any::BigInt(123));
+    // The fields that are not defined in the schema are allowed.
+    collab.insert_with_txn(txn, "version", "1.0");
+  });
+
+  let violations = view_schema().validate(&collab.transact());
+  assert!(violations.is_empty(), "{:?}", violations);
+}
+
+#[test]
+fn validate_missing_and_mismatched_data_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.with_transact_mut(|txn| {
+    let views = collab.insert_with_txn(txn, "views", MapPrelim::<lib0Any>::new());
+    let view = views.insert(txn, "v1", MapPrelim::<lib0Any>::new());
+    view.insert(txn, "name", lib0Any::BigInt(1));
+    view.insert(txn, "created_at", "today");
+    views.insert(txn, "v2", "not a view");
+    collab.insert_with_txn(
+      txn,
+      "labels",
+      lib0Any::Array(vec![lib0Any::String("a".into()), lib0Any::Bool(true)].into()),
+    );
+  });
+
+  let violations = view_schema().validate(&collab.transact());
+  assert_eq!(
+    violations,
+    vec![
+      SchemaViolation {
+        path: "/views/v1/id".to_string(),
+        kind: SchemaViolationKind::Missing,
+      },
+      type_mismatch("/views/v1/name", "string", "integer"),
+      type_mismatch("/views/v1/created_at", "integer", "string"),
+      type_mismatch("/views/v2", "map", "string"),
+      type_mismatch("/labels/1", "string", "bool"),
+    ]
+  );
+}
+
+#[test]
+fn validate_empty_data_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let violations = view_schema().validate(&collab.transact());
+  assert_eq!(
+    violations,
+    vec![SchemaViolation {
+      path: "/views".to_string(),
+      kind: SchemaViolationKind::Missing,
+    }]
+  );
+  assert_eq!(violations[0].to_string(), "/views is missing");
+}
+
+#[test]
+fn schema_registry_test() {
+  let registry = SchemaRegistry::new()
+    .with_schema(view_schema())
+    .with_schema(CollabSchema::new("document", MapSchema::new()));
+  assert_eq!(registry.get("folder").unwrap().as_ref(), &view_schema());
+  assert!(registry.get("document").is_some());
+  assert!(registry.get("database").is_none());
+}
+
+#[test]
+fn validate_changed_paths_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.with_transact_mut(|txn| {
+    let views = collab.insert_with_txn(txn, "views", MapPrelim::<lib0Any>::new());
+    let view = views.insert(txn, "v1", MapPrelim::<lib0Any>::new());
+    view.insert(txn, "name", lib0Any::BigInt(1));
+    let view = views.insert(txn, "v2", MapPrelim::<lib0Any>::new());
+    view.insert(txn, "id", "v2");
+    view.insert(txn, "created_at", "today");
+  });
+
+  // Only the values at the given paths are validated, with their nested values.
+  let txn = collab.transact();
+  assert_eq!(
+    view_schema().validate_paths(&txn, &["/views/v2".to_string()]),
+    vec![type_mismatch("/views/v2/created_at", "integer", "string")]
+  );
+  assert_eq!(
+    view_schema().validate_paths(&txn, &["/views/v1/name".to_string()]),
+    vec![type_mismatch("/views/v1/name", "string", "integer")]
+  );
+  assert_eq!(
+    view_schema().validate_paths(&txn, &["/labels".to_string(), "/version".to_string()]),
+    vec![]
+  );
+  assert_eq!(
+    view_schema().validate_paths(&txn, &["/views/v1/id".to_string()]),
+    vec![SchemaViolation {
+      path: "/views/v1/id".to_string(),
+      kind: SchemaViolationKind::Missing,
+    }]
+  );
+}
+
+/// Rejects the remote updates that break the [view_schema] and records the updates that it
+/// receives.
+#[derive(Default)]
+struct ValidationPlugin {
+  origins: Mutex<Vec<CollabOrigin>>,
+  changed_paths: Mutex<Vec<String>>,
+  received_updates: Mutex<Vec<Vec<u8>>>,
+}
+
+impl CollabPlugin for ValidationPlugin {
+  fn receive_update(&self, _object_id: &str, _txn: &TransactionMut, update: &[u8]) {
+    self.received_updates.lock().push(update.to_vec());
+  }
+
+  fn accept_remote_update(
+    &self,
+    origin: &CollabOrigin,
+    _object_id: &str,
+    txn: &TransactionMut,
+    changed_paths: &[String],
+  ) -> bool {
+    self.origins.lock().push(origin.clone());
+    self.changed_paths.lock().extend_from_slice(changed_paths);
+    view_schema().validate_paths(txn, changed_paths).is_empty()
+  }
+}
+
+fn make_collab(plugin: Arc<ValidationPlugin>) -> MutexCollab {
+  let collab = MutexCollab::new(
+    CollabOrigin::Client(CollabClient::new(1, "1")),
+    "1",
+    vec![plugin],
+  );
+  collab.lock().initialize();
+  collab
+}
+
+fn apply_remote_update(collab: &MutexCollab, f: impl FnOnce(&Collab)) -> Vec<u8> {
+  let remote_collab = Collab::new(2, "1", vec![]);
+  f(&remote_collab);
+  let update = remote_collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  let collab = collab.lock();
+  let mut txn = collab.get_doc().transact_mut_with(CollabOrigin::Server);
+  txn.apply_update(Update::decode_v1(&update).unwrap());
+  update
+}
+
+#[tokio::test]
+async fn accept_remote_update_test() {
+  let plugin = Arc::new(ValidationPlugin::default());
+  let collab = make_collab(plugin.clone());
+
+  // The local update is not passed to the accept_remote_update.
+  collab.lock().insert("views", MapPrelim::<lib0Any>::new());
+  assert!(plugin.origins.lock().is_empty());
+
+  apply_remote_update(&collab, |remote| {
+    remote.insert("labels", lib0Any::Array(vec![].into()));
+  });
+  assert_eq!(*plugin.origins.lock(), vec![CollabOrigin::Server]);
+  assert_eq!(*plugin.changed_paths.lock(), vec!["/labels".to_string()]);
+  assert!(!collab.lock().is_quarantined());
+  assert_eq!(plugin.received_updates.lock().len(), 2);
+}
+
+#[tokio::test]
+async fn quarantine_rejected_remote_update_test() {
+  let plugin = Arc::new(ValidationPlugin::default());
+  let collab = make_collab(plugin.clone());
+  collab.lock().insert("views", MapPrelim::<lib0Any>::new());
+  let received_count = plugin.received_updates.lock().len();
+
+  // The rejected update is applied, but it's not passed to the plugins.
+  let update = apply_remote_update(&collab, |remote| {
+    remote.insert("labels", "not an array");
+  });
+  assert!(collab.lock().is_quarantined());
+  assert_eq!(collab.lock().to_json_value()["labels"], "not an array");
+  assert_eq!(plugin.received_updates.lock().len(), received_count);
+
+  // The local updates after it are not held back.
+  collab
+    .lock()
+    .insert("labels", lib0Any::Array(vec![].into()));
+  assert_eq!(plugin.received_updates.lock().len(), received_count + 1);
+
+  let released = collab.lock().release_quarantine();
+  assert_eq!(released.len(), 1);
+  assert_eq!(released[0].origin, CollabOrigin::Server);
+  assert_eq!(released[0].update, update);
+  assert!(!collab.lock().is_quarantined());
+  assert_eq!(plugin.received_updates.lock().len(), received_count + 2);
+}
+
+#[tokio::test]
+async fn close_releases_quarantined_updates_test() {
+  let plugin = Arc::new(ValidationPlugin::default());
+  let collab = make_collab(plugin.clone());
+  let received_count = plugin.received_updates.lock().len();
+
+  let update = apply_remote_update(&collab, |remote| {
+    remote.insert("labels", "not an array");
+  });
+  assert!(collab.lock().is_quarantined());
+
+  collab.lock().close();
+  assert!(!collab.lock().is_quarantined());
+  let received_updates = plugin.received_updates.lock();
+  assert_eq!(received_updates.len(), received_count + 1);
+  assert_eq!(received_updates.last().unwrap(), &update);
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use collab::preclude::{lib0Any, Collab, Map, YrsValue};
+use collab::serde::{
+  from_any, from_map_ref, push_to_array_ref, to_any, to_map_ref, to_map_ref_with_key,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Task {
+  id: i64,
+  title: String,
+  score: f64,
+  done: bool,
+  owner: Option<String>,
+  tags: Vec<String>,
+  status: Status,
+  extra: HashMap<String, i32>,
+  sub_tasks: Vec<Task>,
+  bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Status {
+  Todo,
+  Doing(u8),
+  Done { at: i64 },
+}
+
+fn test_task() -> Task {
+  Task {
+    id: 1,
+    title: "write tests".to_string(),
+    score: 1.5,
+    done: false,
+    owner: None,
+    tags: vec!["a".to_string(), "b".to_string()],
+    status: Status::Done { at: 100 },
+    extra: HashMap::from([("priority".to_string(), 3)]),
+    sub_tasks: vec![Task {
+      id: 2,
+      title: "review".to_string(),
+      score: 0.0,
+      done: true,
+      owner: Some("nathan".to_string()),
+      tags: vec![],
+      status: Status::Doing(50),
+      extra: HashMap::new(),
+      sub_tasks: vec![],
+      bytes: vec![],
+    }],
+    bytes: vec![1, 2, 3],
+  }
+}
+
+#[tokio::test]
+async fn serialize_into_map_ref_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let task = test_task();
+  collab
+    .with_transact_mut(|txn| {
+      let map_ref = collab.insert_map_with_txn(txn, "task");
+      to_map_ref(txn, &map_ref, "task", &task)
+    })
+    .unwrap();
+
+  let txn = collab.transact();
+  let map_ref = collab.get_map_with_txn(&txn, vec!["task"]).unwrap();
+  // The nested structs and sequences are stored as yrs types
+  assert!(matches!(
+    map_ref.get(&txn, "status"),
+    Some(YrsValue::YMap(_))
+  ));
+  assert!(matches!(
+    map_ref.get(&txn, "sub_tasks"),
+    Some(YrsValue::YArray(_))
+  ));
+
+  let value: Task = from_map_ref(&txn, &map_ref).unwrap();
+  assert_eq!(value, task);
+}
+
+#[tokio::test]
+async fn serialize_with_key_and_push_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab
+    .with_transact_mut(|txn| {
+      let root = collab.insert_map_with_txn(txn, "root");
+      to_map_ref_with_key(txn, &root, "status", &Status::Todo)?;
+      to_map_ref_with_key(txn, &root, "level", &3)?;
+      let array_ref = collab.create_array_with_txn::<lib0Any>(txn, "tasks", vec![]);
+      push_to_array_ref(txn, &array_ref, &test_task())?;
+      push_to_array_ref(txn, &array_ref, &test_task())
+    })
+    .unwrap();
+
+  let json_value = collab.to_json_value();
+  assert_eq!(json_value["root"], json!({ "status": "Todo", "level": 3 }));
+  assert_eq!(
+    json_value["tasks"][0],
+    serde_json::to_value(test_task()).unwrap()
+  );
+  assert_eq!(
+    json_value["tasks"][1]["sub_tasks"][0]["status"],
+    json!({ "Doing": 50 })
+  );
+}
+
+#[tokio::test]
+async fn get_and_insert_json_with_path_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.insert_json_with_path(vec![], "task", test_task());
+  let task = collab
+    .get_json_with_path::<Task>(vec!["task".to_string()])
+    .unwrap();
+  assert_eq!(task, test_task());
+}
+
+#[tokio::test]
+async fn any_round_trip_test() {
+  let task = test_task();
+  let any = to_any(&task).unwrap();
+  assert_eq!(from_any::<Task>(&any).unwrap(), task);
+
+  // The integers that were converted from json might be stored as numbers
+  let any = lib0Any::from_json(r#"{"id": 1, "height": 20}"#).unwrap();
+  let value: HashMap<String, i32> = from_any(&any).unwrap();
+  assert_eq!(value["height"], 20);
+}
@@ -0,0 +1,43 @@
+use collab::core::collab::CollabBuilder;
+use collab::preclude::Snapshot;
+use serde_json::json;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+
+#[test]
+fn read_collab_at_snapshot_test() {
+  let collab = CollabBuilder::new(1, "1").build();
+  collab.initial();
+  collab.lock().insert("title", "hello");
+  let snapshot_1 = collab.snapshot();
+
+  collab.lock().insert("title", "hello world");
+  collab.lock().insert("desc", "my document");
+  let snapshot_2 = collab.snapshot();
+
+  collab.lock().remove("desc");
+
+  assert_eq!(
+    collab.to_json_value_at(&snapshot_1).unwrap(),
+    json!({ "title": "hello" })
+  );
+  assert_eq!(
+    collab.to_json_value_at(&snapshot_2).unwrap(),
+    json!({ "title": "hello world", "desc": "my document" })
+  );
+  // The current state is not changed
+  assert_eq!(collab.to_json_value(), json!({ "title": "hello world" }));
+}
+
+#[test]
+fn read_collab_at_encoded_snapshot_test() {
+  let collab = CollabBuilder::new(1, "1").build();
+  collab.initial();
+  collab.lock().insert("title", "hello");
+  let encoded_snapshot = collab.snapshot().encode_v1();
+
+  collab.lock().insert("title", "hello world");
+  let snapshot = Snapshot::decode_v1(&encoded_snapshot).unwrap();
+  let view = collab.lock().snapshot_view(&snapshot).unwrap();
+  assert_eq!(view.to_json_value(), json!({ "title": "hello" }));
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use collab::core::collab_state::{CollabState, CollabStateHandle};
+use collab::preclude::{Collab, CollabPlugin};
+use parking_lot::RwLock;
+use yrs::TransactionMut;
+
+/// A plugin that fails to load the document.
+#[derive(Default, Clone)]
+struct FailToLoadPlugin {
+  handle: Arc<RwLock<Option<CollabStateHandle>>>,
+}
+
+impl CollabPlugin for FailToLoadPlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.handle.write() = Some(handle);
+  }
+
+  fn init(&self, _object_id: &str, _txn: &mut TransactionMut) {
+    if let Some(handle) = self.handle.read().as_ref() {
+      handle.set(CollabState::PersistError, "disk is full");
+    }
+  }
+
+  fn will_close(&self, _object_id: &str) {
+    self.handle.write().take();
+  }
+}
+
+#[tokio::test]
+async fn initialize_state_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let rx = collab.subscribe_state_change();
+  assert_eq!(collab.get_state(), CollabState::Uninitialized);
+
+  collab.initialize();
+  assert_eq!(collab.get_state(), CollabState::Initialized);
+  assert_eq!(rx.borrow().state, CollabState::Initialized);
+}
+
+#[tokio::test]
+async fn plugin_publish_state_test() {
+  let plugin = FailToLoadPlugin::default();
+  let collab = Collab::new(1, "1", vec![Arc::new(plugin.clone())]);
+  let rx = collab.subscribe_state_change();
+  collab.initialize();
+
+  // The state that was published by the plugin is kept after initializing.
+  assert_eq!(collab.get_state(), CollabState::PersistError);
+  let change = rx.borrow().clone();
+  assert_eq!(change.state, CollabState::PersistError);
+  assert_eq!(change.reason, "disk is full");
+
+  let handle = plugin.handle.read().clone().unwrap();
+  handle.set(CollabState::Synced, "");
+  assert!(collab.get_state().is_synced());
+}
+
+#[tokio::test]
+async fn drop_collab_close_state_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let handle = collab.state_handle();
+  let rx = handle.subscribe().unwrap();
+  drop(collab);
+  assert!(handle.get().is_closed());
+  assert!(handle.subscribe().is_none());
+  assert!(rx.borrow().state.is_closed());
+}
+
+/// A plugin that keeps the state handle until it is dropped.
+#[derive(Default)]
+struct KeepStatePlugin {
+  handle: RwLock<Option<CollabStateHandle>>,
+}
+
+impl CollabPlugin for KeepStatePlugin {
+  fn set_state_handle(&self, _object_id: &str, handle: CollabStateHandle) {
+    *self.handle.write() = Some(handle);
+  }
+}
+
+#[tokio::test]
+async fn drop_collab_release_plugin_test() {
+  let plugin = Arc::new(KeepStatePlugin::default());
+  let weak_plugin = Arc::downgrade(&plugin);
+  let handle = {
+    let collab = Collab::new(1, "1", vec![plugin.clone()]);
+    collab.initialize();
+    drop(plugin);
+    collab.state_handle()
+  };
+
+  // The state handle that was kept by the plugin doesn't keep the collab alive.
+  assert!(weak_plugin.upgrade().is_none());
+  assert!(handle.get().is_closed());
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use collab::core::collab::Collab;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::{CollabPlugin, CollabStats};
+
+#[tokio::test]
+async fn empty_collab_stats_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  let stats = collab.stats().unwrap();
+  assert_eq!(stats.block_count, 0);
+  assert_eq!(stats.deleted_len, 0);
+  assert!(stats.clients.is_empty());
+  assert_eq!(stats.deleted_ratio(), 0.0);
+  assert_eq!(stats.disk_update_count, None);
+}
+
+#[tokio::test]
+async fn deleted_items_stats_test() {
+  let origin = CollabOrigin::Client(CollabClient::new(1, "a"));
+  let mut collab = Collab::new_with_client(origin, "1", vec![]);
+  collab.set_author_recording(true);
+  collab.initialize();
+  collab.insert("1", "a");
+  collab.insert("2", "b");
+  let stats = collab.stats().unwrap();
+  // The blocks of the two entries and of the author of the client.
+  assert_eq!(stats.block_count, 3);
+  assert_eq!(stats.deleted_block_count, 0);
+  assert!(stats.encoded_state_size > 0);
+
+  collab.insert("1", "c");
+  collab.remove("2");
+  let stats = collab.stats().unwrap();
+  assert_eq!(stats.block_count, 4);
+  assert_eq!(stats.deleted_block_count, 2);
+  assert_eq!(stats.deleted_len, 2);
+  assert!(stats.deleted_ratio() > 0.0);
+
+  assert_eq!(stats.clients.len(), 1);
+  let client = &stats.clients[0];
+  assert_eq!(client.client, Some(CollabClient::new(1, "a")));
+  assert_eq!(client.block_count, 4);
+  assert_eq!(client.deleted_block_count, 2);
+}
+
+struct UpdateCountPlugin;
+
+impl CollabPlugin for UpdateCountPlugin {
+  fn collect_stats(&self, _object_id: &str, stats: &mut CollabStats) {
+    stats.disk_update_count = Some(10);
+  }
+}
+
+#[tokio::test]
+async fn plugin_collect_stats_test() {
+  let collab = Collab::new(1, "1", vec![Arc::new(UpdateCountPlugin)]);
+  let stats = collab.stats().unwrap();
+  assert_eq!(stats.disk_update_count, Some(10));
+}
@@ -34,3 +34,74 @@ impl From<TaskInfo> for Any {
     serde_json::from_value(a).unwrap()
   }
 }
+
+#[derive(Collab)]
+pub struct Board {
+  pub id: String,
+  #[collab(nested)]
+  pub owner: Owner,
+  #[collab(nested)]
+  pub reviewer: Option<Owner>,
+  pub labels: Vec<String>,
+  #[collab(nested)]
+  pub cards: Vec<TaskInfo>,
+  #[collab(nested)]
+  pub columns: HashMap<String, TaskInfo>,
+  #[collab(enum)]
+  pub layout: BoardLayout,
+  #[collab(enum)]
+  pub previous_layout: Option<BoardLayout>,
+  #[collab(enum)]
+  pub layout_history: Vec<BoardLayout>,
+  #[collab(enum)]
+  pub column_layouts: HashMap<String, BoardLayout>,
+}
+
+#[derive(Collab, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoardLayout {
+  #[default]
+  Kanban,
+  Timeline = 5,
+}
+
+#[derive(Collab)]
+pub struct Note {
+  #[collab(rename = "note_id")]
+  pub id: String,
+  #[collab(text)]
+  pub content: String,
+  #[collab(text)]
+  pub summary: Option<String>,
+  #[collab(default = "default_priority")]
+  pub priority: i64,
+  #[collab(nested)]
+  pub owner: Owner,
+  #[collab(skip)]
+  pub is_dirty: bool,
+  #[collab(skip, default = "default_priority")]
+  pub local_priority: i64,
+}
+
+fn default_priority() -> i64 {
+  3
+}
+
+#[derive(Collab, Debug, Default, Clone, PartialEq)]
+pub struct BoardView {
+  #[collab(rename = "view_id")]
+  pub id: String,
+  #[collab(text)]
+  pub description: String,
+  #[collab(enum)]
+  pub layout: BoardLayout,
+}
+
+#[derive(Collab)]
+pub struct Project {
+  #[collab(nested)]
+  pub view: BoardView,
+  #[collab(nested)]
+  pub views: Vec<BoardView>,
+  #[collab(nested)]
+  pub named_views: HashMap<String, BoardView>,
+}
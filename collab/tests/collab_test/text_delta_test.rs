@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use collab::core::collab::Collab;
+use collab::preclude::{text_deltas_from_json, text_deltas_to_json, GetString, TextRefWrapper};
+use parking_lot::Mutex;
+use serde_json::json;
+
+fn create_text(collab: &Collab) -> TextRefWrapper {
+  collab.with_transact_mut(|txn| {
+    let map = collab.insert_map_with_txn(txn, "document");
+    map.insert_text_with_txn(txn, "text")
+  })
+}
+
+fn apply_json_delta(text: &TextRefWrapper, delta: serde_json::Value) {
+  let deltas = text_deltas_from_json(&delta).unwrap();
+  text.with_transact_mut(|txn| text.apply_delta_with_txn(txn, deltas));
+}
+
+fn get_json_delta(text: &TextRefWrapper) -> serde_json::Value {
+  let txn = text.transact();
+  text_deltas_to_json(&text.get_delta_with_txn(&txn))
+}
+
+#[test]
+fn apply_quill_delta_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let text = create_text(&collab);
+  apply_json_delta(&text, json!({ "ops": [{ "insert": "Hi 😀 there" }] }));
+  // The emoji is two UTF-16 code units
+  apply_json_delta(
+    &text,
+    json!([
+      { "retain": 3 },
+      { "retain": 2, "attributes": { "bold": true } },
+      { "delete": 1 },
+      { "insert": "!" },
+    ]),
+  );
+  assert_eq!(text.get_string(&text.transact()), "Hi 😀!there");
+  assert_eq!(
+    get_json_delta(&text),
+    json!({ "ops": [
+      { "insert": "Hi " },
+      { "insert": "😀", "attributes": { "bold": true } },
+      { "insert": "!there" },
+    ]})
+  );
+}
+
+#[test]
+fn remove_attribute_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let text = create_text(&collab);
+  apply_json_delta(
+    &text,
+    json!([{ "insert": "hello world", "attributes": { "bold": true, "italic": true } }]),
+  );
+  apply_json_delta(
+    &text,
+    json!([{ "retain": 5, "attributes": { "bold": null } }]),
+  );
+  assert_eq!(
+    get_json_delta(&text),
+    json!({ "ops": [
+      { "insert": "hello", "attributes": { "italic": true } },
+      { "insert": " world", "attributes": { "bold": true, "italic": true } },
+    ]})
+  );
+}
+
+#[test]
+fn insert_embed_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let text = create_text(&collab);
+  apply_json_delta(
+    &text,
+    json!([
+      { "insert": "ab" },
+      { "insert": { "image": "https://appflowy.io/logo.png" } },
+      { "insert": "c" },
+    ]),
+  );
+  // The embed counts as one
+  apply_json_delta(&text, json!([{ "retain": 3 }, { "delete": 1 }]));
+  assert_eq!(
+    get_json_delta(&text),
+    json!({ "ops": [
+      { "insert": "ab" },
+      { "insert": { "image": "https://appflowy.io/logo.png" } },
+    ]})
+  );
+}
+
+#[test]
+fn text_event_to_delta_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let mut text = create_text(&collab);
+  apply_json_delta(&text, json!([{ "insert": "😀 hello" }]));
+
+  let deltas = Arc::new(Mutex::new(vec![]));
+  let cloned_deltas = deltas.clone();
+  let _sub = text.observe_delta(move |_, changes| {
+    cloned_deltas.lock().extend(changes);
+  });
+  apply_json_delta(
+    &text,
+    json!([
+      { "retain": 3 },
+      { "delete": 1 },
+      { "insert": "H", "attributes": { "bold": true } },
+    ]),
+  );
+  assert_eq!(
+    text_deltas_to_json(&deltas.lock()),
+    json!({ "ops": [
+      { "retain": 3 },
+      { "delete": 1 },
+      { "insert": "H", "attributes": { "bold": true } },
+    ]})
+  );
+
+  // Replay the changes on another text
+  let other_collab = Collab::new(2, "2", vec![]);
+  other_collab.initialize();
+  let other = create_text(&other_collab);
+  apply_json_delta(&other, json!([{ "insert": "😀 hello" }]));
+  let changes = deltas.lock().clone();
+  other.with_transact_mut(|txn| other.apply_delta_with_txn(txn, changes));
+  assert_eq!(get_json_delta(&other), get_json_delta(&text));
+}
+
+#[test]
+fn text_event_to_delta_with_deleted_emoji_test() {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.initialize();
+  let mut text = create_text(&collab);
+  apply_json_delta(&text, json!([{ "insert": "héllo 😀 world" }]));
+
+  let deltas = Arc::new(Mutex::new(vec![]));
+  let cloned_deltas = deltas.clone();
+  let _sub = text.observe_delta(move |_, changes| {
+    cloned_deltas.lock().extend(changes);
+  });
+  // The lengths are counted in UTF-16 code units, the deleted emoji counts as two.
+  let delta = json!([
+    { "retain": 6 },
+    { "delete": 3 },
+    { "retain": 5, "attributes": { "bold": true } },
+  ]);
+  apply_json_delta(&text, delta.clone());
+  assert_eq!(text_deltas_to_json(&deltas.lock()), json!({ "ops": delta }));
+  assert_eq!(
+    get_json_delta(&text),
+    json!({ "ops": [
+      { "insert": "héllo " },
+      { "insert": "world", "attributes": { "bold": true } },
+    ]})
+  );
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::error::CollabError;
+use collab::preclude::{Collab, MapPrelim, UndoConfig, UndoStackChange, UndoStackKind};
+use parking_lot::Mutex;
+use serde_json::json;
+use yrs::{Map, Transact};
+
+fn create_collab_with_blocks() -> Collab {
+  let collab = Collab::new(1, "1", vec![]);
+  collab.with_transact_mut(|txn| {
+    collab.insert_with_txn(txn, "block_a", MapPrelim::<lib0::any::Any>::new());
+    collab.insert_with_txn(txn, "block_b", MapPrelim::<lib0::any::Any>::new());
+  });
+  collab
+}
+
+fn set_block_value(collab: &Collab, block: &str, value: &str) {
+  collab.with_transact_mut(|txn| {
+    let map_ref = collab
+      .get_map_with_txn(&*txn, vec![block])
+      .unwrap()
+      .into_inner();
+    map_ref.insert(txn, "value", value);
+  });
+}
+
+#[tokio::test]
+async fn undo_with_capture_timeout_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.enable_undo_redo_with_config(UndoConfig::new().capture_timeout_millis(0));
+  collab.insert("a", "1");
+  sleep(Duration::from_millis(5));
+  collab.insert("b", "2");
+
+  // Each change is its own stack item because the capture timeout is 0.
+  assert!(collab.undo().unwrap());
+  assert_eq!(collab.to_json_value(), json!({"a": "1"}));
+  assert!(collab.undo().unwrap());
+  assert_eq!(collab.to_json_value(), json!({}));
+  assert!(!collab.can_undo());
+
+  assert!(collab.redo().unwrap());
+  assert_eq!(collab.to_json_value(), json!({"a": "1"}));
+}
+
+#[tokio::test]
+async fn undo_tracked_origin_test() {
+  let remote_origin = CollabOrigin::Client(CollabClient::new(2, "remote"));
+  let untracked_origin = CollabOrigin::Client(CollabClient::new(3, "other"));
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.enable_undo_redo_with_config(
+    UndoConfig::new()
+      .capture_timeout_millis(0)
+      .track_origin(remote_origin.clone()),
+  );
+
+  let data = collab.get_doc().get_or_insert_map("data");
+  {
+    let mut txn = collab.get_doc().transact_mut_with(untracked_origin);
+    data.insert(&mut txn, "untracked", "1");
+  }
+  assert!(!collab.can_undo());
+
+  {
+    let mut txn = collab.get_doc().transact_mut_with(remote_origin);
+    data.insert(&mut txn, "tracked", "2");
+  }
+  assert!(collab.can_undo());
+  assert!(collab.undo().unwrap());
+  assert_eq!(collab.to_json_value(), json!({"untracked": "1"}));
+}
+
+#[tokio::test]
+async fn independent_undo_scopes_test() {
+  let collab = create_collab_with_blocks();
+  collab
+    .add_undo_scope("a", vec!["block_a"], UndoConfig::new())
+    .unwrap();
+  collab
+    .add_undo_scope("b", vec!["block_b"], UndoConfig::new())
+    .unwrap();
+
+  set_block_value(&collab, "block_a", "a");
+  set_block_value(&collab, "block_b", "b");
+
+  assert!(collab.can_undo_in_scope("a"));
+  assert!(collab.undo_in_scope("a").unwrap());
+  assert_eq!(
+    collab.to_json_value(),
+    json!({"block_a": {}, "block_b": {"value": "b"}})
+  );
+  assert!(!collab.can_undo_in_scope("a"));
+  assert!(collab.can_redo_in_scope("a"));
+  assert!(collab.can_undo_in_scope("b"));
+
+  assert!(collab.redo_in_scope("a").unwrap());
+  assert_eq!(
+    collab.to_json_value(),
+    json!({"block_a": {"value": "a"}, "block_b": {"value": "b"}})
+  );
+
+  assert!(collab.remove_undo_scope("b"));
+  assert!(!collab.can_undo_in_scope("b"));
+  assert!(matches!(
+    collab.undo_in_scope("b"),
+    Err(CollabError::UndoScopeNotFound(_))
+  ));
+}
+
+#[tokio::test]
+async fn expand_undo_scope_test() {
+  let collab = create_collab_with_blocks();
+  collab
+    .add_undo_scope("blocks", vec!["block_a"], UndoConfig::new())
+    .unwrap();
+  collab.expand_undo_scope("blocks", vec!["block_b"]).unwrap();
+
+  set_block_value(&collab, "block_b", "b");
+  assert!(collab.undo_in_scope("blocks").unwrap());
+  assert_eq!(
+    collab.to_json_value(),
+    json!({"block_a": {}, "block_b": {}})
+  );
+}
+
+#[tokio::test]
+async fn invalid_undo_scope_test() {
+  let collab = create_collab_with_blocks();
+  collab.insert("text", "hello");
+  assert!(matches!(
+    collab.add_undo_scope("a", vec!["not_exist"], UndoConfig::new()),
+    Err(CollabError::InvalidUndoScope(_))
+  ));
+  assert!(matches!(
+    collab.add_undo_scope("a", vec!["text"], UndoConfig::new()),
+    Err(CollabError::InvalidUndoScope(_))
+  ));
+}
+
+#[tokio::test]
+async fn undo_scope_restore_selection_test() {
+  let collab = create_collab_with_blocks();
+  let cursor = Arc::new(Mutex::new(json!({"block": "block_a", "offset": 0})));
+  let restored = Arc::new(Mutex::new(None));
+
+  let cloned_cursor = cursor.clone();
+  let cloned_restored = restored.clone();
+  collab
+    .add_undo_scope(
+      "a",
+      vec!["block_a"],
+      UndoConfig::new()
+        .with_selection_provider(move || Some(cloned_cursor.lock().clone()))
+        .with_selection_restorer(move |selection| *cloned_restored.lock() = Some(selection)),
+    )
+    .unwrap();
+
+  set_block_value(&collab, "block_a", "a");
+  *cursor.lock() = json!({"block": "block_a", "offset": 1});
+
+  assert!(collab.undo_in_scope("a").unwrap());
+  assert_eq!(
+    restored.lock().clone().unwrap(),
+    json!({"block": "block_a", "offset": 0})
+  );
+}
+
+#[tokio::test]
+async fn undo_stack_items_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.enable_undo_redo_with_config(UndoConfig::new().capture_timeout_millis(0));
+  collab.insert("a", "1");
+  sleep(Duration::from_millis(5));
+  collab.insert("b", "2");
+
+  assert_eq!(collab.undo_stack_len(), 2);
+  assert_eq!(collab.redo_stack_len(), 0);
+  let items = collab.undo_stack_items();
+  assert_eq!(items[0].touched_paths, vec!["/a".to_string()]);
+  assert_eq!(items[1].touched_paths, vec!["/b".to_string()]);
+  assert_eq!(
+    items[1].origin,
+    CollabOrigin::Client(CollabClient::new(1, ""))
+  );
+
+  collab.undo().unwrap();
+  assert_eq!(collab.undo_stack_len(), 1);
+  assert_eq!(collab.redo_stack_len(), 1);
+  assert_eq!(
+    collab.redo_stack_items()[0].touched_paths,
+    vec!["/b".to_string()]
+  );
+
+  // A new change clears the redo stack.
+  collab.insert("c", "3");
+  assert_eq!(collab.undo_stack_len(), 2);
+  assert_eq!(collab.redo_stack_len(), 0);
+}
+
+#[tokio::test]
+async fn undo_stack_in_scope_touched_paths_test() {
+  let collab = create_collab_with_blocks();
+  collab
+    .add_undo_scope("a", vec!["block_a"], UndoConfig::new())
+    .unwrap();
+  set_block_value(&collab, "block_a", "a");
+
+  let items = collab.undo_stack_items_in_scope("a").unwrap();
+  assert_eq!(items.len(), 1);
+  assert_eq!(items[0].touched_paths, vec!["/block_a/value".to_string()]);
+  assert!(collab.redo_stack_items_in_scope("a").unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn undo_stack_notification_test() {
+  let mut collab = Collab::new(1, "1", vec![]);
+  collab.enable_undo_redo();
+  let mut rx = collab.subscribe_undo_stack();
+
+  collab.insert("a", "1");
+  let event = rx.try_recv().unwrap();
+  assert_eq!(event.scope_id, None);
+  assert!(matches!(
+    event.change,
+    UndoStackChange::Pushed(UndoStackKind::Undo, _)
+  ));
+  assert_eq!((event.undo_len, event.redo_len), (1, 0));
+
+  collab.undo().unwrap();
+  let mut events = vec![];
+  while let Ok(event) = rx.try_recv() {
+    events.push(event);
+  }
+  assert!(events.iter().any(|event| matches!(
+    event.change,
+    UndoStackChange::Pushed(UndoStackKind::Redo, _)
+  )));
+  assert!(events.iter().any(|event| matches!(
+    event.change,
+    UndoStackChange::Popped(UndoStackKind::Undo, _)
+  )));
+  let last = events.last().unwrap();
+  assert_eq!((last.undo_len, last.redo_len), (0, 1));
+}
@@ -1,5 +1,15 @@
+use collab::core::collab::MutexCollab;
+use collab::core::map_wrapper::MapRefExtension;
+use collab::preclude::{Array, CollabBuilder, CustomMapRef, Text};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::helper::make_collab_pair;
-use crate::struct_define::{DocumentMapRef, Owner, OwnerMapRef, TaskInfoMapRef};
+use crate::struct_define::{
+  BoardLayout, BoardMapRef, BoardView, DocumentMapRef, Note, NoteMapRef, Owner, OwnerMapRef,
+  ProjectMapRef, TaskInfo, TaskInfoMapRef,
+};
 
 #[test]
 fn derive_string_test() {
@@ -134,3 +144,442 @@ fn derive_into_inner_test() {
   let owner = map_ref.into_object(&local.lock().transact());
   assert_eq!(owner.name, "nathan.fu".to_string());
 }
+
+fn make_collab() -> MutexCollab {
+  let collab = CollabBuilder::new(1, "1").build();
+  collab.initial();
+  collab
+}
+
+fn make_map_ref<M: CustomMapRef>(key: &str) -> (MutexCollab, M) {
+  let collab = make_collab();
+  let map_ref = {
+    let collab = collab.lock();
+    collab.with_transact_mut(|txn| collab.insert_map_with_txn(txn, key))
+  };
+  (collab, M::from_map_ref(map_ref))
+}
+
+fn make_board() -> (MutexCollab, BoardMapRef) {
+  make_map_ref("board")
+}
+
+fn owner(name: &str) -> Owner {
+  Owner {
+    id: format!("{}_id", name),
+    name: name.to_string(),
+    email: format!("{}@appflowy.io", name),
+    location: None,
+  }
+}
+
+#[test]
+fn derive_nested_struct_test() {
+  let (collab, mut map_ref) = make_board();
+  assert!(map_ref.get_owner(&collab.lock().transact()).is_none());
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_owner(txn, owner("nathan"));
+  });
+
+  let mut owner_map_ref = map_ref
+    .get_owner_map_ref(&collab.lock().transact())
+    .unwrap();
+  collab.lock().with_transact_mut(|txn| {
+    owner_map_ref.set_location(txn, "SG".to_string());
+  });
+
+  let owner = map_ref.get_owner(&collab.lock().transact()).unwrap();
+  assert_eq!(owner.name, "nathan");
+  assert_eq!(owner.location.unwrap(), "SG");
+}
+
+#[test]
+fn derive_optional_nested_struct_test() {
+  let (collab, mut map_ref) = make_board();
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_reviewer(txn, owner("lucas"));
+  });
+  let reviewer = map_ref.get_reviewer(&collab.lock().transact()).unwrap();
+  assert_eq!(reviewer.email, "lucas@appflowy.io");
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.remove_reviewer(txn);
+  });
+  assert!(map_ref.get_reviewer(&collab.lock().transact()).is_none());
+  assert!(map_ref
+    .get_reviewer_map_ref(&collab.lock().transact())
+    .is_none());
+}
+
+#[test]
+fn derive_vec_test() {
+  let (collab, mut map_ref) = make_board();
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_labels(txn, vec!["bug".to_string(), "feature".to_string()]);
+    map_ref.push_labels(txn, "question".to_string()).unwrap();
+    map_ref.remove_labels_at(txn, 0);
+  });
+
+  let labels = map_ref.get_labels(&collab.lock().transact()).unwrap();
+  assert_eq!(labels, vec!["feature".to_string(), "question".to_string()]);
+
+  let array_ref = map_ref
+    .get_labels_array_ref(&collab.lock().transact())
+    .unwrap();
+  assert_eq!(array_ref.len(&collab.lock().transact()), 2);
+}
+
+#[test]
+fn derive_vec_of_nested_struct_test() {
+  let (collab, mut map_ref) = make_board();
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref
+      .push_cards(
+        txn,
+        TaskInfo {
+          title: "Card 1".to_string(),
+          repeated: false,
+        },
+      )
+      .unwrap();
+  });
+
+  let mut card_map_refs = map_ref.get_cards_map_refs(&collab.lock().transact());
+  assert_eq!(card_map_refs.len(), 1);
+  collab.lock().with_transact_mut(|txn| {
+    card_map_refs[0].set_repeated(txn, true);
+  });
+
+  let cards = map_ref.get_cards(&collab.lock().transact()).unwrap();
+  assert_eq!(cards[0].title, "Card 1");
+  assert!(cards[0].repeated);
+}
+
+#[test]
+fn derive_hash_map_of_nested_struct_test() {
+  let (collab, mut map_ref) = make_board();
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.update_columns_key_value(
+      txn,
+      "todo",
+      TaskInfo {
+        title: "Todo".to_string(),
+        repeated: false,
+      },
+    );
+    map_ref.update_columns_key_value(
+      txn,
+      "done",
+      TaskInfo {
+        title: "Done".to_string(),
+        repeated: false,
+      },
+    );
+  });
+
+  let mut todo_map_ref = map_ref
+    .get_columns_map_ref(&collab.lock().transact(), "todo")
+    .unwrap();
+  collab.lock().with_transact_mut(|txn| {
+    todo_map_ref.set_title(txn, "To do".to_string());
+    map_ref.remove_columns_key(txn, "done");
+  });
+
+  let todo = map_ref
+    .get_columns_value(&collab.lock().transact(), "todo")
+    .unwrap();
+  assert_eq!(todo.title, "To do");
+  let columns = map_ref.get_columns(&collab.lock().transact()).unwrap();
+  assert_eq!(columns.len(), 1);
+}
+
+#[test]
+fn derive_enum_test() {
+  let (collab, mut map_ref) = make_board();
+  assert!(map_ref.get_layout(&collab.lock().transact()).is_none());
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_layout(txn, BoardLayout::Timeline);
+    map_ref.set_previous_layout(txn, BoardLayout::Kanban);
+  });
+
+  let collab = collab.lock();
+  let txn = collab.transact();
+  assert_eq!(map_ref.get_layout(&txn).unwrap(), BoardLayout::Timeline);
+  assert_eq!(map_ref.get_i64_with_txn(&txn, "layout").unwrap(), 5);
+  assert_eq!(
+    map_ref.get_previous_layout(&txn).unwrap(),
+    BoardLayout::Kanban
+  );
+  assert!(BoardLayout::try_from(1_i64).is_err());
+}
+
+#[test]
+fn derive_enum_collections_test() {
+  let (collab, mut map_ref) = make_board();
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_layout_history(txn, vec![BoardLayout::Kanban, BoardLayout::Timeline]);
+    map_ref
+      .push_layout_history(txn, BoardLayout::Kanban)
+      .unwrap();
+    map_ref.set_column_layouts(
+      txn,
+      HashMap::from([("todo".to_string(), BoardLayout::Timeline)]),
+    );
+    map_ref.update_column_layouts_key_value(txn, "done", BoardLayout::Kanban);
+  });
+
+  let collab = collab.lock();
+  let txn = collab.transact();
+  assert_eq!(
+    map_ref.get_layout_history(&txn).unwrap(),
+    vec![
+      BoardLayout::Kanban,
+      BoardLayout::Timeline,
+      BoardLayout::Kanban
+    ]
+  );
+  assert_eq!(
+    map_ref.get_column_layouts(&txn).unwrap(),
+    HashMap::from([
+      ("todo".to_string(), BoardLayout::Timeline),
+      ("done".to_string(), BoardLayout::Kanban),
+    ])
+  );
+  assert_eq!(
+    map_ref.get_column_layouts_value(&txn, "todo").unwrap(),
+    BoardLayout::Timeline
+  );
+
+  // The enums are stored as i64 in the collections too.
+  let column_layouts = map_ref.get_map_with_txn(&txn, "column_layouts").unwrap();
+  assert_eq!(column_layouts.get_i64_with_txn(&txn, "todo").unwrap(), 5);
+  let layout_history = map_ref.get_json_with_txn::<Vec<i64>, _>(&txn, "layout_history");
+  assert_eq!(layout_history.unwrap(), vec![0, 5, 0]);
+
+  let board = map_ref.into_object(&txn);
+  assert_eq!(board.layout_history.len(), 3);
+  assert_eq!(board.column_layouts["done"], BoardLayout::Kanban);
+}
+
+#[test]
+fn derive_nested_into_object_test() {
+  let (collab, mut map_ref) = make_board();
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_id(txn, "board_id".to_string());
+    map_ref.set_owner(txn, owner("nathan"));
+    map_ref.set_labels(txn, vec!["bug".to_string()]);
+    map_ref.set_layout(txn, BoardLayout::Timeline);
+  });
+
+  let board = map_ref.into_object(&collab.lock().transact());
+  assert_eq!(board.id, "board_id");
+  assert_eq!(board.owner.name, "nathan");
+  assert!(board.reviewer.is_none());
+  assert_eq!(board.labels, vec!["bug".to_string()]);
+  assert!(board.cards.is_empty());
+  assert!(board.columns.is_empty());
+  assert_eq!(board.layout, BoardLayout::Timeline);
+  assert!(board.previous_layout.is_none());
+}
+
+fn board_view(id: &str) -> BoardView {
+  BoardView {
+    id: id.to_string(),
+    description: format!("{} description", id),
+    layout: BoardLayout::Timeline,
+  }
+}
+
+#[test]
+fn derive_nested_struct_with_attributes_test() {
+  let (collab, mut map_ref) = make_map_ref::<ProjectMapRef>("project");
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_view(txn, board_view("v1"));
+    map_ref.set_views(txn, vec![board_view("v2"), board_view("v3")]);
+    map_ref.set_named_views(txn, HashMap::from([("v4".to_string(), board_view("v4"))]));
+  });
+
+  let collab = collab.lock();
+  let txn = collab.transact();
+  let view_map_ref = map_ref.get_view_map_ref(&txn).unwrap();
+  assert_eq!(
+    view_map_ref.get_str_with_txn(&txn, "view_id").unwrap(),
+    "v1"
+  );
+  assert!(view_map_ref.get_str_with_txn(&txn, "id").is_none());
+  assert!(view_map_ref.get_description_text_ref(&txn).is_some());
+  assert_eq!(view_map_ref.get_i64_with_txn(&txn, "layout").unwrap(), 5);
+  assert_eq!(map_ref.get_view(&txn).unwrap(), board_view("v1"));
+
+  let view_map_refs = map_ref.get_views_map_refs(&txn);
+  assert_eq!(
+    view_map_refs[1].get_str_with_txn(&txn, "view_id").unwrap(),
+    "v3"
+  );
+  assert!(view_map_refs[1].get_description_text_ref(&txn).is_some());
+  assert_eq!(
+    map_ref.get_views(&txn).unwrap(),
+    vec![board_view("v2"), board_view("v3")]
+  );
+
+  let view_map_ref = map_ref.get_named_views_map_ref(&txn, "v4").unwrap();
+  assert_eq!(
+    view_map_ref.get_str_with_txn(&txn, "view_id").unwrap(),
+    "v4"
+  );
+  assert_eq!(view_map_ref.get_i64_with_txn(&txn, "layout").unwrap(), 5);
+  assert_eq!(
+    map_ref.get_named_views_value(&txn, "v4").unwrap(),
+    board_view("v4")
+  );
+  assert_eq!(
+    map_ref.get_named_views(&txn).unwrap(),
+    HashMap::from([("v4".to_string(), board_view("v4"))])
+  );
+
+  let project = map_ref.into_object(&txn);
+  assert_eq!(project.view, board_view("v1"));
+  assert_eq!(project.views.len(), 2);
+  assert_eq!(project.named_views["v4"], board_view("v4"));
+}
+
+fn test_note() -> Note {
+  Note {
+    id: "note_id".to_string(),
+    content: "Hello".to_string(),
+    summary: Some("Greeting".to_string()),
+    priority: 1,
+    owner: owner("nathan"),
+    is_dirty: true,
+    local_priority: 1,
+  }
+}
+
+#[test]
+fn derive_from_object_test() {
+  let collab = make_collab();
+  let map_ref = {
+    let collab = collab.lock();
+    collab.with_transact_mut(|txn| {
+      let map_ref = collab.insert_map_with_txn(txn, "note");
+      NoteMapRef::from_object(txn, map_ref, test_note())
+    })
+  };
+
+  let collab = collab.lock();
+  let txn = collab.transact();
+  assert_eq!(
+    map_ref.get_str_with_txn(&txn, "note_id").unwrap(),
+    "note_id"
+  );
+  assert!(map_ref.get_str_with_txn(&txn, "id").is_none());
+  assert!(map_ref.get_content_text_ref(&txn).is_some());
+  assert!(map_ref.get_bool_with_txn(&txn, "is_dirty").is_none());
+
+  let note = map_ref.into_object(&txn);
+  assert_eq!(note.id, "note_id");
+  assert_eq!(note.content, "Hello");
+  assert_eq!(note.summary.unwrap(), "Greeting");
+  assert_eq!(note.priority, 1);
+  assert_eq!(note.owner.name, "nathan");
+  assert!(!note.is_dirty);
+  assert_eq!(note.local_priority, 3);
+}
+
+#[test]
+fn derive_fill_with_none_test() {
+  let (collab, mut map_ref) = make_map_ref::<NoteMapRef>("note");
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.fill_with_txn(txn, test_note());
+  });
+  assert!(map_ref.get_summary(&collab.lock().transact()).is_some());
+
+  let mut note = test_note();
+  note.summary = None;
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.fill_with_txn(txn, note);
+  });
+  assert!(map_ref.get_summary(&collab.lock().transact()).is_none());
+}
+
+#[test]
+fn derive_default_attribute_test() {
+  let (collab, map_ref) = make_map_ref::<NoteMapRef>("note");
+  let note = map_ref.into_object(&collab.lock().transact());
+  assert_eq!(note.priority, 3);
+  assert!(note.content.is_empty());
+}
+
+#[test]
+fn derive_text_field_test() {
+  let (collab, mut map_ref) = make_map_ref::<NoteMapRef>("note");
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_content(txn, "Hello".to_string());
+    let text_ref = map_ref.get_content_text_ref(txn).unwrap();
+    text_ref.insert(txn, 5, " world");
+  });
+  assert_eq!(
+    map_ref.get_content(&collab.lock().transact()).unwrap(),
+    "Hello world"
+  );
+
+  // Setting the text replaces its content.
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_content(txn, "Hi".to_string());
+  });
+  assert_eq!(
+    map_ref.get_content(&collab.lock().transact()).unwrap(),
+    "Hi"
+  );
+}
+
+#[test]
+fn derive_observer_test() {
+  let (collab, mut map_ref) = make_map_ref::<NoteMapRef>("note");
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.fill_with_txn(txn, test_note());
+  });
+
+  let priorities = Arc::new(Mutex::new(vec![]));
+  let cloned_priorities = priorities.clone();
+  let _priority_subscription = map_ref.observe_priority(move |_, priority| {
+    cloned_priorities.lock().push(priority);
+  });
+
+  let contents = Arc::new(Mutex::new(vec![]));
+  let cloned_contents = contents.clone();
+  let _content_subscription = map_ref.observe_content(move |_, content| {
+    cloned_contents.lock().push(content);
+  });
+
+  let owners = Arc::new(Mutex::new(vec![]));
+  let cloned_owners = owners.clone();
+  let _owner_subscription = map_ref.observe_owner(move |_, owner| {
+    cloned_owners.lock().push(owner.map(|owner| owner.name));
+  });
+
+  collab.lock().with_transact_mut(|txn| {
+    map_ref.set_priority(txn, 5);
+  });
+  collab.lock().with_transact_mut(|txn| {
+    let text_ref = map_ref.get_content_text_ref(txn).unwrap();
+    text_ref.insert(txn, 5, "!");
+  });
+  let mut owner_map_ref = map_ref
+    .get_owner_map_ref(&collab.lock().transact())
+    .unwrap();
+  collab.lock().with_transact_mut(|txn| {
+    owner_map_ref.set_name(txn, "lucas".to_string());
+  });
+
+  assert_eq!(*priorities.lock(), vec![Some(5)]);
+  assert_eq!(*contents.lock(), vec![Some("Hello!".to_string())]);
+  assert_eq!(*owners.lock(), vec![Some("lucas".to_string())]);
+}